@@ -0,0 +1,281 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Guard against users flooding the bot with commands, which could get the whole bot rate limited
+//! by Discord: each user can run a limited number of commands per minute (across all guilds), and
+//! those going over it are muted for a while, longer each time they do it again.
+
+use serenity::all::UserId;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Window in which the commands of a user are counted.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Duration of the first mute of a user; each further one doubles it, up to `MAX_MUTE`.
+const FIRST_MUTE: Duration = Duration::from_secs(60);
+
+/// Longest mute.
+const MAX_MUTE: Duration = Duration::from_secs(60 * 60);
+
+/// Time without being muted after which a user's previous mutes are forgiven.
+const STRIKE_RESET: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Number of users tracked above which the idle ones are forgotten.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Recent usage of the commands by a user.
+#[derive(Default)]
+struct Usage {
+    /// When the user ran their commands in the last `WINDOW`, oldest first.
+    recent: VecDeque<Instant>,
+    /// Number of times the user was muted (since they were last forgiven).
+    strikes: u32,
+    /// When the user was last muted.
+    last_mute: Option<Instant>,
+    /// Until when the user is muted, if they are.
+    muted_until: Option<Instant>,
+}
+
+/// Outcome of checking a command of a user.
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    /// The command can run.
+    Allowed,
+    /// The user is muted for the given time, and has just been muted if `new`.
+    Muted { remaining: Duration, new: bool },
+}
+
+/// Per-user limit of the commands run, shared by all the guilds.
+pub struct CommandGuard {
+    /// Maximum number of commands a user can run per minute. 0 disables the guard.
+    max_per_minute: u32,
+    users: Mutex<HashMap<UserId, Usage>>,
+}
+
+impl CommandGuard {
+    /// Constructor for a guard allowing the given number of commands per minute to each user (0
+    /// for no limit).
+    pub fn new(max_per_minute: u32) -> CommandGuard {
+        Self {
+            max_per_minute,
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a command of the user, returning whether it can run.
+    pub fn check(&self, user: UserId) -> Verdict {
+        self.check_at(user, Instant::now())
+    }
+
+    /// Counts a command of the user, run at the given time (see `check`).
+    fn check_at(&self, user: UserId, now: Instant) -> Verdict {
+        if self.max_per_minute == 0 {
+            return Verdict::Allowed;
+        }
+
+        let mut users = self.users.lock().unwrap();
+        if users.len() > PRUNE_THRESHOLD {
+            users.retain(|_, usage| {
+                usage
+                    .recent
+                    .back()
+                    .is_some_and(|last| now.duration_since(*last) < WINDOW)
+                    || usage
+                        .last_mute
+                        .is_some_and(|last| now.duration_since(last) < STRIKE_RESET)
+            });
+        }
+        let usage = users.entry(user).or_default();
+
+        if let Some(until) = usage.muted_until.filter(|until| *until > now) {
+            return Verdict::Muted {
+                remaining: until - now,
+                new: false,
+            };
+        }
+        if usage
+            .last_mute
+            .is_some_and(|last| now.duration_since(last) >= STRIKE_RESET)
+        {
+            usage.strikes = 0;
+        }
+
+        while usage
+            .recent
+            .front()
+            .is_some_and(|first| now.duration_since(*first) >= WINDOW)
+        {
+            usage.recent.pop_front();
+        }
+        usage.recent.push_back(now);
+        if usage.recent.len() <= self.max_per_minute as usize {
+            return Verdict::Allowed;
+        }
+
+        // Mute the user, for longer each time:
+        let mute = FIRST_MUTE
+            .saturating_mul(2u32.saturating_pow(usage.strikes))
+            .min(MAX_MUTE);
+        usage.strikes += 1;
+        usage.last_mute = Some(now);
+        usage.muted_until = Some(now + mute);
+        usage.recent.clear();
+        tracing::warn!(
+            "[abuseguard] User {} ran more than {} commands in a minute, so they are muted for {} \
+            minute(s) (mute #{}).",
+            user,
+            self.max_per_minute,
+            mute.as_secs() / 60,
+            usage.strikes
+        );
+
+        Verdict::Muted {
+            remaining: mute,
+            new: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: UserId = UserId::new(1);
+
+    /// Runs commands of the user at the given time, returning the verdict of the last one.
+    fn run(guard: &CommandGuard, commands: u32, now: Instant) -> Verdict {
+        let mut verdict = Verdict::Allowed;
+        for _ in 0..commands {
+            verdict = guard.check_at(USER, now);
+        }
+        verdict
+    }
+
+    #[test]
+    fn mutes_escalate() {
+        let guard = CommandGuard::new(2);
+        let start = Instant::now();
+
+        assert_eq!(run(&guard, 2, start), Verdict::Allowed);
+        assert_eq!(
+            guard.check_at(USER, start),
+            Verdict::Muted {
+                remaining: FIRST_MUTE,
+                new: true
+            }
+        );
+        let later = start + Duration::from_secs(20);
+        assert_eq!(
+            guard.check_at(USER, later),
+            Verdict::Muted {
+                remaining: FIRST_MUTE - Duration::from_secs(20),
+                new: false
+            }
+        );
+
+        // Each mute doubles the previous one, up to the longest one:
+        let mut now = start + FIRST_MUTE;
+        let mut expected = FIRST_MUTE * 2;
+        for _ in 0..8 {
+            assert_eq!(run(&guard, 2, now), Verdict::Allowed);
+            assert_eq!(
+                guard.check_at(USER, now),
+                Verdict::Muted {
+                    remaining: expected,
+                    new: true
+                }
+            );
+            now += expected;
+            expected = (expected * 2).min(MAX_MUTE);
+        }
+        assert_eq!(expected, MAX_MUTE);
+    }
+
+    #[test]
+    fn commands_are_counted_in_the_window() {
+        let guard = CommandGuard::new(2);
+        let start = Instant::now();
+
+        assert_eq!(guard.check_at(USER, start), Verdict::Allowed);
+        assert_eq!(guard.check_at(USER, start + WINDOW / 2), Verdict::Allowed);
+        // The first command is out of the window by now:
+        assert_eq!(guard.check_at(USER, start + WINDOW), Verdict::Allowed);
+        assert!(matches!(
+            guard.check_at(USER, start + WINDOW),
+            Verdict::Muted { new: true, .. }
+        ));
+
+        // Other users are counted on their own, and 0 means no limit:
+        assert_eq!(
+            guard.check_at(UserId::new(2), start + WINDOW),
+            Verdict::Allowed
+        );
+        let unlimited = CommandGuard::new(0);
+        assert_eq!(run(&unlimited, 1000, start), Verdict::Allowed);
+    }
+
+    #[test]
+    fn strikes_are_forgiven() {
+        let guard = CommandGuard::new(1);
+        let start = Instant::now();
+        assert!(matches!(run(&guard, 2, start), Verdict::Muted { .. }));
+        let second = start + FIRST_MUTE;
+        assert_eq!(
+            run(&guard, 2, second),
+            Verdict::Muted {
+                remaining: FIRST_MUTE * 2,
+                new: true
+            }
+        );
+
+        // After a long time without being muted, the next mute is the first one again:
+        let forgiven = second + STRIKE_RESET;
+        assert_eq!(
+            run(&guard, 2, forgiven),
+            Verdict::Muted {
+                remaining: FIRST_MUTE,
+                new: true
+            }
+        );
+    }
+
+    #[test]
+    fn idle_users_are_forgotten() {
+        let guard = CommandGuard::new(1);
+        let start = Instant::now();
+        for user in 1..=PRUNE_THRESHOLD as u64 {
+            guard.check_at(UserId::new(user), start);
+        }
+        let muted = UserId::new(PRUNE_THRESHOLD as u64 + 1);
+        guard.check_at(muted, start);
+        guard.check_at(muted, start);
+
+        // Once there are too many, only the users active in the window, or muted recently, are
+        // kept:
+        let later = start + WINDOW;
+        let active = UserId::new(PRUNE_THRESHOLD as u64 + 2);
+        guard.check_at(active, later);
+        let users = guard.users.lock().unwrap();
+        assert_eq!(users.len(), 2);
+        assert!(users.contains_key(&muted));
+        assert!(users.contains_key(&active));
+    }
+}
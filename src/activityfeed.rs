@@ -0,0 +1,240 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Live activity feed of each guild: compact embeds posted to the guild's bot channel on notable
+//! changes (e.g. a team confirmed, or Tablón becoming unreachable), so its admins can follow what
+//! happens without reading the server logs. Guilds can disable it (see `BotConfig::activity_feed`).
+//!
+//! Posting is done in the background, through the bot's `Feed` (see `Data`), so the command-line
+//! modes (e.g. the load test) never post anything.
+
+use crate::{alerts, cache::Caches, events::Event, utils};
+use poise::serenity_prelude as serenity;
+use serenity::all::{GuildId, Http, UserId};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Maximum length of the values shown in the feed (e.g. new configuration values).
+const MAX_VALUE_LEN: usize = 100;
+
+/// Notable changes posted to the activity feed.
+pub enum Activity {
+    /// An admin confirmed a team (or it became full).
+    TeamConfirmed { team: String },
+    /// The teams' passwords were imported (see `/passwords`).
+    PasswordsImported { updated: usize, future: usize },
+    /// The guild's configuration changed, by a user (or by Hermes itself, if `None`).
+    ConfigChanged {
+        user: Option<UserId>,
+        field: Option<String>,
+        value: serde_json::Value,
+    },
+    /// A backup of the persistent data was created.
+    BackupCompleted { dir: String },
+    /// Teams were reminded of not having sent any request since they got their password (see
+    /// `inactivity`).
+    InactiveTeams { teams: Vec<String>, days: u16 },
+    /// Tablón has been unreachable for a while.
+    TablonDown { minutes: u64 },
+    /// Tablón is reachable again, after being reported as unreachable.
+    TablonBack,
+}
+
+impl Activity {
+    /// Builds the embed for the activity.
+    pub(crate) fn embed(&self) -> serenity::CreateEmbed {
+        let (title, description, colour) = match self {
+            Activity::TeamConfirmed { team } => (
+                "✅ Team confirmed",
+                format!("Team {} is now definitive.", team),
+                serenity::Colour::DARK_GREEN,
+            ),
+            Activity::PasswordsImported { updated, future } => (
+                "🔑 Passwords imported",
+                format!(
+                    "{} team(s) updated, {} password(s) stored for future teams.",
+                    updated, future
+                ),
+                serenity::Colour::BLUE,
+            ),
+            Activity::ConfigChanged { user, field, value } => {
+                let author = match user {
+                    Some(user) => format!("<@{}>", user),
+                    None => "Hermes".to_string(),
+                };
+                let description = match field {
+                    Some(field) => {
+                        let mut value = value.to_string();
+                        if value.chars().count() > MAX_VALUE_LEN {
+                            value = value.chars().take(MAX_VALUE_LEN).collect::<String>() + "…";
+                        }
+                        format!("{} set `{}` to `{}`.", author, field, value)
+                    }
+                    None => format!("{} replaced the whole configuration.", author),
+                };
+                (
+                    "⚙️ Configuration updated",
+                    description,
+                    serenity::Colour::GOLD,
+                )
+            }
+            Activity::BackupCompleted { dir } => (
+                "💾 Backup completed",
+                format!("The persistent data was copied to `{}`.", dir),
+                serenity::Colour::BLUE,
+            ),
+            Activity::InactiveTeams { teams, days } => (
+                "💤 Inactive teams reminded",
+                format!(
+                    "{} team(s) have not sent any request {} day(s) after getting their password, \
+                    and were reminded of it: {}.",
+                    teams.len(),
+                    days,
+                    teams.join(", ")
+                ),
+                serenity::Colour::LIGHT_GREY,
+            ),
+            Activity::TablonDown { minutes } => (
+                "🚨 Tablón unreachable",
+                format!(
+                    "Tablón has been unreachable for {} minute(s). Requests may fail meanwhile.",
+                    minutes
+                ),
+                serenity::Colour::RED,
+            ),
+            Activity::TablonBack => (
+                "📶 Tablón reachable again",
+                "Tablón can be reached again.".to_string(),
+                serenity::Colour::DARK_GREEN,
+            ),
+        };
+
+        serenity::CreateEmbed::new()
+            .title(title)
+            .description(description)
+            .colour(colour)
+            .timestamp(serenity::Timestamp::now())
+    }
+
+    /// The activity to post for an event of a guild's event stream, if it is a notable one.
+    pub fn from_event(event: &Event) -> Option<Activity> {
+        match event {
+            Event::TeamConfirmed { team } => Some(Activity::TeamConfirmed { team: team.clone() }),
+            Event::ConfigChanged { user, field, value } => Some(Activity::ConfigChanged {
+                user: *user,
+                field: field.clone(),
+                value: value.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Handle to post to the guilds' activity feeds, shared by its clones.
+#[derive(Clone)]
+pub struct Feed {
+    /// Client to post to Discord with.
+    http: Arc<Http>,
+    /// Caches to read the guilds' configurations through (see `cache`).
+    caches: Caches,
+    /// Time Tablón must be unreachable for before posting about it.
+    tablon_threshold: Duration,
+    /// Whether Tablón was posted as unreachable (and not as reachable again since).
+    tablon_posted: Arc<AtomicBool>,
+}
+
+impl Feed {
+    /// Creates the handle, posting with the given client (and reading the guilds' configurations
+    /// through the given caches), and about Tablón once it has been unreachable for the given time.
+    pub fn new(http: Arc<Http>, caches: Caches, tablon_threshold: Duration) -> Self {
+        Self {
+            http,
+            caches,
+            tablon_threshold,
+            tablon_posted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Posts an activity to the feed of a guild (in the background), unless the guild disabled it.
+    pub fn post(&self, guild_id: GuildId, activity: Activity) {
+        let config = utils::load_config(&self.caches, &guild_id);
+        if !config.activity_feed {
+            return;
+        }
+
+        let http = Arc::clone(&self.http);
+        tokio::spawn(async move {
+            let channel = guild_id.channels(&http).await.ok().and_then(|channels| {
+                channels
+                    .into_values()
+                    .find(|channel| channel.name == config.bot_channel)
+            });
+            let Some(channel) = channel else {
+                tracing::warn!(
+                    "[activityfeed] Guild {} has no channel named #{} to post its activity to.",
+                    guild_id,
+                    config.bot_channel
+                );
+                return;
+            };
+            if let Err(e) = channel
+                .send_message(
+                    &http,
+                    serenity::CreateMessage::new().embed(activity.embed()),
+                )
+                .await
+            {
+                tracing::warn!(
+                    "[activityfeed] Could not post to the activity feed of guild {}: {}",
+                    guild_id,
+                    e
+                );
+            }
+        });
+    }
+
+    /// Posts an activity affecting every guild (e.g. a backup) to all of their feeds.
+    pub fn post_all(&self, activity: impl Fn() -> Activity) {
+        for gid in utils::load_guildmap().into_values() {
+            self.post(gid, activity());
+        }
+    }
+
+    /// Posts whether Tablón can be reached (see `alerts::tablon_down_since`): once it has been
+    /// unreachable for longer than the configured threshold, and once it is reachable again.
+    pub fn check_tablon(&self) {
+        match alerts::tablon_down_since() {
+            Some(since) if since.elapsed() >= self.tablon_threshold => {
+                if !self.tablon_posted.swap(true, Ordering::SeqCst) {
+                    let minutes = since.elapsed().as_secs() / 60;
+                    self.post_all(|| Activity::TablonDown { minutes });
+                }
+            }
+            Some(_) => {}
+            None => {
+                if self.tablon_posted.swap(false, Ordering::SeqCst) {
+                    self.post_all(|| Activity::TablonBack);
+                }
+            }
+        }
+    }
+}
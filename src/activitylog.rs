@@ -0,0 +1,110 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::alerts::{self, AlertKind};
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+use serenity::all::{GuildId, UserId};
+use std::{fs, io::Write as _};
+
+/// Data structure defining an entry of a guild's activity log: a command invocation and its
+/// outcome.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct ActivityEntry {
+    /// When the command finished, as a UNIX timestamp (in seconds).
+    #[getset(get_copy = "pub")]
+    timestamp: i64,
+    /// The user that triggered the command.
+    #[getset(get_copy = "pub")]
+    user: UserId,
+    /// The qualified name of the command (e.g. "team join").
+    #[getset(get = "pub")]
+    command: String,
+    /// The full invocation of the command, including its arguments.
+    #[getset(get = "pub")]
+    invocation: String,
+    /// Whether the command returned successfully.
+    #[getset(get_copy = "pub")]
+    success: bool,
+    /// How long the command took, in milliseconds.
+    #[getset(get_copy = "pub")]
+    duration_ms: u64,
+}
+
+impl ActivityEntry {
+    pub fn new(
+        timestamp: i64,
+        user: UserId,
+        command: String,
+        invocation: String,
+        success: bool,
+        duration_ms: u64,
+    ) -> ActivityEntry {
+        Self {
+            timestamp,
+            user,
+            command,
+            invocation,
+            success,
+            duration_ms,
+        }
+    }
+}
+
+/// Path of the activity log of a guild. Each line is an `ActivityEntry`, as JSON.
+fn log_path(guild_id: &GuildId) -> String {
+    format!("guilds/{}/activity.log", guild_id)
+}
+
+/// Appends an entry to the activity log of a guild.
+///
+/// Failures are only reported through the logs, as they should never make a command fail.
+pub fn append(guild_id: &GuildId, entry: &ActivityEntry) {
+    let json = serde_json::to_string(entry).unwrap_or_else(|e| {
+        panic!(
+            "[activitylog] Failed to serialize an activity entry for guild {}: {:?}",
+            guild_id, e
+        )
+    });
+    let result = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path(guild_id))
+        .and_then(|mut log| writeln!(log, "{}", json));
+    if let Err(e) = result {
+        alerts::alert(
+            AlertKind::DiskWrite,
+            format!(
+                "Failed to write to the activity log of guild {}: {}",
+                guild_id, e
+            ),
+        );
+    }
+}
+
+/// Loads the activity log of a guild, oldest entries first. Malformed lines are skipped.
+pub fn load(guild_id: &GuildId) -> Vec<ActivityEntry> {
+    let Ok(content) = fs::read_to_string(log_path(guild_id)) else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
@@ -0,0 +1,132 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Alerts about operational problems (e.g. Tablón being unreachable, or failed disk writes), sent
+//! to a Discord webhook configured by the operator (`--alert-webhook`), besides being logged.
+
+use chrono::Utc;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+/// Minimum time between two alerts of the same kind, so a persistent problem does not flood the
+/// webhook.
+const ALERT_COOLDOWN: Duration = Duration::from_secs(30 * 60);
+
+/// Kinds of operational problems reported through alerts.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertKind {
+    TablonUnreachable,
+    DiskWrite,
+    DataLoad,
+    Backup,
+    TaskCrash,
+}
+
+/// Alerting configuration, set once at startup.
+struct AlertConfig {
+    /// URL of the Discord webhook receiving the alerts.
+    webhook_url: String,
+    /// Time Tablón must be unreachable for before alerting about it.
+    tablon_threshold: Duration,
+}
+
+static CONFIG: OnceLock<AlertConfig> = OnceLock::new();
+
+/// When each kind of alert was last sent.
+static LAST_SENT: Mutex<Option<HashMap<AlertKind, Instant>>> = Mutex::new(None);
+
+/// Since when Tablón has been unreachable, if it currently is.
+static TABLON_DOWN_SINCE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Enables the alerts, sending them to the given webhook.
+pub fn init(webhook_url: String, tablon_threshold: Duration) {
+    let _ = CONFIG.set(AlertConfig {
+        webhook_url,
+        tablon_threshold,
+    });
+}
+
+/// Sends an alert to the webhook (in the background), unless alerts are not enabled, or one of
+/// the same kind was sent recently.
+pub fn alert(kind: AlertKind, message: String) {
+    tracing::error!("[alerts] {}", message);
+
+    let Some(config) = CONFIG.get() else {
+        return;
+    };
+    {
+        let mut last_sent = LAST_SENT.lock().unwrap();
+        let last_sent = last_sent.get_or_insert_with(HashMap::new);
+        if last_sent
+            .get(&kind)
+            .is_some_and(|sent| sent.elapsed() < ALERT_COOLDOWN)
+        {
+            return;
+        }
+        last_sent.insert(kind, Instant::now());
+    }
+
+    let url = config.webhook_url.clone();
+    let content = format!(
+        "🚨 **Hermes alert** (<t:{}>): {}",
+        Utc::now().timestamp(),
+        message
+    );
+    tokio::spawn(async move {
+        let result = reqwest::Client::new()
+            .post(url)
+            .json(&serde_json::json!({ "content": content }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            tracing::error!("[alerts] Failed to send an alert to the webhook: {}", e);
+        }
+    });
+}
+
+/// Since when Tablón has been unreachable, if it currently is (whether alerts are enabled or not).
+pub fn tablon_down_since() -> Option<Instant> {
+    *TABLON_DOWN_SINCE.lock().unwrap()
+}
+
+/// Records whether Tablón could be reached, alerting once it has been unreachable for longer than
+/// the configured threshold (the activity feed follows it too, see `Feed::check_tablon`).
+pub fn report_tablon_reachable(reachable: bool) {
+    let mut down_since = TABLON_DOWN_SINCE.lock().unwrap();
+    if reachable {
+        *down_since = None;
+        return;
+    }
+
+    let since = *down_since.get_or_insert_with(Instant::now);
+    let Some(config) = CONFIG.get() else {
+        return;
+    };
+    if since.elapsed() >= config.tablon_threshold {
+        alert(
+            AlertKind::TablonUnreachable,
+            format!(
+                "Tablón has been unreachable for {} minute(s).",
+                since.elapsed().as_secs() / 60
+            ),
+        );
+    }
+}
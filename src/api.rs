@@ -0,0 +1,217 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Admin API for external tooling (e.g. course scripts, or grading pipelines), to query the state of
+//! a guild without scraping Discord or reading the bot's files directly.
+//!
+//! Every endpoint is under `/api/guilds/<guild_id>/`, and requires the guild's token (see
+//! `/botconfig api_token`) in an `Authorization: Bearer <token>` header:
+//! - `GET teams`: the guild's teams.
+//! - `GET teams/<team_id>`: a team, and its members.
+//! - `GET submissions`: the number of requests sent through Hermes by each team.
+//! - `POST leaderboard/refresh`: refreshes the guild's leaderboards (currently, the champion roles
+//!   of each exercise).
+
+use crate::{
+    http::{self, HttpState},
+    leaderboard, student,
+    team::{self, Team},
+    utils,
+};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use poise::serenity_prelude as serenity;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A team, as listed by the API.
+#[derive(Serialize)]
+struct TeamSummary {
+    id: String,
+    name: String,
+    confirmed: bool,
+    /// Whether the team is anonymous in public leaderboards (see `Team::public_name`).
+    anonymous: bool,
+    members: Vec<serenity::UserId>,
+}
+
+impl From<&Team> for TeamSummary {
+    fn from(team: &Team) -> TeamSummary {
+        let mut members: Vec<_> = team.members().iter().copied().collect();
+        members.sort();
+
+        Self {
+            id: team.id().clone(),
+            name: team.name().clone(),
+            confirmed: team.confirmed(),
+            anonymous: team.anonymous(),
+            members,
+        }
+    }
+}
+
+/// A team and its members, as detailed by the API.
+#[derive(Serialize)]
+struct TeamDetails {
+    id: String,
+    name: String,
+    confirmed: bool,
+    anonymous: bool,
+    members: Vec<MemberDetails>,
+}
+
+/// A member of a team, as detailed by the API.
+#[derive(Serialize)]
+struct MemberDetails {
+    discord_id: serenity::UserId,
+    name: String,
+    university_id: Option<String>,
+    requests: usize,
+}
+
+/// Routes of the API.
+pub fn routes() -> Router<HttpState> {
+    Router::new()
+        .route("/api/guilds/{guild_id}/teams", get(list_teams))
+        .route("/api/guilds/{guild_id}/teams/{team_id}", get(get_team))
+        .route("/api/guilds/{guild_id}/submissions", get(submission_counts))
+        .route(
+            "/api/guilds/{guild_id}/leaderboard/refresh",
+            post(refresh_leaderboard),
+        )
+}
+
+/// Checks that a request is authorized to query the given guild, returning its identifier.
+fn authorize(guild_id: u64, headers: &HeaderMap) -> Result<serenity::GuildId, StatusCode> {
+    let gid = http::known_guild(guild_id).ok_or(StatusCode::NOT_FOUND)?;
+    http::authorize(headers, utils::load_secrets(&gid).api_token.as_ref()).inspect_err(
+        |status| tracing::warn!("[api] Rejected a request for guild {} ({}).", gid, status),
+    )?;
+
+    Ok(gid)
+}
+
+/// Runs the file operations of an endpoint out of the async runtime, failing with an internal error
+/// if they panic (e.g. on a corrupted file).
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, StatusCode> + Send + 'static,
+) -> Result<T, StatusCode> {
+    tokio::task::spawn_blocking(f).await.unwrap_or_else(|e| {
+        tracing::error!("[api] A request failed: {}", e);
+        Err(StatusCode::INTERNAL_SERVER_ERROR)
+    })
+}
+
+async fn list_teams(
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TeamSummary>>, StatusCode> {
+    let gid = authorize(guild_id, &headers)?;
+
+    blocking(move || {
+        Ok(Json(
+            team::get_all_teams(&gid)
+                .iter()
+                .map(TeamSummary::from)
+                .collect(),
+        ))
+    })
+    .await
+}
+
+async fn get_team(
+    State(state): State<HttpState>,
+    Path((guild_id, team_id)): Path<(u64, String)>,
+    headers: HeaderMap,
+) -> Result<Json<TeamDetails>, StatusCode> {
+    let gid = authorize(guild_id, &headers)?;
+
+    blocking(move || {
+        let team = http::known_team(&gid, &team_id).ok_or(StatusCode::NOT_FOUND)?;
+
+        let mut members = Vec::new();
+        for member in TeamSummary::from(&team).members {
+            let Some(student) = student::get_student(&state.caches, &member) else {
+                continue;
+            };
+            members.push(MemberDetails {
+                discord_id: member,
+                name: student.name().clone(),
+                university_id: student.university_id().clone(),
+                requests: student.request_history().get(&gid).map_or(0, Vec::len),
+            });
+        }
+
+        Ok(Json(TeamDetails {
+            id: team.id().clone(),
+            name: team.name().clone(),
+            confirmed: team.confirmed(),
+            anonymous: team.anonymous(),
+            members,
+        }))
+    })
+    .await
+}
+
+async fn submission_counts(
+    State(state): State<HttpState>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+) -> Result<Json<BTreeMap<String, usize>>, StatusCode> {
+    let gid = authorize(guild_id, &headers)?;
+
+    blocking(move || {
+        let mut counts = BTreeMap::new();
+        for student in student::get_all_students(&state.caches) {
+            let (Some(team_id), Some(hist)) = (
+                student.get_team_id(&gid),
+                student.request_history().get(&gid),
+            ) else {
+                continue;
+            };
+            *counts.entry(team_id).or_default() += hist.len();
+        }
+
+        Ok(Json(counts))
+    })
+    .await
+}
+
+async fn refresh_leaderboard(
+    State(state): State<HttpState>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    let gid = authorize(guild_id, &headers)?;
+
+    // The leaderboards are still WIP (see `/leaderboard`), so only the champion roles are refreshed:
+    leaderboard::refresh_champion_roles(&state.http, &state.caches, &gid)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(|e| {
+            tracing::warn!(
+                "[api] Could not refresh the champion roles of guild {}: {}",
+                gid,
+                e
+            );
+            StatusCode::BAD_GATEWAY
+        })
+}
@@ -0,0 +1,55 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use chrono::Utc;
+use std::{fs, io, path::Path, path::PathBuf};
+
+/// Directories holding the persistent data, which are included in the backups.
+const DATA_DIRS: [&str; 2] = ["guilds", "users"];
+
+/// Recursively copies a directory.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates a backup of the persistent data, as a copy under `backups/<timestamp>/`.
+///
+/// Returns the directory of the new backup.
+pub fn create_backup() -> io::Result<PathBuf> {
+    let backup_dir = PathBuf::from(format!(
+        "backups/{}",
+        Utc::now().format("%Y-%m-%dT%H-%M-%SZ")
+    ));
+    for dir in DATA_DIRS {
+        if Path::new(dir).is_dir() {
+            copy_dir(Path::new(dir), &backup_dir.join(dir))?;
+        }
+    }
+
+    Ok(backup_dir)
+}
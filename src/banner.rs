@@ -0,0 +1,90 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Identification banners prepended to the programs sent to Tablón (see
+//! `BotConfig::tag_submissions`): a comment with the team, the student, when and through which
+//! version of Hermes each program was sent.
+
+use std::{fs, io, path::Path};
+
+/// Line comment prefix for the language of a file, detected from its extension (or its name, for
+/// makefiles), if known.
+fn comment_prefix(filename: &str) -> Option<&'static str> {
+    let path = Path::new(filename);
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name == "makefile" || name == "cmakelists.txt" {
+        return Some("#");
+    }
+
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hxx" | "cu" | "cuh" | "cl" | "java" | "js"
+        | "ts" | "rs" | "go" | "cs" | "kt" | "scala" | "swift" => Some("//"),
+        "py" | "sh" | "bash" | "r" | "rb" | "pl" | "jl" | "mk" | "cmake" => Some("#"),
+        "f" | "f77" | "f90" | "f95" | "f03" | "f08" => Some("!"),
+        "hs" | "lua" | "sql" | "adb" | "ads" => Some("--"),
+        "m" | "tex" => Some("%"),
+        _ => None,
+    }
+}
+
+/// Builds the banner for a program, as comments in its language, if it is known.
+pub fn banner(filename: &str, team: &str, user: &str, timestamp: &str) -> Option<String> {
+    let prefix = comment_prefix(filename)?;
+
+    Some(format!(
+        "{prefix} Sent through Hermes v{} by {} (team {}) at {}.\n",
+        env!("CARGO_PKG_VERSION"),
+        user,
+        team,
+        timestamp
+    ))
+}
+
+/// Prepends the banner to a program on disk, after its shebang line (if any), so scripts still
+/// run.
+///
+/// Returns whether the banner was added (i.e. whether the program's language is known).
+pub fn inject(path: &Path, team: &str, user: &str, timestamp: &str) -> io::Result<bool> {
+    let filename = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let Some(banner) = banner(&filename, team, user, timestamp) else {
+        return Ok(false);
+    };
+
+    let program = fs::read(path)?;
+    let split = if program.starts_with(b"#!") {
+        program
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(program.len(), |newline| newline + 1)
+    } else {
+        0
+    };
+    let mut tagged = Vec::with_capacity(banner.len() + program.len() + 1);
+    tagged.extend_from_slice(&program[..split]);
+    if split > 0 && !program[..split].ends_with(b"\n") {
+        tagged.push(b'\n');
+    }
+    tagged.extend_from_slice(banner.as_bytes());
+    tagged.extend_from_slice(&program[split..]);
+    fs::write(path, tagged)?;
+
+    Ok(true)
+}
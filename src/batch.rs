@@ -0,0 +1,67 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{cache::Caches, student, student::Student, team::Team};
+use serenity::all::{GuildId, UserId};
+use std::collections::BTreeMap;
+
+/// Students and teams modified by a command, saved to disk once at the end (see `Batch::flush`),
+/// instead of after every change (e.g. when setting the passwords of every team of a guild).
+pub struct Batch {
+    /// Caches the students are retrieved from.
+    caches: Caches,
+    students: BTreeMap<UserId, Student>,
+    teams: BTreeMap<(GuildId, String), Team>,
+}
+
+impl Batch {
+    pub fn new(caches: &Caches) -> Self {
+        Self {
+            caches: caches.clone(),
+            students: BTreeMap::new(),
+            teams: BTreeMap::new(),
+        }
+    }
+
+    /// Retrieves a student to modify, from the batch if it was already modified in it.
+    pub fn student(&mut self, id: &UserId) -> Option<&mut Student> {
+        if !self.students.contains_key(id) {
+            self.students
+                .insert(*id, student::get_student(&self.caches, id)?);
+        }
+
+        self.students.get_mut(id)
+    }
+
+    /// Marks a team as modified, to be saved with the current state once the batch is flushed.
+    ///
+    /// Later changes to the team must be recorded again.
+    pub fn save_team(&mut self, team: &Team) {
+        self.teams
+            .insert((*team.guild(), team.id().clone()), team.clone());
+    }
+
+    /// Saves every modified student and team to disk.
+    pub fn flush(self) {
+        for student in self.students.values() {
+            student.save();
+        }
+        for team in self.teams.values() {
+            team.save();
+        }
+    }
+}
@@ -0,0 +1,85 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! In-memory caches of the persistent data needed by most commands (the guilds' configurations and
+//! the students), so it is not re-read from disk every time. They are owned by the framework's
+//! `Data`, and handed to the tasks and servers running outside of the commands.
+use crate::{student::Student, utils::BotConfig};
+use serenity::all::{GuildId, UserId};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// Handle to the caches, shared by its clones.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Default)]
+pub struct Caches {
+    /// Configurations of the guilds, kept up to date by `utils::update_config_persistence`.
+    configs: Arc<Mutex<BTreeMap<GuildId, BotConfig>>>,
+    /// Students, kept up to date by `Student::save`.
+    students: Arc<Mutex<BTreeMap<UserId, Student>>>,
+}
+
+impl Caches {
+    /// Cached configuration of a guild, if any.
+    pub fn config(&self, guild_id: &GuildId) -> Option<BotConfig> {
+        self.configs
+            .lock()
+            .expect("The configuration cache was poisoned.")
+            .get(guild_id)
+            .cloned()
+    }
+
+    /// Caches the configuration of a guild.
+    pub fn set_config(&self, guild_id: &GuildId, config: &BotConfig) {
+        self.configs
+            .lock()
+            .expect("The configuration cache was poisoned.")
+            .insert(*guild_id, config.clone());
+    }
+
+    /// Cached student, if any.
+    pub fn student(&self, id: &UserId) -> Option<Student> {
+        self.students
+            .lock()
+            .expect("The student cache was poisoned.")
+            .get(id)
+            .cloned()
+    }
+
+    /// Caches a student.
+    pub fn set_student(&self, student: Student) {
+        self.students
+            .lock()
+            .expect("The student cache was poisoned.")
+            .insert(student.id(), student);
+    }
+
+    /// Clears the caches, so the persistent files are read from disk again (e.g. after editing
+    /// them by hand).
+    pub fn clear(&self) {
+        self.configs
+            .lock()
+            .expect("The configuration cache was poisoned.")
+            .clear();
+        self.students
+            .lock()
+            .expect("The student cache was poisoned.")
+            .clear();
+    }
+}
@@ -0,0 +1,50 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Fault injection for the persistent storage (with the `chaos` feature), to check the bot degrades
+//! gracefully (replying with an error, and leaving the data intact) when reads or writes fail.
+//!
+//! The fraction of the reads and writes to fail is set with the `HERMES_CHAOS_RATE` environment
+//! variable (between 0 and 1, 0 by default). Without the feature, nothing ever fails.
+
+#[cfg(feature = "chaos")]
+use rand::Rng as _;
+use std::io;
+#[cfg(feature = "chaos")]
+use std::sync::OnceLock;
+
+/// Fails the operation on the given file, as many times as configured.
+pub fn inject(path: &str) -> io::Result<()> {
+    #[cfg(feature = "chaos")]
+    {
+        static RATE: OnceLock<f64> = OnceLock::new();
+        let rate = *RATE.get_or_init(|| {
+            std::env::var("HERMES_CHAOS_RATE")
+                .ok()
+                .and_then(|rate| rate.parse::<f64>().ok())
+                .map_or(0.0, |rate| rate.clamp(0.0, 1.0))
+        });
+        if rand::thread_rng().gen_bool(rate) {
+            tracing::warn!("[chaos] Injecting a failure on {}.", path);
+            return Err(io::Error::other(format!("injected failure on {}", path)));
+        }
+    }
+    #[cfg(not(feature = "chaos"))]
+    let _ = path;
+
+    Ok(())
+}
@@ -0,0 +1,310 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    activitylog::ActivityEntry,
+    deadline::GuildDeadlines,
+    redact,
+    student::{GuildSlice, Student},
+    team::GuildTeamInfo,
+    team::Team,
+    utils,
+    utils::GuildSecrets,
+};
+use clap::Parser;
+use serde::de::DeserializeOwned;
+use serenity::all::GuildId;
+use std::{collections::HashMap, fs, path::Path, path::PathBuf};
+
+/// Command line arguments of the bot.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Directory holding the bot's persistent data (`guilds/`, `users/`, and the default
+    /// `config.json`). Defaults to the working directory.
+    #[arg(long, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+    /// File containing the Discord token. If not given, the DISCORD_TOKEN environment variable, or
+    /// the systemd credential or Docker secret named `discord_token`, are used.
+    #[arg(long, env = "DISCORD_TOKEN_FILE", value_name = "FILE")]
+    pub token_file: Option<PathBuf>,
+    /// Log level (or any RUST_LOG-like filter, e.g. "warn,hermes=debug"). Overrides RUST_LOG.
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<String>,
+    /// Register the slash commands (globally, and the WIP ones in the guilds enabling them), and
+    /// exit without connecting to the gateway.
+    #[arg(long, conflicts_with = "dry_run")]
+    pub register_commands_only: bool,
+    /// Validate the persistent data, and exit without connecting to Discord.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Serve a mock of Tablón's pages (see `mocktablon`) on the given address, to point a test
+    /// guild's `tablon_url` to, and exit once stopped.
+    #[cfg(feature = "mock-tablon")]
+    #[arg(long, value_name = "ADDR")]
+    pub mock_tablon: Option<std::net::SocketAddr>,
+    /// Discord webhook URL to send alerts about operational problems to (e.g. Tablón being
+    /// unreachable, failed disk writes or backups, or crashed background tasks).
+    #[arg(long, env = "HERMES_ALERT_WEBHOOK", value_name = "URL")]
+    pub alert_webhook: Option<String>,
+    /// Minutes Tablón must be unreachable for before alerting about it (and posting it to the
+    /// guilds' activity feeds).
+    #[arg(long, default_value_t = 10, value_name = "MINUTES")]
+    pub tablon_alert_minutes: u64,
+    /// Maximum number of commands each user can run per minute, across all guilds; those going
+    /// over it are muted for a while (see `abuseguard`). 0 disables the limit.
+    #[arg(long, default_value_t = 20, value_name = "N")]
+    pub max_commands_per_minute: u32,
+    /// Seconds between changes of the bot's presence, which rotates through live stats of all the
+    /// guilds (see `presence`). 0 disables the rotation, leaving no activity shown.
+    #[arg(long, default_value_t = 300, value_name = "SECONDS")]
+    pub presence_interval: u64,
+    /// Stats shown in the bot's presence, in order (comma-separated).
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "teams,requests-today,tablon",
+        value_name = "STATS"
+    )]
+    pub presence_stats: Vec<crate::presence::Stat>,
+    /// Number of gateway shards to use. Defaults to the number recommended by Discord.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub shards: Option<u32>,
+    /// Address to serve Hermes's HTTP endpoints on (e.g. Tablón's result notifications, see
+    /// `webhook`). No HTTP server is started if unset.
+    #[arg(long, env = "HERMES_HTTP_LISTEN", value_name = "ADDR")]
+    pub http_listen: Option<std::net::SocketAddr>,
+}
+
+/// Name of the token file in the systemd credentials directory (`LoadCredential=discord_token:...`)
+/// and among the Docker secrets.
+const TOKEN_SECRET_NAME: &str = "discord_token";
+
+/// Directory where Docker mounts the secrets.
+const DOCKER_SECRETS_DIR: &str = "/run/secrets";
+
+/// Reads a token from a file, ignoring surrounding whitespace (e.g. a trailing newline).
+fn read_token_file(path: &Path) -> String {
+    let token = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Could not read the token file {}: {:?}", path.display(), e))
+        .trim()
+        .to_string();
+    if token.is_empty() {
+        panic!("The token file {} is empty.", path.display());
+    }
+
+    token
+}
+
+impl Args {
+    /// Reads the Discord token from, in order of preference: the token file (`--token-file`, or
+    /// `DISCORD_TOKEN_FILE`), the `DISCORD_TOKEN` environment variable, the systemd credential, or
+    /// the Docker secret (both named `discord_token`).
+    pub fn token(&self) -> String {
+        if let Some(path) = &self.token_file {
+            return read_token_file(path);
+        }
+        if let Ok(token) = std::env::var("DISCORD_TOKEN") {
+            return token;
+        }
+        if let Ok(credentials_dir) = std::env::var("CREDENTIALS_DIRECTORY") {
+            let path = Path::new(&credentials_dir).join(TOKEN_SECRET_NAME);
+            if path.exists() {
+                return read_token_file(&path);
+            }
+        }
+        let path = Path::new(DOCKER_SECRETS_DIR).join(TOKEN_SECRET_NAME);
+        if path.exists() {
+            return read_token_file(&path);
+        }
+
+        panic!(
+            "Discord token not provided (with --token-file, the DISCORD_TOKEN_FILE or DISCORD_TOKEN \
+            environment variables, or as a systemd credential or Docker secret named {}).",
+            TOKEN_SECRET_NAME
+        );
+    }
+}
+
+/// Makes sure the token (and any other secret, see `redact`) never shows up in panic messages (e.g.
+/// from errors including a request), by redacting it from them.
+pub fn scrub_token_from_panics(token: String) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info.to_string();
+        let redacted = redact::redact(&message).replace(&token, redact::PLACEHOLDER);
+        if redacted != message {
+            eprintln!("{}", redacted);
+        } else {
+            default_hook(info);
+        }
+    }));
+}
+
+/// Checks that the file at the given path can be parsed as a `T`, recording a problem otherwise.
+fn check_json<T: DeserializeOwned>(path: &Path, problems: &mut Vec<String>) {
+    match fs::read_to_string(path) {
+        Ok(json) => {
+            if let Err(e) = serde_json::from_str::<T>(&json) {
+                problems.push(format!("{}: {}", path.display(), e));
+            }
+        }
+        Err(e) => problems.push(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// Lists the entries of a directory whose names are numeric IDs (e.g. guilds or students), along
+/// with their paths.
+fn numeric_entries(dir: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.parse::<u64>().is_ok())
+        })
+        .collect()
+}
+
+/// Validates the persistent data in the working directory, without modifying it.
+///
+/// Returns the number of files checked, and the problems found.
+pub fn validate_data() -> (usize, Vec<String>) {
+    let mut problems = Vec::new();
+    let mut checked = 0;
+
+    // Global maps:
+    for (path, exists) in [
+        (
+            "guilds/guildMap.json",
+            Path::new("guilds/guildMap.json").exists(),
+        ),
+        (
+            "users/userMap.json",
+            Path::new("users/userMap.json").exists(),
+        ),
+    ] {
+        if !exists {
+            problems.push(format!("{}: missing", path));
+            continue;
+        }
+        checked += 1;
+        if path.starts_with("guilds") {
+            check_json::<HashMap<String, GuildId>>(Path::new(path), &mut problems);
+        } else {
+            match fs::read_to_string(path) {
+                Ok(json) => {
+                    if let Err(e) = utils::parse_usermap(&json) {
+                        problems.push(format!("{}: {}", path, e));
+                    }
+                }
+                Err(e) => problems.push(format!("{}: {}", path, e)),
+            }
+        }
+    }
+
+    // Students:
+    for path in numeric_entries("users") {
+        checked += 1;
+        check_json::<Student>(&path, &mut problems);
+    }
+
+    // Guilds:
+    for guild_dir in numeric_entries("guilds").into_iter().filter(|p| p.is_dir()) {
+        let config_path = guild_dir.join("config.json");
+        checked += 1;
+        match fs::read_to_string(&config_path) {
+            Ok(json) => {
+                if let Err(e) = utils::parse_config(&json) {
+                    problems.push(format!("{}: {}", config_path.display(), e));
+                }
+            }
+            Err(e) => problems.push(format!("{}: {}", config_path.display(), e)),
+        }
+        let name_map_path = guild_dir.join("nameMap.json");
+        if name_map_path.exists() {
+            checked += 1;
+            check_json::<HashMap<String, String>>(&name_map_path, &mut problems);
+        }
+        let secrets_path = guild_dir.join("secrets.json");
+        if secrets_path.exists() {
+            checked += 1;
+            check_json::<GuildSecrets>(&secrets_path, &mut problems);
+        }
+        let deadlines_path = guild_dir.join("deadlines.json");
+        if deadlines_path.exists() {
+            checked += 1;
+            check_json::<GuildDeadlines>(&deadlines_path, &mut problems);
+        }
+        if let Ok(slice_files) = fs::read_dir(guild_dir.join("students")) {
+            for path in slice_files.filter_map(|entry| entry.ok()).map(|e| e.path()) {
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                checked += 1;
+                check_json::<GuildSlice>(&path, &mut problems);
+            }
+        }
+        let activity_path = guild_dir.join("activity.log");
+        if let Ok(content) = fs::read_to_string(&activity_path) {
+            checked += 1;
+            for (i, line) in content.lines().enumerate() {
+                if let Err(e) = serde_json::from_str::<ActivityEntry>(line) {
+                    problems.push(format!("{}:{}: {}", activity_path.display(), i + 1, e));
+                }
+            }
+        }
+        let Ok(team_files) = fs::read_dir(guild_dir.join("teams")) else {
+            continue;
+        };
+        let team_problems = problems.len();
+        for path in team_files.filter_map(|entry| entry.ok()).map(|e| e.path()) {
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            checked += 1;
+            if path.file_name().is_some_and(|name| name == "info.json") {
+                check_json::<GuildTeamInfo>(&path, &mut problems);
+            } else {
+                check_json::<Team>(&path, &mut problems);
+            }
+        }
+
+        // Team identifiers, once the teams and the name map can be loaded:
+        let guild_id = guild_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<u64>().ok())
+            .map(GuildId::new);
+        if problems.len() == team_problems && name_map_path.exists() {
+            if let Some(info) = guild_id.and_then(|gid| crate::team::get_guild_team_info(&gid)) {
+                let info_path = guild_dir.join("teams/info.json");
+                for problem in info.problems() {
+                    problems.push(format!("{}: {}", info_path.display(), problem));
+                }
+            }
+        }
+    }
+
+    (checked, problems)
+}
@@ -0,0 +1,155 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{student, team, utils, utils::get_guild_id, Context, Error};
+use poise::serenity_prelude as serenity;
+use std::time::Duration;
+
+/// Time to wait between DMs, to stay well within Discord's rate limits.
+const DM_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Maximum length of the list of unreachable members in the delivery report (Discord limits
+/// messages to 2000 characters).
+const MAX_REPORT_LIST_LEN: usize = 1500;
+
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    subcommands("dm"),
+    subcommand_required,
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn announce(_: Context<'_>) -> Result<(), Error> {
+    // This function will not be executed, as the command has subcommands.
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Send a message by DM to the members of some teams, or to every student in the server."
+    ),
+    description_localized(
+        "es-ES",
+        "Enviar un mensaje por MD a los miembros de algunos equipos, o a todos los estudiantes del servidor."
+    )
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn dm(
+    ctx: Context<'_>,
+    #[description = "The message to send."] message: String,
+    #[description = "The teams to send it to, separated by commas or spaces. Leave empty for every student."]
+    teams: Option<String>,
+    #[description = "A file to attach to the message."] attachment: Option<serenity::Attachment>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+
+    // Sending the DMs takes a while:
+    ctx.defer_ephemeral().await?;
+
+    // Collect the recipients:
+    let mut recipients = Vec::new();
+    let mut unknown_teams = Vec::new();
+    match teams {
+        Some(teams) => {
+            for team_id in teams
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|id| !id.is_empty())
+            {
+                match team::get_team(&gid, team_id) {
+                    Some(team) => recipients.extend(team.members().iter().copied()),
+                    None => unknown_teams.push(format!("`{}`", team_id)),
+                }
+            }
+        }
+        None => {
+            recipients = utils::fetch_all_members(ctx.http(), &gid)
+                .await?
+                .into_iter()
+                .filter(|member| !member.user.bot)
+                .map(|member| member.user.id)
+                .filter(|uid| student::get_student(&ctx.data().caches, uid).is_some())
+                .collect();
+        }
+    }
+    recipients.sort();
+    recipients.dedup();
+
+    // Download the attachment once, to re-upload it with every DM:
+    let attachment = match attachment {
+        Some(attachment) => Some((attachment.download().await?, attachment.filename)),
+        None => None,
+    };
+
+    // Send the DMs, throttled:
+    let gname = gid
+        .name(ctx.cache())
+        .unwrap_or_else(|| format!("guild {}", gid));
+    let content = format!("📢 **Announcement from {}:**\n{}", gname, message);
+    let mut unreachable = Vec::new();
+    for (i, uid) in recipients.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(DM_INTERVAL).await;
+        }
+        let mut msg = serenity::CreateMessage::new().content(content.clone());
+        if let Some((bytes, filename)) = &attachment {
+            msg = msg.add_file(serenity::CreateAttachment::bytes(
+                bytes.clone(),
+                filename.clone(),
+            ));
+        }
+        if uid.direct_message(ctx.http(), msg).await.is_err() {
+            unreachable.push(format!("<@{}>", uid));
+        }
+    }
+
+    // Reply with the delivery report:
+    let mut reply = format!(
+        "Announcement delivered to {} of {} recipients.",
+        recipients.len() - unreachable.len(),
+        recipients.len()
+    );
+    if !unknown_teams.is_empty() {
+        reply.push_str(format!("\nUnknown teams: {}.", unknown_teams.join(", ")).as_str());
+    }
+    if !unreachable.is_empty() {
+        let list = unreachable.join(", ");
+        if list.len() <= MAX_REPORT_LIST_LEN {
+            reply.push_str(format!("\nCould not be reached by DM: {}.", list).as_str());
+        } else {
+            reply.push_str(
+                format!(
+                    "\n{} members could not be reached by DM.",
+                    unreachable.len()
+                )
+                .as_str(),
+            );
+        }
+    }
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[announce] Failed to send the delivery report of an announcement in guild {}: {:?}",
+            gid, e
+        )
+    });
+
+    Ok(())
+}
@@ -18,60 +18,54 @@
 extern crate reqwest;
 
 use crate::{
+    activitylog,
+    commands::{registry, request},
+    events::{self, Event},
+    http,
+    i18n::{self, tr},
     team, utils,
-    utils::get_guild_id,
-    {Context, Error},
+    utils::{get_guild_id, BotConfig, Visibility},
+    welcome, Credentials, {Context, Data, Error},
 };
-use poise::{serenity_prelude as serenity, serenity_prelude::GuildChannel};
+use poise::{serenity_prelude as serenity, ChoiceParameter as _, CreateReply};
 use serde_json;
-use std::fs;
+use std::{fmt::Write as _, fs};
 
-async fn check_on_proper_channel(ctx: Context<'_>, channel_name: &String) -> bool {
-    let gid = get_guild_id!(ctx);
-    let cmd_channel = ctx
-        .guild_channel()
-        .await
-        .expect("[boconfig] The command was not invoked in a guild channel.");
-    // TODO: Check if the channel exists in the guild, and send different error messages.
-    // Previous attempts caused an "Future is not Send" error on the await for the ctx.reply() calls.
-    if cmd_channel.name != *channel_name {
-        ctx.reply(
-            format!(
-                "This command should only be used in the configured bot channel: #{}.",
-                channel_name
-            )
-            .as_str(),
-        )
-        .await
-        .expect(
-            format!(
-            "[botconfig] Failed to send reply using the command in an invalid channel in guild {}.",
-            gid
-        )
-            .as_str(),
-        );
+/// Default number of log entries shown by `/botconfig logs`.
+const DEFAULT_LOG_ENTRIES: usize = 20;
 
-        return false;
-    }
+/// Length of the longest log listing sent as a message, instead of as an attachment (Discord limits
+/// messages to 2000 characters).
+const MAX_LOG_MESSAGE_LEN: usize = 1900;
+
+/// Builds the `/botconfig` command, including the getter/setter subcommands generated for each field
+/// of `BotConfig`.
+pub fn command() -> poise::Command<Data, Error> {
+    let mut cmd = botconfig();
+    cmd.subcommands.extend(BotConfig::commands());
 
-    return true;
+    cmd
 }
 
 #[poise::command(
     slash_command,
+    category = "Admin",
     subcommands(
         "show",
-        "tablon_url",
-        "team_capacity",
-        "team_prefix",
-        "bot_channel",
-        "lb_channel",
-        "notify_leaders",
-        "leader_count",
-        "public_notify",
-        "bot_news_channel",
-        "column_separator",
         "update",
+        "tablon_admin",
+        "clear_tablon_admin",
+        "webhook_token",
+        "api_token",
+        "wip_commands",
+        "logs",
+        "audit_permissions",
+        "permissions",
+        "visibility",
+        "lb_champion_role",
+        "client_extension",
+        "team_prefix",
+        "welcome_message"
     ),
     default_member_permissions = "MANAGE_GUILD",
     guild_only,
@@ -87,33 +81,46 @@ pub async fn botconfig(ctx: Context<'_>) -> Result<(), Error> {
     slash_command,
     ephemeral,
     description_localized("en-US", "Show the current configuration for the bot."),
-    description_localized("es-ES", "Show the current configuration for the bot.")
+    description_localized("es-ES", "Mostrar la configuración actual del bot.")
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+    let config = utils::load_config(&ctx.data().caches, &gid);
+
+    // Never show the admin credentials, only whether they are set:
+    let admin_status = match utils::get_admin_credentials(&gid) {
+        Some(_) => tr!(lang, "set", "establecidas"),
+        None => tr!(lang, "not set", "sin establecer"),
+    };
 
     // Reply with the current configuration:
-    ctx.reply(format!(
-        "Current configuration:\n\
-        ```json\n{}\n```",
-        serde_json::to_string_pretty(&config).expect(
-            format!(
-                "[botconfig] Failed to serialize the config for guild {}.",
-                gid
-            )
-            .as_str()
-        )
-    ))
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Current configuration:\n\
+            ```json\n{}\n```\n\
+            Tablón admin credentials: {}.",
+            "Configuración actual:\n\
+            ```json\n{}\n```\n\
+            Credenciales de administrador de Tablón: {}.",
+            serde_json::to_string_pretty(&config).unwrap_or_else(|e| panic!(
+                "[botconfig] Failed to serialize the config for guild {}: {:?}",
+                gid, e
+            )),
+            admin_status
+        ),
+    )
     .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send the configuration for guild {}.",
-            gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send the configuration for guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
@@ -121,75 +128,216 @@ pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
 #[poise::command(
     slash_command,
     ephemeral,
-    description_localized("en-US", "Change the URL for Tablón's endpoint."),
-    description_localized("es-ES", "Change the URL for Tablón's endpoint.")
+    description_localized(
+        "en-US",
+        "Update the configuration for the bot with the provided or a default file."
+    ),
+    description_localized(
+        "es-ES",
+        "Actualizar la configuración del bot con el fichero dado o con uno por defecto."
+    )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn tablon_url(
+pub async fn update(
     ctx: Context<'_>,
-    #[description = "The new URL for Tablón's endpoint."] url: String,
+    #[description = "JSON configuration file with the new configuration."] file: Option<
+        serenity::Attachment,
+    >,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
 
-    // Update the configuration:
-    config.tablon_url = url.clone();
-    utils::update_config_persistence(&config, &gid);
+    // Read the config JSON - Attachment, or default file:
+    let config_json = if let Some(config_file) = file {
+        // Handle attachement:
+        reqwest::get(&config_file.url)
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[botconfig update] Could not download the config file from URL: {}: {:?}",
+                    config_file.url, e
+                )
+            })
+            .text()
+            .await
+            .expect("[botconfig update] Could not read the teams file into a string.")
+    } else {
+        // Use the default configuration file (possibly new):
+        if fs::metadata("config.json").is_ok() {
+            fs::read_to_string("config.json")
+                .expect("[botconfig update] Could not read the default configuration file.")
+        } else {
+            utils::reply_error(
+                ctx,
+                tr!(
+                    lang,
+                    "No configuration file was provided, and the default configuration file was not found.",
+                    "No se proporcionó ningún fichero de configuración, y no se encontró el fichero de configuración por defecto."
+                ),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[botconfig update] Failed to send error message for missing configuration \
+                    file for guild {}: {:?}",
+                    gid, e
+                )
+            });
 
-    // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "The Tablón URL has been changed to <{}>.",
-            config.tablon_url
+            return Ok(());
+        }
+    };
+    let config = match utils::parse_config(&config_json) {
+        Ok((config, _)) => config,
+        Err(e) => {
+            utils::reply_error(
+                ctx,
+                tr!(
+                    lang,
+                    "**Error:** The configuration file could not be parsed: {}.",
+                    "**Error:** No se pudo interpretar el fichero de configuración: {}.",
+                    e
+                ),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[botconfig update] Failed to send error message for invalid configuration \
+                    file for guild {}: {:?}",
+                    gid, e
+                )
+            });
+
+            return Ok(());
+        }
+    };
+
+    // The team prefix can only change along with the existing teams (see `team_prefix`):
+    let old_prefix = utils::load_config(&ctx.data().caches, &gid).team_prefix;
+    if config.team_prefix != old_prefix && !team::get_all_teams(&gid).is_empty() {
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "**Error:** The new configuration changes the team prefix (`{}` to `{}`), but there are already teams in this server. Keep the current prefix, and use `/botconfig team_prefix` to change it.",
+                "**Error:** La nueva configuración cambia el prefijo de los equipos (`{}` a `{}`), pero ya hay equipos en este servidor. Mantén el prefijo actual, y usa `/botconfig team_prefix` para cambiarlo.",
+                old_prefix,
+                config.team_prefix
+            ),
         )
-        .as_str(),
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig update] Failed to send error message for a team prefix change for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Update and save the new configuration:
+    utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+    events::record_notable(
+        &ctx.data().feed,
+        &gid,
+        Event::ConfigChanged {
+            user: Some(ctx.author().id),
+            field: None,
+            value: serde_json::to_value(&config).unwrap_or_default(),
+        },
+    );
+    team::propagate_prefix(&gid, &config);
+
+    // Reply to the user, as confirmation:
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "The configuration has been updated! New configuration:\n```json\n{}\n```",
+            "¡Se ha actualizado la configuración! Nueva configuración:\n```json\n{}\n```",
+            serde_json::to_string_pretty(&config).unwrap_or_else(|e| {
+                panic!(
+                    "[botconfig] Failed to serialize the config for guild {}: {:?}",
+                    gid, e
+                )
+            })
+        ),
     )
     .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send confirmation of Tablón URL change for guild {}.",
-            gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[botconfig update] Failed to send confirmation of configuration update for guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
 
+/// Modal for entering the admin-level Tablón credentials, so they are not part of the command's
+/// invocation (which gets logged).
+#[derive(poise::Modal)]
+#[name = "Tablón admin credentials"]
+struct TablonAdminModal {
+    #[name = "User"]
+    #[placeholder = "Admin user for Tablón"]
+    user: String,
+    #[name = "Password"]
+    #[placeholder = "Admin password for Tablón"]
+    password: String,
+}
+
 #[poise::command(
     slash_command,
     ephemeral,
-    description_localized("en-US", "Change the capacity for teams on this server."),
-    description_localized("es-ES", "Change the capacity for teams on this server.")
+    description_localized(
+        "en-US",
+        "Set the admin credentials for Tablón, used for privileged operations."
+    ),
+    description_localized(
+        "es-ES",
+        "Establecer las credenciales de administración de Tablón, para operaciones privilegiadas."
+    )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn team_capacity(
-    ctx: Context<'_>,
-    #[description = "The new capacity for teams."] capacity: u8,
-) -> Result<(), Error> {
+pub async fn tablon_admin(ctx: poise::ApplicationContext<'_, Data, Error>) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
 
-    // Update the configuration:
-    config.team_capacity = capacity;
-    utils::update_config_persistence(&config, &gid);
+    // Ask for the credentials:
+    let Some(modal) = poise::execute_modal(ctx, None::<TablonAdminModal>, None).await? else {
+        // Timed out; nothing to do.
+        return Ok(());
+    };
+
+    // Update the secrets:
+    let mut secrets = utils::load_secrets(&gid);
+    secrets.tablon_admin = Some(Credentials {
+        team: modal.user,
+        password: Some(modal.password),
+    });
+    utils::update_secrets_persistence(&secrets, &gid);
 
     // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "The team capacity has been changed to {}.",
-            config.team_capacity
-        )
-        .as_str(),
+    utils::reply(
+        ctx.into(),
+        tr!(
+            lang,
+            "The admin credentials for Tablón have been updated.",
+            "Se han actualizado las credenciales de administrador de Tablón."
+        ),
     )
     .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send confirmation of team capacity change for guild {}.",
-            gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of Tablón admin credentials change for \
+                guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
@@ -197,42 +345,41 @@ pub async fn team_capacity(
 #[poise::command(
     slash_command,
     ephemeral,
-    description_localized("en-US", "Change the prefix for the IDs of the teams of this guild."),
-    description_localized("es-ES", "Change the prefix for the IDs of the teams of this guild.")
+    description_localized("en-US", "Remove the admin credentials for Tablón."),
+    description_localized("es-ES", "Eliminar las credenciales de administración de Tablón.")
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn team_prefix(
-    ctx: Context<'_>,
-    #[description = "The new prefix for team IDs."] prefix: String,
-) -> Result<(), Error> {
+pub async fn clear_tablon_admin(ctx: Context<'_>) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
-
-    // Update the configuration:
-    config.team_prefix = prefix.clone();
-    utils::update_config_persistence(&config, &gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
 
-    // Propagate the update to the corresponding team guild configuration:
-    if let Some(mut guild_team_config) = team::get_guild_team_info(&gid) {
-        guild_team_config.update_prefix(prefix);
-    }
+    // Update the secrets:
+    let mut secrets = utils::load_secrets(&gid);
+    let had_credentials = secrets.tablon_admin.take().is_some();
+    utils::update_secrets_persistence(&secrets, &gid);
 
     // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "The team prefix has been changed to {}.",
-            config.team_prefix
+    let reply = if had_credentials {
+        tr!(
+            lang,
+            "The admin credentials for Tablón have been removed.",
+            "Se han eliminado las credenciales de administrador de Tablón."
         )
-        .as_str(),
-    )
-    .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send confirmation of team prefix change for guild {}.",
-            gid
+    } else {
+        tr!(
+            lang,
+            "There were no admin credentials for Tablón set.",
+            "No había credenciales de administrador de Tablón establecidas."
         )
-        .as_str(),
-    );
+    };
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of Tablón admin credentials removal for \
+            guild {}: {:?}",
+            gid, e
+        )
+    });
 
     Ok(())
 }
@@ -242,79 +389,274 @@ pub async fn team_prefix(
     ephemeral,
     description_localized(
         "en-US",
-        "Change the guild's channel for usage of special admin-only bot functionalities."
+        "Generate a new token for Tablón to notify this server's results, or revoke it."
     ),
     description_localized(
         "es-ES",
-        "Change the guild's channel for usage of special admin-only bot functionalities."
+        "Generar un token nuevo para que Tablón notifique los resultados de este servidor, o revocarlo."
     )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn bot_channel(
+pub async fn webhook_token(
     ctx: Context<'_>,
-    #[description = "The the new channel for admin bot usage."] channel: GuildChannel,
+    #[description = "Whether to revoke the current token, instead of generating a new one."]
+    revoke: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
 
-    // Update the configuration:
-    config.bot_channel = channel.name;
-    utils::update_config_persistence(&config, &gid);
+    // Update the secrets:
+    let mut secrets = utils::load_secrets(&gid);
+    let reply = if revoke.unwrap_or(false) {
+        secrets.webhook_token = None;
+        tr!(
+            lang,
+            "The token for Tablón's result notifications has been revoked.",
+            "Se ha revocado el token para las notificaciones de resultados de Tablón."
+        )
+    } else {
+        let token = http::generate_token();
+        secrets.webhook_token = Some(token.clone());
+        tr!(
+            lang,
+            "New token for Tablón's result notifications (the previous one no longer works):\n\
+            ||`{}`||\n\
+            Tablón must send them to `/tablon/{}/results`, with an `Authorization: Bearer <token>` \
+            header.",
+            "Nuevo token para las notificaciones de resultados de Tablón (el anterior ya no \
+            funciona):\n||`{}`||\n\
+            Tablón debe enviarlas a `/tablon/{}/results`, con una cabecera `Authorization: Bearer \
+            <token>`.",
+            token,
+            gid
+        )
+    };
+    utils::update_secrets_persistence(&secrets, &gid);
 
     // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "The bot channel has been changed to #{}.",
-            config.bot_channel
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of webhook token change for guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Generate a new token for external tools to use this server's admin API, or revoke it."
+    ),
+    description_localized(
+        "es-ES",
+        "Generar un token nuevo para usar la API de administración de este servidor, o revocarlo."
     )
-    .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send confirmation of bot channel change for guild {}.",
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn api_token(
+    ctx: Context<'_>,
+    #[description = "Whether to revoke the current token, instead of generating a new one."]
+    revoke: Option<bool>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+
+    // Update the secrets:
+    let mut secrets = utils::load_secrets(&gid);
+    let reply = if revoke.unwrap_or(false) {
+        secrets.api_token = None;
+        tr!(
+            lang,
+            "The token for the admin API has been revoked.",
+            "Se ha revocado el token para la API de administración."
+        )
+    } else {
+        let token = http::generate_token();
+        secrets.api_token = Some(token.clone());
+        tr!(
+            lang,
+            "New token for the admin API (the previous one no longer works):\n||`{}`||\n\
+            Its endpoints are under `/api/guilds/{}/`, and require an `Authorization: Bearer \
+            <token>` header.",
+            "Nuevo token para la API de administración (el anterior ya no funciona):\n||`{}`||\n\
+            Sus endpoints están bajo `/api/guilds/{}/`, y requieren una cabecera `Authorization: \
+            Bearer <token>`.",
+            token,
             gid
         )
-        .as_str(),
-    );
+    };
+    utils::update_secrets_persistence(&secrets, &gid);
+
+    // Reply to the user, as confirmation:
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of API token change for guild {}: {:?}",
+            gid, e
+        )
+    });
 
     Ok(())
 }
 
+/// Autocompletes the names of the work-in-progress commands.
+async fn autocomplete_wip<'a>(
+    _ctx: Context<'a>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    registry::wip_names()
+        .into_iter()
+        .filter(move |name| name.starts_with(partial))
+}
+
 #[poise::command(
     slash_command,
     ephemeral,
-    description_localized("en-US", "Change the guild's channel for Tablón's leaderboard."),
-    description_localized("es-ES", "Change the guild's channel for Tablón's leaderboard.")
+    description_localized(
+        "en-US",
+        "Enable or disable a work-in-progress command in this server, or list them."
+    ),
+    description_localized(
+        "es-ES",
+        "Activar o desactivar un comando en desarrollo en este servidor, o listarlos."
+    )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn lb_channel(
+pub async fn wip_commands(
     ctx: Context<'_>,
-    #[description = "The new channel for the leaderboard."] channel_name: GuildChannel,
+    #[description = "The WIP command to enable or disable. Leave empty to list them."]
+    #[autocomplete = "autocomplete_wip"]
+    command: Option<String>,
+    #[description = "Whether to enable the command (default: true)."] enabled: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+    let mut config = utils::load_config(&ctx.data().caches, &gid);
 
-    // Update the configuration:
-    config.lb_channel = channel_name.name;
-    utils::update_config_persistence(&config, &gid);
+    let Some(command) = command else {
+        // List the WIP commands, and whether they are enabled:
+        let mut list = String::new();
+        for name in registry::wip_names() {
+            let status = if registry::is_enabled(&name, &config) {
+                tr!(lang, "enabled", "activado")
+            } else {
+                tr!(lang, "disabled", "desactivado")
+            };
+            list.push_str(format!("- `/{}`: {}\n", name, status).as_str());
+        }
+        if list.is_empty() {
+            list.push_str(&tr!(
+                lang,
+                "There are no work-in-progress commands.",
+                "No hay comandos en desarrollo."
+            ));
+        } else if registry::wip_enabled_globally() {
+            list.push_str(&tr!(
+                lang,
+                "\n(This build enables every work-in-progress command.)",
+                "\n(Esta versión activa todos los comandos en desarrollo.)"
+            ));
+        }
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "Work-in-progress commands:\n{}",
+                "Comandos en desarrollo:\n{}",
+                list
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send the list of WIP commands for guild {}: {:?}",
+                gid, e
+            )
+        });
 
-    // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "The leaderboard channel has been changed to #{}.",
-            config.lb_channel
+        return Ok(());
+    };
+
+    if !registry::wip_names().contains(&command) {
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "`/{}` is not a work-in-progress command.",
+                "`/{}` no es un comando en desarrollo.",
+                command
+            ),
         )
-        .as_str(),
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send error message for unknown WIP command in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Update and save the configuration:
+    let enabled = enabled.unwrap_or(true);
+    config.wip_commands.retain(|name| *name != command);
+    if enabled {
+        config.wip_commands.push(command.clone());
+    }
+    utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+    events::record_notable(
+        &ctx.data().feed,
+        &gid,
+        Event::ConfigChanged {
+            user: Some(ctx.author().id),
+            field: Some("wip_commands".to_string()),
+            value: serde_json::to_value(&config.wip_commands).unwrap_or_default(),
+        },
+    );
+
+    // Update the guild's registered commands:
+    registry::register_in_guild(ctx, &gid, &config)
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to register the guild commands for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+    // Reply to the user, as confirmation:
+    utils::reply(
+        ctx,
+        if enabled {
+            tr!(
+                lang,
+                "`/{}` has been enabled in this server.",
+                "Se ha activado `/{}` en este servidor.",
+                command
+            )
+        } else {
+            tr!(
+                lang,
+                "`/{}` has been disabled in this server.",
+                "Se ha desactivado `/{}` en este servidor.",
+                command
+            )
+        },
     )
     .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send confirmation of leaderboard channel change for guild {}.",
-            gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of WIP command change for guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
@@ -324,120 +666,573 @@ pub async fn lb_channel(
     ephemeral,
     description_localized(
         "en-US",
-        "Change whether to notify leaders of leaderboard position changes."
+        "Show the last entries of the server's activity log, optionally filtered."
     ),
     description_localized(
         "es-ES",
-        "Change whether to notify leaders of leaderboard position changes."
+        "Mostrar las últimas entradas del registro de actividad del servidor, opcionalmente filtradas."
     )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn notify_leaders(
+pub async fn logs(
     ctx: Context<'_>,
-    #[description = "Whether to notify leaders."] notify: bool,
+    #[description = "Number of entries to show (default: 20)."]
+    #[min = 1]
+    #[max = 1000]
+    count: Option<usize>,
+    #[description = "Only show the commands used by this user."] user: Option<serenity::User>,
+    #[description = "Only show the commands used by the members of this team (e.g. g04)."]
+    team: Option<String>,
+    #[description = "Only show the commands used on this date (YYYY-MM-DD)."] date: Option<String>,
+    #[description = "Send the entries as a file, instead of a message."] attachment: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+    let config = utils::load_config(&ctx.data().caches, &gid);
 
-    // Update the configuration:
-    config.notify_leaders = notify;
-    utils::update_config_persistence(&config, &gid);
+    // Prepare the filters:
+    let members = match &team {
+        Some(team_id) => {
+            let Some(team) = team::get_team(&gid, team_id) else {
+                utils::reply_error(
+                    ctx,
+                    tr!(
+                        lang,
+                        "There is no team `{}` in this server.",
+                        "No hay ningún equipo `{}` en este servidor.",
+                        team_id
+                    ),
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[botconfig] Failed to send error message for unknown team in guild \
+                            {}: {:?}",
+                        gid, e
+                    )
+                });
 
-    // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "Leaderboard position notifications have been set to {}.",
-            config.notify_leaders
+                return Ok(());
+            };
+            Some(team.members().clone())
+        }
+        None => None,
+    };
+    let day = match &date {
+        Some(date) => {
+            let Some(start) = utils::parse_date(&config, date) else {
+                utils::reply_error(
+                    ctx,
+                    tr!(
+                        lang,
+                        "`{}` is not a valid date; use the YYYY-MM-DD format.",
+                        "`{}` no es una fecha válida; usa el formato AAAA-MM-DD.",
+                        date
+                    ),
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[botconfig] Failed to send error message for invalid date in guild {}: {:?}",
+                        gid, e
+                    )
+                });
+
+                return Ok(());
+            };
+            Some(start..start + 24 * 3600)
+        }
+        None => None,
+    };
+
+    // Collect the last matching entries:
+    let entries = activitylog::load(&gid);
+    let mut matching = entries
+        .iter()
+        .rev()
+        .filter(|e| user.as_ref().is_none_or(|user| e.user() == user.id))
+        .filter(|e| members.as_ref().is_none_or(|m| m.contains(&e.user())))
+        .filter(|e| day.as_ref().is_none_or(|day| day.contains(&e.timestamp())))
+        .take(count.unwrap_or(DEFAULT_LOG_ENTRIES))
+        .collect::<Vec<_>>();
+    matching.reverse();
+
+    if matching.is_empty() {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "There are no matching entries in the activity log.",
+                "No hay entradas que cumplan los filtros en el registro de actividad."
+            ),
         )
-        .as_str(),
-    )
-    .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send confirmation of leaderboard position notifications change for guild {}.",
-            gid
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send empty log query result for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    }
+
+    let mut listing = String::new();
+    for entry in matching.iter() {
+        writeln!(
+            &mut listing,
+            "{} | {} | {} | {} | {} ms",
+            utils::format_unix_timestamp(&config, i18n::guild_language(&config), entry.timestamp()),
+            entry.user(),
+            entry.invocation(),
+            if entry.success() { "ok" } else { "failed" },
+            entry.duration_ms()
         )
-        .as_str(),
-    );
+        .unwrap();
+    }
+
+    // Reply with the entries, as a message if they fit:
+    let reply = if attachment.unwrap_or(false) || listing.len() > MAX_LOG_MESSAGE_LEN {
+        CreateReply::default()
+            .content(tr!(
+                lang,
+                "Last {} matching log entries:",
+                "Últimas {} entradas del registro que cumplen los filtros:",
+                matching.len()
+            ))
+            .attachment(serenity::CreateAttachment::bytes(
+                listing.into_bytes(),
+                format!("activity_{}.log", gid),
+            ))
+    } else {
+        CreateReply::default().content(tr!(
+            lang,
+            "Last {} matching log entries:\n```\n{}```",
+            "Últimas {} entradas del registro que cumplen los filtros:\n```\n{}```",
+            matching.len(),
+            listing
+        ))
+    };
+    utils::send(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send the log query result for guild {}: {:?}",
+            gid, e
+        )
+    });
 
     Ok(())
 }
 
+/// Autocompletes the qualified names of the bot's commands.
+async fn autocomplete_qualified_name<'a>(
+    ctx: Context<'a>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    let mut names = Vec::new();
+    for cmd in ctx.framework().options().commands.iter() {
+        names.push(cmd.qualified_name.clone());
+        names.extend(cmd.subcommands.iter().map(|sub| sub.qualified_name.clone()));
+    }
+
+    names
+        .into_iter()
+        .filter(move |name| name.starts_with(partial.trim_start_matches('/')))
+        .take(25)
+}
+
+// Note that Discord still hides the commands with `default_member_permissions` (e.g. the admin
+// ones) from members lacking those permissions, unless the server's integration settings allow
+// them; the overrides set here only restrict who can run each command.
 #[poise::command(
     slash_command,
     ephemeral,
     description_localized(
         "en-US",
-        "Change the number of teams to count as \"leaders\" for position update notifications."
+        "Restrict which roles can run a command in this server, or list the current restrictions."
     ),
     description_localized(
         "es-ES",
-        "Change the number of teams to count as \"leaders\" for position update notifications."
+        "Restringir qué roles pueden usar un comando en este servidor, o listar las restricciones."
     )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn leader_count(
+pub async fn permissions(
     ctx: Context<'_>,
-    #[description = "The number leaders."] count: u8,
+    #[description = "The command (e.g. \"teamdump\"). Leave empty to list the restrictions."]
+    #[autocomplete = "autocomplete_qualified_name"]
+    command: Option<String>,
+    #[description = "The role to allow or disallow. Leave empty to remove the command's restriction."]
+    role: Option<serenity::Role>,
+    #[description = "Whether to allow the role to run the command (default: true)."]
+    allowed: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+    let mut config = utils::load_config(&ctx.data().caches, &gid);
 
-    // Update the configuration:
-    config.leader_count = count;
-    utils::update_config_persistence(&config, &gid);
+    let Some(command) = command else {
+        // List the current permission overrides:
+        let mut list = String::new();
+        for (name, roles) in config.permissions.iter() {
+            let roles = roles
+                .iter()
+                .map(|role| format!("<@&{}>", role))
+                .collect::<Vec<_>>()
+                .join(", ");
+            list.push_str(format!("- `/{}`: {}\n", name, roles).as_str());
+        }
+        if list.is_empty() {
+            list.push_str(&tr!(
+                lang,
+                "There are no restrictions: every command follows Discord's permissions.",
+                "No hay restricciones: todos los comandos siguen los permisos de Discord."
+            ));
+        }
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "Command permission overrides:\n{}",
+                "Restricciones de permisos de los comandos:\n{}",
+                list
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send the permission overrides for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    };
+
+    let command = command.trim().trim_start_matches('/').to_string();
+    let exists = ctx.framework().options().commands.iter().any(|cmd| {
+        cmd.qualified_name == command
+            || cmd
+                .subcommands
+                .iter()
+                .any(|sub| sub.qualified_name == command)
+    });
+    if !exists {
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "There is no command named `/{}`.",
+                "No hay ningún comando llamado `/{}`.",
+                command
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send error message for unknown command in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Update and save the configuration:
+    let reply = match role {
+        Some(role) => {
+            let allowed = allowed.unwrap_or(true);
+            let roles = config.permissions.entry(command.clone()).or_default();
+            roles.retain(|id| *id != role.id);
+            if allowed {
+                roles.push(role.id);
+            }
+            if roles.is_empty() {
+                config.permissions.remove(&command);
+            }
+            if allowed {
+                tr!(
+                    lang,
+                    "<@&{}> is now allowed to run `/{}`.",
+                    "<@&{}> ya puede usar `/{}`.",
+                    role.id,
+                    command
+                )
+            } else {
+                tr!(
+                    lang,
+                    "<@&{}> is no longer allowed to run `/{}`.",
+                    "<@&{}> ya no puede usar `/{}`.",
+                    role.id,
+                    command
+                )
+            }
+        }
+        None => {
+            config.permissions.remove(&command);
+            tr!(
+                lang,
+                "`/{}` is no longer restricted to any roles.",
+                "`/{}` ya no está restringido a ningún rol.",
+                command
+            )
+        }
+    };
+    utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+    events::record_notable(
+        &ctx.data().feed,
+        &gid,
+        Event::ConfigChanged {
+            user: Some(ctx.author().id),
+            field: Some("permissions".to_string()),
+            value: serde_json::to_value(&config.permissions).unwrap_or_default(),
+        },
+    );
 
     // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "The number of teams to count as \"leaders\" for position update notifications has been set to {}.",
-            config.leader_count
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of a permission override in guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Set whether a command's replies are public or ephemeral, or list the current settings."
+    ),
+    description_localized(
+        "es-ES",
+        "Elegir si las respuestas de un comando son públicas o efímeras, o listar los ajustes actuales."
     )
-        .await.expect(
-        format!(
-            "[botconfig] Failed to send confirmation of leader count change for guild {}.",
-            gid
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn visibility(
+    ctx: Context<'_>,
+    #[description = "The command (e.g. \"team create\"). Leave empty to list the settings."]
+    #[autocomplete = "autocomplete_qualified_name"]
+    command: Option<String>,
+    #[description = "The visibility of its replies. Leave empty to use the command's default."]
+    visibility: Option<Visibility>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+    let mut config = utils::load_config(&ctx.data().caches, &gid);
+
+    let Some(command) = command else {
+        // List the current visibility overrides:
+        let mut list = String::new();
+        for (name, visibility) in config.reply_visibility.iter() {
+            list.push_str(format!("- `/{}`: {}\n", name, visibility.name()).as_str());
+        }
+        if list.is_empty() {
+            list.push_str(&tr!(
+                lang,
+                "There are no overrides: every command uses its default visibility.",
+                "No hay ajustes: todos los comandos usan su visibilidad por defecto."
+            ));
+        }
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "Reply visibility overrides:\n{}",
+                "Ajustes de visibilidad de las respuestas:\n{}",
+                list
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send the visibility overrides for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    };
+
+    let command = command.trim().trim_start_matches('/').to_string();
+    let exists = ctx.framework().options().commands.iter().any(|cmd| {
+        cmd.qualified_name == command
+            || cmd
+                .subcommands
+                .iter()
+                .any(|sub| sub.qualified_name == command)
+    });
+    if !exists {
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "There is no command named `/{}`.",
+                "No hay ningún comando llamado `/{}`.",
+                command
+            ),
         )
-        .as_str(),
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send error message for unknown command in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Update and save the configuration:
+    let reply = match visibility {
+        Some(visibility) => {
+            config.reply_visibility.insert(command.clone(), visibility);
+            tr!(
+                lang,
+                "The replies of `/{}` are now {}.",
+                "Las respuestas de `/{}` son ahora {}.",
+                command,
+                visibility.name()
+            )
+        }
+        None => {
+            config.reply_visibility.remove(&command);
+            tr!(
+                lang,
+                "The replies of `/{}` now use its default visibility.",
+                "Las respuestas de `/{}` usan ahora su visibilidad por defecto.",
+                command
+            )
+        }
+    };
+    utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+    events::record_notable(
+        &ctx.data().feed,
+        &gid,
+        Event::ConfigChanged {
+            user: Some(ctx.author().id),
+            field: Some("reply_visibility".to_string()),
+            value: serde_json::to_value(&config.reply_visibility).unwrap_or_default(),
+        },
     );
 
+    // Reply to the user, as confirmation:
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of a visibility override in guild {}: {:?}",
+            gid, e
+        )
+    });
+
     Ok(())
 }
 
 #[poise::command(
     slash_command,
     ephemeral,
-    description_localized("en-US", "Change whether to notify the leaderboard changes publicly."),
-    description_localized("es-ES", "Change whether to notify the leaderboard changes publicly.")
+    description_localized(
+        "en-US",
+        "Set the role of the best team on an exercise (queue), or list the current ones."
+    ),
+    description_localized(
+        "es-ES",
+        "Establecer el rol del mejor equipo en un ejercicio (cola), o listar los actuales."
+    )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn public_notify(
+pub async fn lb_champion_role(
     ctx: Context<'_>,
-    #[description = "Whether to do public notifications."] public_notify: bool,
+    #[description = "The exercise (i.e. Tablón's queue). Leave empty to list the roles."]
+    exercise: Option<String>,
+    #[description = "The role for its best team. Leave empty to stop giving it."] role: Option<
+        serenity::Role,
+    >,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+    let mut config = utils::load_config(&ctx.data().caches, &gid);
 
-    // Update the configuration:
-    config.public_notify = public_notify;
-    utils::update_config_persistence(&config, &gid);
+    let Some(exercise) = exercise else {
+        // List the current champion roles:
+        let mut list = String::new();
+        for (exercise, role) in config.lb_champion_roles.iter() {
+            list.push_str(format!("- `{}`: <@&{}>\n", exercise, role).as_str());
+        }
+        if list.is_empty() {
+            list.push_str(&tr!(
+                lang,
+                "There are no champion roles.",
+                "No hay roles de campeón."
+            ));
+        }
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "Champion roles of each exercise:\n{}",
+                "Roles de campeón de cada ejercicio:\n{}",
+                list
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send the champion roles for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    };
+
+    // Update and save the configuration:
+    let exercise = exercise.trim().to_string();
+    let reply = match role {
+        Some(role) => {
+            config.lb_champion_roles.insert(exercise.clone(), role.id);
+            tr!(
+                lang,
+                "The best team on `{}` will get the role <@&{}>.",
+                "El mejor equipo en `{}` recibirá el rol <@&{}>.",
+                exercise,
+                role.id
+            )
+        }
+        None => {
+            config.lb_champion_roles.remove(&exercise);
+            tr!(
+                lang,
+                "The best team on `{}` will no longer get a role.",
+                "El mejor equipo en `{}` ya no recibirá ningún rol.",
+                exercise
+            )
+        }
+    };
+    utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+    events::record_notable(
+        &ctx.data().feed,
+        &gid,
+        Event::ConfigChanged {
+            user: Some(ctx.author().id),
+            field: Some("lb_champion_roles".to_string()),
+            value: serde_json::to_value(&config.lb_champion_roles).unwrap_or_default(),
+        },
+    );
 
     // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "Leaderboard position notifications have been set to {}.",
-            config.public_notify
-        )
-        .as_str(),
-    ).await.expect(
-        format!(
-            "[botconfig] Failed to send confirmation of public leaderboard position notifications change for guild {}.",
-            gid
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of a champion role in guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
@@ -447,41 +1242,231 @@ pub async fn public_notify(
     ephemeral,
     description_localized(
         "en-US",
-        "Change the channel for bot news (e.g. public position update notifications)."
+        "Set the client for the programs with a file extension, or list the current ones."
     ),
     description_localized(
         "es-ES",
-        "Change the channel for bot news (e.g. public position update notifications)."
+        "Establecer el cliente para los programas con una extensión, o listar los actuales."
     )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn bot_news_channel(
+pub async fn client_extension(
     ctx: Context<'_>,
-    #[description = "The new channel for bot news."] channel: GuildChannel,
+    #[description = "The file extension (e.g. \"py\"). Leave empty to list the clients."]
+    extension: Option<String>,
+    #[description = "The client's name, in the guild's clients directory. Leave empty to use the default one."]
+    client: Option<String>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+    let mut config = utils::load_config(&ctx.data().caches, &gid);
 
-    // Update the configuration:
-    config.bot_news_channel = channel.name;
-    utils::update_config_persistence(&config, &gid);
+    let Some(extension) = extension else {
+        // List the clients, and the extensions using them:
+        let mut list = String::new();
+        for (extension, client) in config.client_extensions.iter() {
+            list.push_str(format!("- `.{}`: `{}`\n", extension, client).as_str());
+        }
+        if list.is_empty() {
+            list.push_str(&tr!(
+                lang,
+                "Every program uses the default client.\n",
+                "Todos los programas usan el cliente por defecto.\n"
+            ));
+        }
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "Clients of each file extension:\n{}Available clients: {}",
+                "Clientes de cada extensión de fichero:\n{}Clientes disponibles: {}",
+                list,
+                request::guild_clients(&gid).join(", ")
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send the clients of each extension for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    };
+
+    if let Some(client) = client
+        .as_ref()
+        .filter(|client| !request::guild_clients(&gid).contains(client))
+    {
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "**Error:** There is no client named `{}` in `guilds/{}/clients/`.",
+                "**Error:** No hay ningún cliente llamado `{}` en `guilds/{}/clients/`.",
+                client,
+                gid
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send error message for unknown client in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Update and save the configuration:
+    let extension = extension.trim().trim_start_matches('.').to_lowercase();
+    let reply = match client {
+        Some(client) => {
+            config
+                .client_extensions
+                .insert(extension.clone(), client.clone());
+            tr!(
+                lang,
+                "The programs ending in `.{}` will be sent with the client `{}`.",
+                "Los programas terminados en `.{}` se enviarán con el cliente `{}`.",
+                extension,
+                client
+            )
+        }
+        None => {
+            config.client_extensions.remove(&extension);
+            tr!(
+                lang,
+                "The programs ending in `.{}` will be sent with the default client.",
+                "Los programas terminados en `.{}` se enviarán con el cliente por defecto.",
+                extension
+            )
+        }
+    };
+    utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+    events::record_notable(
+        &ctx.data().feed,
+        &gid,
+        Event::ConfigChanged {
+            user: Some(ctx.author().id),
+            field: Some("client_extensions".to_string()),
+            value: serde_json::to_value(&config.client_extensions).unwrap_or_default(),
+        },
+    );
 
     // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "The bot news channel has been changed to #{}.",
-            config.bot_news_channel
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of a client extension in guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Check the bot has the permissions it needs in the configured channels."
+    ),
+    description_localized(
+        "es-ES",
+        "Comprobar que el bot tiene los permisos que necesita en los canales configurados."
+    )
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn audit_permissions(ctx: Context<'_>) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+    let config = utils::load_config(&ctx.data().caches, &gid);
+
+    // Permissions needed in each configured channel:
+    let common = serenity::Permissions::VIEW_CHANNEL | serenity::Permissions::SEND_MESSAGES;
+    let checks = [
+        (
+            "bot_channel",
+            &config.bot_channel,
+            common | serenity::Permissions::EMBED_LINKS | serenity::Permissions::ATTACH_FILES,
+        ),
+        (
+            "lb_channel",
+            &config.lb_channel,
+            common | serenity::Permissions::EMBED_LINKS,
+        ),
+        ("bot_news_channel", &config.bot_news_channel, common),
+    ];
+
+    let channels = gid.channels(ctx.http()).await?;
+    let bot = gid.member(ctx.http(), ctx.framework().bot_id).await?;
+
+    // Compute the bot's permissions (the guild is only borrowed from the cache in this block, as it
+    // cannot be held across awaits):
+    let mut report = String::new();
+    let mut problems = 0;
+    {
+        let Some(guild) = ctx.guild() else {
+            return Err("The guild is not in the cache.".into());
+        };
+        for (field, name, needed) in checks {
+            let Some(channel) = channels.values().find(|channel| channel.name == *name) else {
+                problems += 1;
+                report.push_str(
+                    tr!(
+                        lang,
+                        "❌ `{}`: there is no channel named #{}.\n",
+                        "❌ `{}`: no hay ningún canal llamado #{}.\n",
+                        field,
+                        name
+                    )
+                    .as_str(),
+                );
+                continue;
+            };
+
+            let missing = needed - guild.user_permissions_in(channel, &bot);
+            if missing.is_empty() {
+                report.push_str(format!("✅ `{}` (<#{}>): OK.\n", field, channel.id).as_str());
+            } else {
+                problems += 1;
+                report.push_str(
+                    tr!(
+                        lang,
+                        "❌ `{}` (<#{}>): missing {}.\n",
+                        "❌ `{}` (<#{}>): faltan {}.\n",
+                        field,
+                        channel.id,
+                        missing.get_permission_names().join(", ")
+                    )
+                    .as_str(),
+                );
+            }
+        }
+    }
+
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Permission audit ({} problem(s) found):\n{}",
+            "Auditoría de permisos ({} problema(s) encontrado(s)):\n{}",
+            problems,
+            report
+        ),
     )
     .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send confirmation of bot news channel change for guild {}.",
-            gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send the permission audit for guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
@@ -491,41 +1476,118 @@ pub async fn bot_news_channel(
     ephemeral,
     description_localized(
         "en-US",
-        "Change the separator for multi-field columns in leaderboards."
+        "Change the prefix for the IDs of the teams of this guild, renaming the existing teams."
     ),
     description_localized(
         "es-ES",
-        "Change the separator for multi-field columns in leaderboards."
+        "Cambiar el prefijo de los IDs de los equipos del servidor, renombrando los existentes."
     )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn column_separator(
+pub async fn team_prefix(
     ctx: Context<'_>,
-    #[description = "The new separator."] separator: String,
+    #[description = "The new prefix. Leave empty to show the current one."] value: Option<String>,
+    #[description = "Rename the existing teams to the new prefix (required if there are any)."]
+    migrate: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut config = utils::load_config(&gid);
+    let mut config = utils::load_config(&ctx.data().caches, &gid);
+    let lang = i18n::guild_language(&config);
+
+    // Show the current value, if no new value was given:
+    let Some(value) = value else {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "`team_prefix` is currently set to `{}`.",
+                "`team_prefix` tiene actualmente el valor `{}`.",
+                config.team_prefix
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send the current `team_prefix` for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    };
+    let value = value.trim().to_string();
+
+    // Check the new prefix, and whether the existing teams must be renamed:
+    let team_count = team::get_all_teams(&gid).len();
+    let error = if let Err(msg) = utils::validate_team_prefix(&value) {
+        Some(msg)
+    } else if value == config.team_prefix {
+        Some(tr!(
+            lang,
+            "`team_prefix` is already set to `{}`.",
+            "`team_prefix` ya tiene el valor `{}`.",
+            value
+        ))
+    } else if team_count > 0 && !migrate.unwrap_or(false) {
+        Some(tr!(
+            lang,
+            "There are already {} teams in this server, with identifiers starting with `{}`. Set `migrate` to rename all of them to the new prefix (e.g. {} would become {}).",
+            "Ya hay {} equipos en este servidor, con identificadores que empiezan por `{}`. Activa `migrate` para renombrarlos todos con el nuevo prefijo (p. ej. {} pasaría a ser {}).",
+            team_count,
+            config.team_prefix,
+            format!("{}01", config.team_prefix),
+            format!("{}01", value)
+        ))
+    } else {
+        // Also renames the passwords of the teams yet to be created:
+        team::migrate_prefix(&ctx.data().caches, &gid, &value).err()
+    };
+    if let Some(msg) = error {
+        utils::reply_error(ctx, format!("**Error:** {}", msg))
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[botconfig] Failed to send error message for invalid `team_prefix` for guild {}: {:?}",
+                    gid, e
+                )
+            });
+
+        return Ok(());
+    }
 
     // Update the configuration:
-    config.column_separator = separator;
-    utils::update_config_persistence(&config, &gid);
+    config.team_prefix = value;
+    utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+    events::record_notable(
+        &ctx.data().feed,
+        &gid,
+        Event::ConfigChanged {
+            user: Some(ctx.author().id),
+            field: Some("team_prefix".to_string()),
+            value: serde_json::to_value(&config.team_prefix).unwrap_or_default(),
+        },
+    );
+    team::propagate_prefix(&gid, &config);
 
     // Reply to the user, as confirmation:
-    ctx.reply(
-        format!(
-            "The column separator has been changed to {}.",
-            config.column_separator
-        )
-        .as_str(),
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "`team_prefix` has been changed to `{}`, and {} teams have been renamed.",
+            "`team_prefix` se ha cambiado a `{}`, y se han renombrado {} equipos.",
+            config.team_prefix,
+            team_count
+        ),
     )
     .await
-    .expect(
-        format!(
-            "[botconfig] Failed to send confirmation of column separator change for guild {}.",
-            gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[botconfig] Failed to send confirmation of `team_prefix` change for guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
@@ -535,86 +1597,149 @@ pub async fn column_separator(
     ephemeral,
     description_localized(
         "en-US",
-        "Update the configuration for the bot with the provided or a default file."
+        "Set the message for the new members of this guild, or show the current one."
     ),
     description_localized(
         "es-ES",
-        "Update the configuration for the bot with the provided or a default file."
+        "Establecer el mensaje para los nuevos miembros del servidor, o mostrar el actual."
     )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
-pub async fn update(
+pub async fn welcome_message(
     ctx: Context<'_>,
-    #[description = "JSON configuration file with the new configuration."] file: Option<
-        serenity::Attachment,
+    #[description = "The message, with {user}, {guild}, {bot_channel}, {team_capacity}, {team_prefix} and \\n."]
+    message: Option<String>,
+    #[description = "Send the message to this channel, instead of by DM."] channel: Option<
+        serenity::GuildChannel,
     >,
+    #[description = "Send the message by DM again, instead of to a channel."] dm: Option<bool>,
+    #[description = "Stop sending a message to the new members."] disable: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
+    let mut config = utils::load_config(&ctx.data().caches, &gid);
+    let lang = i18n::guild_language(&config);
+    let previous = (
+        config.welcome_message.clone(),
+        config.welcome_channel.clone(),
+    );
 
-    // Read the config JSON - Attachment, or default file:
-    let config_json = if let Some(config_file) = file {
-        // Handle attachement:
-        reqwest::get(&config_file.url)
-            .await
-            .expect(
-                format!(
-                    "[botconfig update] Could not download the config file from URL: {}",
-                    config_file.url
-                )
-                .as_str(),
+    // Describes where the message is sent, and previews it for the user:
+    let describe = |config: &BotConfig| {
+        let destination = if config.welcome_channel.is_empty() {
+            tr!(lang, "by DM", "por MD")
+        } else {
+            tr!(lang, "in #{}", "en #{}", config.welcome_channel)
+        };
+        let gname = gid
+            .name(ctx.cache())
+            .unwrap_or_else(|| format!("guild {}", gid));
+        tr!(
+            lang,
+            "The new members are sent the following message, {}:\n{}",
+            "A los nuevos miembros se les envía el siguiente mensaje, {}:\n{}",
+            destination,
+            welcome::render(
+                config,
+                &ctx.author().id,
+                &gname,
+                &format!("#{}", config.bot_channel)
+            )
+        )
+    };
+
+    let reply = if disable.unwrap_or(false) {
+        config.welcome_message.clear();
+        config.welcome_channel.clear();
+        tr!(
+            lang,
+            "The new members will not be sent a welcome message.",
+            "A los nuevos miembros no se les enviará ningún mensaje de bienvenida."
+        )
+    } else if message.is_none() && channel.is_none() && dm.is_none() {
+        // Show the current message, if no change was given:
+        let reply = if config.welcome_message.is_empty() {
+            tr!(
+                lang,
+                "There is no welcome message for the new members.",
+                "No hay ningún mensaje de bienvenida para los nuevos miembros."
             )
-            .text()
-            .await
-            .expect("[botconfig update] Could not read the teams file into a string.")
-    } else {
-        // Use the default configuration file (possibly new):
-        if fs::metadata("config.json").is_ok() {
-            fs::read_to_string("config.json")
-                .expect("[botconfig update] Could not read the default configuration file.")
         } else {
-            ctx.reply("No configuration file was provided, and the default configuration file was not found.")
+            describe(&config)
+        };
+        for chunk in utils::split_message(&reply, utils::MAX_MESSAGE_LEN) {
+            utils::reply(ctx, chunk).await.unwrap_or_else(|e| {
+                panic!(
+                    "[botconfig] Failed to send the current welcome message for guild {}: {:?}",
+                    gid, e
+                )
+            });
+        }
+
+        return Ok(());
+    } else {
+        let error = match message.as_ref() {
+            Some(message) => welcome::validate_template(message).err(),
+            None if config.welcome_message.is_empty() => Some(tr!(
+                lang,
+                "Set the welcome message first.",
+                "Establece primero el mensaje de bienvenida."
+            )),
+            None => None,
+        };
+        if let Some(msg) = error {
+            utils::reply_error(ctx, format!("**Error:** {}", msg))
                 .await
-                .expect(
-                    format!(
-                        "[botconfig update] Failed to send error message for missing configuration file for guild {}.",
-                        gid
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[botconfig] Failed to send error message for invalid welcome message for guild {}: {:?}",
+                        gid, e
                     )
-                    .as_str(),
-                );
+                });
 
             return Ok(());
         }
+
+        if let Some(message) = message {
+            config.welcome_message = message;
+        }
+        if let Some(channel) = channel {
+            config.welcome_channel = channel.name;
+        } else if dm.unwrap_or(false) {
+            config.welcome_channel.clear();
+        }
+        describe(&config)
     };
-    let config = serde_json::from_str(config_json.as_str()).expect(
-        format!(
-            "[botconfig update] Failed to parse the configuration file for guild {}.",
-            gid
-        )
-        .as_str(),
-    );
 
-    // Update and save the new configuration:
-    utils::update_config_persistence(&config, &gid);
+    // Update the configuration:
+    utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+    for (field, value, previous) in [
+        ("welcome_message", &config.welcome_message, &previous.0),
+        ("welcome_channel", &config.welcome_channel, &previous.1),
+    ] {
+        if value == previous {
+            continue;
+        }
+        events::record_notable(
+            &ctx.data().feed,
+            &gid,
+            Event::ConfigChanged {
+                user: Some(ctx.author().id),
+                field: Some(field.to_string()),
+                value: serde_json::to_value(value).unwrap_or_default(),
+            },
+        );
+    }
 
     // Reply to the user, as confirmation:
-    ctx.reply(format!(
-        "The configuration has been updated! New configuration:\n```json\n{}\n```",
-        serde_json::to_string_pretty(&config).expect(
-            format!(
-                "[botconfig] Failed to serialize the config for guild {}.",
-                gid
+    for chunk in utils::split_message(&reply, utils::MAX_MESSAGE_LEN) {
+        utils::reply(ctx, chunk).await.unwrap_or_else(|e| {
+            panic!(
+                "[botconfig] Failed to send confirmation of the welcome message for guild {}: {:?}",
+                gid, e
             )
-            .as_str()
-        )
-    ))
-    .await
-    .expect(
-        format!(
-            "[botconfig update] Failed to send confirmation of configuration update for guild {}.",
-            gid
-        )
-        .as_str(),
-    );
+        });
+    }
 
     Ok(())
 }
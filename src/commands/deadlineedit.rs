@@ -0,0 +1,124 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{deadline, i18n, utils, utils::get_guild_id, Context, Error};
+
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    subcommands("set", "remove"),
+    subcommand_required,
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn deadlineedit(_: Context<'_>) -> Result<(), Error> {
+    // This function will not be executed, as the command has subcommands.
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Add a submission deadline, or change an existing one."),
+    description_localized(
+        "es-ES",
+        "Añadir una fecha límite de entrega, o cambiar una existente."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "The name of the deadline (e.g. the assignment)."] name: String,
+    #[description = "When the deadline expires (YYYY-MM-DD HH:MM), in the guild's timezone."]
+    datetime: String,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+
+    // Parse the expiration date:
+    let Some(timestamp) = utils::parse_datetime(&config, &datetime) else {
+        utils::reply_error(
+            ctx,
+            format!(
+                "**Error:** `{}` is not a valid date and time. Use the YYYY-MM-DD HH:MM format.",
+                datetime
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[deadlineedit] Failed to send reply after an invalid deadline date in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    };
+
+    // Set the deadline:
+    deadline::get_guild_deadlines(&gid).set_deadline(name.clone(), timestamp);
+
+    // Reply, as confirmation:
+    utils::reply(
+        ctx,
+        format!(
+            "Deadline **{}** set to {} (<t:{}:R>).",
+            name,
+            utils::format_unix_timestamp(&config, i18n::guild_language(&config), timestamp),
+            timestamp
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[deadlineedit] Failed to send reply after setting deadline {} in guild {}: {:?}",
+            name, gid, e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Remove a submission deadline."),
+    description_localized("es-ES", "Eliminar una fecha límite de entrega.")
+)]
+#[hermes::log_cmd]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "The name of the deadline to remove."] name: String,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+
+    let reply = if deadline::get_guild_deadlines(&gid).remove_deadline(&name) {
+        format!("Deadline **{}** removed.", name)
+    } else {
+        format!("Deadline **{}** does not exist in this guild.", name)
+    };
+
+    // Reply, as confirmation:
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[deadlineedit] Failed to send reply after removing deadline {} in guild {}: {:?}",
+            name, gid, e
+        )
+    });
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{deadline, i18n, i18n::tr, student, utils, utils::get_guild_id, Context, Error};
+use poise::{serenity_prelude as serenity, CreateReply};
+
+#[poise::command(
+    slash_command,
+    category = "Student",
+    guild_only,
+    ephemeral,
+    description_localized("en-US", "List the upcoming submission deadlines."),
+    description_localized("es-ES", "Listar las próximas fechas límite de entrega.")
+)]
+#[hermes::log_cmd]
+pub async fn deadlines(ctx: Context<'_>) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let lang = match student::get_student(&ctx.data().caches, &ctx.author().id) {
+        Some(student) => i18n::user_language(&student, &config, &gid),
+        None => i18n::guild_language(&config),
+    };
+    let deadlines = deadline::get_guild_deadlines(&gid);
+
+    let now = chrono::Utc::now().timestamp();
+    let mut reply = String::new();
+    for deadline in deadlines.upcoming(now) {
+        reply.push_str(
+            format!(
+                "- **{}**: {} (<t:{}:R>)\n",
+                deadline.name(),
+                utils::format_unix_timestamp(&config, lang, deadline.timestamp()),
+                deadline.timestamp()
+            )
+            .as_str(),
+        );
+    }
+    let reply = if reply.is_empty() {
+        "There are no upcoming deadlines in this server.".to_string()
+    } else {
+        format!("**Upcoming deadlines:**\n{}", reply)
+    };
+
+    // Send the list in as many messages as needed:
+    for chunk in utils::split_message(&reply, utils::MAX_MESSAGE_LEN) {
+        utils::reply(ctx, chunk).await.unwrap_or_else(|e| {
+            panic!(
+                "[deadlines] Failed to send the list of deadlines for guild {}: {:?}",
+                gid, e
+            )
+        });
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    category = "Student",
+    subcommands("export"),
+    subcommand_required,
+    guild_only,
+    ephemeral
+)]
+pub async fn deadline(_: Context<'_>) -> Result<(), Error> {
+    // This function will not be executed, as the command has subcommands.
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Get the upcoming deadlines as a calendar file (.ics), to import them into your calendar."
+    ),
+    description_localized(
+        "es-ES",
+        "Obtener las próximas fechas límite como un fichero de calendario (.ics), para importarlas."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn export(ctx: Context<'_>) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let lang = match student::get_student(&ctx.data().caches, &ctx.author().id) {
+        Some(student) => i18n::user_language(&student, &config, &gid),
+        None => i18n::guild_language(&config),
+    };
+    let deadlines = deadline::get_guild_deadlines(&gid);
+
+    let now = chrono::Utc::now().timestamp();
+    let count = deadlines.upcoming(now).count();
+    if count == 0 {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "There are no upcoming deadlines in this server.",
+                "No hay próximas fechas límite en este servidor."
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[deadline] Failed to send reply for no deadlines to export in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    }
+
+    let reply = CreateReply::default()
+        .content(tr!(
+            lang,
+            "{} upcoming deadline(s), ready to import into your calendar:",
+            "{} próxima(s) fecha(s) límite, lista(s) para importar en tu calendario:",
+            count
+        ))
+        .attachment(serenity::CreateAttachment::bytes(
+            deadlines
+                .to_ical(now, utils::config_timezone(&config))
+                .into_bytes(),
+            "deadlines.ics",
+        ));
+    utils::send(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[deadline] Failed to send the calendar file of the deadlines for guild {}: {:?}",
+            gid, e
+        )
+    });
+
+    Ok(())
+}
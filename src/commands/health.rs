@@ -0,0 +1,74 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{utils, Context, Error};
+use poise::{serenity_prelude as serenity, CreateReply};
+use std::sync::atomic::Ordering;
+
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral,
+    description_localized("en-US", "Show the status of the bot and its background tasks."),
+    description_localized("es-ES", "Mostrar el estado del bot y de sus tareas en segundo plano.")
+)]
+#[hermes::log_cmd]
+pub async fn health(ctx: Context<'_>) -> Result<(), Error> {
+    let latency = ctx.ping().await;
+    let maintenance = ctx.data().maintenance.load(Ordering::Relaxed);
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title("Hermes status")
+        .description(format!(
+            "Gateway latency: {} ms\nMaintenance mode: {}",
+            latency.as_millis(),
+            if maintenance { "enabled" } else { "disabled" }
+        ));
+
+    let statuses = ctx.data().tasks.statuses();
+    if statuses.is_empty() {
+        embed = embed.field("Background tasks", "None running.", false);
+    }
+    for (name, status) in statuses {
+        let last_run = match status.last_run() {
+            Some(ts) => format!("<t:{}:R>", ts),
+            None => "never".to_string(),
+        };
+        let next_run = match status.next_run() {
+            Some(ts) => format!("<t:{}:R>", ts),
+            None => "unknown".to_string(),
+        };
+        let mut info = format!(
+            "Last run: {}\nNext run: {}\nRestarts: {}",
+            last_run,
+            next_run,
+            status.restarts()
+        );
+        if let Some(error) = status.last_error() {
+            info.push_str(format!("\nLast error: `{}`", error).as_str());
+        }
+        embed = embed.field(name, info, true);
+    }
+
+    utils::send(ctx, CreateReply::default().embed(embed))
+        .await
+        .expect("[health] Failed to send the status of the bot.");
+
+    Ok(())
+}
@@ -15,18 +15,208 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{Context, Error};
+use crate::{
+    commands::registry,
+    i18n::{self, tr, Language},
+    student, utils, Context, Data, Error,
+};
+use poise::{serenity_prelude as serenity, CreateReply};
+
+/// Categories of the commands, in the order they are shown in the help message.
+const CATEGORIES: [&str; 3] = ["Student", "Team", "Admin"];
+
+/// Name of a command category in the given language.
+fn category_name(category: &str, lang: Language) -> String {
+    match category {
+        "Student" => tr!(lang, "Student commands", "Comandos para estudiantes"),
+        "Team" => tr!(lang, "Team commands", "Comandos de equipos"),
+        "Admin" => tr!(lang, "Admin commands", "Comandos de administración"),
+        _ => tr!(lang, "Other commands", "Otros comandos"),
+    }
+}
+
+/// Description of a command in the given language, if it has any.
+fn command_description(cmd: &poise::Command<Data, Error>, lang: Language) -> String {
+    let locale = match lang {
+        Language::English => "en-US",
+        Language::Spanish => "es-ES",
+    };
+    cmd.description_localizations
+        .get(locale)
+        .or(cmd.description.as_ref())
+        .cloned()
+        .unwrap_or_else(|| tr!(lang, "[No description]", "[Sin descripción]"))
+}
+
+/// Finds a command (or subcommand) given its qualified name (e.g. "team join").
+fn find_command<'a>(
+    commands: &'a [poise::Command<Data, Error>],
+    name: &str,
+) -> Option<&'a poise::Command<Data, Error>> {
+    let mut parts = name.trim().trim_start_matches('/').split_whitespace();
+    let root = parts.next()?;
+    let mut cmd = commands.iter().find(|cmd| cmd.name == root)?;
+    for part in parts {
+        cmd = cmd.subcommands.iter().find(|sub| sub.name == part)?;
+    }
+
+    Some(cmd)
+}
+
+/// Autocompletes the qualified names of the commands.
+async fn autocomplete_command<'a>(
+    ctx: Context<'a>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    let mut names = Vec::new();
+    for cmd in ctx.framework().options().commands.iter() {
+        names.push(cmd.qualified_name.clone());
+        names.extend(cmd.subcommands.iter().map(|sub| sub.qualified_name.clone()));
+    }
+
+    names
+        .into_iter()
+        .filter(move |name| name.starts_with(partial.trim_start_matches('/')))
+        .take(25)
+}
 
 #[poise::command(
     slash_command,
+    category = "Student",
     guild_only,
     ephemeral,
-    description_localized("en-US", "Get help for using Hermes [CURRENTLY UNUSED]."),
-    description_localized("es-ES", "Get help for using Hermes [CURRENTLY UNUSED].")
+    description_localized("en-US", "Get help for using Hermes."),
+    description_localized("es-ES", "Obtener ayuda para usar Hermes.")
 )]
 #[hermes::log_cmd]
-async fn help(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.reply("This command is still WIP, sorry! Please check back later.")
+pub async fn help(
+    ctx: Context<'_>,
+    #[description = "Command to get detailed help about (e.g. \"team join\")."]
+    #[autocomplete = "autocomplete_command"]
+    command: Option<String>,
+) -> Result<(), Error> {
+    let gid = utils::get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let lang = match student::get_student(&ctx.data().caches, &ctx.author().id) {
+        Some(student) => i18n::user_language(&student, &config, &gid),
+        None => i18n::guild_language(&config),
+    };
+    let commands = &ctx.framework().options().commands;
+
+    let embed = match command {
+        // Detailed help about a command:
+        Some(name) => {
+            let Some(cmd) = find_command(commands, &name).filter(|cmd| {
+                let root = cmd.qualified_name.split(' ').next().unwrap_or_default();
+                registry::is_enabled(root, &config)
+            }) else {
+                utils::reply(
+                    ctx,
+                    tr!(
+                        lang,
+                        "There is no command named `/{}`.",
+                        "No existe ningún comando llamado `/{}`.",
+                        name.trim().trim_start_matches('/')
+                    ),
+                )
+                .await
+                .expect("[help] Failed to send reply for an unknown command.");
+
+                return Ok(());
+            };
+
+            let mut embed = serenity::CreateEmbed::new()
+                .title(format!("/{}", cmd.qualified_name))
+                .description(command_description(cmd, lang));
+            if !cmd.parameters.is_empty() {
+                let mut params = String::new();
+                for param in cmd.parameters.iter() {
+                    let description = param
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| tr!(lang, "[No description]", "[Sin descripción]"));
+                    let optional = if param.required {
+                        String::new()
+                    } else {
+                        tr!(lang, " (optional)", " (opcional)")
+                    };
+                    params.push_str(
+                        format!("- `{}`{}: {}\n", param.name, optional, description).as_str(),
+                    );
+                }
+                embed = embed.field(tr!(lang, "Arguments", "Argumentos"), params, false);
+            }
+            if !cmd.subcommands.is_empty() {
+                let mut subcommands = String::new();
+                for sub in cmd.subcommands.iter().filter(|sub| !sub.hide_in_help) {
+                    subcommands.push_str(
+                        format!(
+                            "- `/{}`: {}\n",
+                            sub.qualified_name,
+                            command_description(sub, lang)
+                        )
+                        .as_str(),
+                    );
+                }
+                embed = embed.field(tr!(lang, "Subcommands", "Subcomandos"), subcommands, false);
+            }
+
+            embed
+        }
+        // Categorized list of commands:
+        None => {
+            let mut embed = serenity::CreateEmbed::new()
+                .title(tr!(lang, "Hermes commands", "Comandos de Hermes"))
+                .description(tr!(
+                    lang,
+                    "Use `/help <command>` for more details about a command.",
+                    "Usa `/help <comando>` para ver más detalles sobre un comando."
+                ));
+            let mut categories = CATEGORIES.map(Some).to_vec();
+            categories.push(None);
+            for category in categories {
+                let mut list = String::new();
+                for cmd in commands.iter().filter(|cmd| {
+                    !cmd.hide_in_help
+                        && registry::is_enabled(&cmd.name, &config)
+                        && match category {
+                            Some(category) => cmd.category.as_deref() == Some(category),
+                            None => cmd
+                                .category
+                                .as_deref()
+                                .is_none_or(|c| !CATEGORIES.contains(&c)),
+                        }
+                }) {
+                    if cmd.subcommands.is_empty() {
+                        list.push_str(
+                            format!("- `/{}`: {}\n", cmd.name, command_description(cmd, lang))
+                                .as_str(),
+                        );
+                    } else {
+                        let subcommands = cmd
+                            .subcommands
+                            .iter()
+                            .filter(|sub| !sub.hide_in_help)
+                            .map(|sub| sub.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        list.push_str(format!("- `/{}` ({})\n", cmd.name, subcommands).as_str());
+                    }
+                }
+                if !list.is_empty() {
+                    embed = embed.field(
+                        category_name(category.unwrap_or_default(), lang),
+                        list,
+                        false,
+                    );
+                }
+            }
+
+            embed
+        }
+    };
+
+    utils::send(ctx, CreateReply::default().embed(embed))
         .await
         .expect("[help] Failed to send reply.");
 
@@ -15,60 +15,650 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{student, utils, utils::get_guild_id, Context, Error};
+use crate::{
+    diff,
+    i18n::{self, tr, Language},
+    requestrecord::{self, RequestMetadata, RequestRecord},
+    student, tablon, utils,
+    utils::get_guild_id,
+    utils::BotConfig,
+    Context, Data, Error,
+};
+use poise::{serenity_prelude as serenity, CreateReply};
+use serenity::{GuildId, UserId};
+use std::time::Duration;
+
+/// Number of requests shown in each page of the history.
+const PAGE_SIZE: usize = 10;
+
+/// Time to wait for button presses on the history message before disabling pagination.
+const PAGINATION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
 #[poise::command(
     slash_command,
+    category = "Student",
+    subcommands("show", "details", "compare", "clear", "forget"),
+    subcommand_required,
     guild_only,
+    ephemeral
+)]
+pub async fn history(_: Context<'_>) -> Result<(), Error> {
+    // This function will not be executed, as the command has subcommands.
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Get your history of previous Tablón requests."),
+    description_localized("es-ES", "Obtener tu historial de peticiones anteriores a Tablón.")
+)]
+#[hermes::log_cmd]
+pub async fn show(
+    ctx: Context<'_>,
+    #[description = "Only show the requests sent to this queue."] queue: Option<String>,
+    #[description = "Only show the requests sent since this date (YYYY-MM-DD)."] since: Option<
+        String,
+    >,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+
+    // Get the request history for the student triggering the commnad (archived requests included):
+    let student = utils::get_triggering_student!(ctx);
+    let lang = i18n::user_language(&student, &config, &gid);
+    let hist = student.full_request_history(&gid);
+    if hist.is_empty() {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "You don't have any request sent through Hermes in this server. Try using the `/request` command first!",
+                "No tienes ninguna petición enviada a través de Hermes en este servidor. ¡Prueba primero el comando `/request`!"
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[history] Couldn't send message to user with no history {} ({}): {:?}",
+                student.name(), student.id(), e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Parse the date filter, if any:
+    let since = match since {
+        Some(date) => {
+            let Some(since) = utils::parse_date(&config, &date) else {
+                utils::reply_error(
+                    ctx,
+                    tr!(
+                        lang,
+                        "**Error:** `{}` is not a valid date. Use the YYYY-MM-DD format.",
+                        "**Error:** `{}` no es una fecha válida. Usa el formato AAAA-MM-DD.",
+                        date
+                    ),
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[history] Couldn't send invalid date message to user {} ({}): {:?}",
+                        student.name(),
+                        student.id(),
+                        e
+                    )
+                });
+
+                return Ok(());
+            };
+            Some(since)
+        }
+        None => None,
+    };
+
+    // Filter the requests, newest first:
+    let requests = hist
+        .iter()
+        .rev()
+        .filter(|req| queue.is_none() || req.queue() == &queue)
+        .filter(|req| since.is_none_or(|since| req.timestamp().is_some_and(|ts| ts >= since)))
+        .cloned()
+        .collect::<Vec<_>>();
+    if requests.is_empty() {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "None of your requests in this server match the given filters.",
+                "Ninguna de tus peticiones en este servidor cumple los filtros indicados."
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[history] Couldn't send message to user with no matching history {} ({}): {:?}",
+                student.name(),
+                student.id(),
+                e
+            )
+        });
+
+        return Ok(());
+    }
+    let page_count = requests.len().div_ceil(PAGE_SIZE);
+
+    // Send the first page, with pagination buttons if there is more than one:
+    let ctx_id = ctx.id();
+    let prev_button_id = format!("{}prev", ctx_id);
+    let next_button_id = format!("{}next", ctx_id);
+    let embed = history_page(ctx.data(), &config, lang, &gid, &student.id(), &requests, 0).await;
+    let mut reply = CreateReply::default().embed(embed);
+    if page_count > 1 {
+        reply = reply.components(vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(&prev_button_id).emoji('◀'),
+            serenity::CreateButton::new(&next_button_id).emoji('▶'),
+        ])]);
+    }
+    utils::send(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[history] Couldn't send the history message to user {} ({}): {:?}",
+            student.name(),
+            student.id(),
+            e
+        )
+    });
+    if page_count == 1 {
+        return Ok(());
+    }
+
+    // Handle the pagination:
+    let mut current_page = 0;
+    while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(PAGINATION_TIMEOUT)
+        .await
+    {
+        if press.data.custom_id == next_button_id {
+            current_page = (current_page + 1) % page_count;
+        } else if press.data.custom_id == prev_button_id {
+            current_page = current_page.checked_sub(1).unwrap_or(page_count - 1);
+        } else {
+            continue;
+        }
+
+        let embed = history_page(
+            ctx.data(),
+            &config,
+            lang,
+            &gid,
+            &student.id(),
+            &requests,
+            current_page,
+        )
+        .await;
+        press
+            .create_response(
+                ctx.serenity_context(),
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new().embed(embed),
+                ),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[history] Couldn't update the history message for user {} ({}): {:?}",
+                    student.name(),
+                    student.id(),
+                    e
+                )
+            });
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Show the details of one of your requests."),
+    description_localized("es-ES", "Mostrar los detalles de una de tus peticiones.")
+)]
+#[hermes::log_cmd]
+pub async fn details(
+    ctx: Context<'_>,
+    #[description = "The ID of the request."] rid: u16,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let student = utils::get_triggering_student!(ctx);
+    let lang = i18n::user_language(&student, &config, &gid);
+
+    // Only the requests in the student's history can be seen:
+    let in_history = student
+        .full_request_history(&gid)
+        .iter()
+        .any(|req| req.id() == rid);
+    let metadata = requestrecord::get_metadata(&gid, rid);
+    let (true, Some(metadata)) = (in_history, metadata) else {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "There are no details of request #{} in your history in this server.",
+                "No hay detalles de la petición #{} en tu historial en este servidor.",
+                rid
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[history] Couldn't send the missing request details to user {} ({}): {:?}",
+                student.name(),
+                student.id(),
+                e
+            )
+        });
+
+        return Ok(());
+    };
+
+    let verdict = match metadata.verdict() {
+        Some(verdict) => i18n::localize_decimals(lang, verdict),
+        None => tr!(lang, "Unknown", "Desconocido"),
+    };
+    let embed = serenity::CreateEmbed::new()
+        .title(tr!(lang, "Request #{}", "Petición #{}", rid))
+        .url(format!("{}/request?rid={}", config.tablon_url, rid))
+        .field(tr!(lang, "Team", "Equipo"), metadata.team(), true)
+        .field(
+            tr!(lang, "Sent by", "Enviada por"),
+            format!("<@{}>", metadata.user()),
+            true,
+        )
+        .field(
+            tr!(lang, "Date", "Fecha"),
+            utils::format_unix_timestamp(&config, lang, metadata.timestamp()),
+            true,
+        )
+        .field(
+            tr!(lang, "Program", "Programa"),
+            format!("`{}` (`{}`)", metadata.filename(), metadata.hash()),
+            false,
+        )
+        .field(
+            tr!(lang, "Arguments", "Argumentos"),
+            format!("`{}`", metadata.args()),
+            false,
+        )
+        .field(tr!(lang, "Verdict", "Veredicto"), verdict, false);
+    utils::send(ctx, CreateReply::default().embed(embed))
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[history] Couldn't send the request details to user {} ({}): {:?}",
+                student.name(),
+                student.id(),
+                e
+            )
+        });
+
+    Ok(())
+}
+
+/// Number of unchanged lines shown around each change, in the diffs of `/history compare`.
+const DIFF_CONTEXT: usize = 3;
+
+/// Gets the verdict of a request, from its metadata or from Tablón (caching it in the history of
+/// the student who sent it, once final).
+async fn metadata_verdict(
+    data: &Data,
+    config: &BotConfig,
+    gid: &GuildId,
+    metadata: &RequestMetadata,
+) -> Option<String> {
+    if let Some(verdict) = metadata.verdict() {
+        return Some(verdict.clone());
+    }
+
+    let status = data
+        .tablon
+        .fetch_request_status(&config.tablon_url, metadata.id())
+        .await?;
+    if !tablon::is_pending(&status) {
+        if let Some(mut student) = student::get_student(&data.caches, &metadata.user()) {
+            student.set_request_verdict(gid, metadata.id(), status.clone());
+        }
+    }
+
+    Some(status)
+}
+
+#[poise::command(
+    slash_command,
     ephemeral,
     description_localized(
         "en-US",
-        "Get your history of previous Tablón request. (Maximum of 30.)"
+        "Compare the results and the programs of two requests of your team."
     ),
     description_localized(
         "es-ES",
-        "Get your history of previous Tablón request. (Maximum of 30.)"
+        "Comparar los resultados y los programas de dos peticiones de tu equipo."
     )
 )]
 #[hermes::log_cmd]
-pub async fn history(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn compare(
+    ctx: Context<'_>,
+    #[description = "The ID of the first request."] rid1: u16,
+    #[description = "The ID of the second request."] rid2: u16,
+    #[description = "Also show the differences between the programs sent."] show_diff: Option<bool>,
+) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-
-    let tablon_url = crate::utils::load_config(&gid).tablon_url;
-
-    // Get the request history for the student triggering the commnad:
+    let config = utils::load_config(&ctx.data().caches, &gid);
     let student = utils::get_triggering_student!(ctx);
-    let Some(hist) = student.request_history().get(&gid) else {
-        ctx.reply("You don't have any request sent through Hermes in this server. Try using the `/request` command first!").await.expect(
-            format!(
-                "[history] Couldn't send message to user with no history {} ({})",
+    let lang = i18n::user_language(&student, &config, &gid);
+
+    // Only the requests of the student's current team can be compared:
+    let team_id = student.get_team_id(&gid);
+    let of_team = |rid| {
+        requestrecord::get_metadata(&gid, rid)
+            .filter(|metadata| Some(metadata.team()) == team_id.as_ref())
+    };
+    let (Some(first), Some(second)) = (of_team(rid1), of_team(rid2)) else {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "Both requests must have been sent by your team, through me.",
+                "Ambas peticiones deben haber sido enviadas por tu equipo, a través de mí."
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[history] Couldn't send the missing requests to compare to user {} ({}): {:?}",
                 student.name(),
-                student.id()
+                student.id(),
+                e
             )
-            .as_str(),
-        );
+        });
 
         return Ok(());
     };
 
-    // Get at most last 30 requests:
-    let requests = hist.iter().rev().take(30).collect::<Vec<_>>();
-    let mut reply = "**Last requests sent to Tablón:**\n".to_string();
+    // Describe each request, and the difference in their results:
+    let mut reply_msg = String::new();
+    let mut results = Vec::with_capacity(2);
+    for metadata in [&first, &second] {
+        let verdict = metadata_verdict(ctx.data(), &config, &gid, metadata).await;
+        results.push(verdict.as_deref().and_then(tablon::verdict_result));
+        reply_msg.push_str(&tr!(
+            lang,
+            "**Request #{}** (`{}`, {}): {}\n",
+            "**Petición #{}** (`{}`, {}): {}\n",
+            metadata.id(),
+            metadata.filename(),
+            utils::format_unix_timestamp(&config, lang, metadata.timestamp()),
+            i18n::localize_decimals(
+                lang,
+                &verdict.unwrap_or_else(|| tr!(lang, "Unknown", "Desconocido"))
+            )
+        ));
+    }
+    match (results[0], results[1]) {
+        (Some(old), Some(new)) => {
+            let percentage = if old != 0.0 {
+                format!(" ({:+.1}%)", (new - old) / old * 100.0)
+            } else {
+                String::new()
+            };
+            reply_msg.push_str(&i18n::localize_decimals(
+                lang,
+                &tr!(
+                    lang,
+                    "Difference in the results: {:+.3}{}\n",
+                    "Diferencia en los resultados: {:+.3}{}\n",
+                    new - old,
+                    percentage
+                ),
+            ));
+        }
+        _ => reply_msg.push_str(&tr!(
+            lang,
+            "The results of both requests are needed to compare them.\n",
+            "Hacen falta los resultados de ambas peticiones para compararlos.\n"
+        )),
+    }
 
-    for req in requests {
-        let req_url = format!("<{}/request?rid={}>\n", tablon_url, req);
-        reply.push_str(&req_url);
+    // Show the differences between the programs, if asked to (attached, if too long):
+    let mut reply = CreateReply::default();
+    if show_diff.unwrap_or(false) {
+        let read_program = |rid| {
+            std::fs::read(requestrecord::metadata_program_path(&gid, rid))
+                .map(|program| String::from_utf8_lossy(&program).into_owned())
+                .ok()
+        };
+        let programs = read_program(rid1).zip(read_program(rid2));
+        let program_diff = programs.and_then(|(old, new)| {
+            diff::unified_diff(
+                &old,
+                &new,
+                &format!("#{}/{}", rid1, first.filename()),
+                &format!("#{}/{}", rid2, second.filename()),
+                DIFF_CONTEXT,
+            )
+        });
+        match program_diff {
+            None => reply_msg.push_str(&tr!(
+                lang,
+                "\nThe programs could not be compared (they are missing, or over {} lines).",
+                "\nNo se pudieron comparar los programas (no están, o superan las {} líneas).",
+                diff::MAX_LINES
+            )),
+            Some(program_diff) if program_diff.is_empty() => reply_msg.push_str(&tr!(
+                lang,
+                "\nThe programs are identical.",
+                "\nLos programas son idénticos."
+            )),
+            Some(program_diff)
+                if reply_msg.len() + program_diff.len() + 16 <= utils::MAX_MESSAGE_LEN =>
+            {
+                reply_msg.push_str(&format!("```diff\n{}```", program_diff));
+            }
+            Some(program_diff) => {
+                reply = reply.attachment(serenity::CreateAttachment::bytes(
+                    program_diff.into_bytes(),
+                    format!("compare_{}_{}.diff", rid1, rid2),
+                ));
+            }
+        }
     }
+    utils::send(ctx, reply.content(reply_msg))
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[history] Couldn't send the comparison of requests to user {} ({}): {:?}",
+                student.name(),
+                student.id(),
+                e
+            )
+        });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Remove all the requests from your history in this server."),
+    description_localized(
+        "es-ES",
+        "Eliminar todas las peticiones de tu historial en este servidor."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn clear(ctx: Context<'_>) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let mut student = utils::get_triggering_student!(ctx);
 
-    // Send the reply:
-    ctx.reply(reply).await.expect(
-        format!(
-            "[history] Couldn't send the history message to user {} ({})",
+    let removed = student.clear_request_history(&gid);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
+    );
+
+    // Reply, as confirmation:
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Your request history has been cleared ({} request(s) removed).",
+            "Se ha borrado tu historial de peticiones ({} petición(es) eliminada(s)).",
+            removed
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[history] Couldn't send confirmation of history clearing to user {} ({}): {:?}",
             student.name(),
-            student.id()
+            student.id(),
+            e
         )
-        .as_str(),
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Remove a request from your history."),
+    description_localized("es-ES", "Eliminar una petición de tu historial.")
+)]
+#[hermes::log_cmd]
+pub async fn forget(
+    ctx: Context<'_>,
+    #[description = "The ID of the request to remove."] rid: u16,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let mut student = utils::get_triggering_student!(ctx);
+
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
     );
+    let reply = if student.forget_request(&gid, rid) {
+        tr!(
+            lang,
+            "Request #{} has been removed from your history.",
+            "Se ha eliminado la petición #{} de tu historial.",
+            rid
+        )
+    } else {
+        tr!(
+            lang,
+            "Request #{} is not in your history in this server.",
+            "La petición #{} no está en tu historial en este servidor.",
+            rid
+        )
+    };
+
+    // Reply, as confirmation:
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[history] Couldn't send confirmation of forgetting request {} to user {} ({}): {:?}",
+            rid,
+            student.name(),
+            student.id(),
+            e
+        )
+    });
 
     Ok(())
 }
+
+/// Builds the embed for a page of a student's (filtered) request history.
+///
+/// Verdicts that are not cached are fetched from Tablón, and cached in the student's history once
+/// the requests have finished.
+async fn history_page(
+    data: &Data,
+    config: &BotConfig,
+    lang: Language,
+    gid: &GuildId,
+    uid: &UserId,
+    requests: &[RequestRecord],
+    page: usize,
+) -> serenity::CreateEmbed {
+    let page_count = requests.len().div_ceil(PAGE_SIZE);
+    let mut description = String::new();
+
+    for req in requests.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+        // Get the verdict, from the cache or from Tablón:
+        let verdict = match req.verdict() {
+            Some(verdict) => verdict.clone(),
+            None => match data
+                .tablon
+                .fetch_request_status(&config.tablon_url, req.id())
+                .await
+            {
+                Some(status) => {
+                    if !tablon::is_pending(&status) {
+                        // Reload the student, in case it changed while paginating:
+                        if let Some(mut student) = student::get_student(&data.caches, uid) {
+                            student.set_request_verdict(gid, req.id(), status.clone());
+                        }
+                    }
+                    status
+                }
+                None => tr!(lang, "Unknown", "Desconocido"),
+            },
+        };
+        let date = match req.timestamp() {
+            Some(ts) => utils::format_unix_timestamp(config, lang, ts),
+            None => tr!(lang, "Unknown date", "Fecha desconocida"),
+        };
+        let queue = match req.queue() {
+            Some(queue) => queue.clone(),
+            None => tr!(lang, "Unknown queue", "Cola desconocida"),
+        };
+        // The program's name, if the request's metadata was recorded:
+        let filename = requestrecord::get_metadata(gid, req.id())
+            .map(|metadata| format!(" · `{}`", metadata.filename()))
+            .unwrap_or_default();
+
+        description.push_str(
+            format!(
+                "[**#{}**](<{}/request?rid={}>) · {}{} · `{}` · {}\n",
+                req.id(),
+                config.tablon_url,
+                req.id(),
+                date,
+                filename,
+                queue,
+                i18n::localize_decimals(lang, &verdict)
+            )
+            .as_str(),
+        );
+    }
+
+    serenity::CreateEmbed::new()
+        .title(tr!(
+            lang,
+            "Requests sent to Tablón",
+            "Peticiones enviadas a Tablón"
+        ))
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(tr!(
+            lang,
+            "Page {}/{} · {} request(s)",
+            "Página {}/{} · {} petición(es)",
+            page + 1,
+            page_count,
+            requests.len()
+        )))
+}
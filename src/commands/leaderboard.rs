@@ -15,21 +15,118 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{Context, Error};
+use crate::{
+    i18n::{self, tr},
+    leaderboard, utils,
+    utils::get_guild_id,
+    Context, Error,
+};
+use poise::serenity_prelude::{self as serenity, CreateMessage, GuildChannel};
 
 #[poise::command(
     slash_command,
+    category = "Admin",
+    subcommands("post"),
+    subcommand_required,
     default_member_permissions = "MANAGE_GUILD",
     guild_only,
     ephemeral,
-    description_localized("en-US", "Create a new leaderboard [CURRENTLY UNUSED]."),
-    description_localized("es-ES", "Create a new leaderboard [CURRENTLY UNUSED].")
+    description_localized("en-US", "Manage the guild's leaderboards [WIP]."),
+    description_localized("es-ES", "Gestionar las clasificaciones del servidor [en desarrollo].")
 )]
 #[hermes::log_cmd]
-async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.reply("Experimental leaderboard integration is still WIP, and may be discarded altogether in a future update.")
+pub async fn leaderboard(_ctx: Context<'_>) -> Result<(), Error> {
+    // This function will not be executed, as the command has subcommands.
+    Ok(())
+}
+
+/// Autocompletes the exercises with a leaderboard in the guild.
+async fn autocomplete_exercise(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let Some(gid) = ctx.guild_id() else {
+        return Vec::new();
+    };
+
+    leaderboard::standings(&ctx.data().caches, &gid)
+        .into_keys()
+        .filter(|exercise| exercise.starts_with(partial))
+        .collect()
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Post a snapshot of the current leaderboards in a channel."),
+    description_localized("es-ES", "Publicar las clasificaciones actuales en un canal.")
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn post(
+    ctx: Context<'_>,
+    #[description = "The channel to post the leaderboards in."] channel: GuildChannel,
+    #[description = "The leaderboard (i.e. Tablón's queue) to post. Leave empty to post them all."]
+    #[autocomplete = "autocomplete_exercise"]
+    leaderboard: Option<String>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+
+    let mut standings = leaderboard::standings(&ctx.data().caches, &gid);
+    if let Some(exercise) = &leaderboard {
+        standings.retain(|queue, _| queue == exercise);
+    }
+    if standings.is_empty() {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "There are no results to post yet.",
+                "Aún no hay resultados que publicar."
+            ),
+        )
         .await
-        .expect("[leaderboard] Failed to send reply.");
+        .unwrap_or_else(|e| {
+            panic!(
+                "[leaderboard] Failed to send reply for empty leaderboards in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Post each leaderboard, split into several messages if needed:
+    for (exercise, standings) in standings.iter() {
+        let content = leaderboard::render(lang, exercise, standings);
+        for msg in utils::split_message(&content, utils::MAX_MESSAGE_LEN) {
+            channel
+                .send_message(
+                    ctx.http(),
+                    CreateMessage::new()
+                        .content(msg)
+                        .allowed_mentions(serenity::CreateAllowedMentions::new()),
+                )
+                .await?;
+        }
+    }
+
+    // Reply to the user, as confirmation:
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Posted {} leaderboard(s) in <#{}>.",
+            "Publicadas {} clasificación(es) en <#{}>.",
+            standings.len(),
+            channel.id
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[leaderboard] Failed to send confirmation of the posted leaderboards in guild {}: {:?}",
+            gid, e
+        )
+    });
 
     Ok(())
 }
@@ -15,10 +15,11 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{Context, Error};
+use crate::{utils, Context, Error};
 
 #[poise::command(
     slash_command,
+    category = "Student",
     guild_only,
     ephemeral,
     description_localized("en-US", "Show this software's license information.."),
@@ -26,7 +27,8 @@ use crate::{Context, Error};
 )]
 #[hermes::log_cmd]
 pub async fn license(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.reply(
+    utils::reply(
+        ctx,
         "Hermes  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>\n\
         This program comes with ABSOLUTELY NO WARRANTY.\n\
         This is free software, and you are welcome to redistribute it under certain conditions.\n\
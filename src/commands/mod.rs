@@ -15,14 +15,25 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+pub mod announce;
 pub mod botconfig;
+pub mod deadlineedit;
+pub mod deadlines;
+pub mod health;
 pub mod help;
 pub mod history;
 pub mod leaderboard;
 pub mod license;
+pub mod owner;
 pub mod passwords;
+pub mod registry;
+pub mod remindme;
 pub mod request;
 pub mod settings;
+pub mod sourcedump;
+pub mod stats;
 pub mod team;
 pub mod teamdump;
 pub mod teamedit;
+pub mod tutorial;
+pub mod whoami;
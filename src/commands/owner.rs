@@ -0,0 +1,377 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    activityfeed::Activity,
+    alerts::{self, AlertKind},
+    backup, cli,
+    commands::registry,
+    student, team, utils, Context, Error,
+};
+use poise::serenity_prelude as serenity;
+use std::{fmt::Write as _, sync::atomic::Ordering};
+
+#[poise::command(
+    slash_command,
+    subcommands(
+        "reload",
+        "backup",
+        "leave_guild",
+        "maintenance",
+        "register",
+        "resync",
+        "student"
+    ),
+    subcommand_required,
+    owners_only,
+    hide_in_help,
+    default_member_permissions = "ADMINISTRATOR"
+)]
+pub async fn owner(_: Context<'_>) -> Result<(), Error> {
+    // This function will not be executed, as the command has subcommands.
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    owners_only,
+    ephemeral,
+    description_localized("en-US", "Re-read and validate the persistent data files."),
+    description_localized("es-ES", "Volver a leer y validar los ficheros de datos persistentes.")
+)]
+#[hermes::log_cmd]
+pub async fn reload(ctx: Context<'_>) -> Result<(), Error> {
+    // Drop the cached configurations and students, so files edited by hand are read again, and
+    // check they are still valid:
+    ctx.data().caches.clear();
+    let (checked, problems) = cli::validate_data();
+
+    let mut reply = format!(
+        "Checked {} file(s): {} problem(s) found.",
+        checked,
+        problems.len()
+    );
+    for problem in problems.iter().take(20) {
+        write!(&mut reply, "\n- `{}`", problem).unwrap();
+    }
+    if problems.len() > 20 {
+        write!(&mut reply, "\n- ... and {} more.", problems.len() - 20).unwrap();
+    }
+    utils::reply(ctx, reply)
+        .await
+        .expect("[owner] Failed to send the result of reloading the data.");
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    owners_only,
+    ephemeral,
+    description_localized("en-US", "Create a backup of the persistent data."),
+    description_localized("es-ES", "Crear una copia de seguridad de los datos persistentes.")
+)]
+#[hermes::log_cmd]
+pub async fn backup(ctx: Context<'_>) -> Result<(), Error> {
+    let (reply, failed) = match backup::create_backup() {
+        Ok(dir) => {
+            ctx.data().feed.post_all(|| Activity::BackupCompleted {
+                dir: dir.display().to_string(),
+            });
+            (format!("Backup created in `{}`.", dir.display()), false)
+        }
+        Err(e) => {
+            alerts::alert(
+                AlertKind::Backup,
+                format!("Failed to create a backup: {}", e),
+            );
+            (
+                format!("**Error:** Failed to create the backup: {}", e),
+                true,
+            )
+        }
+    };
+    let sent = if failed {
+        utils::reply_error(ctx, reply).await
+    } else {
+        utils::reply(ctx, reply).await
+    };
+    sent.expect("[owner] Failed to send the result of the backup.");
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    owners_only,
+    ephemeral,
+    description_localized("en-US", "Make the bot leave a server."),
+    description_localized("es-ES", "Hacer que el bot abandone un servidor.")
+)]
+#[hermes::log_cmd]
+pub async fn leave_guild(
+    ctx: Context<'_>,
+    #[description = "ID of the server to leave."] guild_id: String,
+) -> Result<(), Error> {
+    let Ok(gid) = guild_id.trim().parse::<u64>().map(serenity::GuildId::new) else {
+        utils::reply_error(ctx, format!("`{}` is not a valid server ID.", guild_id))
+            .await
+            .expect("[owner] Failed to send reply for an invalid guild ID.");
+
+        return Ok(());
+    };
+
+    let sent = match gid.leave(ctx.http()).await {
+        Ok(()) => utils::reply(ctx, format!("Hermes has left the server {}.", gid)).await,
+        Err(e) => {
+            utils::reply_error(
+                ctx,
+                format!("**Error:** Failed to leave the server {}: {}", gid, e),
+            )
+            .await
+        }
+    };
+    sent.unwrap_or_else(|e| {
+        panic!(
+            "[owner] Failed to send the result of leaving guild {}: {:?}",
+            gid, e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    owners_only,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Toggle the maintenance mode, in which every command is rejected."
+    ),
+    description_localized(
+        "es-ES",
+        "Activar o desactivar el modo de mantenimiento, en el que se rechaza cualquier comando."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn maintenance(
+    ctx: Context<'_>,
+    #[description = "Whether to enable the maintenance mode (default: toggle it)."] enabled: Option<
+        bool,
+    >,
+) -> Result<(), Error> {
+    let maintenance = &ctx.data().maintenance;
+    let enabled = enabled.unwrap_or(!maintenance.load(Ordering::Relaxed));
+    maintenance.store(enabled, Ordering::Relaxed);
+
+    utils::reply(
+        ctx,
+        format!(
+            "Maintenance mode {}.",
+            if enabled { "enabled" } else { "disabled" }
+        ),
+    )
+    .await
+    .expect("[owner] Failed to send confirmation of maintenance mode change.");
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    owners_only,
+    ephemeral,
+    description_localized("en-US", "Re-register the slash commands."),
+    description_localized("es-ES", "Volver a registrar los comandos de barra.")
+)]
+#[hermes::log_cmd]
+pub async fn register(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral()
+        .await
+        .expect("[owner] Failed to defer the response.");
+
+    poise::builtins::register_globally(ctx, &registry::global()).await?;
+    let mut failed = Vec::new();
+    for gid in utils::load_guildmap().values() {
+        if registry::register_in_guild(ctx, gid, &utils::load_config(&ctx.data().caches, gid))
+            .await
+            .is_err()
+        {
+            failed.push(gid.to_string());
+        }
+    }
+
+    let reply = if failed.is_empty() {
+        "The commands have been registered.".to_string()
+    } else {
+        format!(
+            "The commands have been registered, except in the servers: {}.",
+            failed.join(", ")
+        )
+    };
+    utils::reply(ctx, reply)
+        .await
+        .expect("[owner] Failed to send confirmation of command registration.");
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    owners_only,
+    ephemeral,
+    description_localized("en-US", "Scan the members of a server (or all of them) again."),
+    description_localized("es-ES", "Volver a escanear los miembros de un servidor (o de todos).")
+)]
+#[hermes::log_cmd]
+pub async fn resync(
+    ctx: Context<'_>,
+    #[description = "ID of the server to scan (default: all of them)."] guild_id: Option<String>,
+) -> Result<(), Error> {
+    let guilds = ctx.cache().guilds();
+    let gids = match guild_id {
+        Some(guild_id) => {
+            let Some(gid) = guild_id
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .map(serenity::GuildId::new)
+                .filter(|gid| guilds.contains(gid))
+            else {
+                utils::reply(
+                    ctx,
+                    format!("`{}` is not the ID of a known server.", guild_id),
+                )
+                .await
+                .expect("[owner] Failed to send reply for an invalid guild ID.");
+
+                return Ok(());
+            };
+            vec![gid]
+        }
+        None => guilds,
+    };
+
+    ctx.defer_ephemeral()
+        .await
+        .expect("[owner] Failed to defer the response.");
+
+    let count = gids.len();
+    crate::bootstrap_guilds(
+        ctx.serenity_context().clone(),
+        ctx.data().caches.clone(),
+        gids,
+        true,
+    )
+    .await;
+
+    utils::reply(
+        ctx,
+        format!("The members of {} server(s) have been scanned.", count),
+    )
+    .await
+    .expect("[owner] Failed to send confirmation of the member scan.");
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    owners_only,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "List the servers a user is known in, with their team and submissions in each."
+    ),
+    description_localized(
+        "es-ES",
+        "Listar los servidores en que se conoce a un usuario, con su equipo y sus envíos en cada uno."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn student(
+    ctx: Context<'_>,
+    #[description = "The user to look up."] user: serenity::User,
+) -> Result<(), Error> {
+    let Some(student) = student::get_student(&ctx.data().caches, &user.id) else {
+        utils::reply(
+            ctx,
+            format!("<@{}> is not a student known to Hermes.", user.id),
+        )
+        .await
+        .expect("[owner] Failed to send reply for an unknown student.");
+
+        return Ok(());
+    };
+
+    // The guilds with data of the student, and those they are a member of:
+    let mut gids = student.guilds();
+    for gid in ctx.cache().guilds() {
+        if ctx
+            .cache()
+            .guild(gid)
+            .is_some_and(|guild| guild.members.contains_key(&user.id))
+        {
+            gids.insert(gid);
+        }
+    }
+
+    let mut reply = format!("**{}** (<@{}>)", student.name(), user.id);
+    if let Some(university_id) = student.university_id() {
+        write!(&mut reply, ", university ID `{}`", university_id).unwrap();
+    }
+    if gids.is_empty() {
+        reply.push_str(": not known in any server.");
+    } else {
+        write!(&mut reply, ": known in {} server(s).", gids.len()).unwrap();
+    }
+
+    let mut total = 0;
+    for gid in gids.iter() {
+        let gname = gid
+            .name(ctx.cache())
+            .unwrap_or_else(|| "unknown server".to_string());
+        let team = match student.get_team_id(gid) {
+            Some(team_id) => match team::get_team(gid, &team_id) {
+                Some(team) if team.confirmed() => format!("team {} (confirmed)", team_id),
+                Some(_) => format!("team {}", team_id),
+                None => format!("team {} (missing)", team_id),
+            },
+            None => "no team".to_string(),
+        };
+        let submissions = student.full_request_history(gid).len();
+        total += submissions;
+        write!(
+            &mut reply,
+            "\n- **{}** (`{}`): {}, {} submission(s).",
+            gname, gid, team, submissions
+        )
+        .unwrap();
+    }
+    if gids.len() > 1 {
+        write!(&mut reply, "\nTotal: {} submission(s).", total).unwrap();
+    }
+
+    for chunk in utils::split_message(&reply, utils::MAX_MESSAGE_LEN) {
+        utils::reply(ctx, chunk)
+            .await
+            .expect("[owner] Failed to send the overview of a student.");
+    }
+
+    Ok(())
+}
@@ -18,84 +18,411 @@
 extern crate reqwest;
 
 use crate::{
-    team, utils,
+    activityfeed::Activity,
+    batch::Batch,
+    cache::Caches,
+    tablon::Tablon,
+    team,
+    team::GuildTeamInfo,
+    utils,
     utils::get_guild_id,
-    {Context, Error},
+    Credentials, {Context, Error},
 };
 use poise::serenity_prelude as serenity;
 use std::collections::HashMap;
 
+/// Names (lowercase) of the team column in the header row of a passwords file.
+const TEAM_HEADERS: [&str; 4] = ["team", "team_id", "id", "equipo"];
+
+/// Names (lowercase) of the password column in the header row of a passwords file.
+const PASSWORD_HEADERS: [&str; 3] = ["password", "pass", "contraseña"];
+
+/// Splits a line of a passwords file into its fields, given the separator (`None` for whitespace).
+/// Fields may be enclosed in double quotes (as in CSV), to include the separator.
+fn split_fields(line: &str, separator: Option<char>) -> Vec<String> {
+    let Some(separator) = separator else {
+        return line.split_whitespace().map(str::to_string).collect();
+    };
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+        .into_iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// Parses a passwords file, either with one whitespace-separated team-password pair per line, or
+/// as CSV/TSV (comma, semicolon or tab-separated), optionally with a header row naming the team and
+/// password columns.
+///
+/// Returns the team-password pairs, and the (1-based) numbers of the lines that were skipped for
+/// being malformed.
+fn parse_passwords(content: &str) -> (HashMap<String, String>, Vec<usize>) {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .peekable();
+
+    // Detect the separator from the first line:
+    let first = lines.peek().map(|(_, line)| *line).unwrap_or_default();
+    let separator = ['\t', ';', ',']
+        .into_iter()
+        .find(|sep| first.contains(*sep));
+
+    // Find the team and password columns in the header row, if any:
+    let mut columns = (0, 1);
+    let header = split_fields(first, separator)
+        .iter()
+        .map(|field| field.to_lowercase())
+        .collect::<Vec<_>>();
+    let team_column = header
+        .iter()
+        .position(|f| TEAM_HEADERS.contains(&f.as_str()));
+    let password_column = header
+        .iter()
+        .position(|f| PASSWORD_HEADERS.contains(&f.as_str()));
+    if let (Some(team), Some(password)) = (team_column, password_column) {
+        columns = (team, password);
+        lines.next();
+    }
+
+    let mut pass_map = HashMap::new();
+    let mut skipped = Vec::new();
+    for (i, line) in lines {
+        let fields = split_fields(line, separator);
+        match (fields.get(columns.0), fields.get(columns.1)) {
+            (Some(tid), Some(password)) if !tid.is_empty() && !password.is_empty() => {
+                pass_map.insert(tid.clone(), password.clone());
+            }
+            _ => skipped.push(i + 1),
+        }
+    }
+
+    (pass_map, skipped)
+}
+
+/// Sets the passwords of the guild's teams (both of the existing teams, and in the guild's team
+/// info, for future ones), returning the (sorted) identifiers of the existing teams updated.
+///
+/// The teams and their members are saved once, at the end.
+fn apply_passwords(
+    caches: &Caches,
+    info: &mut GuildTeamInfo,
+    gid: &serenity::GuildId,
+    pass_map: HashMap<String, String>,
+) -> Vec<String> {
+    let mut batch = Batch::new(caches);
+    let mut updated = Vec::new();
+    for (tid, password) in pass_map.iter() {
+        // Set the password for the team, if it exists:
+        if let Some(mut team) = team::get_team(gid, tid) {
+            team.set_password(password.clone(), &mut batch);
+            updated.push(tid.clone());
+        }
+    }
+    batch.flush();
+    info.update_passwords(pass_map);
+    updated.sort();
+
+    updated
+}
+
+/// Formats a line of the summary of `/passwords`, listing the given items after their count, or
+/// nothing if there are none.
+fn summary_line(title: &str, items: &[String]) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+
+    format!("\n- {} ({}): {}.", title, items.len(), items.join(", "))
+}
+
+/// Logs in to Tablón as each of the given teams with a password, returning a summary of the
+/// check, listing the teams whose password was rejected.
+///
+/// The teams are checked one at a time, and the check stops at the first one that cannot be
+/// checked, as Tablón is most likely unreachable then.
+pub async fn check_team_logins(tablon: &Tablon, tablon_url: &str, teams: &[team::Team]) -> String {
+    let mut rejected = Vec::new();
+    let mut checked = 0;
+    for team in teams {
+        let Some(password) = team.pass() else {
+            continue;
+        };
+        let credentials = Credentials {
+            team: team.id().clone(),
+            password: Some(password.clone()),
+        };
+        match tablon.check_credentials(tablon_url, &credentials).await {
+            Some(true) => {}
+            Some(false) => rejected.push(team.id().clone()),
+            None => {
+                let mut summary = format!(
+                    "\nTablón could not be reached to check the rest of the passwords ({} checked).",
+                    checked
+                );
+                summary.push_str(&summary_line("Passwords rejected by Tablón", &rejected));
+
+                return summary;
+            }
+        }
+        checked += 1;
+    }
+
+    if rejected.is_empty() {
+        format!(
+            "\nTablón accepted the passwords of the {} teams checked.",
+            checked
+        )
+    } else {
+        summary_line("Passwords rejected by Tablón", &rejected)
+    }
+}
+
+/// Compares the passwords fetched from Tablón with the known ones, listing the differences.
+fn reconcile(known: &HashMap<String, String>, fetched: &HashMap<String, String>) -> Vec<String> {
+    let mut differences = Vec::new();
+    for (tid, password) in fetched.iter() {
+        match known.get(tid) {
+            Some(known_password) if known_password != password => {
+                differences.push(format!("- `{}`: the password differs from Tablón's.", tid))
+            }
+            None => differences.push(format!("- `{}`: only registered in Tablón.", tid)),
+            _ => {}
+        }
+    }
+    for tid in known.keys().filter(|tid| !fetched.contains_key(*tid)) {
+        differences.push(format!("- `{}`: not registered in Tablón.", tid));
+    }
+    differences.sort();
+
+    differences
+}
+
 #[poise::command(
     slash_command,
+    category = "Admin",
     default_member_permissions = "MANAGE_GUILD",
     guild_only,
     ephemeral,
-    description_localized("en-US", "Set the passwords for the guild's teams."),
-    description_localized("es-ES", "Set the passwords for the guild's teams.")
+    description_localized(
+        "en-US",
+        "Set the passwords for the guild's teams, from a file or from Tablón."
+    ),
+    description_localized(
+        "es-ES",
+        "Establecer las contraseñas de los equipos del servidor, desde un fichero o desde Tablón."
+    )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn passwords(
     ctx: Context<'_>,
-    #[description = "File with the team's passwords."] file: serenity::Attachment,
+    #[description = "File with the team's passwords (one pair per line, or CSV/TSV)."] file: Option<
+        serenity::Attachment,
+    >,
+    #[description = "Fetch the passwords from Tablón instead, with the admin credentials."]
+    from_tablon: Option<bool>,
+    #[description = "Only report the differences with Tablón's passwords, without updating them."]
+    verify: Option<bool>,
+    #[description = "Log in to Tablón as each existing team afterwards, to check its password."]
+    check_logins: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let check_logins = check_logins.unwrap_or(false);
+    if check_logins {
+        // Logging in as every team takes a while:
+        ctx.defer_ephemeral().await?;
+    }
 
-    // Read the provided file:
-    let content = reqwest::get(&file.url)
-        .await
-        .expect(
-            format!(
-                "[passwords] Could not download teams file from URL: {}",
-                file.url
+    // Guild's team info, to update:
+    let mut info = match team::get_guild_team_info(&gid) {
+        Some(info) => info,
+        None => {
+            // Create guild team info file, if it does not exist:
+            team::GuildTeamInfo::new(gid, config.team_prefix.clone())
+        }
+    };
+
+    let from_tablon = from_tablon.unwrap_or(false) || verify.unwrap_or(false);
+    if from_tablon {
+        // Fetch the passwords from Tablón, with the guild's admin credentials:
+        let Some(credentials) = utils::get_admin_credentials(&gid) else {
+            utils::reply_error(ctx, "There are no admin credentials for Tablón. Use `/botconfig tablon_admin` to set them.")
+                .await
+                .expect("[passwords] Could not send error message for missing admin credentials.");
+
+            return Ok(());
+        };
+        let Some(fetched) = ctx
+            .data()
+            .tablon
+            .fetch_team_passwords(&config.tablon_url, &credentials, &config.team_prefix)
+            .await
+        else {
+            utils::reply_error(
+                ctx,
+                "Could not fetch the teams from Tablón. Check its URL and the admin credentials.",
             )
-            .as_str(),
-        )
-        .text()
-        .await
-        .expect("[passwords] Could not read the teams file into a string.");
+            .await
+            .expect("[passwords] Could not send error message for unreachable Tablón.");
+
+            return Ok(());
+        };
 
-    // Password map to update the gulid's team info:
-    let mut pass_map = HashMap::<String, String>::new();
-
-    // Split the file into lines (one team-password pair per line):
-    let lines: Vec<&str> = content.lines().collect();
-    for line in lines {
-        let mut parts = line.split_whitespace();
-        let tid = parts
-            .next()
-            .expect(format!("Could not read team name from teams file. Line: {}", line).as_str());
-        let password = parts.next().expect(
+        let differences = reconcile(info.passwords(), &fetched);
+        let mut reply = if differences.is_empty() {
             format!(
-                "Could not read team password from teams file. Line: {}",
-                line
+                "The passwords of the {} teams match Tablón's.",
+                fetched.len()
             )
-            .as_str(),
-        );
+        } else {
+            format!(
+                "Found {} differences with Tablón's passwords:\n{}",
+                differences.len(),
+                differences.join("\n")
+            )
+        };
+        if !verify.unwrap_or(false) && !differences.is_empty() {
+            // Tablón's passwords take precedence, but those of teams unknown to it are kept:
+            let mut pass_map = info.passwords().clone();
+            pass_map.extend(fetched);
+            let updated = apply_passwords(&ctx.data().caches, &mut info, &gid, pass_map);
+            reply.push_str(
+                format!(
+                    "\n\nPasswords for the teams updated from Tablón ({} existing teams updated).",
+                    updated.len()
+                )
+                .as_str(),
+            );
+        }
+        if check_logins {
+            reply.push_str(
+                &check_team_logins(
+                    &ctx.data().tablon,
+                    &config.tablon_url,
+                    &team::get_all_teams(&gid),
+                )
+                .await,
+            );
+        }
 
-        // Set the password for the team, if it exists:
-        if let Some(mut team) = team::get_team(&gid, &tid.to_string()) {
-            team.set_password(password.to_string());
+        for chunk in utils::split_message(&reply, utils::MAX_MESSAGE_LEN) {
+            utils::reply(ctx, chunk)
+                .await
+                .expect("[passwords] Could not send the reconciliation with Tablón.");
         }
 
-        // Add the team-password pair to the password map:
-        pass_map.insert(tid.to_string(), password.to_string());
+        return Ok(());
     }
 
-    // Update the guild's team info:
-    let mut info = match team::get_guild_team_info(&gid) {
-        Some(info) => info,
-        None => {
-            // Create guild team info file, if it does not exist:
-            let prefix = utils::load_config(&gid).team_prefix;
-            team::GuildTeamInfo::new(gid, prefix)
-        }
+    let Some(file) = file else {
+        utils::reply_error(
+            ctx,
+            "Provide a file with the passwords, or fetch them with `from_tablon`.",
+        )
+        .await
+        .expect("[passwords] Could not send error message for a missing password source.");
+
+        return Ok(());
     };
-    info.update_passwords(pass_map);
 
-    // Reply to the user, as confirmation:
-    poise::say_reply(ctx, "Passwords for the teams updated successfully!")
+    // Read the provided file:
+    let content = reqwest::get(&file.url)
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[passwords] Could not download teams file from URL: {}: {:?}",
+                file.url, e
+            )
+        })
+        .text()
         .await
-        .expect("[passwords] Could not send confirmation message.");
+        .expect("[passwords] Could not read the teams file into a string.");
+
+    // Password map to update the guild's team info, with the team identifiers in their canonical
+    // form (e.g. `g1` is stored as `g01`), ignoring those that are not team identifiers:
+    let (parsed, skipped) = parse_passwords(&content);
+    let read = parsed.len();
+    let mut pass_map = HashMap::with_capacity(read);
+    let mut invalid = Vec::new();
+    for (tid, password) in parsed {
+        match info.canonical_team_id(&tid) {
+            Some((tid, _)) => {
+                pass_map.insert(tid, password);
+            }
+            None => invalid.push(format!("`{}`", tid)),
+        }
+    }
+    invalid.sort();
+    let mut future = pass_map
+        .keys()
+        .filter(|tid| team::get_team(&gid, tid).is_none())
+        .cloned()
+        .collect::<Vec<_>>();
+    future.sort();
+
+    let updated = apply_passwords(&ctx.data().caches, &mut info, &gid, pass_map);
+    ctx.data().feed.post(
+        gid,
+        Activity::PasswordsImported {
+            updated: updated.len(),
+            future: future.len(),
+        },
+    );
+
+    // Reply to the user with a summary, as confirmation:
+    let skipped = skipped
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>();
+    let mut reply = format!(
+        "Passwords for the teams updated: {} passwords read from the file.",
+        read
+    );
+    reply.push_str(&summary_line("Existing teams updated", &updated));
+    reply.push_str(&summary_line(
+        "Stored for teams that don't exist yet",
+        &future,
+    ));
+    reply.push_str(&summary_line(
+        "Ignored, as they are not team IDs of this server",
+        &invalid,
+    ));
+    reply.push_str(&summary_line("Malformed lines skipped", &skipped));
+    if check_logins {
+        reply.push_str(
+            &check_team_logins(
+                &ctx.data().tablon,
+                &config.tablon_url,
+                &team::get_all_teams(&gid),
+            )
+            .await,
+        );
+    }
+    for chunk in utils::split_message(&reply, utils::MAX_MESSAGE_LEN) {
+        utils::reply(ctx, chunk)
+            .await
+            .expect("[passwords] Could not send confirmation message.");
+    }
 
     Ok(())
 }
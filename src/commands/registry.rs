@@ -0,0 +1,118 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Assembly of the bot's command list.
+//!
+//! Finished commands are always registered globally. Work-in-progress (WIP) commands are always
+//! part of the framework (so they can be dispatched), but they are only registered globally when
+//! the bot is built with the `wip` feature; otherwise, they are only registered in the guilds that
+//! enable them in their configuration (see `BotConfig::wip_commands`).
+
+use crate::{commands, utils::BotConfig, Data, Error};
+use poise::serenity_prelude as serenity;
+use serenity::GuildId;
+
+/// Finished commands, always available in every guild.
+pub fn finished() -> Vec<poise::Command<Data, Error>> {
+    vec![
+        commands::announce::announce(),
+        commands::botconfig::command(),
+        commands::deadlines::deadline(),
+        commands::deadlineedit::deadlineedit(),
+        commands::deadlines::deadlines(),
+        commands::health::health(),
+        commands::help::help(),
+        commands::history::history(),
+        commands::license::license(),
+        commands::owner::owner(),
+        commands::passwords::passwords(),
+        commands::remindme::remindme(),
+        commands::request::request(),
+        commands::settings::settings(),
+        commands::sourcedump::sourcedump(),
+        commands::stats::stats(),
+        commands::team::team(),
+        commands::team::invite_user(),
+        commands::teamdump::teamdump(),
+        commands::teamedit::teamedit(),
+        commands::teamedit::view_team(),
+        commands::tutorial::tutorial(),
+        commands::whoami::whoami(),
+    ]
+}
+
+/// Work-in-progress commands, only available where enabled.
+pub fn wip() -> Vec<poise::Command<Data, Error>> {
+    vec![commands::leaderboard::leaderboard()]
+}
+
+/// Names of the work-in-progress commands.
+pub fn wip_names() -> Vec<String> {
+    wip().into_iter().map(|cmd| cmd.name).collect()
+}
+
+/// Every command the framework can dispatch.
+pub fn all() -> Vec<poise::Command<Data, Error>> {
+    let mut commands = finished();
+    commands.extend(wip());
+
+    commands
+}
+
+/// Whether the WIP commands are enabled for every guild in this build.
+pub fn wip_enabled_globally() -> bool {
+    cfg!(feature = "wip")
+}
+
+/// Whether the (top-level) command with the given name is available in a guild with the given
+/// configuration.
+pub fn is_enabled(name: &str, config: &BotConfig) -> bool {
+    wip_enabled_globally()
+        || !wip_names().iter().any(|wip| wip == name)
+        || config.wip_commands.iter().any(|wip| wip == name)
+}
+
+/// Commands to register globally.
+pub fn global() -> Vec<poise::Command<Data, Error>> {
+    if wip_enabled_globally() {
+        all()
+    } else {
+        finished()
+    }
+}
+
+/// WIP commands to register only in the given guild, according to its configuration.
+pub fn guild_only(config: &BotConfig) -> Vec<poise::Command<Data, Error>> {
+    if wip_enabled_globally() {
+        return Vec::new();
+    }
+
+    wip()
+        .into_iter()
+        .filter(|cmd| config.wip_commands.contains(&cmd.name))
+        .collect()
+}
+
+/// Registers, in the given guild, the WIP commands it enables (replacing any previous guild-specific
+/// registration, so disabled commands are removed).
+pub async fn register_in_guild(
+    ctx: impl AsRef<serenity::Http>,
+    gid: &GuildId,
+    config: &BotConfig,
+) -> Result<(), serenity::Error> {
+    poise::builtins::register_in_guild(ctx, &guild_only(config), *gid).await
+}
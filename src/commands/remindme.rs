@@ -0,0 +1,125 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    student,
+    utils::{self, get_guild_id, get_triggering_student},
+    Context, Error,
+};
+
+/// Default offsets (in hours before each deadline) for deadline reminders.
+const DEFAULT_OFFSETS: [u32; 2] = [24, 1];
+
+#[poise::command(
+    slash_command,
+    category = "Student",
+    guild_only,
+    ephemeral,
+    description_localized("en-US", "Receive DM reminders before the submission deadlines."),
+    description_localized(
+        "es-ES",
+        "Recibir recordatorios por MD antes de las fechas límite de entrega."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn remindme(
+    ctx: Context<'_>,
+    #[description = "Whether to receive reminders."] enable: bool,
+    #[description = "Hours before each deadline to be reminded at, comma-separated (default: 24,1)."]
+    offsets: Option<String>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let mut student = get_triggering_student!(ctx);
+
+    // Opt out:
+    if !enable {
+        let reply = if student.clear_reminders(&gid) {
+            "You will no longer receive deadline reminders for this server."
+        } else {
+            "You were not receiving deadline reminders for this server."
+        };
+        utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+            panic!(
+                "[remindme] Failed to send reply after user {} opted out of reminders: {:?}",
+                student.id(),
+                e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Parse the offsets, if given:
+    let offsets = match offsets {
+        Some(offsets) => {
+            let Ok(mut offsets) = offsets
+                .split(',')
+                .map(|offset| offset.trim().trim_end_matches('h').parse::<u32>())
+                .collect::<Result<Vec<_>, _>>()
+            else {
+                utils::reply_error(
+                    ctx,
+                    format!(
+                        "**Error:** `{}` is not a valid list of hours. Use something like `24,1`.",
+                        offsets
+                    ),
+                )
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[remindme] Failed to send reply after user {} gave invalid offsets: {:?}",
+                        student.id(),
+                        e
+                    )
+                });
+
+                return Ok(());
+            };
+            offsets.sort_unstable_by(|a, b| b.cmp(a));
+            offsets.dedup();
+            offsets
+        }
+        None => DEFAULT_OFFSETS.to_vec(),
+    };
+
+    // Opt in:
+    student.set_reminders(gid, offsets.clone());
+
+    // Reply, as confirmation:
+    utils::reply(
+        ctx,
+        format!(
+            "You will receive a DM {} hour(s) before each deadline in this server. \
+        Make sure you allow DMs from this server's members!",
+            offsets
+                .iter()
+                .map(|offset| offset.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[remindme] Failed to send reply after user {} opted in to reminders: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    Ok(())
+}
@@ -17,312 +17,984 @@
  */
 extern crate reqwest;
 
-use crate::{student, utils, utils::get_guild_id, Context, Error};
-use poise::serenity_prelude as serenity;
+use crate::{
+    banner,
+    cache::Caches,
+    deadline,
+    events::{self, Event},
+    i18n::{self, tr},
+    requestrecord,
+    requestrecord::{RequestMetadata, RequestRecord},
+    service, student,
+    student::Student,
+    tablonclient::{self, ClientInvocation},
+    team,
+    team::Team,
+    utils, Context, Error,
+};
+use poise::serenity_prelude::{self as serenity, GuildId, UserId};
 use regex::Regex;
-use std::{io::Write, process::Command};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Maximum size of the programs sent to Tablón, in bytes.
+const MAX_PROGRAM_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Time to wait for the student to confirm a duplicate request, before cancelling it.
+const DUPLICATE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Downloads a program to the given path, streaming it to disk, and failing if it is larger than
+/// `max_size` bytes.
+///
+/// The partially downloaded file is removed on failure.
+async fn download_program(url: &str, path: &str, max_size: u64) -> Result<(), String> {
+    let result = async {
+        let mut response = reqwest::get(url)
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("could not download the program: {}", e))?;
+        let mut out_program = std::fs::File::create(path)
+            .map_err(|e| format!("could not create the program file: {}", e))?;
+
+        let mut size = 0;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("could not download the program: {}", e))?
+        {
+            size += chunk.len() as u64;
+            if size > max_size {
+                return Err(format!("the program is larger than {} bytes", max_size));
+            }
+            out_program
+                .write_all(&chunk)
+                .map_err(|e| format!("could not write the program file: {}", e))?;
+        }
 
-#[poise::command(
-    slash_command,
-    guild_only,
-    ephemeral,
-    description_localized("en-US", "Send a program to Tablón."),
-    description_localized("es-ES", "Send a program to Tablón.")
-)]
-#[hermes::log_cmd]
-pub async fn request(
-    ctx: Context<'_>,
-    #[description = "File to send to Tablón."] file: serenity::Attachment,
-    #[description = "Additional arguments to send to Tablón (queue, threads, processes, and program args)."]
-    extra_args: Option<String>,
-) -> Result<(), Error> {
-    let gid = get_guild_id!(ctx);
+        Ok(())
+    }
+    .await;
 
-    let mut student = utils::get_triggering_student!(ctx);
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
 
-    // Retrieve the credentials of the student, and handle the possible error:
-    let Some(credentials) = student.get_credentials(&gid) else {
-        ctx.reply("**Error:** You cannot send requests to Tablón, as you are not part of a team.")
-            .await
-            .expect(
-                format!(
-                    "[request] Failed to send reply to student with no credentials {}.",
-                    student.id()
-                )
-                .as_str(),
-            );
+    result
+}
 
-        return Ok(());
-    };
-    // Retrieve the team sending the request (from the credentials):
-    let team = credentials.team();
-    // Retrieve the password of the team (from the credentials), and handle the possible error:
-    let Some(password) = credentials.password() else {
-        ctx.reply("**Error:** You cannot send requests to Tablón, as your team has not been registered yet.")
-            .await
-            .expect(
-                format!(
-                    "[request] Failed to send reply to student with no password {}.",
-                    student.id()
-                )
-                .as_str(),
-            );
+/// Whether the surge protection before a deadline applies to the guild at the given UNIX timestamp
+/// (in seconds).
+pub fn in_surge(gid: &GuildId, config: &utils::BotConfig, now: i64) -> bool {
+    deadline::get_guild_deadlines(gid)
+        .in_surge(now, config.surge_minutes)
+        .is_some()
+}
 
-        return Ok(());
+/// Waits for the request's turn in the guild's local queue during the surge protection, so the
+/// guild's requests reach Tablón at least `BotConfig::surge_spacing` seconds apart, in order.
+async fn wait_surge_turn(submissions: &Submissions, gid: &GuildId, spacing: u32) {
+    let turn = {
+        let mut queue = submissions
+            .surge_queue
+            .lock()
+            .expect("The surge queue was poisoned.");
+        let now = Instant::now();
+        let turn = queue.get(gid).map_or(now, |next| (*next).max(now));
+        queue.insert(*gid, turn + Duration::from_secs(spacing as u64));
+        turn
     };
 
-    // Check if last command has to be used:
-    let mut extra_args = if let Some(given_args) = extra_args {
-        match given_args.as_str() {
-            "l" => {
-                if let Some(last_command) = student.get_last_command(&gid) {
-                    last_command.clone()
-                } else {
-                    ctx.reply(
-                        "**Error:** Can't send request without all arguments, as there is no previous command.",
-                    )
-                    .await
-                    .expect(
-                        format!(
-                            "[request] Failed to send reply to student {} with no previous command.",
-                            student.id()
-                        )
-                        .as_str(),
-                    );
-
-                    return Ok(());
-                }
-            }
-            _ => given_args,
-        }
+    tokio::time::sleep_until(turn.into()).await;
+}
+
+/// Asks the student whether to send a request that duplicates one recently sent by their team (see
+/// `service::find_duplicate`), with buttons to confirm or cancel it.
+///
+/// Returns whether it was confirmed; it is cancelled if no button is pressed in time.
+async fn confirm_duplicate(
+    ctx: Context<'_>,
+    lang: i18n::Language,
+    duplicate: &RequestMetadata,
+) -> bool {
+    let confirm_button_id = format!("{}confirm", ctx.id());
+    let cancel_button_id = format!("{}cancel", ctx.id());
+    let reply = utils::send(
+        ctx,
+        poise::CreateReply::default()
+            .content(tr!(
+                lang,
+                "**Warning:** <@{}> already sent this same program, with the same arguments, for \
+                your team <t:{}:R> (request #{}). Send it again anyway?",
+                "**Aviso:** <@{}> ya envió este mismo programa, con los mismos argumentos, de tu \
+                equipo <t:{}:R> (petición #{}). ¿Enviarlo de nuevo igualmente?",
+                duplicate.user(),
+                duplicate.timestamp(),
+                duplicate.id()
+            ))
+            .components(vec![serenity::CreateActionRow::Buttons(vec![
+                serenity::CreateButton::new(&confirm_button_id)
+                    .label(tr!(lang, "Send anyway", "Enviar igualmente"))
+                    .style(serenity::ButtonStyle::Primary),
+                serenity::CreateButton::new(&cancel_button_id)
+                    .label(tr!(lang, "Cancel", "Cancelar"))
+                    .style(serenity::ButtonStyle::Secondary),
+            ])]),
+    )
+    .await
+    .expect("[request] Failed to ask for the confirmation of a duplicate request.");
+
+    let author_id = ctx.author().id;
+    let button_ids = [confirm_button_id.clone(), cancel_button_id];
+    let press = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| {
+            button_ids.contains(&press.data.custom_id) && press.user.id == author_id
+        })
+        .timeout(DUPLICATE_TIMEOUT)
+        .await;
+    let confirmed = press
+        .as_ref()
+        .is_some_and(|press| press.data.custom_id == confirm_button_id);
+
+    // Remove the buttons, once used (or expired):
+    let content = if confirmed {
+        tr!(
+            lang,
+            "Sending the request again...",
+            "Enviando la petición de nuevo..."
+        )
     } else {
-        "".to_string()
+        tr!(lang, "Request cancelled.", "Petición cancelada.")
     };
-
-    // Add the queue to send the request to:
-    if !extra_args.contains("-q") {
-        if let Some(preferred_queue) = student.get_preferred_queue(&gid) {
-            extra_args = format!("-q {} {}", preferred_queue, extra_args);
-        } else {
-            ctx.reply(
-                "**Error:** Can't send request, as no queue was specified, and no preferred was set.",
+    match press {
+        Some(press) => press
+            .create_response(
+                ctx.serenity_context(),
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(Vec::new()),
+                ),
             )
             .await
-            .expect(
-                format!(
-                    "[request] Failed to send reply to student with unspecified queue {}.",
-                    student.id()
-                )
-                .as_str(),
-            );
-
-            return Ok(());
-        }
+            .expect("[request] Failed to update the confirmation of a duplicate request."),
+        None => reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(content)
+                    .components(Vec::new()),
+            )
+            .await
+            .expect("[request] Failed to remove the expired confirmation of a duplicate request."),
     }
 
-    // Arguments to append to the request:
-    let args = format!("-u {} -x {} {}", team, password, extra_args);
+    confirmed
+}
 
-    // Save the file to disk:
-    let Ok(mut out_program) = std::fs::File::create(format!("guilds/{}/{}", gid, file.filename))
-    else {
-        ctx.reply(
-            "**Error:** Failed to save your program to disk. Try again later, or contact an administrator.",
-        )
-        .await
-        .expect(
-            format!(
-                "[request] Failed to send reply to student {} with failed file creation.",
-                student.id(),
-            )
-            .as_str(),
-        );
+/// Creates a directory of its own for a submission, `guilds/<guild_id>/submissions/<random_id>/`,
+/// so programs with the same file name sent at the same time do not collide.
+fn create_submission_dir(gid: &GuildId) -> std::io::Result<String> {
+    let dir = format!("guilds/{}/submissions/{:016x}", gid, rand::random::<u64>());
+    std::fs::create_dir_all(&dir)?;
 
-        eprintln!(
-            "[request] Failed to save program file to disk, sent by student {}.",
-            student.id()
+    Ok(dir)
+}
+
+/// Removes the directory of a submission once done with it, unless the guild keeps them for
+/// debugging (see `BotConfig::keep_submissions`).
+fn remove_submission_dir(config: &utils::BotConfig, dir: &str) {
+    if config.keep_submissions {
+        return;
+    }
+    if let Err(e) = std::fs::remove_dir_all(dir) {
+        tracing::warn!(
+            "[request] Could not remove the submission directory {}: {}",
+            dir,
+            e
         );
+    }
+}
 
-        return Ok(());
+/// Prefix of the custom IDs of the retry buttons, followed by the guild and the student's IDs (e.g.
+/// `request:retry:<guild_id>:<user_id>`), so they are handled even after a restart.
+pub const RETRY_PREFIX: &str = "request:retry:";
+
+/// Last program sent by a student in a guild, kept to retry it (see `RETRY_PREFIX`).
+///
+/// It is saved as `guilds/<guild_id>/retry/<user_id>.json`, next to the program itself
+/// (`<user_id>.program`).
+#[derive(Serialize, Deserialize)]
+struct RetryRecord {
+    filename: String,
+    args: String,
+    /// Path of the client the program was sent with (the guild's default one, for records saved by
+    /// older versions of the bot).
+    #[serde(default)]
+    client: Option<String>,
+}
+
+/// Path of the files kept to retry a student's last request, without extension.
+fn retry_path(gid: &GuildId, uid: &UserId) -> String {
+    format!("guilds/{}/retry/{}", gid, uid)
+}
+
+/// Names of the clients of a guild (in `guilds/<guild_id>/clients/`), sorted.
+pub fn guild_clients(gid: &GuildId) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(format!("guilds/{}/clients", gid)) else {
+        return Vec::new();
     };
-    let file_bytes = reqwest::get(&file.url)
-        .await
-        .expect(format!("Could not download program from URL: {}", file.url).as_str())
-        .bytes()
-        .await
-        .expect("Could not get program bytes from http response.");
-    out_program
-        .write_all(&file_bytes)
-        .expect(format!("Could not save program to disk: {}", file.filename).as_str());
 
-    // TODO: Develop a way to conveniently set the client for a guild using Hermes.
-    // TODO: Add Hermes identification to files, for clout 😎
-    // TODO: Consider adding a request embed.
+    let mut clients: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    clients.sort();
 
-    // Equivalent CLI string:
-    let req_cmd_str = format!(
-        "guilds/{}/client guilds/{}/{} {}",
-        gid, gid, file.filename, args
-    );
+    clients
+}
+
+/// Path of the client to send a program with: the one picked by the student (if it exists), the
+/// one for the program's extension (see `BotConfig::client_extensions`), or the guild's default
+/// one.
+///
+/// Returns `None` if the picked client does not exist.
+fn resolve_client(
+    gid: &GuildId,
+    config: &utils::BotConfig,
+    picked: Option<&String>,
+    filename: &str,
+) -> Option<String> {
+    let name = match picked {
+        Some(name) => {
+            if !tablonclient::is_safe_filename(name) || !guild_clients(gid).contains(name) {
+                return None;
+            }
+            Some(name.clone())
+        }
+        None => std::path::Path::new(filename)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| config.client_extensions.get(&extension.to_lowercase()))
+            .cloned(),
+    };
+
+    Some(match name {
+        Some(name) => format!("guilds/{}/clients/{}", gid, name),
+        None => format!("guilds/{}/client", gid),
+    })
+}
+
+/// Outcome of sending a program to Tablón.
+enum Submission {
+    /// The team cannot send more requests for now.
+    OverBudget(service::BudgetError),
+    /// The client could not be run.
+    ClientFailed,
+    /// Tablón rejected the request, with the client's output.
+    Rejected(String),
+    /// The request was sent, with the client's output, and the URL of its page in Tablón (if it
+    /// could be found).
+    Sent { output: String, url: Option<String> },
+}
+
+/// State shared by the requests being sent to Tablón, owned by the framework's `Data`, and shared by
+/// its clones.
+#[derive(Clone, Default)]
+pub struct Submissions {
+    /// Serializes the reservations of requests in the teams' budgets (see
+    /// `service::reserve_submission`).
+    budget_lock: Arc<Mutex<()>>,
+    /// Earliest time the next request of each guild can reach Tablón during the surge protection
+    /// before a deadline (see `BotConfig::surge_minutes`).
+    surge_queue: Arc<Mutex<BTreeMap<GuildId, Instant>>>,
+}
+
+/// Sends the program (already saved in its own directory, see `create_submission_dir`) to the Tablón
+/// of the team's guild with the given invocation of the client, recording the request in the
+/// student's history and keeping the program to retry it. The submission's directory is removed
+/// afterwards.
+///
+/// The request is reserved in the team's budget before running the client, and released if it is
+/// not sent. During the surge protection before a deadline, the request waits its turn in the
+/// guild's local queue next (see `wait_surge_turn`).
+async fn submit(
+    submissions: &Submissions,
+    caches: &Caches,
+    config: &utils::BotConfig,
+    student: &mut Student,
+    team: &mut Team,
+    invocation: ClientInvocation,
+) -> Submission {
+    let gid = &team.guild().to_owned();
+    let program_path = std::path::Path::new(invocation.program());
+    let filename = &program_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extra_args = &tablonclient::join_args(invocation.args());
+
+    // Reserve the request (a teammate may have sent one since the team's budget was checked):
+    let storage = service::FileStorage(caches);
+    let reserved_at = chrono::Utc::now().timestamp();
+    let surge = in_surge(gid, config, reserved_at);
+    let previous = match service::reserve_submission(
+        &storage,
+        &submissions.budget_lock,
+        team,
+        student.id(),
+        reserved_at,
+        surge,
+    ) {
+        Ok(previous) => previous,
+        Err(e) => {
+            if let Some(dir) = program_path.parent() {
+                remove_submission_dir(config, &dir.display().to_string());
+            }
+
+            return Submission::OverBudget(e);
+        }
+    };
+    let uid = student.id();
+    let release = |team: &mut Team| {
+        service::release_submission(
+            &storage,
+            &submissions.budget_lock,
+            team,
+            uid,
+            reserved_at,
+            &previous,
+        )
+    };
+
+    if surge {
+        wait_surge_turn(submissions, gid, config.surge_spacing).await;
+    }
+
+    // Equivalent CLI string (without the password, so it can be logged):
+    let req_cmd_str = invocation.redacted();
 
     // Log request:
     let mut req_log = std::fs::OpenOptions::new()
         .append(true)
         .open(format!("guilds/{}/requests.log", gid))
-        .expect(
-            format!(
-                "[requests] Failed to open the guild's log file for guild {}.",
-                gid
+        .unwrap_or_else(|e| {
+            panic!(
+                "[requests] Failed to open the guild's log file for guild {}: {:?}",
+                gid, e
             )
-            .as_str(),
-        );
-    write!(
+        });
+    writeln!(
         req_log,
-        "Request received from {} ({}): {}\n",
+        "Request received from {} ({}): {}",
         student.name(),
         student.id(),
         req_cmd_str
     )
-    .expect(
-        format!(
-            "[requests] Failed to write to the guild's log file for guild {}.",
-            gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[requests] Failed to write to the guild's log file for guild {}: {:?}",
+            gid, e
         )
-        .as_str(),
-    );
-
-    // Construct the command, execute, and handle errors:
-    let mut cmd = Command::new(format!("guilds/{}/client", gid));
-    cmd.arg(format!("guilds/{}/{}", gid, file.filename));
-    for opt in args.split_whitespace() {
-        cmd.arg(opt);
+    });
+
+    // Keep the file sent to retry it (as sent by the student, without the banner):
+    let retry_path = retry_path(gid, &student.id());
+    std::fs::create_dir_all(format!("guilds/{}/retry", gid)).unwrap_or_else(|e| {
+        panic!(
+            "Could not create the retry directory of guild {}: {:?}",
+            gid, e
+        )
+    });
+    std::fs::copy(program_path, format!("{}.program", retry_path))
+        .unwrap_or_else(|e| panic!("Could not keep the program to retry: {}: {:?}", filename, e));
+    let record = RetryRecord {
+        filename: filename.clone(),
+        args: extra_args.to_string(),
+        client: Some(invocation.client().to_string()),
+    };
+    utils::write_file(
+        &format!("{}.json", retry_path),
+        serde_json::to_string(&record).expect("[request] Could not serialize a retry record."),
+    )
+    .unwrap_or_else(|e| {
+        panic!(
+            "Could not save the retry record of student {}: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    // Identify the program as sent through Hermes, if the guild asks for it:
+    if config.tag_submissions {
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let user = format!("{} ({})", student.name(), student.id());
+        if let Err(e) = banner::inject(program_path, team.id(), &user, &timestamp) {
+            tracing::warn!(
+                "[request] Could not add the banner to {}: {}",
+                program_path.display(),
+                e
+            );
+        }
     }
 
-    let req_output = cmd.output();
-
-    // Remove the file sent from disk:
-    std::fs::remove_file(format!("guilds/{}/{}", gid, file.filename))
-        .expect(format!("Could not remove file from disk: {}", file.filename).as_str());
+    // Execute the command, and remove the submission's directory:
+    let req_output = invocation.command().output().await;
+    if let Some(dir) = program_path.parent() {
+        remove_submission_dir(config, &dir.display().to_string());
+    }
 
     // Process the client's output:
     let Ok(req_output) = req_output else {
-        ctx.reply(
-            "**Error:** Failed to send request to Tablón. Try again later, or contact an administrator.",
-        )
-        .await
-        .expect(
-            format!(
-                "[request] Failed to send reply to student {} with failed client response for {}.",
-                student.id(),
-                req_cmd_str,
-            )
-            .as_str(),
-        );
-
-        eprintln!(
+        tracing::warn!(
             "[request] Failed to send request, triggered by student {} ({}). \
             Request: {}",
             student.id(),
             student.name(),
             req_cmd_str
         );
+        release(team);
 
-        return Ok(());
+        return Submission::ClientFailed;
     };
 
-    let stdout_str = String::from_utf8(req_output.stdout).expect(
-        format!(
-        "[request] Failed to transform the stdout of a request command to a string. Command: {}",
-        req_cmd_str,
-    )
-        .as_str(),
-    );
+    let stdout_str = String::from_utf8(req_output.stdout).unwrap_or_else(|e| {
+        panic!(
+            "[request] Failed to transform the stdout of a request command to a string. Command: {}: {:?}",
+            req_cmd_str, e
+        )
+    });
 
     // Check if there was an error, and continue processing if not:
-    if !stdout_str.contains("Error - ") {
-        ctx.reply(format!("Correctly sent the request:\n```{}```", stdout_str))
-            .await
-            .expect(
-                format!(
-                    "[request] Failed to send reply to student {} with successful client response for {}",
-                    student.id(),
-                    req_cmd_str,
-                )
-                .as_str(),
+    if stdout_str.contains("Error - ") {
+        release(team);
+
+        return Submission::Rejected(stdout_str);
+    }
+
+    // Save previous command:
+    student.set_last_command(*gid, extra_args.to_string());
+
+    // Save request id in the student's history.
+    let req_url = stdout_str
+        .lines()
+        .find(|line| line.starts_with("http"))
+        .map(str::to_string);
+    let req_regex = Regex::new(r"(\d+)$").expect("Failed to compile regex for request id.");
+    let Some(rid) = req_url
+        .as_ref()
+        .and_then(|req_url| req_regex.captures(req_url))
+        .and_then(|captures| captures.get(0))
+        .and_then(|rid| rid.as_str().parse::<u16>().ok())
+    else {
+        tracing::warn!(
+            "[request] Failed to find the request ID in the output of command {}\nOutput: {}",
+            req_cmd_str,
+            stdout_str,
+        );
+
+        return Submission::Sent {
+            output: stdout_str,
+            url: None,
+        };
+    };
+
+    let queue = requestrecord::queue_from_args(extra_args);
+    student.add_request(
+        gid,
+        config,
+        RequestRecord::new(rid, chrono::Utc::now().timestamp(), queue.clone()),
+    );
+    if let Err(e) = RequestMetadata::record(
+        gid,
+        rid,
+        team.id().clone(),
+        student.id(),
+        filename.clone(),
+        extra_args.to_string(),
+        &format!("{}.program", retry_path),
+    ) {
+        tracing::warn!(
+            "[request] Failed to record the metadata of request {} in guild {}: {}",
+            rid,
+            gid,
+            e
+        );
+    }
+    events::record(
+        gid,
+        Event::Submission {
+            team: team.id().clone(),
+            user: student.id(),
+            request_id: rid,
+            queue,
+            program: filename.clone(),
+        },
+    );
+
+    Submission::Sent {
+        output: stdout_str,
+        url: req_url,
+    }
+}
+
+/// Builds the reply to a submission: its outcome (with the requests the team has left for the day,
+/// if limited), a button to retry it, and a link to the request in Tablón (if it was sent).
+fn submission_reply(
+    submission: &Submission,
+    gid: &GuildId,
+    config: &utils::BotConfig,
+    student: &Student,
+    team: &Team,
+) -> (String, Vec<serenity::CreateActionRow>) {
+    let lang = i18n::user_language(student, config, gid);
+    let retry_button =
+        serenity::CreateButton::new(format!("{}{}:{}", RETRY_PREFIX, gid, student.id()))
+            .label(tr!(lang, "Retry", "Reintentar"))
+            .emoji('🔁');
+    let mut buttons = vec![retry_button];
+
+    let mut content = match submission {
+        Submission::OverBudget(e) => e.message(lang),
+        Submission::ClientFailed => tr!(
+            lang,
+            "**Error:** Failed to send request to Tablón. Try again later, or contact an administrator.",
+            "**Error:** No se pudo enviar la petición a Tablón. Inténtalo más tarde, o contacta con un administrador."
+        ),
+        Submission::Rejected(output) => tr!(
+            lang,
+            "**Error:** Incorrect request:\n```{}```",
+            "**Error:** Petición incorrecta:\n```{}```",
+            output
+        ),
+        Submission::Sent {
+            output,
+            url: Some(url),
+        } => {
+            buttons.push(
+                serenity::CreateButton::new_link(url)
+                    .label(tr!(lang, "View on Tablón", "Ver en Tablón")),
             );
+            tr!(
+                lang,
+                "Correctly sent the request:\n```{}```",
+                "Petición enviada correctamente:\n```{}```",
+                output
+            )
+        }
+        Submission::Sent { output, url: None } => tr!(
+            lang,
+            "Correctly sent the request:\n```{}```\n\
+            Ooops! I couldn't find the URL generated for your request. That's weird!\n\
+            However, it seems that the request itself was sent successfully.\n\
+            Please, check manually: <{}>",
+            "Petición enviada correctamente:\n```{}```\n\
+            ¡Vaya! No he encontrado la URL generada para tu petición. ¡Qué raro!\n\
+            Aun así, parece que la petición se envió correctamente.\n\
+            Por favor, compruébalo manualmente: <{}>",
+            output,
+            config.tablon_url
+        ),
+    };
+    let remaining = service::remaining_requests(team, config, chrono::Utc::now().timestamp());
+    if let (Submission::Sent { .. }, Some(remaining)) = (submission, remaining) {
+        content.push_str(&tr!(
+            lang,
+            "\nYour team can send {} more request(s) today.",
+            "\nTu equipo puede enviar {} petición(es) más hoy.",
+            remaining
+        ));
+    }
 
-        // Save previous command:
-        student.set_last_command(gid, extra_args);
-
-        // Save request id in the student's history.
-        let req_url = stdout_str.lines().find(|line| line.starts_with("http"));
-        let req_regex = Regex::new(r"(\d+)$").expect("Failed to compile regex for request id.");
-        if let Some(req_url) = req_url {
-            let rid = req_regex
-                .captures(req_url)
-                .expect(
-                    format!(
-                        "[request] Failed to find the request ID in the URL {}.",
-                        req_url,
-                    )
-                    .as_str(),
-                )
-                .get(0)
-                .expect(
-                    format!(
-                        "[request] Failed to find the request ID in the URL {}.",
-                        req_url,
-                    )
-                    .as_str(),
-                )
-                .as_str();
-            let rid = rid
-                .parse::<u16>()
-                .expect(format!("[request] Failed to parse the request ID {}.", rid).as_str());
+    (content, vec![serenity::CreateActionRow::Buttons(buttons)])
+}
 
-            student.add_request(&gid, rid);
-        } else {
-            let root_url = utils::load_config(&gid).tablon_url;
+/// Handles a press of a retry button (see `RETRY_PREFIX`), sending the student's last program again,
+/// with the same arguments.
+pub async fn handle_retry(
+    ctx: serenity::Context,
+    caches: Caches,
+    submissions: Submissions,
+    press: serenity::ComponentInteraction,
+) {
+    let ids = press
+        .data
+        .custom_id
+        .trim_start_matches(RETRY_PREFIX)
+        .split_once(':')
+        .and_then(|(gid, uid)| Some((gid.parse::<u64>().ok()?, uid.parse::<u64>().ok()?)));
+    let Some((gid, uid)) = ids.map(|(gid, uid)| (GuildId::new(gid), UserId::new(uid))) else {
+        return;
+    };
+
+    press
+        .defer_ephemeral(&ctx.http)
+        .await
+        .expect("[request] Failed to defer the response to a retry button.");
+    let followup = |content: String| {
+        serenity::CreateInteractionResponseFollowup::new()
+            .content(content)
+            .ephemeral(true)
+    };
+
+    // Only the student who sent the request can retry it, while still in a team with a password:
+    let team_and_password = student::get_student(&caches, &uid).and_then(|student| {
+        let team = team::get_team(&gid, &student.get_team_id(&gid)?)?;
+        let password = student.get_credentials(&gid)?.password().clone()?;
+        Some((student, team, password))
+    });
+    let record = utils::read_file(&format!("{}.json", retry_path(&gid, &uid)))
+        .ok()
+        .and_then(|json| serde_json::from_str::<RetryRecord>(&json).ok());
+    let (Some((mut student, mut team, password)), Some(record), true) =
+        (team_and_password, record, press.user.id == uid)
+    else {
+        let config = utils::load_config(&caches, &gid);
+        let lang = match student::get_student(&caches, &press.user.id) {
+            Some(student) => i18n::user_language(&student, &config, &gid),
+            None => i18n::guild_language(&config),
+        };
+        press
+            .create_followup(
+                &ctx.http,
+                followup(tr!(
+                    lang,
+                    "This request can no longer be retried.",
+                    "Esta petición ya no se puede reintentar."
+                )),
+            )
+            .await
+            .expect("[request] Failed to reply to a retry button that cannot be used.");
+
+        return;
+    };
 
-            ctx.reply(
-                format!(
-                    "Ooops! I couldn't find the URL generated for your request. That's weird!\n\
-                    However, it seems that the request itself was sent successfully.\n\
-                    Please, check manually: <{}>", root_url
+    // Check the arguments are still valid, and the team can still send requests:
+    let config = utils::load_config(&caches, &gid);
+    let args = match tablonclient::split_args(&record.args) {
+        Ok(args) => args,
+        Err(reason) => {
+            let lang = i18n::user_language(&student, &config, &gid);
+            press
+                .create_followup(
+                    &ctx.http,
+                    followup(service::RequestError::InvalidArgs(reason).message(lang)),
                 )
+                .await
+                .expect("[request] Failed to reply to a retry button with invalid arguments.");
+
+            return;
+        }
+    };
+    let now = chrono::Utc::now().timestamp();
+    let surge = in_surge(&gid, &config, now);
+    if let Err(e) = service::check_budget(&team, &config, now, surge) {
+        let lang = i18n::user_language(&student, &config, &gid);
+        press
+            .create_followup(&ctx.http, followup(e.message(lang)))
+            .await
+            .expect("[request] Failed to reply to a retry button over the team's budget.");
+
+        return;
+    }
+
+    // Put the program back in a submission directory, and send it again (with the same client):
+    let client = record
+        .client
+        .clone()
+        .unwrap_or_else(|| format!("guilds/{}/client", gid));
+    let program_path = create_submission_dir(&gid).and_then(|dir| {
+        let program_path = format!("{}/{}", dir, record.filename);
+        std::fs::copy(format!("{}.program", retry_path(&gid, &uid)), &program_path)?;
+        Ok(program_path)
+    });
+    let submission = match program_path {
+        Ok(program_path) => {
+            let invocation =
+                ClientInvocation::new(client, program_path, team.id().clone(), password, args);
+            submit(
+                &submissions,
+                &caches,
+                &config,
+                &mut student,
+                &mut team,
+                invocation,
+            )
+            .await
+        }
+        Err(_) => Submission::ClientFailed,
+    };
+
+    let (content, components) = submission_reply(&submission, &gid, &config, &student, &team);
+    press
+        .create_followup(&ctx.http, followup(content).components(components))
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[request] Failed to reply to student {} with the outcome of their retried request: {:?}",
+                uid, e
             )
+        });
+}
+
+/// Autocompletes the names of the guild's clients.
+async fn autocomplete_client(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let Some(gid) = ctx.guild_id() else {
+        return Vec::new();
+    };
+
+    guild_clients(&gid)
+        .into_iter()
+        .filter(|name| name.starts_with(partial))
+        .collect()
+}
+
+#[poise::command(
+    slash_command,
+    category = "Student",
+    guild_only,
+    ephemeral,
+    description_localized("en-US", "Send a program to Tablón."),
+    description_localized("es-ES", "Enviar un programa a Tablón.")
+)]
+#[hermes::require_team(password, mut)]
+#[hermes::log_cmd]
+pub async fn request(
+    ctx: Context<'_>,
+    #[description = "File to send to Tablón."] file: serenity::Attachment,
+    #[description = "Additional arguments to send to Tablón (queue, threads, processes, and program args)."]
+    extra_args: Option<String>,
+    #[description = "Client to send the program with, if the server has several."]
+    #[autocomplete = "autocomplete_client"]
+    client: Option<String>,
+) -> Result<(), Error> {
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let lang = i18n::user_language(&student, &config, &gid);
+
+    // Check the team can still send requests (the budget is shared by all its members):
+    let now = chrono::Utc::now().timestamp();
+    let surge = in_surge(&gid, &config, now);
+    if let Err(e) = service::check_budget(&team, &config, now, surge) {
+        utils::reply_error(ctx, e.message(lang))
             .await
-            .expect(
-                format!(
-                    "[request] Failed to send reply to student {} with failed request ID extraction for {}.",
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[request] Failed to send reply to student {} over their team's budget: {:?}",
                     student.id(),
-                    req_cmd_str,
+                    e
                 )
-                .as_str(),
-            );
+            });
 
-            eprintln!(
-                "[request] Failed to find the request ID in the output of command {}\nOutput: {}",
-                req_cmd_str, stdout_str,
-            );
+        return Ok(());
+    }
+
+    // Build the arguments, with the last command or the preferred queue if needed:
+    let (args, extra_args) = match service::request_args(&student, &gid, &config, extra_args) {
+        Ok(args) => args,
+        Err(e) => {
+            utils::reply_error(ctx, e.message(lang))
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[request] Failed to send reply to student {} with invalid arguments: {:?}",
+                        student.id(),
+                        e
+                    )
+                });
 
             return Ok(());
         }
-    } else {
-        ctx.reply(format!(
-            "**Error:** Incorrect request:\n```{}```",
-            stdout_str
-        ))
+    };
+
+    // Check the file name cannot point outside the guild's directory:
+    if !tablonclient::is_safe_filename(&file.filename) {
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "**Error:** Invalid file name. Use only letters, digits, and `-`, `_`, `.` or `+` \
+                (not at the start).",
+                "**Error:** Nombre de fichero no válido. Usa solo letras, dígitos, y `-`, `_`, `.` \
+                o `+` (no al principio)."
+            ),
+        )
         .await
-        .expect(
-            format!(
-                "[request] Failed to send reply to student {} with errored client response for {}",
+        .unwrap_or_else(|e| {
+            panic!(
+                "[request] Failed to send reply to student {} with an invalid file name: {:?}",
                 student.id(),
-                req_cmd_str,
+                e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Pick the client to send the program with:
+    let Some(client) = resolve_client(&gid, &config, client.as_ref(), &file.filename) else {
+        let mut clients = guild_clients(&gid)
+            .iter()
+            .map(|name| format!("`{}`", name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if clients.is_empty() {
+            clients = tr!(lang, "none", "ninguno");
+        }
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "**Error:** There is no such client in this server. Available clients: {}.",
+                "**Error:** No existe ese cliente en este servidor. Clientes disponibles: {}.",
+                clients
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[request] Failed to send reply to student {} with an unknown client: {:?}",
+                student.id(),
+                e
+            )
+        });
+
+        return Ok(());
+    };
+
+    // Reject programs over the size limit before downloading them:
+    if file.size as u64 > MAX_PROGRAM_SIZE {
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "**Error:** Your program is too large (the limit is {} KiB).",
+                "**Error:** Tu programa es demasiado grande (el límite es de {} KiB).",
+                MAX_PROGRAM_SIZE / 1024
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[request] Failed to send reply to student {} with a too large file: {:?}",
+                student.id(),
+                e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Save the file to disk, in a directory of its own:
+    let download = match create_submission_dir(&gid) {
+        Ok(dir) => {
+            let program_path = format!("{}/{}", dir, file.filename);
+            match download_program(&file.url, &program_path, MAX_PROGRAM_SIZE).await {
+                Ok(()) => Ok((dir, program_path)),
+                Err(e) => {
+                    remove_submission_dir(&config, &dir);
+                    Err(e)
+                }
+            }
+        }
+        Err(e) => Err(format!("could not create the submission directory: {}", e)),
+    };
+    let (dir, program_path) = match download {
+        Ok(download) => download,
+        Err(e) => {
+            utils::reply_error(
+                ctx,
+                tr!(
+                    lang,
+                    "**Error:** Failed to save your program to disk. Try again later, or contact an administrator.",
+                    "**Error:** No se pudo guardar tu programa en disco. Inténtalo más tarde, o contacta con un administrador."
+                ),
             )
-            .as_str(),
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[request] Failed to send reply to student {} with failed file download: {:?}",
+                    student.id(), e
+                )
+            });
+
+            tracing::warn!(
+                "[request] Failed to save program file to disk, sent by student {}: {}",
+                student.id(),
+                e
+            );
+
+            return Ok(());
+        }
+    };
+
+    // Ask for confirmation if the team recently sent the same program with the same arguments:
+    if config.duplicate_window > 0 {
+        let program = std::fs::read(&program_path).unwrap_or_default();
+        let hash = format!("{:016x}", utils::content_hash(&program));
+        let since = now - config.duplicate_window as i64 * 60;
+        let duplicate = service::find_duplicate(
+            &service::FileStorage(&ctx.data().caches),
+            &gid,
+            &team,
+            &hash,
+            &extra_args,
+            since,
         );
+        if let Some(duplicate) = duplicate {
+            if !confirm_duplicate(ctx, lang, &duplicate).await {
+                remove_submission_dir(&config, &dir);
+
+                return Ok(());
+            }
+        }
     }
 
+    // TODO: Develop a way to conveniently set the client for a guild using Hermes.
+
+    // The request may wait in the local queue during the surge protection, for longer than
+    // Discord waits for a reply:
+    if surge {
+        if utils::is_ephemeral(ctx) {
+            ctx.defer_ephemeral().await?;
+        } else {
+            ctx.defer().await?;
+        }
+    }
+
+    let invocation = ClientInvocation::new(client, program_path, team.id().clone(), password, args);
+    let submission = submit(
+        &ctx.data().submissions,
+        &ctx.data().caches,
+        &config,
+        &mut student,
+        &mut team,
+        invocation,
+    )
+    .await;
+
+    // Reply with the outcome, and the buttons to retry and view the request:
+    let (content, components) = submission_reply(&submission, &gid, &config, &student, &team);
+    let reply = poise::CreateReply::default()
+        .content(content)
+        .components(components);
+    let sent = match submission {
+        Submission::OverBudget(_) | Submission::ClientFailed | Submission::Rejected(_) => {
+            utils::send_error(ctx, reply).await
+        }
+        _ => utils::send(ctx, reply).await,
+    };
+    sent.unwrap_or_else(|e| {
+        panic!(
+            "[request] Failed to send reply to student {} with the outcome of their request: {:?}",
+            student.id(),
+            e
+        )
+    });
+
     Ok(())
 }
@@ -15,10 +15,34 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{student, utils, Context, Error};
-use std::fmt::Write;
+use crate::{
+    i18n::{self, tr, Language},
+    service, student,
+    teamnotify::TeamNotifications,
+    utils, Context, Error,
+};
+use poise::{serenity_prelude as serenity, CreateReply};
+use std::time::Duration;
 
-#[poise::command(slash_command, subcommands("get", "set_queue"), guild_only, ephemeral)]
+/// Time to wait for the password to be revealed in `/settings get`, before disabling the button.
+/// It is also disabled once pressed, as the password is only revealed once.
+const REVEAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[poise::command(
+    slash_command,
+    category = "Student",
+    subcommands(
+        "get",
+        "set_queue",
+        "clear_queue",
+        "clear_last_command",
+        "clear_all",
+        "language",
+        "team_notifications"
+    ),
+    guild_only,
+    ephemeral
+)]
 #[hermes::log_cmd]
 pub async fn settings(ctx: Context<'_>) -> Result<(), Error> {
     // This function will not be executed, as the command has subcommands.
@@ -29,12 +53,17 @@ pub async fn settings(ctx: Context<'_>) -> Result<(), Error> {
     slash_command,
     ephemeral,
     description_localized("en-US", "Print your current settings."),
-    description_localized("es-ES", "Print your current settings.")
+    description_localized("es-ES", "Mostrar tu configuración actual.")
 )]
 #[hermes::log_cmd]
 pub async fn get(ctx: Context<'_>) -> Result<(), Error> {
     let guild_id = utils::get_guild_id!(ctx);
     let student = utils::get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &guild_id),
+        &guild_id,
+    );
 
     let credentials_or_none = student.get_credentials(&guild_id);
     let queue_or_none = student.get_preferred_queue(&guild_id);
@@ -42,77 +71,445 @@ pub async fn get(ctx: Context<'_>) -> Result<(), Error> {
 
     // Construct reply message in function of what settings exist:
     // Team and password:
-    let mut reply_msg = "Your current settings for this server are:\n".to_string();
+    let mut reply_msg = tr!(
+        lang,
+        "Your current settings for this server are:\n",
+        "Tu configuración actual en este servidor es:\n"
+    );
     if let Some(credentials) = credentials_or_none {
         let team = credentials.team();
         let password_or_none = credentials.password();
-        write!(&mut reply_msg, "- Team: `{}`\n", team).unwrap();
-        if let Some(password) = password_or_none {
-            write!(&mut reply_msg, "- Password: ||`{}`||\n", password).unwrap();
+        reply_msg.push_str(&tr!(lang, "- Team: `{}`\n", "- Equipo: `{}`\n", team));
+        if password_or_none.is_some() {
+            // Hidden, to avoid accidental exposure (e.g. while sharing the screen):
+            reply_msg.push_str(&tr!(
+                lang,
+                "- Password: [Hidden]\n",
+                "- Contraseña: [Oculta]\n"
+            ));
         } else {
-            write!(&mut reply_msg, "- Password: [Not set]\n").unwrap();
+            reply_msg.push_str(&tr!(
+                lang,
+                "- Password: [Not set]\n",
+                "- Contraseña: [Sin asignar]\n"
+            ));
         }
     } else {
-        write!(&mut reply_msg, "- You are not in a team in this server\n").unwrap();
+        reply_msg.push_str(&tr!(
+            lang,
+            "- You are not in a team in this server\n",
+            "- No estás en ningún equipo en este servidor\n"
+        ));
     }
     // Queue:
     if let Some(queue) = queue_or_none {
-        write!(
-            &mut reply_msg,
+        reply_msg.push_str(&tr!(
+            lang,
             "- Default queue for requests: `{}`\n",
+            "- Cola por defecto para las peticiones: `{}`\n",
             queue
-        )
-        .unwrap();
+        ));
     } else {
-        write!(&mut reply_msg, "- Default queue for requests: [Not set]\n").unwrap();
+        reply_msg.push_str(&tr!(
+            lang,
+            "- Default queue for requests: [Not set]\n",
+            "- Cola por defecto para las peticiones: [Sin asignar]\n"
+        ));
     }
     // Last request command:
     if let Some(request) = request_or_none {
-        write!(&mut reply_msg, "- Last request command: `{}`\n", request).unwrap();
+        reply_msg.push_str(&tr!(
+            lang,
+            "- Last request command: `{}`\n",
+            "- Último comando de petición: `{}`\n",
+            request
+        ));
     }
 
-    // Reply, as confirmation:
-    ctx.reply(reply_msg).await.expect(
-        format!(
-            "[settings] Failed to send reply after user {} accessed their settings.",
-            student.id()
+    // Team notifications:
+    let notifications = match student.get_team_notifications(&guild_id) {
+        TeamNotifications::Dm => tr!(lang, "by DM", "por DM"),
+        TeamNotifications::Channel => tr!(lang, "in the bot channel", "en el canal del bot"),
+        TeamNotifications::Off => tr!(lang, "off", "desactivados"),
+    };
+    reply_msg.push_str(&tr!(
+        lang,
+        "- Notifications of changes in your team: {}\n",
+        "- Avisos de cambios en tu equipo: {}\n",
+        notifications
+    ));
+
+    // Reply, with a button for revealing the password, if any:
+    let reveal_button_id = format!("{}reveal", ctx.id());
+    let reveal_button = |disabled: bool| {
+        vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(&reveal_button_id)
+                .label(tr!(lang, "Reveal password", "Mostrar contraseña"))
+                .style(serenity::ButtonStyle::Secondary)
+                .disabled(disabled),
+        ])]
+    };
+    let mut reply = CreateReply::default().content(reply_msg.clone());
+    if credentials_or_none.is_some_and(|credentials| credentials.password().is_some()) {
+        reply = reply.components(reveal_button(false));
+    }
+    let handle = utils::send(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[settings] Failed to send reply after user {} accessed their settings: {:?}",
+            student.id(),
+            e
         )
-        .as_str(),
-    );
+    });
+
+    // Reveal the password in a separate message, when the button is pressed:
+    let Some(password) = credentials_or_none.and_then(|credentials| credentials.password().clone())
+    else {
+        return Ok(());
+    };
+    let author_id = ctx.author().id;
+    let filter_id = reveal_button_id.clone();
+    let press = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id == filter_id && press.user.id == author_id)
+        .timeout(REVEAL_TIMEOUT)
+        .await;
+
+    if let Some(press) = press {
+        press
+            .create_response(
+                ctx.serenity_context(),
+                serenity::CreateInteractionResponse::Message(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .content(tr!(
+                            lang,
+                            "Your team's password: ||`{}`||",
+                            "La contraseña de tu equipo: ||`{}`||",
+                            password
+                        ))
+                        .ephemeral(true),
+                ),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[settings] Failed to reveal the password to user {}: {:?}",
+                    student.id(),
+                    e
+                )
+            });
+    }
+
+    // Disable the button, whether it was pressed or it timed out (after answering the press):
+    if let Err(e) = handle
+        .edit(
+            ctx,
+            CreateReply::default()
+                .content(reply_msg)
+                .components(reveal_button(true)),
+        )
+        .await
+    {
+        tracing::warn!(
+            "[settings] Could not disable the button to reveal the password of user {}: {}",
+            student.id(),
+            e
+        );
+    }
 
     Ok(())
 }
 
+/// Autocompletes the names of the guild's queues.
+async fn autocomplete_queue(ctx: Context<'_>, partial: &str) -> Vec<String> {
+    let Some(gid) = ctx.guild_id() else {
+        return Vec::new();
+    };
+
+    service::guild_queues(&utils::load_config(&ctx.data().caches, &gid))
+        .into_iter()
+        .filter(|queue| queue.starts_with(partial))
+        .collect()
+}
+
 #[poise::command(
     slash_command,
     ephemeral,
     description_localized("en-US", "Change your default queue for requests."),
-    description_localized("es-ES", "Change your default queue for requests.")
+    description_localized("es-ES", "Cambiar tu cola por defecto para las peticiones.")
 )]
 #[hermes::log_cmd]
 pub async fn set_queue(
     ctx: Context<'_>,
-    #[description = "The queue to set as default for your requests."] queue: String,
+    #[description = "The queue to set as default for your requests."]
+    #[autocomplete = "autocomplete_queue"]
+    queue: String,
 ) -> Result<(), Error> {
     let guild_id = utils::get_guild_id!(ctx);
     let mut student = utils::get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &guild_id),
+        &guild_id,
+    );
 
     student.set_preferred_queue(guild_id, queue.clone());
 
     // Reply, as confirmation:
-    ctx.reply(format!(
-        "Your default queue for requests has been set to `{}`",
-        queue
-    ))
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Your default queue for requests has been set to `{}`",
+            "Tu cola por defecto para las peticiones es ahora `{}`",
+            queue
+        ),
+    )
     .await
-    .expect(
-        format!(
-            "[settings] Failed to send reply after user {} set their default queue to {}.",
+    .unwrap_or_else(|e| {
+        panic!(
+            "[settings] Failed to send reply after user {} set their default queue to {}: {:?}",
             student.id(),
-            queue
+            queue,
+            e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Remove your default queue for requests."),
+    description_localized("es-ES", "Eliminar tu cola por defecto para las peticiones.")
+)]
+#[hermes::log_cmd]
+pub async fn clear_queue(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = utils::get_guild_id!(ctx);
+    let mut student = utils::get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &guild_id),
+        &guild_id,
+    );
+
+    let reply = if student.clear_preferred_queue(&guild_id) {
+        tr!(
+            lang,
+            "Your default queue for requests has been removed.",
+            "Se ha eliminado tu cola por defecto para las peticiones."
+        )
+    } else {
+        tr!(
+            lang,
+            "You did not have a default queue for requests.",
+            "No tenías ninguna cola por defecto para las peticiones."
+        )
+    };
+
+    // Reply, as confirmation:
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[settings] Failed to send reply after user {} cleared their default queue: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Forget your last request command."),
+    description_localized("es-ES", "Olvidar tu último comando de petición.")
+)]
+#[hermes::log_cmd]
+pub async fn clear_last_command(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = utils::get_guild_id!(ctx);
+    let mut student = utils::get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &guild_id),
+        &guild_id,
+    );
+
+    let reply = if student.clear_last_command(&guild_id) {
+        tr!(
+            lang,
+            "Your last request command has been forgotten.",
+            "Se ha olvidado tu último comando de petición."
+        )
+    } else {
+        tr!(
+            lang,
+            "You did not have a last request command.",
+            "No tenías ningún último comando de petición."
         )
-        .as_str(),
+    };
+
+    // Reply, as confirmation:
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[settings] Failed to send reply after user {} cleared their last command: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Remove your default queue and forget your last request command."
+    ),
+    description_localized(
+        "es-ES",
+        "Eliminar tu cola por defecto y olvidar tu último comando de petición."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn clear_all(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = utils::get_guild_id!(ctx);
+    let mut student = utils::get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &guild_id),
+        &guild_id,
     );
 
+    student.clear_preferred_queue(&guild_id);
+    student.clear_last_command(&guild_id);
+
+    // Reply, as confirmation:
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Your default queue and last request command have been cleared.",
+            "Se han eliminado tu cola por defecto y tu último comando de petición."
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[settings] Failed to send reply after user {} cleared their settings: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Change the language of the replies only you can see."),
+    description_localized("es-ES", "Cambiar el idioma de las respuestas que solo ves tú.")
+)]
+#[hermes::log_cmd]
+pub async fn language(
+    ctx: Context<'_>,
+    #[description = "The language to use. Leave empty to use the server's default."]
+    language: Option<Language>,
+) -> Result<(), Error> {
+    let guild_id = utils::get_guild_id!(ctx);
+    let mut student = utils::get_triggering_student!(ctx);
+
+    match language {
+        Some(language) => student.set_language(guild_id, language),
+        None => {
+            student.clear_language(&guild_id);
+        }
+    }
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &guild_id),
+        &guild_id,
+    );
+
+    // Reply, as confirmation:
+    let reply = match language {
+        Some(_) => tr!(
+            lang,
+            "I will reply to you in English from now on.",
+            "A partir de ahora te responderé en español."
+        ),
+        None => tr!(
+            lang,
+            "I will reply to you in the server's default language from now on.",
+            "A partir de ahora te responderé en el idioma por defecto del servidor."
+        ),
+    };
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[settings] Failed to send reply after user {} changed their language: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Choose how to be told when someone joins or leaves your team, or it is renamed."
+    ),
+    description_localized(
+        "es-ES",
+        "Elegir cómo enterarte de cuándo alguien entra o sale de tu equipo, o se renombra."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn team_notifications(
+    ctx: Context<'_>,
+    #[description = "How to be told of the changes in your team (default: DM)."]
+    mode: TeamNotifications,
+) -> Result<(), Error> {
+    let guild_id = utils::get_guild_id!(ctx);
+    let mut student = utils::get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &guild_id),
+        &guild_id,
+    );
+
+    student.set_team_notifications(guild_id, mode);
+
+    // Reply, as confirmation:
+    let reply = match mode {
+        TeamNotifications::Dm => tr!(
+            lang,
+            "I will tell you of the changes in your team by DM.",
+            "Te avisaré de los cambios en tu equipo por DM."
+        ),
+        TeamNotifications::Channel => tr!(
+            lang,
+            "I will tell you of the changes in your team by pinging you in the bot channel.",
+            "Te avisaré de los cambios en tu equipo mencionándote en el canal del bot."
+        ),
+        TeamNotifications::Off => tr!(
+            lang,
+            "I will no longer tell you of the changes in your team.",
+            "Ya no te avisaré de los cambios en tu equipo."
+        ),
+    };
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[settings] Failed to send reply after user {} changed their team notifications: {:?}",
+            student.id(),
+            e
+        )
+    });
+
     Ok(())
 }
@@ -0,0 +1,247 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    commands::teamdump,
+    requestrecord::{self, RequestMetadata},
+    team, utils,
+    utils::get_guild_id,
+    Context, Error,
+};
+use poise::{serenity_prelude::CreateAttachment, CreateReply};
+use std::{collections::BTreeMap, fmt::Write as _, fs};
+
+/// Largest bundle sent as an attachment; bigger ones are left in the data directory instead.
+const MAX_ATTACHMENT_SIZE: usize = 8 * 1024 * 1024;
+
+/// Directory of the bundle for the requests sent without a known queue.
+const NO_QUEUE_DIR: &str = "_no_queue";
+
+/// Makes a queue or file name safe to use as a path component in the bundle.
+fn path_component(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.+".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() || name.starts_with('.') {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+/// Writes an octal field of a tar header, NUL-terminated.
+fn tar_octal(field: &mut [u8], value: u64) {
+    let width = field.len() - 1;
+    let digits = format!("{:0width$o}", value, width = width);
+    field[..width].copy_from_slice(digits.as_bytes());
+}
+
+/// Packs files (given by their path in the archive) into an uncompressed tar archive, as expected
+/// by `tar -xf`.
+fn tar(files: &[(String, Vec<u8>)], mtime: i64) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (path, contents) in files {
+        let mut header = [0u8; 512];
+        // Long paths are split into a prefix (the directories) and a name:
+        let (prefix, name) = if path.len() > 100 {
+            path.rsplit_once('/').unwrap_or(("", path))
+        } else {
+            ("", path.as_str())
+        };
+        header[..name.len().min(100)].copy_from_slice(&name.as_bytes()[..name.len().min(100)]);
+        tar_octal(&mut header[100..108], 0o644);
+        tar_octal(&mut header[108..116], 0);
+        tar_octal(&mut header[116..124], 0);
+        tar_octal(&mut header[124..136], contents.len() as u64);
+        tar_octal(&mut header[136..148], mtime.max(0) as u64);
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len().min(155)]
+            .copy_from_slice(&prefix.as_bytes()[..prefix.len().min(155)]);
+        // The checksum is computed with its own field as spaces:
+        header[148..156].fill(b' ');
+        let checksum: u32 = header.iter().map(|byte| *byte as u32).sum();
+        tar_octal(&mut header[148..155], checksum as u64);
+
+        out.extend_from_slice(&header);
+        out.extend_from_slice(contents);
+        out.resize(out.len().next_multiple_of(512), 0);
+    }
+    // End of the archive:
+    out.resize(out.len() + 1024, 0);
+
+    out
+}
+
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    guild_only,
+    default_member_permissions = "MANAGE_GUILD",
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Export the latest program of each team per exercise, laid out for MOSS or JPlag."
+    ),
+    description_localized(
+        "es-ES",
+        "Exportar el último programa de cada equipo por ejercicio, organizado para MOSS o JPlag."
+    )
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn sourcedump(
+    ctx: Context<'_>,
+    #[description = "Only export the programs sent to this queue."] queue: Option<String>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+
+    // The students' programs are only used beyond sending them if the guild retains them:
+    if !config.keep_submissions {
+        utils::reply_error(
+            ctx,
+            "**Error:** This guild does not retain the submitted programs. Enable it with \
+            `/botconfig keep_submissions` first.",
+        )
+        .await
+        .expect("[sourcedump] Could not send error message for disabled file retention.");
+
+        return Ok(());
+    }
+    ctx.defer_ephemeral().await?;
+
+    // Latest request of each team on each exercise (by queue):
+    let mut latest: BTreeMap<(String, String), RequestMetadata> = BTreeMap::new();
+    for request in requestrecord::get_all_metadata(&gid) {
+        let exercise = request
+            .queue()
+            .as_deref()
+            .map_or(NO_QUEUE_DIR.to_string(), path_component);
+        if queue
+            .as_ref()
+            .is_some_and(|queue| request.queue().as_ref() != Some(queue))
+        {
+            continue;
+        }
+        let key = (exercise, request.team().clone());
+        if latest
+            .get(&key)
+            .is_none_or(|other| other.timestamp() <= request.timestamp())
+        {
+            latest.insert(key, request);
+        }
+    }
+
+    // One directory per exercise, with one directory per team holding its program, as MOSS and
+    // JPlag expect, and an index mapping the directories to the teams:
+    let mut files = Vec::new();
+    let mut index =
+        "directory,exercise,team,team_name,request_id,user,sent_at,verdict\n".to_string();
+    let mut missing = 0;
+    for ((exercise, team_id), request) in latest.iter() {
+        let Ok(program) = fs::read(requestrecord::metadata_program_path(&gid, request.id())) else {
+            missing += 1;
+            continue;
+        };
+        let dir = format!("{}/{}", exercise, path_component(team_id));
+        files.push((
+            format!("sources/{}/{}", dir, path_component(request.filename())),
+            program,
+        ));
+
+        let team_name = team::get_team(&gid, team_id)
+            .map(|team| team.name().clone())
+            .unwrap_or_default();
+        writeln!(
+            index,
+            "{},{},{},{},{},{},{},{}",
+            teamdump::csv_field(&dir),
+            teamdump::csv_field(request.queue().as_deref().unwrap_or_default()),
+            teamdump::csv_field(team_id),
+            teamdump::csv_field(&team_name),
+            request.id(),
+            request.user(),
+            request.timestamp(),
+            teamdump::csv_field(request.verdict().as_deref().unwrap_or_default())
+        )
+        .unwrap();
+    }
+    if files.is_empty() {
+        utils::reply(ctx, "There are no retained programs to export.")
+            .await
+            .expect("[sourcedump] Could not send message for no programs to export.");
+
+        return Ok(());
+    }
+    let exported = files.len();
+    files.insert(0, ("sources/index.csv".to_string(), index.into_bytes()));
+
+    let now = chrono::Utc::now();
+    let bundle = tar(&files, now.timestamp());
+    let mut summary = format!(
+        "Exported the latest program of {} team/exercise pair(s), with `index.csv` mapping each \
+        directory to its team.",
+        exported
+    );
+    if missing > 0 {
+        summary.push_str(&format!(
+            " {} program(s) could not be read, and were skipped.",
+            missing
+        ));
+    }
+
+    // Attach the bundle, or leave it in the data directory if it is too big to send:
+    let file_name = format!("sources-{}.tar", now.format("%Y-%m-%dT%H-%M-%SZ"));
+    let msg = if bundle.len() <= MAX_ATTACHMENT_SIZE {
+        Ok(CreateReply::default()
+            .content(summary)
+            .attachment(CreateAttachment::bytes(bundle, file_name)))
+    } else {
+        let path = format!("guilds/{}/exports/{}", gid, file_name);
+        let saved = fs::create_dir_all(format!("guilds/{}/exports", gid))
+            .and_then(|_| utils::write_file(&path, bundle));
+        match saved {
+            Ok(()) => Ok(CreateReply::default().content(format!(
+                "{}\nThe bundle is too big to attach, so it was saved as `{}` in the bot's data \
+                directory.",
+                summary, path
+            ))),
+            Err(e) => {
+                tracing::warn!("[sourcedump] Could not save {}: {}", path, e);
+                Err(CreateReply::default().content(
+                    "**Error:** The bundle is too big to attach, and could not be saved either.",
+                ))
+            }
+        }
+    };
+    let sent = match msg {
+        Ok(msg) => utils::send(ctx, msg.ephemeral(true)).await,
+        Err(msg) => utils::send_error(ctx, msg.ephemeral(true)).await,
+    };
+    sent.expect("[sourcedump] Could not send the message with the programs bundle.");
+
+    Ok(())
+}
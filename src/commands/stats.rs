@@ -0,0 +1,402 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    activitylog::{self, ActivityEntry},
+    i18n::{self, Language},
+    requestrecord::RequestRecord,
+    student, tablon, team,
+    utils::{self, get_guild_id, get_triggering_student, BotConfig},
+    Context, Error,
+};
+use chrono::{NaiveDate, TimeZone, Utc};
+use poise::{serenity_prelude as serenity, CreateReply};
+use std::{collections::BTreeMap, collections::HashMap, fmt::Write};
+
+/// Number of days shown in the over-time charts.
+const CHART_DAYS: i64 = 14;
+
+/// Number of teams shown in the most active teams ranking.
+const TOP_TEAMS: usize = 5;
+
+/// Counts the given timestamps per day (in the guild's timezone), for the last `CHART_DAYS` days.
+fn count_per_day(
+    config: &BotConfig,
+    timestamps: impl Iterator<Item = i64>,
+) -> BTreeMap<NaiveDate, usize> {
+    let tz = utils::config_timezone(config);
+    let today = Utc::now().with_timezone(&tz).date_naive();
+    let mut per_day = BTreeMap::new();
+    for i in 0..CHART_DAYS {
+        per_day.insert(today - chrono::Duration::days(i), 0usize);
+    }
+    for ts in timestamps {
+        if let Some(date) = tz.timestamp_opt(ts, 0).single().map(|dt| dt.date_naive()) {
+            if let Some(count) = per_day.get_mut(&date) {
+                *count += 1;
+            }
+        }
+    }
+
+    per_day
+}
+
+/// Counts the failed commands in an activity log (i.e. those that returned an error, replied with
+/// one, or panicked; see `utils::record_cmd_outcome`), per command.
+fn count_errors(activity: &[ActivityEntry]) -> BTreeMap<&String, usize> {
+    let mut errors_per_command = BTreeMap::new();
+    for entry in activity.iter().filter(|e| !e.success()) {
+        *errors_per_command.entry(entry.command()).or_default() += 1;
+    }
+
+    errors_per_command
+}
+
+/// Renders a per-day count as a text bar chart, in a code block.
+fn per_day_chart(lang: Language, per_day: &BTreeMap<NaiveDate, usize>) -> String {
+    let mut chart = String::from("```\n");
+    for (date, count) in per_day {
+        writeln!(
+            &mut chart,
+            "{} {:>3} {}",
+            i18n::format_day(lang, date),
+            count,
+            "█".repeat((*count).min(40))
+        )
+        .unwrap();
+    }
+    chart.push_str("```");
+
+    chart
+}
+
+#[poise::command(
+    slash_command,
+    subcommands("me", "guild"),
+    subcommand_required,
+    guild_only
+)]
+pub async fn stats(_: Context<'_>) -> Result<(), Error> {
+    // This function will not be executed, as the command has subcommands.
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    category = "Student",
+    ephemeral,
+    description_localized("en-US", "Show statistics about your (or your team's) requests."),
+    description_localized(
+        "es-ES",
+        "Mostrar estadísticas de tus peticiones (o las de tu equipo)."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn me(
+    ctx: Context<'_>,
+    #[description = "Show the statistics of your whole team, instead of just yours."] team: Option<
+        bool,
+    >,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let student = get_triggering_student!(ctx);
+    let lang = i18n::user_language(&student, &config, &gid);
+
+    // Collect the requests to aggregate:
+    let mut requests = Vec::<RequestRecord>::new();
+    let title = if team.unwrap_or(false) {
+        let Some(team_id) = student.get_team_id(&gid) else {
+            utils::reply_error(ctx, "You are not in a team in this server.")
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[stats] Failed to send reply after user {} requested team statistics \
+                        without being in a team: {:?}",
+                        student.id(),
+                        e
+                    )
+                });
+
+            return Ok(());
+        };
+        let team = team::get_existing_team!(&gid, &team_id);
+        for member in team.members() {
+            requests.extend(
+                student::get_existing_student!(&ctx.data().caches, member)
+                    .full_request_history(&gid),
+            );
+        }
+        format!("Statistics for team {}", team.name())
+    } else {
+        requests.extend(student.full_request_history(&gid));
+        "Your statistics".to_string()
+    };
+
+    if requests.is_empty() {
+        utils::reply(
+            ctx,
+            "There are no requests sent through Hermes to compute statistics from.",
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[stats] Failed to send reply after user {} requested empty statistics: {:?}",
+                student.id(),
+                e
+            )
+        });
+
+        return Ok(());
+    }
+
+    // Success/failure breakdown:
+    let (mut successes, mut failures, mut unknown) = (0, 0, 0);
+    for req in requests.iter() {
+        match req.verdict() {
+            Some(verdict) if tablon::is_failure(verdict) => failures += 1,
+            Some(_) => successes += 1,
+            None => unknown += 1,
+        }
+    }
+
+    // Favorite queue:
+    let mut queue_counts = HashMap::<&String, usize>::new();
+    for queue in requests.iter().filter_map(|req| req.queue().as_ref()) {
+        *queue_counts.entry(queue).or_default() += 1;
+    }
+    let favorite_queue = queue_counts.into_iter().max_by_key(|(_, count)| *count);
+
+    // Best result (lowest reported result among the successful requests):
+    let best = requests
+        .iter()
+        .filter_map(|req| {
+            let verdict = req.verdict().as_ref()?;
+            if tablon::is_failure(verdict) {
+                return None;
+            }
+            tablon::verdict_result(verdict).map(|result| (req, result))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    // Submissions over time (per day, in the guild's timezone):
+    let per_day = count_per_day(&config, requests.iter().filter_map(|req| req.timestamp()));
+
+    // Construct the reply:
+    let mut reply = format!("**{}:**\n", title);
+    writeln!(&mut reply, "- Requests: {}", requests.len()).unwrap();
+    writeln!(
+        &mut reply,
+        "- Successful: {} · Failed: {} · Unknown verdict: {}",
+        successes, failures, unknown
+    )
+    .unwrap();
+    if let Some((queue, count)) = favorite_queue {
+        writeln!(
+            &mut reply,
+            "- Favorite queue: `{}` ({} request(s))",
+            queue, count
+        )
+        .unwrap();
+    }
+    if let Some((req, _)) = best {
+        writeln!(
+            &mut reply,
+            "- Best result: [#{}](<{}/request?rid={}>) ({})",
+            req.id(),
+            config.tablon_url,
+            req.id(),
+            i18n::localize_decimals(lang, req.verdict().as_deref().unwrap_or_default())
+        )
+        .unwrap();
+    }
+    write!(
+        &mut reply,
+        "**Requests in the last {} days:**\n{}",
+        CHART_DAYS,
+        per_day_chart(lang, &per_day)
+    )
+    .unwrap();
+
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[stats] Failed to send the statistics to user {}: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    category = "Admin",
+    required_permissions = "MANAGE_GUILD",
+    ephemeral,
+    description_localized("en-US", "Show usage statistics of the bot in this server."),
+    description_localized("es-ES", "Mostrar estadísticas de uso del bot en este servidor.")
+)]
+#[hermes::log_cmd]
+pub async fn guild(
+    ctx: Context<'_>,
+    #[description = "Also attach the statistics as a CSV file."] csv: Option<bool>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+
+    // Commands and errors, from the activity log:
+    let activity = activitylog::load(&gid);
+    let commands_per_day = count_per_day(&config, activity.iter().map(|e| e.timestamp()));
+    let errors_per_command = count_errors(&activity);
+    let error_count: usize = errors_per_command.values().sum();
+
+    // Submissions per team and queue, from the students' request histories:
+    let mut requests_per_team = HashMap::<String, usize>::new();
+    let mut requests_per_queue = BTreeMap::<String, usize>::new();
+    let mut request_count = 0;
+    for student in student::get_all_students(&ctx.data().caches) {
+        let hist = student.full_request_history(&gid);
+        request_count += hist.len();
+        if let Some(team_id) = student.get_team_id(&gid) {
+            *requests_per_team.entry(team_id).or_default() += hist.len();
+        }
+        for req in hist.iter() {
+            let queue = req.queue().clone().unwrap_or_else(|| "?".to_string());
+            *requests_per_queue.entry(queue).or_default() += 1;
+        }
+    }
+    let mut top_teams = requests_per_team.into_iter().collect::<Vec<_>>();
+    top_teams.sort_by(|(a_id, a), (b_id, b)| b.cmp(a).then_with(|| a_id.cmp(b_id)));
+    top_teams.truncate(TOP_TEAMS);
+
+    // Construct the embed:
+    let list_or_none = |list: String| {
+        if list.is_empty() {
+            "None.".to_string()
+        } else {
+            list
+        }
+    };
+    let mut teams_list = String::new();
+    for (i, (team_id, count)) in top_teams.iter().enumerate() {
+        writeln!(
+            &mut teams_list,
+            "{}. `{}`: {} request(s)",
+            i + 1,
+            team_id,
+            count
+        )
+        .unwrap();
+    }
+    let mut queues_list = String::new();
+    for (queue, count) in requests_per_queue.iter() {
+        writeln!(&mut queues_list, "- `{}`: {}", queue, count).unwrap();
+    }
+    let mut errors_list = String::new();
+    for (command, count) in errors_per_command.iter() {
+        writeln!(&mut errors_list, "- `/{}`: {}", command, count).unwrap();
+    }
+    let embed = serenity::CreateEmbed::new()
+        .title("Server statistics")
+        .description(format!(
+            "Commands run: {} · Failed: {} · Requests sent: {}",
+            activity.len(),
+            error_count,
+            request_count
+        ))
+        .field(
+            format!("Commands in the last {} days", CHART_DAYS),
+            per_day_chart(i18n::guild_language(&config), &commands_per_day),
+            false,
+        )
+        .field("Most active teams", list_or_none(teams_list), true)
+        .field("Requests per queue", list_or_none(queues_list), true)
+        .field("Errors per command", list_or_none(errors_list), false);
+    let mut reply = CreateReply::default().embed(embed);
+
+    // Attach the CSV file, if requested:
+    if csv.unwrap_or(false) {
+        let mut content = String::from("metric,key,count\n");
+        for (date, count) in commands_per_day.iter() {
+            writeln!(&mut content, "commands_per_day,{},{}", date, count).unwrap();
+        }
+        for (team_id, count) in top_teams.iter() {
+            writeln!(&mut content, "requests_per_team,{},{}", team_id, count).unwrap();
+        }
+        for (queue, count) in requests_per_queue.iter() {
+            writeln!(&mut content, "requests_per_queue,{},{}", queue, count).unwrap();
+        }
+        for (command, count) in errors_per_command.iter() {
+            writeln!(&mut content, "errors_per_command,{},{}", command, count).unwrap();
+        }
+        reply = reply.attachment(serenity::CreateAttachment::bytes(
+            content.into_bytes(),
+            format!("stats_{}.csv", gid),
+        ));
+    }
+
+    utils::send(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[stats] Failed to send the server statistics for guild {}: {:?}",
+            gid, e
+        )
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testutil::TempDataDir,
+        utils::{record_cmd_outcome, CmdOutcome},
+    };
+    use std::{fs, time::Duration};
+
+    #[test]
+    fn error_replies_and_panics_count_as_errors() {
+        let _dir = TempDataDir::new("stats-errors");
+        let gid = serenity::GuildId::new(1);
+        let user = serenity::UserId::new(2);
+        fs::create_dir_all(format!("guilds/{}", gid)).unwrap();
+        let record = |command: &str, outcome| {
+            record_cmd_outcome(
+                Some(gid),
+                user,
+                command,
+                format!("/{}", command),
+                Duration::from_millis(10),
+                outcome,
+            )
+        };
+        record("team create", CmdOutcome::Completed);
+        record("team join", CmdOutcome::ErrorReply);
+        record("request", CmdOutcome::Panicked(Some("boom")));
+        record("request", CmdOutcome::Failed(&"no such queue"));
+        record("request", CmdOutcome::Completed);
+
+        let activity = activitylog::load(&gid);
+        assert_eq!(activity.len(), 5);
+        let errors = count_errors(&activity);
+        assert_eq!(errors.get(&"team join".to_string()), Some(&1));
+        assert_eq!(errors.get(&"request".to_string()), Some(&2));
+        assert_eq!(errors.get(&"team create".to_string()), None);
+        assert_eq!(errors.values().sum::<usize>(), 3);
+    }
+}
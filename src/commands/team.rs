@@ -16,18 +16,32 @@
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 use crate::{
-    student, team,
+    i18n::{self, tr},
+    service::{self, FileStorage},
+    student::{self, Student},
     team::Team,
+    teamnotify::{self, TeamChange},
+    teamrequest::{self, RequestStatus},
     utils::{self, get_guild_id, get_triggering_student},
     Context, Error,
 };
-use poise::serenity_prelude::User;
+use poise::serenity_prelude::{User, UserId};
 
 // TODO: Do something with default member permissions for student commands.
 
 #[poise::command(
     slash_command,
-    subcommands("create", "invite", "invitations", "join", "leave", "rename"),
+    category = "Team",
+    subcommands(
+        "create",
+        "invite",
+        "invitations",
+        "decline",
+        "join",
+        "leave",
+        "rename",
+        "privacy"
+    ),
     subcommand_required,
     guild_only
 )]
@@ -45,7 +59,7 @@ pub async fn team(_: Context<'_>) -> Result<(), Error> {
     ),
     description_localized(
         "es-ES",
-        "Create and join a new team, and invite other students to join it."
+        "Crear un equipo nuevo y unirte a él, e invitar a otros estudiantes a unirse."
     )
 )]
 #[hermes::log_cmd]
@@ -55,115 +69,77 @@ pub async fn create(
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
     let mut student = get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
+    );
 
-    // Get if the user is already in a team:
-    if student.get_team_id(&gid).is_some() {
-        ctx.reply("You are already in a team in this server.")
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to create another team.",
-                    student.id()
-                )
-                .as_str(),
-            );
-
-        return Ok(());
-    }
-
-    // Check the amount of invited students do not exceed the allowed time size:
-    let config = utils::load_config(&gid);
-    if others.len() > (config.team_capacity - 1) as usize {
-        ctx.reply(format!(
-            "You can only invite up to {} other student(s) to the team.",
-            config.team_capacity - 1
-        ))
-        .await
-        .expect(
-            format!(
-                "[team] Failed to send reply after user {} tried to invite more students than \
-                allowed to a new team.",
-                student.id()
-            )
-            .as_str(),
-        );
-
-        return Ok(());
-    }
-
-    // Collect the students that can be invited:
-    let mut students_to_invite = Vec::with_capacity(others.len());
-    for other in others {
-        if other.id == student.id() {
-            ctx.reply("You cannot invite yourself to your own team.")
-                .await
-                .expect(
-                    format!(
-                        "[team] Failed to send reply after user {} tried to invite themself to \
-                        their own team.",
-                        student.id()
+    // Create the team, and invite the other students:
+    let others = others.iter().map(|other| other.id).collect::<Vec<_>>();
+    let (team, skipped) =
+        match service::create_team(&FileStorage(&ctx.data().caches), gid, &mut student, &others) {
+            Ok(created) => created,
+            Err(e) => {
+                utils::reply_error(ctx, e.message(lang))
+                    .await
+                    .unwrap_or_else(|e| {
+                        panic!(
+                        "[team] Failed to send reply after user {} failed to create a team: {:?}",
+                        student.id(),
+                        e
                     )
-                    .as_str(),
-                );
-
-            continue;
-        }
-
-        let other_student = student::get_existing_student!(&other.id);
+                    });
 
-        // Check if the student is already in a team:
-        if other_student.get_team_id(&gid).is_some() {
-            ctx.reply(format!(
-                "<@{}> is already in a team in this server.",
-                other.id
-            ))
+                return Ok(());
+            }
+        };
+    for skipped in skipped.iter() {
+        utils::reply(ctx, skipped.message(lang))
             .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to invite to their team \
-                    a student already in another team.",
-                    student.id()
-                )
-                .as_str(),
-            );
-
-            continue;
-        }
-
-        // Add the students to the list of students to invite when the team is created:
-        students_to_invite.push(other_student);
-    }
-
-    // Create guild's team info, if it does not exist:
-    if team::get_guild_team_info(&gid).is_none() {
-        team::GuildTeamInfo::new(gid, config.team_prefix);
-    }
-
-    // Create team:
-    let mut team = Team::new(gid, team::register_team(&gid));
-    team.add_member(&mut student);
-
-    // Send the invitations:
-    for mut other_student in students_to_invite {
-        other_student.add_team_request(gid, team.id().clone(), student.id().clone());
+            .unwrap_or_else(|e| {
+                panic!(
+                "[team] Failed to send reply after user {} invited a student that could not be \
+                invited to their team: {:?}",
+                student.id(), e
+            )
+            });
     }
 
     // Reply to confirm the creation of the team:
     let tid = team.id();
-    ctx.reply(format!(
-        "Team {} has been created successfully.\n\
-        Tell your partner(s) to use `/team join {}` to join the team, \
-        or `/team invitations` to check their invitations.",
-        tid, tid
-    ))
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Team {} has been created successfully.\n\
+            Tell your partner(s) to use `/team join {}` to join the team, \
+            or `/team invitations` to check their invitations.",
+            "El equipo {} se ha creado correctamente.\n\
+            Dile a tus compañeros que usen `/team join {}` para unirse al equipo, \
+            o `/team invitations` para consultar sus invitaciones.",
+            tid,
+            tid
+        ),
+    )
     .await
-    .expect(
-        format!(
-            "[team] Failed to send reply after user {} created a team.",
-            student.id()
+    .unwrap_or_else(|e| {
+        panic!(
+            "[team] Failed to send reply after user {} created a team: {:?}",
+            student.id(),
+            e
         )
-        .as_str(),
-    );
+    });
+    teamnotify::notify_invited(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        &gid,
+        tid,
+        student.id(),
+        &service::invited(&student, &others, &skipped),
+        None,
+    )
+    .await;
 
     Ok(())
 }
@@ -172,59 +148,131 @@ pub async fn create(
 #[poise::command(
     slash_command,
     ephemeral,
-    description_localized("en-US", "Check your pending team invitations."),
-    description_localized("es-ES", "Check your pending team invitations.")
+    description_localized("en-US", "Check your team invitations."),
+    description_localized("es-ES", "Consultar tus invitaciones a equipos.")
 )]
 #[hermes::log_cmd]
 pub async fn invitations(ctx: Context<'_>) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let student = get_triggering_student!(ctx);
-
-    // Get the team invitations:
-    let Some(team_requests) = student.get_team_requests(&gid) else {
-        ctx.reply("You do not have any team invitations.")
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} checked their non-existant \
-                    team invitations.",
-                    student.id()
-                )
-                .as_str(),
-            );
+    let mut student = get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
+    );
 
-        return Ok(());
-    };
+    // Get the team invitations (marking them as seen):
+    let team_requests = teamrequest::list(&mut student, &gid);
     if team_requests.is_empty() {
-        ctx.reply("You do not have any team invitations.")
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} checked their empty \
-                team invitations.",
-                    student.id()
-                )
-                .as_str(),
-            );
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "You do not have any team invitations.",
+                "No tienes ninguna invitación a equipos."
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[team] Failed to send reply after user {} checked their empty \
+                team invitations: {:?}",
+                student.id(),
+                e
+            )
+        });
 
         return Ok(());
     }
-    // Reply with the team requests:
-    let mut reply = "You have the following team invitations:\n".to_string();
-    // I could use a map here, but I think casting inside the loop is prettier.
+    // Reply with the team requests, most recent first:
+    let mut reply = tr!(
+        lang,
+        "You have the following team invitations:\n",
+        "Tienes las siguientes invitaciones a equipos:\n"
+    );
     for req in team_requests {
-        let (team_id, sender_id) = req.into();
-        reply.push_str(format!("- Team {} by <@{}>\n", team_id, sender_id).as_str());
+        reply.push_str(
+            tr!(
+                lang,
+                "- Team {} by <@{}>",
+                "- Equipo {}, de <@{}>",
+                req.team_id(),
+                req.sender_id()
+            )
+            .as_str(),
+        );
+        if let Some(created_at) = req.created_at() {
+            reply.push_str(format!(", <t:{}:R>", created_at).as_str());
+        }
+        reply.push_str(format!(": {}", req.status().label(lang)).as_str());
+        if !req.seen() {
+            reply.push_str(tr!(lang, " (new)", " (nueva)").as_str());
+        }
+        reply.push('\n');
+        if let Some(message) = req.message() {
+            reply.push_str(format!("  > {}\n", message).as_str());
+        }
     }
 
-    ctx.reply(reply).await.expect(
-        format!(
-            "[team] Failed to send reply after user {} checked their team invitations.",
-            student.id()
-        )
-        .as_str(),
+    for chunk in utils::split_message(&reply, utils::MAX_MESSAGE_LEN) {
+        utils::reply(ctx, chunk).await.unwrap_or_else(|e| {
+            panic!(
+                "[team] Failed to send reply after user {} checked their team invitations: {:?}",
+                student.id(),
+                e
+            )
+        });
+    }
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Decline a pending team invitation."),
+    description_localized("es-ES", "Rechazar una invitación pendiente a un equipo.")
+)]
+#[hermes::log_cmd]
+pub async fn decline(
+    ctx: Context<'_>,
+    #[description = "The team whose invitation to decline."]
+    #[rename = "team"]
+    team_id: String,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let mut student = get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
     );
 
+    // Decline the invitation, and reply accordingly:
+    let reply = if teamrequest::resolve(&mut student, &gid, &team_id, RequestStatus::Declined) {
+        tr!(
+            lang,
+            "You have declined the invitation to team {}.",
+            "Has rechazado la invitación al equipo {}.",
+            team_id
+        )
+    } else {
+        tr!(
+            lang,
+            "You do not have a pending invitation to team {}.",
+            "No tienes ninguna invitación pendiente al equipo {}.",
+            team_id
+        )
+    };
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[team] Failed to send reply after user {} declined an invitation to team {}: {:?}",
+            student.id(),
+            team_id,
+            e
+        )
+    });
+
     Ok(())
 }
 
@@ -232,7 +280,7 @@ pub async fn invitations(ctx: Context<'_>) -> Result<(), Error> {
     slash_command,
     ephemeral,
     description_localized("en-US", "Join an existing team."),
-    description_localized("es-ES", "Join an existing team.")
+    description_localized("es-ES", "Unirte a un equipo existente.")
 )]
 #[hermes::log_cmd]
 pub async fn join(
@@ -244,74 +292,63 @@ pub async fn join(
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
     let mut student = get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
+    );
 
-    // Check if the student is already in a team:
-    if student.get_team_id(&gid).is_some() {
-        ctx.reply("You are already in a team in this server.")
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to join another team.",
-                    student.id()
-                )
-                .as_str(),
-            );
-
-        return Ok(());
-    }
-
-    // Check the student was invited to the team:
-    let Some(team_requests) = student.get_team_requests(&gid) else {
-        ctx.reply("You were not invited to that team.")
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to join a team without \
-                being invited on that server.",
-                    student.id()
-                )
-                .as_str(),
-            );
+    // Join the team:
+    let team = match service::join_team(
+        &FileStorage(&ctx.data().caches),
+        gid,
+        &mut student,
+        &team_id,
+    ) {
+        Ok(team) => team,
+        Err(e) => {
+            utils::reply_error(ctx, e.message(lang))
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[team] Failed to send reply after user {} failed to join team {}: {:?}",
+                        student.id(),
+                        team_id,
+                        e
+                    )
+                });
 
-        return Ok(());
+            return Ok(());
+        }
     };
-    if team_requests
-        .iter()
-        .filter(|req| req.team_id() == &team_id)
-        .collect::<Vec<_>>()
-        .is_empty()
-    {
-        ctx.reply("You were not invited to that team.")
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to join a team without \
-            being invited.",
-                    student.id()
-                )
-                .as_str(),
-            );
-
-        return Ok(());
-    }
-
-    // Get the team:
-    let mut team = team::get_existing_team!(&gid, &team_id);
-
-    // Join the team:
-    team.add_member(&mut student);
 
     // Reply, as confirmation:
-    ctx.reply(format!("You have joined team {} successfully.", team_id))
-        .await
-        .expect(
-            format!(
-                "[team] Failed to send reply after user {} joined team {}.",
-                student.id(),
-                team_id
-            )
-            .as_str(),
-        );
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "You have joined team {} successfully.",
+            "Te has unido al equipo {} correctamente.",
+            team_id
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[team] Failed to send reply after user {} joined team {}: {:?}",
+            student.id(),
+            team_id,
+            e
+        )
+    });
+    teamnotify::notify_members(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        &gid,
+        &team,
+        TeamChange::Joined(student.id()),
+    )
+    .await;
 
     Ok(())
 }
@@ -320,61 +357,58 @@ pub async fn join(
     slash_command,
     ephemeral,
     description_localized("en-US", "Leave your current team."),
-    description_localized("es-ES", "Leave your current team.")
+    description_localized("es-ES", "Abandonar tu equipo actual.")
 )]
+#[hermes::require_team(mut)]
 #[hermes::log_cmd]
 pub async fn leave(ctx: Context<'_>) -> Result<(), Error> {
-    let gid = get_guild_id!(ctx);
-    let mut student = get_triggering_student!(ctx);
-
-    // Check if the student is in a team:
-    let Some(team_id) = student.get_team_id(&gid) else {
-        ctx.reply("You are not in a team in this server.")
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to leave a team without \
-                    being in one.",
-                    student.id()
-                )
-                .as_str(),
-            );
-
-        return Ok(());
-    };
-
-    // Get the team:
-    let mut team = team::get_existing_team!(&gid, &team_id);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
+    );
 
-    // Check the team is not confirmed:
-    if team.confirmed() {
-        ctx.reply("You can no longer leave your team, as it is definitive.")
+    // Leave the team, unless it is confirmed:
+    if let Err(e) = service::leave_team(&FileStorage(&ctx.data().caches), &mut team, &mut student) {
+        utils::reply_error(ctx, e.message(lang))
             .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to leave a confirmed team.",
-                    student.id()
-                )
-                .as_str(),
-            );
+            .unwrap_or_else(|e| {
+                panic!(
+                "[team] Failed to send reply after user {} tried to leave a confirmed team: {:?}",
+                student.id(), e
+            )
+            });
 
         return Ok(());
     }
 
-    // Leave the team:
-    team.remove_member(&mut student);
-
     // Reply, as confirmation:
-    ctx.reply(format!("You have left team {} successfully.", team.id()))
-        .await
-        .expect(
-            format!(
-                "[team] Failed to send reply after user {} left team {}.",
-                student.id(),
-                team.id()
-            )
-            .as_str(),
-        );
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "You have left team {} successfully.",
+            "Has dejado el equipo {} correctamente.",
+            team.id()
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[team] Failed to send reply after user {} left team {}: {:?}",
+            student.id(),
+            team.id(),
+            e
+        )
+    });
+    teamnotify::notify_members(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        &gid,
+        &team,
+        TeamChange::Left(student.id()),
+    )
+    .await;
 
     Ok(())
 }
@@ -383,54 +417,68 @@ pub async fn leave(ctx: Context<'_>) -> Result<(), Error> {
     slash_command,
     ephemeral,
     description_localized("en-US", "Rename your team (for aesthetic effects)."),
-    description_localized("es-ES", "Rename your team (for aesthetic effects).")
+    description_localized("es-ES", "Renombrar tu equipo (con fines estéticos).")
 )]
+#[hermes::require_team(mut team)]
 #[hermes::log_cmd]
 pub async fn rename(
     ctx: Context<'_>,
     #[description = "The new name for the team."] new_name: String,
 ) -> Result<(), Error> {
-    let gid = get_guild_id!(ctx);
-    let student = get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
+    );
 
-    // Check if the student is in a team:
-    let Some(team_id) = student.get_team_id(&gid) else {
-        ctx.reply("You are not in a team in this server.")
+    // Rename the team, or notify why it cannot be:
+    if let Err(e) = team.change_name(new_name.clone()) {
+        utils::reply_error(ctx, format!("**Error:** {}", e.message(lang)))
             .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to rename their team without \
-                being in one.",
-                    student.id()
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[team] Failed to send reply after failing to rename team {} in guild {}: {:?}",
+                    team.id(),
+                    gid,
+                    e
                 )
-                .as_str(),
-            );
+            });
 
         return Ok(());
-    };
-
-    // Get the team:
-    let mut team = team::get_existing_team!(&gid, &team_id);
-
-    // Rename the team:
-    team.change_name(new_name.clone());
+    }
 
     // Reply, as confirmation:
-    ctx.reply(format!(
-        "Team {} has been correctly renamed to \"{}\".",
-        team.id(),
-        new_name
-    ))
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Team {} has been correctly renamed to \"{}\".",
+            "El equipo {} se ha renombrado correctamente a \"{}\".",
+            team.id(),
+            new_name
+        ),
+    )
     .await
-    .expect(
-        format!(
-            "[team] Failed to send reply after user {} renamed team {} to \"{}\".",
+    .unwrap_or_else(|e| {
+        panic!(
+            "[team] Failed to send reply after user {} renamed team {} to \"{}\": {:?}",
             student.id(),
             team.id(),
-            new_name
+            new_name,
+            e
         )
-        .as_str(),
-    );
+    });
+    teamnotify::notify_members(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        &gid,
+        &team,
+        TeamChange::Renamed {
+            by: Some(student.id()),
+            name: new_name,
+        },
+    )
+    .await;
 
     Ok(())
 }
@@ -438,130 +486,160 @@ pub async fn rename(
 #[poise::command(
     slash_command,
     ephemeral,
-    description_localized("en-US", "Invite other students to join your current team."),
-    description_localized("es-ES", "Invite other students to join your current team.")
+    description_localized(
+        "en-US",
+        "Choose whether your team appears in public leaderboards under its ID only."
+    ),
+    description_localized(
+        "es-ES",
+        "Elegir si tu equipo aparece en las clasificaciones públicas solo con su ID."
+    )
 )]
+#[hermes::require_team(mut team)]
 #[hermes::log_cmd]
-pub async fn invite(
+pub async fn privacy(
     ctx: Context<'_>,
-    #[description = "The other students to invite to the team."] others: Vec<User>,
+    #[description = "Show only the team's ID (not its name) in public leaderboards."]
+    anonymous: bool,
 ) -> Result<(), Error> {
-    let gid = get_guild_id!(ctx);
-    let student = get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
+    );
 
-    // Check if the student is in a team:
-    let Some(team_id) = student.get_team_id(&gid) else {
-        ctx.reply("You are not in a team in this server.")
-            .await
-            .expect(
-                format!(
-                "[team] Failed to send reply after user {} tried to invite someone to their team \
-                without being in one.",
-                student.id()
-            )
-                .as_str(),
-            );
+    team.set_anonymous(anonymous);
 
-        return Ok(());
+    // Reply, as confirmation:
+    let content = if anonymous {
+        tr!(
+            lang,
+            "Team {} will appear in public leaderboards under its ID only.",
+            "El equipo {} aparecerá en las clasificaciones públicas solo con su ID.",
+            team.id()
+        )
+    } else {
+        tr!(
+            lang,
+            "Team {} will appear in public leaderboards as \"{}\".",
+            "El equipo {} aparecerá en las clasificaciones públicas como \"{}\".",
+            team.id(),
+            team.name()
+        )
     };
+    utils::reply(ctx, content).await.unwrap_or_else(|e| {
+        panic!(
+            "[team] Failed to send reply after user {} changed the privacy of team {}: {:?}",
+            student.id(),
+            team.id(),
+            e
+        )
+    });
 
-    // Get the team:
-    let team = team::get_existing_team!(&gid, &team_id);
-
-    // Check the team is not confirmed:
-    if team.confirmed() {
-        ctx.reply("You can no longer invite other students to your team, as it is definitive.")
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to invite someone to a \
-                    confirmed team.",
-                    student.id()
-                )
-                .as_str(),
-            );
-
-        return Ok(());
-    }
-
-    // Check the amount of invited students do not exceed the allowed team size:
-    let config = utils::load_config(&gid);
-    // FIXME MINOR: This does not account for already existing invitations.
-    let remaining_capacity = config.team_capacity as usize - team.members().len();
-    if others.len() > remaining_capacity {
-        ctx.reply(format!(
-            "You can only invite up to {} other student(s) to the team.",
-            remaining_capacity
-        ))
-        .await.expect(
-            format!(
-                "[team] Failed to send reply after user {} tried to invite more students than allowed to their team.",
-                student.id()
-            )
-            .as_str(),
-        );
-
-        return Ok(());
-    }
-
-    // Collect the students that can be invited:
-    let mut students_to_invite = Vec::with_capacity(others.len());
-    for other in others {
-        if other.id == student.id() {
-            ctx.reply("You cannot invite yourself to your own team.")
-                .await
-                .expect(
-                    format!(
-                        "[team] Failed to send reply after user {} tried to invite themself to \
-                        their own team.",
-                        student.id()
-                    )
-                    .as_str(),
-                );
+    Ok(())
+}
 
-            continue;
-        }
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized("en-US", "Invite other students to join your current team."),
+    description_localized("es-ES", "Invitar a otros estudiantes a unirse a tu equipo actual.")
+)]
+#[hermes::require_team]
+#[hermes::log_cmd]
+pub async fn invite(
+    ctx: Context<'_>,
+    #[description = "The other students to invite to the team."] others: Vec<User>,
+    #[description = "A short message for them, shown with the invitation."]
+    #[max_length = 200]
+    message: Option<String>,
+) -> Result<(), Error> {
+    let others = others.iter().map(|other| other.id).collect::<Vec<_>>();
+    send_invitations(ctx, &team, &student, &others, message).await;
 
-        let other_student = student::get_existing_student!(&other.id);
+    Ok(())
+}
 
-        // Check if the student is already in a team:
-        if other_student.get_team_id(&gid).is_some() {
-            ctx.reply(format!(
-                "<@{}> is already in a team in this server.",
-                other.id
-            ))
-            .await
-            .expect(
-                format!(
-                    "[team] Failed to send reply after user {} tried to invite to their team \
-                    a student already in another team.",
-                    student.id()
+/// Invites other students to the student's team, with an optional message for them, replying with
+/// the outcome and notifying them.
+async fn send_invitations(
+    ctx: Context<'_>,
+    team: &Team,
+    student: &Student,
+    others: &[UserId],
+    message: Option<String>,
+) {
+    let gid = team.guild();
+    let lang = i18n::user_language(student, &utils::load_config(&ctx.data().caches, gid), gid);
+    let skipped = match service::invite_to_team(
+        &FileStorage(&ctx.data().caches),
+        team,
+        student,
+        others,
+        message.clone(),
+    ) {
+        Ok(skipped) => skipped,
+        Err(e) => {
+            utils::reply_error(ctx, e.message(lang)).await.unwrap_or_else(|e| {
+                panic!(
+                    "[team] Failed to send reply after user {} failed to invite to their team: {:?}",
+                    student.id(), e
                 )
-                .as_str(),
-            );
+            });
 
-            continue;
+            return;
         }
-
-        // Add the students to the list of students to invite when the team is created:
-        students_to_invite.push(other_student);
+    };
+    for skipped in skipped.iter() {
+        utils::reply(ctx, skipped.message(lang))
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                "[team] Failed to send reply after user {} invited a student that could not be \
+                invited to their team: {:?}",
+                student.id(), e
+            )
+            });
     }
-
-    // Send the invitations:
-    for mut other_student in students_to_invite {
-        other_student.add_team_request(gid, team.id().clone(), student.id().clone());
+    let invited = service::invited(student, others, &skipped);
+    if invited.is_empty() {
+        return;
     }
 
     // Reply to confirm the sending of the invitations:
-    ctx.reply("Invitations to the other students have been sent successfully.")
-        .await
-        .expect(
-            format!(
-                "[team] Failed to send reply after user {} correctly invited to their team.",
-                student.id()
-            )
-            .as_str(),
-        );
+    utils::reply(
+        ctx,
+        tr!(
+            lang,
+            "Invitations to the other students have been sent successfully.",
+            "Las invitaciones a los otros estudiantes se han enviado correctamente."
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[team] Failed to send reply after user {} correctly invited to their team: {:?}",
+            student.id(),
+            e
+        )
+    });
+    teamnotify::notify_invited(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        gid,
+        team.id(),
+        student.id(),
+        &invited,
+        message.as_deref(),
+    )
+    .await;
+}
+
+#[poise::command(context_menu_command = "Invite to my team", guild_only, ephemeral)]
+#[hermes::require_team]
+#[hermes::log_cmd]
+pub async fn invite_user(ctx: Context<'_>, user: User) -> Result<(), Error> {
+    send_invitations(ctx, &team, &student, &[user.id], None).await;
 
     Ok(())
 }
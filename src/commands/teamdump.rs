@@ -15,42 +15,221 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{student, team, utils, utils::get_guild_id, Context, Error};
+use crate::{student, tablon, team, utils, utils::get_guild_id, Context, Error};
 use poise::{
     serenity_prelude::{CreateAttachment, CreateMessage, GuildChannel},
     CreateReply,
 };
-use std::fs;
+use serde::Serialize;
+use serenity::all::UserId;
+use std::fmt::Write as _;
+
+/// Formats of the team list file sent by `/teamdump`.
+#[derive(Clone, Copy, Default, poise::ChoiceParameter)]
+pub enum DumpFormat {
+    /// One line per member: `<team> <discord_id> [<university_id>]`.
+    #[default]
+    #[name = "txt"]
+    Txt,
+    /// One row per member, with a header row.
+    #[name = "csv"]
+    Csv,
+    /// An array of teams, each with its members.
+    #[name = "json"]
+    Json,
+    /// One row per member with a university ID, with its team's grade (from its best result), as
+    /// expected by Moodle's gradebook import.
+    #[name = "moodle"]
+    Moodle,
+}
+
+impl DumpFormat {
+    /// Extension of the exported file.
+    pub fn extension(self) -> &'static str {
+        match self {
+            DumpFormat::Txt => "txt",
+            DumpFormat::Csv | DumpFormat::Moodle => "csv",
+            DumpFormat::Json => "json",
+        }
+    }
+}
+
+/// Default maximum grade for the Moodle export.
+pub const DEFAULT_MAX_GRADE: f64 = 10.0;
+
+/// A team, as exported by `/teamdump`.
+#[derive(Serialize)]
+pub struct DumpedTeam {
+    pub id: String,
+    pub name: String,
+    pub confirmed: bool,
+    pub members: Vec<DumpedMember>,
+    /// Best (i.e. lowest) result of the team's successful requests, and the request achieving it.
+    #[serde(skip)]
+    pub best_result: Option<(f64, u16)>,
+}
+
+/// A member of a team, as exported by `/teamdump`.
+#[derive(Serialize)]
+pub struct DumpedMember {
+    pub discord_id: UserId,
+    pub discord_name: String,
+    pub university_id: Option<String>,
+}
+
+/// Quotes a CSV field, if needed.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Best (i.e. lowest) result of the successful requests of a team's members, and the request
+/// achieving it.
+fn best_result(gid: &serenity::all::GuildId, members: &[student::Student]) -> Option<(f64, u16)> {
+    members
+        .iter()
+        .filter_map(|member| member.request_history().get(gid))
+        .flatten()
+        .filter_map(|req| {
+            let verdict = req.verdict().as_ref()?;
+            if tablon::is_pending(verdict) || tablon::is_failure(verdict) {
+                return None;
+            }
+            tablon::verdict_result(verdict).map(|result| (result, req.id()))
+        })
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+}
+
+/// Builds the contents of the team list file in the given format.
+///
+/// In the Moodle format, the team with the best result overall gets the maximum grade, and the
+/// others get a grade proportional to how close their best result is to it.
+pub fn dump_file(teams: &[DumpedTeam], format: DumpFormat, max_grade: f64) -> String {
+    let mut out = String::new();
+    match format {
+        DumpFormat::Txt => {
+            for team in teams {
+                for member in team.members.iter() {
+                    match &member.university_id {
+                        Some(university_id) => {
+                            writeln!(out, "{} {} {}", team.id, member.discord_id, university_id)
+                        }
+                        None => writeln!(out, "{} {}", team.id, member.discord_id),
+                    }
+                    .unwrap();
+                }
+            }
+        }
+        DumpFormat::Csv => {
+            out.push_str("team,team_name,confirmed,discord_id,discord_name,university_id\n");
+            for team in teams {
+                for member in team.members.iter() {
+                    writeln!(
+                        out,
+                        "{},{},{},{},{},{}",
+                        csv_field(&team.id),
+                        csv_field(&team.name),
+                        team.confirmed,
+                        member.discord_id,
+                        csv_field(&member.discord_name),
+                        csv_field(member.university_id.as_deref().unwrap_or_default())
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        DumpFormat::Json => {
+            out = serde_json::to_string_pretty(teams)
+                .expect("[teamdump] Could not serialize the team list as JSON.");
+        }
+        DumpFormat::Moodle => {
+            let best_overall = teams
+                .iter()
+                .filter_map(|team| team.best_result.map(|(result, _)| result))
+                .min_by(f64::total_cmp);
+            out.push_str("university_id,grade,comment\n");
+            for team in teams {
+                let (grade, comment) = match (team.best_result, best_overall) {
+                    (Some((result, request_id)), Some(best)) => {
+                        let grade = if result > 0.0 {
+                            max_grade * best / result
+                        } else {
+                            max_grade
+                        };
+                        (
+                            format!("{:.2}", grade),
+                            format!(
+                                "Team {}: best result {} (request #{})",
+                                team.id, result, request_id
+                            ),
+                        )
+                    }
+                    _ => (
+                        String::new(),
+                        format!("Team {}: no successful requests", team.id),
+                    ),
+                };
+                for university_id in team
+                    .members
+                    .iter()
+                    .filter_map(|member| member.university_id.as_ref())
+                {
+                    writeln!(
+                        out,
+                        "{},{},{}",
+                        csv_field(university_id),
+                        grade,
+                        csv_field(&comment)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    out
+}
 
 #[poise::command(
     slash_command,
+    category = "Admin",
     guild_only,
     default_member_permissions = "MANAGE_GUILD",
     ephemeral,
     description_localized(
         "en-US",
-        "Export the guild's teams and their member's identifiers as a plain text file."
+        "Export the guild's teams and their member's identifiers, or their grades for Moodle."
     ),
     description_localized(
         "es-ES",
-        "Export the guild's teams and their member's identifiers as a plain text file."
+        "Exportar los equipos del servidor y los identificadores de sus miembros, o sus notas para Moodle."
     )
 )]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
 pub async fn teamdump(
     ctx: Context<'_>,
     #[description = "Channel to send a message with all the teams and their members (as Discord users)."]
     channel: Option<GuildChannel>,
+    #[description = "Format of the exported file (default: txt)."] format: Option<DumpFormat>,
+    #[description = "Grade of the team with the best result, in the Moodle format (default: 10)."]
+    #[min = 0]
+    max_grade: Option<f64>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let config = utils::load_config(&gid);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let format = format.unwrap_or_default();
+    let max_grade = max_grade.unwrap_or(DEFAULT_MAX_GRADE);
 
     let prefix = config.team_prefix;
     let team_count = team::get_existing_guild_team_info!(&gid).count();
 
     // Construct message and file content:
-    let mut out_file = String::new();
+    let mut teams = Vec::new();
     let mut out_msg = "## List of teams:\n\n".to_string();
-    let mut out_msg_split = Vec::new(); // Messages sent on Discord.
     for i in 0..team_count {
         let tid = format!("{}{:02}", prefix, i + 1);
         if let Some(team) = team::get_team(&gid, &tid) {
@@ -58,38 +237,41 @@ pub async fn teamdump(
                 continue;
             }
 
-            out_msg += format!("**{}** ", tid).as_str();
-            for member in team
+            let mut dumped = DumpedTeam {
+                id: tid.clone(),
+                name: team.name().clone(),
+                confirmed: team.confirmed(),
+                members: Vec::new(),
+                best_result: None,
+            };
+            let students: Vec<_> = team
                 .members()
                 .iter()
-                .map(|m| student::get_existing_student!(m))
-            {
+                .map(|m| student::get_existing_student!(&ctx.data().caches, m))
+                .collect();
+            dumped.best_result = best_result(&gid, &students);
+            out_msg += format!("**{}** ", tid).as_str();
+            for member in students.iter() {
                 let uid = member.id();
-                out_file += format!("{} {}\n", tid, uid).as_str();
-                out_msg += format!("{} ", uid).as_str();
+                if let Some(university_id) = member.university_id() {
+                    out_msg += format!("{} ({}) ", uid, university_id).as_str();
+                } else {
+                    out_msg += format!("{} ", uid).as_str();
+                }
+                dumped.members.push(DumpedMember {
+                    discord_id: uid,
+                    discord_name: member.name().clone(),
+                    university_id: member.university_id().clone(),
+                });
             }
             out_msg += "\n";
-        };
-
-        if out_msg.len()
-            >= 2000
-                - ("**gXXX:**".len()
-                    + config.team_capacity as usize * "<@!000000000000000000>".len()
-                    + "\n".len())
-        {
-            // 2000 = maximum message length.
-            // 10 = "**gXXX:** " length.
-            // 22 = <@!memberID> length.
-            // 1 = space or newline length.
-            out_msg_split.push(out_msg.clone());
-            out_msg = String::new();
+            teams.push(dumped);
         }
     }
-    out_msg_split.push(out_msg);
 
     // Send the list of teams to the channel as a message, if a channel was provided:
     if let Some(channel) = channel {
-        for msg in out_msg_split {
+        for msg in utils::split_message(&out_msg, utils::MAX_MESSAGE_LEN) {
             channel
                 .send_message(&ctx.http(), CreateMessage::new().content(msg))
                 .await
@@ -99,19 +281,16 @@ pub async fn teamdump(
         }
     }
 
-    // Send the list of teams as a plain text file:
-    let file_name = format!("{}/teams/team_list.txt", gid);
-    fs::write(file_name.clone(), out_file).expect("[teamdump] Could not write team list to file.");
-
+    // Send the list of teams as a file:
+    let file_name = format!("team_list.{}", format.extension());
     let msg = CreateReply::default()
         .content("List of teams on the server:")
-        .attachment(
-            CreateAttachment::path(file_name)
-                .await
-                .expect("[teamdump] Could not send the team list file as attachment."),
-        )
+        .attachment(CreateAttachment::bytes(
+            dump_file(&teams, format, max_grade).into_bytes(),
+            file_name,
+        ))
         .ephemeral(true);
-    ctx.send(msg)
+    utils::send(ctx, msg)
         .await
         .expect("[teamdump] Could not send the message with the team list file.");
 
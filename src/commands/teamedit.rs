@@ -15,11 +15,274 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{student, team, team::GuildTeamInfo, utils, utils::get_guild_id, Context, Error};
-use poise::serenity_prelude::User;
+extern crate reqwest;
+
+use crate::{
+    batch::Batch,
+    commands::{passwords, request, teamdump},
+    i18n::{self, tr},
+    service::{self, FileStorage},
+    student, tablon, team,
+    team::GuildTeamInfo,
+    teamnotify::{self, TeamChange},
+    utils,
+    utils::get_guild_id,
+    Context, Error,
+};
+use poise::{
+    serenity_prelude::{self as serenity, User, UserId},
+    CreateReply,
+};
+use rand::{distributions::Alphanumeric, Rng as _};
+use serde::Serialize;
+use std::{fmt::Write as _, time::Duration};
+
+/// Length of the passwords generated by `/teamedit rotate_password`.
+const GENERATED_PASSWORD_LEN: usize = 12;
+
+/// Maximum number of options Discord allows in autocompletions and select menus.
+const MAX_CHOICES: usize = 25;
+
+/// Time to wait for an admin to pick a team in the select menu.
+const SELECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum number of teams suggested when the given one does not exist.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Suggests the existing teams (by ID or name) closest to a team that does not exist.
+fn suggest_teams(gid: &serenity::GuildId, team: &str) -> Vec<String> {
+    let max_distance = (team.chars().count() / 3).max(2);
+    let mut suggestions = utils::load_namemap(gid)
+        .into_iter()
+        .filter_map(|(name, tid)| {
+            let distance = utils::edit_distance(team, &name).min(utils::edit_distance(team, &tid));
+            (distance <= max_distance).then_some((distance, tid))
+        })
+        .collect::<Vec<_>>();
+    suggestions.sort();
+    suggestions.dedup_by(|a, b| a.1 == b.1);
+
+    suggestions
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, tid)| tid)
+        .collect()
+}
+
+/// Resolves the team an admin gave to its canonical ID, registering it if it does not exist and
+/// `create_if_missing` is set. Otherwise, replies suggesting the closest existing teams, and returns
+/// `None`.
+async fn resolve_team(
+    ctx: Context<'_>,
+    gid: &serenity::GuildId,
+    team: &String,
+    create_if_missing: bool,
+) -> Option<String> {
+    // Create guild team info file, if it does not exist:
+    let mut info = match team::get_guild_team_info(gid) {
+        Some(info) => info,
+        None => {
+            let prefix = utils::load_config(&ctx.data().caches, gid).team_prefix;
+            GuildTeamInfo::new(*gid, prefix)
+        }
+    };
+
+    let reply = match info.canonical_team_id(team) {
+        Some((team_id, _)) if team::get_team(gid, &team_id).is_some() => return Some(team_id),
+        _ if create_if_missing => match info.register_specific_team(team) {
+            Ok(team_id) => {
+                let _team = team::Team::new(&info, team_id.clone());
+                return Some(team_id);
+            }
+            Err(e) => e,
+        },
+        _ => {
+            let suggestions = suggest_teams(gid, team);
+            let mut reply = format!(
+                "Team {} does not exist (set `create_if_missing` to create it).",
+                team
+            );
+            if !suggestions.is_empty() {
+                reply.push_str(format!(" Did you mean {}?", suggestions.join(", ")).as_str());
+            }
+            reply
+        }
+    };
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after failing to resolve team {} in guild {}: {:?}",
+            team, gid, e
+        )
+    });
+
+    None
+}
+
+/// Autocompletes the IDs of the guild's existing teams.
+async fn autocomplete_team<'a>(ctx: Context<'a>, partial: &'a str) -> Vec<String> {
+    let Some(gid) = ctx.guild_id() else {
+        return Vec::new();
+    };
+
+    team::get_all_teams(&gid)
+        .into_iter()
+        .map(|team| team.id().clone())
+        .filter(|tid| tid.starts_with(partial))
+        .take(MAX_CHOICES)
+        .collect()
+}
+
+/// Asks the admin to pick one of the guild's teams in a select menu, returning its ID (or `None` if
+/// there are no teams, or none was picked in time).
+async fn select_team(ctx: Context<'_>, gid: &serenity::GuildId) -> Option<String> {
+    let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, gid));
+    let teams = team::get_all_teams(gid);
+    if teams.is_empty() {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "There are no teams in this guild.",
+                "No hay ningún equipo en este servidor."
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after finding no teams to select in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return None;
+    }
+    if teams.len() > MAX_CHOICES {
+        utils::reply(
+            ctx,
+            tr!(
+                lang,
+                "There are more than {} teams in this guild; give the team's ID instead.",
+                "Hay más de {} equipos en este servidor; indica el ID del equipo en su lugar.",
+                MAX_CHOICES
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after finding too many teams to select in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return None;
+    }
+
+    let select_id = format!("{}select", ctx.id());
+    let options = teams
+        .iter()
+        .map(|team| {
+            let label = if team.name() == team.id() {
+                team.id().clone()
+            } else {
+                format!("{} ({})", team.id(), team.name())
+            };
+            serenity::CreateSelectMenuOption::new(label, team.id().clone())
+        })
+        .collect();
+    let reply = ctx
+        .send(
+            poise::CreateReply::default()
+                .content(tr!(lang, "Pick a team:", "Elige un equipo:"))
+                .components(vec![serenity::CreateActionRow::SelectMenu(
+                    serenity::CreateSelectMenu::new(
+                        &select_id,
+                        serenity::CreateSelectMenuKind::String { options },
+                    ),
+                )]),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send the team select menu in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+    let author_id = ctx.author().id;
+    let press = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id == select_id && press.user.id == author_id)
+        .timeout(SELECT_TIMEOUT)
+        .await;
+    let team_id = press.as_ref().and_then(|press| match &press.data.kind {
+        serenity::ComponentInteractionDataKind::StringSelect { values } => values.first().cloned(),
+        _ => None,
+    });
+
+    // Remove the menu, once used (or expired):
+    let content = match &team_id {
+        Some(team_id) => format!("Picked team {}.", team_id),
+        None => "No team was picked.".to_string(),
+    };
+    match press {
+        Some(press) => press
+            .create_response(
+                ctx.serenity_context(),
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(Vec::new()),
+                ),
+            )
+            .await
+            .expect("[teamedit] Failed to update the team select menu."),
+        None => reply
+            .edit(
+                ctx,
+                poise::CreateReply::default()
+                    .content(content)
+                    .components(Vec::new()),
+            )
+            .await
+            .expect("[teamedit] Failed to remove the expired team select menu."),
+    }
+
+    team_id
+}
+
+/// Checks there is room in the team for another member, replying to the admin otherwise.
+async fn check_capacity(ctx: Context<'_>, gid: &serenity::GuildId, team: &team::Team) -> bool {
+    let Err(service::TeamError::Full { members, capacity }) =
+        service::check_capacity(&FileStorage(&ctx.data().caches), team)
+    else {
+        return true;
+    };
+
+    utils::reply(
+        ctx,
+        format!(
+            "Team {} is already full, with {} of {} member(s). Remove one of them first, or raise \
+            the capacity with `/botconfig team_capacity`.",
+            team.id(),
+            members,
+            capacity
+        ),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply for full team {} in guild {}: {:?}",
+            team.id(),
+            gid,
+            e
+        )
+    });
+
+    false
+}
 
 #[poise::command(
     slash_command,
+    category = "Admin",
     subcommands(
         "r#move",
         "add",
@@ -27,7 +290,12 @@ use poise::serenity_prelude::User;
         "unconfirm",
         "confirm",
         "password",
-        "rename"
+        "rotate_password",
+        "clear_password",
+        "rename",
+        "roster",
+        "check",
+        "snapshot"
     ),
     subcommand_required,
     default_member_permissions = "MANAGE_GUILD",
@@ -48,30 +316,59 @@ pub async fn teamedit(_: Context<'_>) -> Result<(), Error> {
     ),
     description_localized(
         "es-ES",
-        "Move a student to a team, leaving their previous one (if any)."
+        "Mover a un estudiante a un equipo, dejando el anterior (si lo hay)."
     )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn r#move(
     ctx: Context<'_>,
     #[description = "The student to move."]
     #[rename = "student"]
     user: User,
-    #[description = "The new team to move the student to."] new_team: String,
+    #[description = "The new team to move the student to (pick it from a menu, if not given)."]
+    #[autocomplete = "autocomplete_team"]
+    new_team: Option<String>,
+    #[description = "Create the team if it does not exist (default: false)."]
+    create_if_missing: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut student = student::get_student_from_user!(user);
+    let mut student = student::get_student_from_user!(&ctx.data().caches, user);
+    let new_team = match new_team {
+        Some(new_team) => new_team,
+        None => {
+            let Some(new_team) = select_team(ctx, &gid).await else {
+                return Ok(());
+            };
+            new_team
+        }
+    };
 
-    // Retrieve the old team of the student, if any, and remove the student from it:
-    if let Some(old_team_id) = student.get_team_id(&gid) {
-        team::get_existing_team!(&gid, &old_team_id).remove_member(&mut student);
+    let Some(new_team) =
+        resolve_team(ctx, &gid, &new_team, create_if_missing.unwrap_or(false)).await
+    else {
+        return Ok(());
+    };
+
+    let team = team::get_existing_team!(&gid, &new_team);
+    if !team.members().contains(&user.id) && !check_capacity(ctx, &gid, &team).await {
+        return Ok(());
     }
 
-    // Add the student to the new team:
-    team::get_or_create_team(&gid, &new_team).add_member(&mut student);
+    // Retrieve the old team of the student, if any, and remove the student from it:
+    let old_team = student.get_team_id(&gid).map(|old_team_id| {
+        let mut old_team = team::get_existing_team!(&gid, &old_team_id);
+        old_team.remove_member(&ctx.data().caches, &mut student);
+        old_team
+    });
+
+    // Add the student to the new team (reloaded, in case it was the old one):
+    let mut team = team::get_existing_team!(&gid, &new_team);
+    team.add_member(&mut student);
 
     // Reply, as confirmation:
-    ctx.reply(
+    utils::reply(
+        ctx,
         format!(
             "Correctly moved student <@{}> to team {}.",
             user.id, new_team
@@ -79,13 +376,32 @@ pub async fn r#move(
         .to_string(),
     )
     .await
-    .expect(
-        format!(
-            "[teamedit] Failed to send reply after moving {} to team {} in guild {}.",
-            user.id, new_team, gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after moving {} to team {} in guild {}: {:?}",
+            user.id, new_team, gid, e
         )
-        .as_str(),
-    );
+    });
+
+    // Notify the members of both teams:
+    if let Some(old_team) = old_team.filter(|old_team| *old_team.id() != new_team) {
+        teamnotify::notify_members(
+            ctx.serenity_context(),
+            &ctx.data().caches,
+            &gid,
+            &old_team,
+            TeamChange::Kicked(user.id),
+        )
+        .await;
+    }
+    teamnotify::notify_members(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        &gid,
+        &team,
+        TeamChange::Joined(user.id),
+    )
+    .await;
 
     Ok(())
 }
@@ -95,52 +411,71 @@ pub async fn r#move(
     ephemeral,
     description_localized(
         "en-US",
-        "Add a student to a specific team. Creates the team if it does not exist."
+        "Add a student to a specific team (creating it, if asked to)."
     ),
     description_localized(
         "es-ES",
-        "Add a student to a specific team. Creates the team if it does not exist."
+        "Añadir a un estudiante a un equipo concreto (creándolo, si se pide)."
     )
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn add(
     ctx: Context<'_>,
     #[description = "The student to add to the team."]
     #[rename = "student"]
     user: User,
-    #[description = "The team to add the student to."] team: String,
+    #[description = "The team to add the student to (pick it from a menu, if not given)."]
+    #[autocomplete = "autocomplete_team"]
+    team: Option<String>,
+    #[description = "Create the team if it does not exist (default: false)."]
+    create_if_missing: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut student = student::get_student_from_user!(user);
-
-    // Register team, if it does not exist:
-    if team::get_team(&gid, &team).is_none() {
-        // Create guild team info file, if it does not exist:
-        let mut info = match team::get_guild_team_info(&gid) {
-            Some(info) => info,
-            None => {
-                let prefix = utils::load_config(&gid).team_prefix;
-                GuildTeamInfo::new(gid, prefix)
-            }
-        };
+    let mut student = student::get_student_from_user!(&ctx.data().caches, user);
+    let team = match team {
+        Some(team) => team,
+        None => {
+            let Some(team) = select_team(ctx, &gid).await else {
+                return Ok(());
+            };
+            team
+        }
+    };
 
-        // Register the team:
-        info.register_specific_team(&team);
-    }
+    // Resolve the team, registering it if it does not exist (and it was asked to):
+    let Some(team) = resolve_team(ctx, &gid, &team, create_if_missing.unwrap_or(false)).await
+    else {
+        return Ok(());
+    };
 
-    // Add the student to the team:
-    team::get_or_create_team(&gid, &team).add_member(&mut student);
+    // Add the student to the team, if it has room for them:
+    let mut team_record = team::get_existing_team!(&gid, &team);
+    if !team_record.members().contains(&user.id) && !check_capacity(ctx, &gid, &team_record).await {
+        return Ok(());
+    }
+    team_record.add_member(&mut student);
 
     // Reply, as confirmation:
-    ctx.reply(format!("Correctly added student <@{}> to team {}.", user.id, team).to_string())
-        .await
-        .expect(
-            format!(
-                "[teamedit] Failed to send reply after adding {} to team {} in guild {}.",
-                user.id, team, gid
-            )
-            .as_str(),
-        );
+    utils::reply(
+        ctx,
+        format!("Correctly added student <@{}> to team {}.", user.id, team).to_string(),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after adding {} to team {} in guild {}: {:?}",
+            user.id, team, gid, e
+        )
+    });
+    teamnotify::notify_members(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        &gid,
+        &team_record,
+        TeamChange::Joined(user.id),
+    )
+    .await;
 
     Ok(())
 }
@@ -149,8 +484,9 @@ pub async fn add(
     slash_command,
     ephemeral,
     description_localized("en-US", "Remove a student from their team."),
-    description_localized("es-ES", "Remove a student from their team.")
+    description_localized("es-ES", "Sacar a un estudiante de su equipo.")
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn remove(
     ctx: Context<'_>,
@@ -159,28 +495,32 @@ pub async fn remove(
     user: User,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
-    let mut student = student::get_student_from_user!(user);
+    let mut student = student::get_student_from_user!(&ctx.data().caches, user);
 
     // Retrieve the team of the student, or notify if they are not in any team:
     let Some(team_id) = student.get_team_id(&gid) else {
-        ctx.reply(
+        utils::reply(
+            ctx,
             format!("Student <@{}> is not in any team on this guild.", user.id).to_string(),
-).await.expect(
-            format!(
-                "[teamedit] Failed to send reply after attempting to remove {} from their non-existant team in {}.",
-                user.id, gid
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after attempting to remove {} from their non-existant team in {}: {:?}",
+                user.id, gid, e
             )
-            .as_str(),
-        );
+        });
 
         return Ok(());
     };
 
     // Remove the student from the team:
-    team::get_existing_team!(&gid, &team_id).remove_member(&mut student);
+    let mut team = team::get_existing_team!(&gid, &team_id);
+    team.remove_member(&ctx.data().caches, &mut student);
 
     // Reply, as confirmation:
-    ctx.reply(
+    utils::reply(
+        ctx,
         format!(
             "Correctly removed student <@{}> from team {}.",
             user.id, team_id
@@ -188,13 +528,20 @@ pub async fn remove(
         .to_string(),
     )
     .await
-    .expect(
-        format!(
-            "[teamedit] Failed to send reply after removing {} from team {} in guild {}.",
-            user.id, team_id, gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after removing {} from team {} in guild {}: {:?}",
+            user.id, team_id, gid, e
         )
-        .as_str(),
-    );
+    });
+    teamnotify::notify_members(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        &gid,
+        &team,
+        TeamChange::Kicked(user.id),
+    )
+    .await;
 
     Ok(())
 }
@@ -203,27 +550,38 @@ pub async fn remove(
     slash_command,
     ephemeral,
     description_localized("en-US", "Unconfirm a team, to make it modifiable."),
-    description_localized("es-ES", "Unconfirm a team, to make it modifiable.")
+    description_localized("es-ES", "Desconfirmar un equipo, para poder modificarlo.")
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn unconfirm(
     ctx: Context<'_>,
     #[description = "The team to unconfirm."]
     #[rename = "team"]
+    #[autocomplete = "autocomplete_team"]
     team_id: String,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
 
     // Retrieve the team, or notify if it does not exist:
     let Some(mut team) = team::get_team(&gid, &team_id) else {
-        ctx.reply(format!("Team {} does not exist in this guild.", team_id).to_string()
-).await.expect(
-            format!(
-                "[teamedit] Failed to send reply after attempting to unconfirm non-existant team {} in guild {}.",
-                team_id, gid
+        let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "Team {} does not exist in this guild.",
+                "El equipo {} no existe en este servidor.",
+                team_id
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after attempting to unconfirm non-existant team {} in guild {}: {:?}",
+                team_id, gid, e
             )
-            .as_str(),
-        );
+        });
 
         return Ok(());
     };
@@ -232,7 +590,8 @@ pub async fn unconfirm(
     team.unconfirm();
 
     // Reply, as confirmation:
-    ctx.reply(
+    utils::reply(
+        ctx,
         format!(
             "Correctly unconfirmed team {}. It is now editable.",
             team_id
@@ -240,13 +599,12 @@ pub async fn unconfirm(
         .to_string(),
     )
     .await
-    .expect(
-        format!(
-            "[teamedit] Failed to send reply after unconfirming team {} in guild {}.",
-            team_id, gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after unconfirming team {} in guild {}: {:?}",
+            team_id, gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
@@ -255,36 +613,48 @@ pub async fn unconfirm(
     slash_command,
     ephemeral,
     description_localized("en-US", "Confirm a team, to make it ready to be used with Tablón."),
-    description_localized("es-ES", "Confirm a team, to make it ready to be used with Tablón.")
+    description_localized("es-ES", "Confirmar un equipo, para que pueda usarse con Tablón.")
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn confirm(
     ctx: Context<'_>,
     #[description = "The team to confirm."]
     #[rename = "team"]
+    #[autocomplete = "autocomplete_team"]
     team_id: String,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
 
     // Retrieve the team, or notify if it does not exist:
     let Some(mut team) = team::get_team(&gid, &team_id) else {
-        ctx.reply(format!("Team {} does not exist in this guild.", team_id).to_string()
-).await.expect(
-            format!(
-                "[teamedit] Failed to send reply after attempting to confirm non-existant team {} in guild {}.",
-                team_id, gid
+        let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "Team {} does not exist in this guild.",
+                "El equipo {} no existe en este servidor.",
+                team_id
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after attempting to confirm non-existant team {} in guild {}: {:?}",
+                team_id, gid, e
             )
-            .as_str(),
-        );
+        });
 
         return Ok(());
     };
 
     // Confirm the team:
-    team.confirm();
+    team.confirm(&ctx.data().feed);
 
     // Reply, as confirmation:
-    ctx.reply(
+    utils::reply(
+        ctx,
         format!(
             "Correctly confirmed team {}. It is no longer editable.",
             team_id
@@ -292,13 +662,12 @@ pub async fn confirm(
         .to_string(),
     )
     .await
-    .expect(
-        format!(
-            "[teamedit] Failed to send reply after confirming team {} in guild {}.",
-            team_id, gid
+    .unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after confirming team {} in guild {}: {:?}",
+            team_id, gid, e
         )
-        .as_str(),
-    );
+    });
 
     Ok(())
 }
@@ -307,45 +676,245 @@ pub async fn confirm(
     slash_command,
     ephemeral,
     description_localized("en-US", "Set the password of a team."),
-    description_localized("es-ES", "Set the password of a team.")
+    description_localized("es-ES", "Establecer la contraseña de un equipo.")
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn password(
     ctx: Context<'_>,
     #[description = "The team to set the password for."]
     #[rename = "team"]
+    #[autocomplete = "autocomplete_team"]
     team_id: String,
     #[description = "The new password for the team."] password: String,
+    #[description = "Log in to Tablón as the team afterwards, to check the password."]
+    check_login: Option<bool>,
 ) -> Result<(), Error> {
     let gid = get_guild_id!(ctx);
+    let check_login = check_login.unwrap_or(false);
+    if check_login {
+        ctx.defer_ephemeral().await?;
+    }
 
     // Retrieve the team, or notify if it does not exist:
     let Some(mut team) = team::get_team(&gid, &team_id) else {
-        ctx.reply(format!("Team {} does not exist in this guild.", team_id).to_string()
-).await.expect(
-            format!(
-                "[teamedit] Failed to send reply after attempting to set password for non-existant team {} in guild {}.",
-                team_id, gid
+        let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "Team {} does not exist in this guild.",
+                "El equipo {} no existe en este servidor.",
+                team_id
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after attempting to set password for non-existant team {} in guild {}: {:?}",
+                team_id, gid, e
             )
-            .as_str(),
-        );
+        });
 
         return Ok(());
     };
 
     // Set the password for the team:
-    team.set_password(password);
+    let mut batch = Batch::new(&ctx.data().caches);
+    team.set_password(password, &mut batch);
+    batch.flush();
 
     // Reply, as confirmation:
-    ctx.reply(format!("Correctly updated teh password for team {}.", team_id).to_string())
+    let mut reply = format!("Correctly updated the password for team {}.", team_id);
+    if check_login {
+        let tablon_url = utils::load_config(&ctx.data().caches, &gid).tablon_url;
+        reply.push_str(
+            &passwords::check_team_logins(
+                &ctx.data().tablon,
+                &tablon_url,
+                std::slice::from_ref(&team),
+            )
+            .await,
+        );
+    }
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after updating password for team {} in guild {}: {:?}",
+            team_id, gid, e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Change the password of a team mid-course, notifying its members."
+    ),
+    description_localized(
+        "es-ES",
+        "Cambiar la contraseña de un equipo a mitad de curso, avisando a sus miembros."
+    )
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn rotate_password(
+    ctx: Context<'_>,
+    #[description = "The team to change the password for."]
+    #[rename = "team"]
+    #[autocomplete = "autocomplete_team"]
+    team_id: String,
+    #[description = "The new password for the team. Leave empty to generate a random one."]
+    password: Option<String>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+
+    // Retrieve the team, or notify if it does not exist:
+    let Some(mut team) = team::get_team(&gid, &team_id) else {
+        let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "Team {} does not exist in this guild.",
+                "El equipo {} no existe en este servidor.",
+                team_id
+            ),
+        )
         .await
-        .expect(
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after attempting to rotate the password of \
+                    non-existant team {} in guild {}: {:?}",
+                team_id, gid, e
+            )
+        });
+
+        return Ok(());
+    };
+
+    // Update the password of the team (and its members' credentials), and the team info:
+    let password = password.unwrap_or_else(|| {
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(GENERATED_PASSWORD_LEN)
+            .map(char::from)
+            .collect()
+    });
+    let mut batch = Batch::new(&ctx.data().caches);
+    team.set_password(password.clone(), &mut batch);
+    batch.flush();
+    if let Some(mut info) = team::get_guild_team_info(&gid) {
+        info.set_password(team_id.clone(), password.clone());
+    }
+
+    // Notify the members (without the password itself, as DMs are less private than the
+    // ephemeral replies):
+    let gname = gid
+        .name(ctx.cache())
+        .unwrap_or_else(|| format!("guild {}", gid));
+    let mut unreachable = Vec::new();
+    for member in team.members() {
+        let msg = format!(
+            "🔑 The Tablón password of your team {} in **{}** has changed. Use `/settings get` \
+            in the server to see the new one.",
+            team_id, gname
+        );
+        if member
+            .direct_message(ctx.http(), serenity::CreateMessage::new().content(msg))
+            .await
+            .is_err()
+        {
+            unreachable.push(format!("<@{}>", member));
+        }
+    }
+
+    // Reply, as confirmation:
+    let mut reply = format!(
+        "The password for team {} is now `{}` (remember to change it in Tablón too, if needed).",
+        team_id, password
+    );
+    if !unreachable.is_empty() {
+        reply.push_str(
             format!(
-                "[teamedit] Failed to send reply after updating password for team {} in guild {}.",
-                team_id, gid
+                "\nThe following members could not be notified by DM: {}.",
+                unreachable.join(", ")
             )
             .as_str(),
         );
+    }
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after rotating the password of team {} in guild {}: {:?}",
+            team_id, gid, e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Remove the password of a team (e.g. when it was dissolved in Tablón)."
+    ),
+    description_localized(
+        "es-ES",
+        "Eliminar la contraseña de un equipo (p. ej., si se disolvió en Tablón)."
+    )
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn clear_password(
+    ctx: Context<'_>,
+    #[description = "The team to remove the password from."]
+    #[rename = "team"]
+    #[autocomplete = "autocomplete_team"]
+    team_id: String,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+
+    // Remove the password of the team (and its members' credentials), if it exists, and from the
+    // team info, for the team's identifier:
+    let had_team_password = match team::get_team(&gid, &team_id) {
+        Some(mut team) => {
+            let had_password = team.pass().is_some();
+            let mut batch = Batch::new(&ctx.data().caches);
+            team.clear_password(&mut batch);
+            batch.flush();
+            Some(had_password)
+        }
+        None => None,
+    };
+    let had_info_password =
+        team::get_guild_team_info(&gid).is_some_and(|mut info| info.clear_password(&team_id));
+
+    // Reply, as confirmation:
+    let reply = match had_team_password {
+        None if !had_info_password => {
+            format!("Team {} does not exist in this guild, nor has a password.", team_id)
+        }
+        Some(false) if !had_info_password => format!("Team {} has no password.", team_id),
+        Some(_) => format!(
+            "The password of team {} has been removed. Its members cannot send requests until a new one is set.",
+            team_id
+        ),
+        None => format!(
+            "The password for team {} has been removed (the team does not exist yet).",
+            team_id
+        ),
+    };
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after removing the password of team {} in guild {}: {:?}",
+            team_id, gid, e
+        )
+    });
 
     Ok(())
 }
@@ -354,13 +923,15 @@ pub async fn password(
     slash_command,
     ephemeral,
     description_localized("en-US", "Rename a team."),
-    description_localized("es-ES", "Rename a team.")
+    description_localized("es-ES", "Renombrar un equipo.")
 )]
+#[hermes::require_channel(bot_channel)]
 #[hermes::log_cmd]
 pub async fn rename(
     ctx: Context<'_>,
     #[description = "The team to rename."]
     #[rename = "team"]
+    #[autocomplete = "autocomplete_team"]
     team_id: String,
     #[description = "The new name for the team."] new_name: String,
 ) -> Result<(), Error> {
@@ -368,33 +939,452 @@ pub async fn rename(
 
     // Retrieve the team, or notify if it does not exist:
     let Some(mut team) = team::get_team(&gid, &team_id) else {
-        ctx.reply(format!("Team {} does not exist in this guild.", team_id).to_string()
-).await.expect(
+        let lang = i18n::guild_language(&utils::load_config(&ctx.data().caches, &gid));
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "Team {} does not exist in this guild.",
+                "El equipo {} no existe en este servidor.",
+                team_id
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after attempting to rename non-existant team {} in guild {}: {:?}",
+                team_id, gid, e
+            )
+        });
+
+        return Ok(());
+    };
+
+    // Rename the team, or notify why it cannot be:
+    if let Err(e) = team.change_name(new_name) {
+        utils::reply_error(
+            ctx,
             format!(
-                "[teamedit] Failed to send reply after attempting to rename non-existant team {} in guild {}.",
-                team_id, gid
+                "**Error:** {}",
+                e.message(i18n::guild_language(&utils::load_config(
+                    &ctx.data().caches,
+                    &gid
+                )))
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after failing to rename team {} in guild {}: {:?}",
+                team_id, gid, e
             )
-            .as_str(),
+        });
+
+        return Ok(());
+    }
+
+    // Reply, as confirmation:
+    utils::reply(
+        ctx,
+        format!("Correctly renamed team {} to {}.", team_id, team.name()).to_string(),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send reply after renaming team {} to {} in guild {}: {:?}",
+            team_id,
+            team.name(),
+            gid,
+            e
+        )
+    });
+    teamnotify::notify_members(
+        ctx.serenity_context(),
+        &ctx.data().caches,
+        &gid,
+        &team,
+        TeamChange::Renamed {
+            by: None,
+            name: team.name().clone(),
+        },
+    )
+    .await;
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Upload a mapping of the guild's students to their university logins or IDs."
+    ),
+    description_localized(
+        "es-ES",
+        "Subir una correspondencia de los estudiantes del servidor con sus usuarios o IDs de la universidad."
+    )
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn roster(
+    ctx: Context<'_>,
+    #[description = "File with a Discord user (ID or name) and a university login per line."]
+    file: serenity::Attachment,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+
+    // Read the provided file:
+    let content = reqwest::get(&file.url)
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit roster] Could not download roster file from URL: {}: {:?}",
+                file.url, e
+            )
+        })
+        .text()
+        .await
+        .expect("[teamedit roster] Could not read the roster file into a string.");
+
+    let user_map = utils::load_usermap();
+    let mut updated = 0;
+    let mut unknown = Vec::new();
+
+    // One user-login pair per line:
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.split_whitespace();
+        let (Some(user), Some(university_id)) = (parts.next(), parts.next()) else {
+            unknown.push(line.to_string());
+            continue;
+        };
+
+        // Users can be given by their Discord ID, or their (current or past) user name, as long as
+        // it is not ambiguous:
+        let uid = match user.parse::<u64>() {
+            Ok(id) => Some(UserId::new(id)),
+            Err(_) => match utils::find_users(&user_map, user)[..] {
+                [uid] => Some(uid),
+                _ => None,
+            },
+        };
+        let Some(mut student) = uid.and_then(|uid| student::get_student(&ctx.data().caches, &uid))
+        else {
+            unknown.push(line.to_string());
+            continue;
+        };
+
+        student.set_university_id(university_id.to_string());
+        updated += 1;
+    }
+
+    // Reply to the user, as confirmation:
+    let mut reply = format!("Updated the university logins of {} student(s).", updated);
+    if !unknown.is_empty() {
+        reply.push_str("\nThe following lines could not be matched to a student:\n```\n");
+        for line in unknown {
+            reply.push_str(format!("{}\n", line).as_str());
+        }
+        reply.push_str("```");
+    }
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[teamedit roster] Failed to send confirmation of roster upload in guild {}: {:?}",
+            gid, e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Check whether a student can send requests to Tablón, and what to fix otherwise."
+    ),
+    description_localized(
+        "es-ES",
+        "Comprobar si un estudiante puede enviar peticiones a Tablón, y qué arreglar si no."
+    )
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn check(
+    ctx: Context<'_>,
+    #[description = "The student to check."]
+    #[rename = "student"]
+    user: User,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    let lang = i18n::guild_language(&config);
+
+    let now = chrono::Utc::now().timestamp();
+    let surge = request::in_surge(&gid, &config, now);
+    let blockers =
+        service::submission_blockers(&FileStorage(&ctx.data().caches), &gid, &user.id, now, surge);
+    let reply = if blockers.is_empty() {
+        tr!(
+            lang,
+            "<@{}> can send requests to Tablón.",
+            "<@{}> puede enviar peticiones a Tablón.",
+            user.id
+        )
+    } else {
+        let mut reply = tr!(
+            lang,
+            "<@{}> can't send requests to Tablón:",
+            "<@{}> no puede enviar peticiones a Tablón:",
+            user.id
         );
+        for blocker in blockers.iter() {
+            reply.push_str(format!("\n- {}", blocker.message(lang)).as_str());
+        }
+        reply
+    };
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send the check of {} in guild {}: {:?}",
+            user.id, gid, e
+        )
+    });
+
+    Ok(())
+}
+
+/// Formats of the team snapshot sent by `/teamedit snapshot`.
+#[derive(Clone, Copy, Default, poise::ChoiceParameter)]
+pub enum SnapshotFormat {
+    /// The team, with an array of its requests.
+    #[default]
+    #[name = "json"]
+    Json,
+    /// One row per request, with a header row.
+    #[name = "csv"]
+    Csv,
+}
+
+/// A request of a team, as exported by `/teamedit snapshot`.
+#[derive(Serialize)]
+struct SnapshotRequest {
+    #[serde(flatten)]
+    request: tablon::TablonRequest,
+    /// The member who sent it through the bot, if it was.
+    sent_by: Option<UserId>,
+}
+
+/// A team's full record in Tablón, as exported by `/teamedit snapshot`.
+#[derive(Serialize)]
+struct TeamSnapshot {
+    team: String,
+    name: String,
+    /// When the snapshot was taken, as a UNIX timestamp (in seconds).
+    taken_at: i64,
+    requests: Vec<SnapshotRequest>,
+}
+
+#[poise::command(
+    slash_command,
+    ephemeral,
+    description_localized(
+        "en-US",
+        "Download all the requests and results of a team from Tablón, e.g. for grading disputes."
+    ),
+    description_localized(
+        "es-ES",
+        "Descargar todas las peticiones y resultados de un equipo de Tablón, p. ej. para revisiones."
+    )
+)]
+#[hermes::require_channel(bot_channel)]
+#[hermes::log_cmd]
+pub async fn snapshot(
+    ctx: Context<'_>,
+    #[description = "The team to take the snapshot of."]
+    #[rename = "team"]
+    #[autocomplete = "autocomplete_team"]
+    team_id: String,
+    #[description = "Format of the exported file (default: json)."] format: Option<SnapshotFormat>,
+) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let config = utils::load_config(&ctx.data().caches, &gid);
+    ctx.defer_ephemeral().await?;
+
+    // Retrieve the team, or notify if it does not exist:
+    let lang = i18n::guild_language(&config);
+    let Some(team) = team::get_team(&gid, &team_id) else {
+        utils::reply_error(
+            ctx,
+            tr!(
+                lang,
+                "Team {} does not exist in this guild.",
+                "El equipo {} no existe en este servidor.",
+                team_id
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[teamedit] Failed to send reply after attempting to take a snapshot of \
+                    non-existant team {} in guild {}: {:?}",
+                team_id, gid, e
+            )
+        });
 
         return Ok(());
     };
 
-    // Rename the team:
-    team.change_name(new_name);
+    // Fetch the team's requests from Tablón, with the guild's admin credentials:
+    let Some(credentials) = utils::get_admin_credentials(&gid) else {
+        utils::reply_error(
+            ctx,
+            "There are no admin credentials for Tablón. Use `/botconfig tablon_admin` to set them.",
+        )
+        .await
+        .expect("[teamedit] Could not send error message for missing admin credentials.");
 
-    // Reply, as confirmation:
-    ctx.reply(format!("Correctly renamed team {} to {}.", team_id, team.name()).to_string())
+        return Ok(());
+    };
+    let Some(requests) = ctx
+        .data()
+        .tablon
+        .fetch_team_requests(&config.tablon_url, &credentials, team.id())
+        .await
+    else {
+        utils::reply_error(
+            ctx,
+            format!(
+                "Could not fetch the requests of team {} from Tablón. Check its URL and the admin \
+                credentials.",
+                team_id
+            ),
+        )
         .await
-        .expect(
+        .expect("[teamedit] Could not send error message for unreachable Tablón.");
+
+        return Ok(());
+    };
+
+    // Add who sent each request through the bot, if known:
+    let senders: Vec<(UserId, Vec<u16>)> = team
+        .members()
+        .iter()
+        .filter_map(|member| student::get_student(&ctx.data().caches, member))
+        .map(|member| {
+            let ids = member
+                .full_request_history(&gid)
+                .iter()
+                .map(|req| req.id())
+                .collect();
+            (member.id(), ids)
+        })
+        .collect();
+    let snapshot = TeamSnapshot {
+        team: team.id().clone(),
+        name: team.name().clone(),
+        taken_at: chrono::Utc::now().timestamp(),
+        requests: requests
+            .into_iter()
+            .map(|request| SnapshotRequest {
+                sent_by: senders
+                    .iter()
+                    .find(|(_, ids)| ids.contains(&request.id))
+                    .map(|(member, _)| *member),
+                request,
+            })
+            .collect(),
+    };
+
+    let format = format.unwrap_or_default();
+    let (content, extension) = match format {
+        SnapshotFormat::Json => (
+            serde_json::to_string_pretty(&snapshot)
+                .expect("[teamedit] Could not serialize the team snapshot as JSON."),
+            "json",
+        ),
+        SnapshotFormat::Csv => {
+            let mut out = "request_id,queue,sent,status,sent_by\n".to_string();
+            for req in snapshot.requests.iter() {
+                writeln!(
+                    out,
+                    "{},{},{},{},{}",
+                    req.request.id,
+                    teamdump::csv_field(&req.request.queue),
+                    teamdump::csv_field(&req.request.sent),
+                    teamdump::csv_field(&req.request.status),
+                    req.sent_by.map(|user| user.to_string()).unwrap_or_default()
+                )
+                .unwrap();
+            }
+            (out, "csv")
+        }
+    };
+    let msg = CreateReply::default()
+        .content(tr!(
+            lang,
+            "Snapshot of the {} request(s) of team {} in Tablón:",
+            "Instantánea de las {} petición(es) del equipo {} en Tablón:",
+            snapshot.requests.len(),
+            snapshot.team
+        ))
+        .attachment(serenity::CreateAttachment::bytes(
+            content.into_bytes(),
+            format!("{}_snapshot.{}", snapshot.team, extension),
+        ))
+        .ephemeral(true);
+    utils::send(ctx, msg).await.unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send the snapshot of team {} in guild {}: {:?}",
+            team_id, gid, e
+        )
+    });
+
+    Ok(())
+}
+
+#[poise::command(
+    context_menu_command = "View team",
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only,
+    ephemeral
+)]
+#[hermes::log_cmd]
+pub async fn view_team(ctx: Context<'_>, user: User) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+
+    let team = student::get_student(&ctx.data().caches, &user.id)
+        .and_then(|student| student.get_team_id(&gid))
+        .and_then(|team_id| team::get_team(&gid, &team_id));
+    let reply = match team {
+        Some(team) => {
+            let members = team
+                .members()
+                .iter()
+                .map(|member| format!("<@{}>", member))
+                .collect::<Vec<_>>()
+                .join(", ");
             format!(
-                "[teamedit] Failed to send reply after renaming team {} to {} in guild {}.",
-                team_id,
+                "<@{}> is in team **{}** ({}), {}.\nMembers: {}.",
+                user.id,
+                team.id(),
                 team.name(),
-                gid
+                if team.confirmed() {
+                    "confirmed"
+                } else {
+                    "not confirmed yet"
+                },
+                members
             )
-            .as_str(),
-        );
+        }
+        None => format!("<@{}> is not in any team on this guild.", user.id),
+    };
+    utils::reply(ctx, reply).await.unwrap_or_else(|e| {
+        panic!(
+            "[teamedit] Failed to send the team of {} in guild {}: {:?}",
+            user.id, gid, e
+        )
+    });
 
     Ok(())
 }
@@ -0,0 +1,270 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    i18n::{self, tr, Language},
+    student::{self, Student},
+    team, teamrequest,
+    utils::{self, get_guild_id, get_triggering_student},
+    Context, Error,
+};
+use poise::{serenity_prelude as serenity, CreateReply};
+use serenity::GuildId;
+use std::time::Duration;
+
+/// Number of steps in the tutorial.
+const STEP_COUNT: usize = 4;
+
+/// Time to wait for the student to move to the next step, before ending the tutorial.
+const STEP_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// Builds the embed for a step of the tutorial, tailored to the current state of the student.
+fn tutorial_step(
+    student: &Student,
+    gid: &GuildId,
+    lang: Language,
+    step: usize,
+) -> serenity::CreateEmbed {
+    let (title, description) = match step {
+        // Join a team:
+        0 => {
+            let title = tr!(lang, "1. Join a team", "1. Únete a un equipo");
+            let description = match student.get_team_id(gid) {
+                Some(team_id) => {
+                    let confirmed = team::get_team(gid, &team_id).is_some_and(|t| t.confirmed());
+                    if confirmed {
+                        tr!(
+                            lang,
+                            "✅ You're already in team `{}`, and it is confirmed.",
+                            "✅ Ya estás en el equipo `{}`, y está confirmado.",
+                            team_id
+                        )
+                    } else {
+                        tr!(
+                            lang,
+                            "✅ You're already in team `{}`. You can still invite other students \
+                            with `/team invite`, until an admin confirms the team.",
+                            "✅ Ya estás en el equipo `{}`. Todavía puedes invitar a otros \
+                            estudiantes con `/team invite`, hasta que un administrador confirme \
+                            el equipo.",
+                            team_id
+                        )
+                    }
+                }
+                None => {
+                    if teamrequest::has_pending(student, gid) {
+                        tr!(
+                            lang,
+                            "You have pending team invitations! Check them with \
+                            `/team invitations`, and join one with `/team join`.",
+                            "¡Tienes invitaciones a equipos pendientes! Revísalas con \
+                            `/team invitations`, y únete a uno con `/team join`."
+                        )
+                    } else {
+                        tr!(
+                            lang,
+                            "You are not in a team yet. Create one with `/team create`, inviting \
+                            your partner(s), or ask them to invite you to theirs.",
+                            "Todavía no estás en ningún equipo. Crea uno con `/team create`, \
+                            invitando a tu(s) compañero(s), o pídeles que te inviten al suyo."
+                        )
+                    }
+                }
+            };
+            (title, description)
+        }
+        // Wait for the password:
+        1 => {
+            let title = tr!(lang, "2. Get your password", "2. Consigue tu contraseña");
+            let description = match student.get_credentials(gid) {
+                Some(credentials) if credentials.password().is_some() => tr!(
+                    lang,
+                    "✅ Your team already has a password for Tablón. You can check it with \
+                    `/settings get`.",
+                    "✅ Tu equipo ya tiene una contraseña para Tablón. Puedes verla con \
+                    `/settings get`."
+                ),
+                Some(_) => tr!(
+                    lang,
+                    "Your team does not have a password yet. The admins will set it once your \
+                    team is registered in Tablón; please wait.",
+                    "Tu equipo todavía no tiene contraseña. Los administradores la asignarán \
+                    cuando tu equipo esté registrado en Tablón; por favor, espera."
+                ),
+                None => tr!(
+                    lang,
+                    "Once you are in a team, the admins will set a password for it, so you can \
+                    send requests to Tablón.",
+                    "Cuando estés en un equipo, los administradores le asignarán una contraseña, \
+                    para que puedas enviar peticiones a Tablón."
+                ),
+            };
+            (title, description)
+        }
+        // Set the queue:
+        2 => {
+            let title = tr!(lang, "3. Set your queue", "3. Elige tu cola");
+            let description = match student.get_preferred_queue(gid) {
+                Some(queue) => tr!(
+                    lang,
+                    "✅ Your default queue is `{}`. You can change it with `/settings set_queue`.",
+                    "✅ Tu cola por defecto es `{}`. Puedes cambiarla con `/settings set_queue`.",
+                    queue
+                ),
+                None => tr!(
+                    lang,
+                    "Set the Tablón queue your requests are sent to by default with \
+                    `/settings set_queue`. You can also give it in each request, with `-q`.",
+                    "Elige la cola de Tablón a la que se envían tus peticiones por defecto con \
+                    `/settings set_queue`. También puedes indicarla en cada petición, con `-q`."
+                ),
+            };
+            (title, description)
+        }
+        // Submit:
+        _ => {
+            let title = tr!(lang, "4. Submit your program", "4. Envía tu programa");
+            let submissions = student
+                .request_history()
+                .get(gid)
+                .map_or(0, |hist| hist.len());
+            let description = if submissions > 0 {
+                tr!(
+                    lang,
+                    "✅ You have already sent {} request(s)! Send more with `/request`, and \
+                    check them with `/history show`.",
+                    "✅ ¡Ya has enviado {} petición(es)! Envía más con `/request`, y revísalas \
+                    con `/history show`.",
+                    submissions
+                )
+            } else {
+                tr!(
+                    lang,
+                    "Send your program to Tablón with `/request`, attaching the source file. \
+                    Then, check your requests with `/history show`.",
+                    "Envía tu programa a Tablón con `/request`, adjuntando el fichero fuente. \
+                    Después, revisa tus peticiones con `/history show`."
+                )
+            };
+            (title, description)
+        }
+    };
+
+    serenity::CreateEmbed::new()
+        .title(title)
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(tr!(
+            lang,
+            "Step {}/{} · Use /help for the full list of commands",
+            "Paso {}/{} · Usa /help para ver la lista completa de comandos",
+            step + 1,
+            STEP_COUNT
+        )))
+}
+
+#[poise::command(
+    slash_command,
+    category = "Student",
+    guild_only,
+    ephemeral,
+    description_localized("en-US", "Learn how to use Hermes, step by step."),
+    description_localized("es-ES", "Aprende a usar Hermes, paso a paso.")
+)]
+#[hermes::log_cmd]
+pub async fn tutorial(ctx: Context<'_>) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let student = get_triggering_student!(ctx);
+    let lang = i18n::user_language(
+        &student,
+        &utils::load_config(&ctx.data().caches, &gid),
+        &gid,
+    );
+
+    let next_button_id = format!("{}next", ctx.id());
+    let next_button = |step: usize| {
+        let label = if step + 1 < STEP_COUNT {
+            tr!(lang, "Next", "Siguiente")
+        } else {
+            tr!(lang, "Done", "Terminar")
+        };
+        vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(&next_button_id).label(label),
+        ])]
+    };
+
+    // Send the first step:
+    let mut step = 0;
+    utils::send(
+        ctx,
+        CreateReply::default()
+            .embed(tutorial_step(&student, &gid, lang, step))
+            .components(next_button(step)),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[tutorial] Failed to send the tutorial to user {}: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    // Move through the steps:
+    let ctx_id = ctx.id();
+    while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id == format!("{}next", ctx_id))
+        .timeout(STEP_TIMEOUT)
+        .await
+    {
+        step += 1;
+        let response = if step < STEP_COUNT {
+            // Reload the student, in case they followed the previous step in the meantime:
+            let student = student::get_existing_student!(&ctx.data().caches, ctx.author().id);
+            serenity::CreateInteractionResponseMessage::new()
+                .embed(tutorial_step(&student, &gid, lang, step))
+                .components(next_button(step))
+        } else {
+            serenity::CreateInteractionResponseMessage::new()
+                .content(tr!(
+                    lang,
+                    "That's all! Good luck with your assignments 🚀",
+                    "¡Eso es todo! Mucha suerte con tus prácticas 🚀"
+                ))
+                .embeds(Vec::new())
+                .components(Vec::new())
+        };
+        press
+            .create_response(
+                ctx.serenity_context(),
+                serenity::CreateInteractionResponse::UpdateMessage(response),
+            )
+            .await
+            .unwrap_or_else(|e| {
+                panic!(
+                    "[tutorial] Failed to update the tutorial for user {}: {:?}",
+                    student.id(),
+                    e
+                )
+            });
+
+        if step >= STEP_COUNT {
+            break;
+        }
+    }
+
+    Ok(())
+}
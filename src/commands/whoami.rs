@@ -0,0 +1,100 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    student, team,
+    utils::{self, get_guild_id, get_triggering_student},
+    Context, Error,
+};
+use std::fmt::Write;
+
+#[poise::command(
+    slash_command,
+    category = "Student",
+    guild_only,
+    ephemeral,
+    description_localized("en-US", "Show a summary of your team, settings, and activity."),
+    description_localized(
+        "es-ES",
+        "Mostrar un resumen de tu equipo, tus ajustes y tu actividad."
+    )
+)]
+#[hermes::log_cmd]
+pub async fn whoami(ctx: Context<'_>) -> Result<(), Error> {
+    let gid = get_guild_id!(ctx);
+    let student = get_triggering_student!(ctx);
+
+    let mut reply_msg = format!("**<@{}> in this server:**\n", student.id());
+
+    // Team information:
+    if let Some(team_id) = student.get_team_id(&gid) {
+        if let Some(team) = team::get_team(&gid, &team_id) {
+            if team.name() != team.id() {
+                writeln!(
+                    &mut reply_msg,
+                    "- Team: `{}` (\"{}\")",
+                    team.id(),
+                    team.name()
+                )
+                .unwrap();
+            } else {
+                writeln!(&mut reply_msg, "- Team: `{}`", team.id()).unwrap();
+            }
+            if team.confirmed() {
+                writeln!(&mut reply_msg, "- Team status: confirmed").unwrap();
+            } else {
+                writeln!(&mut reply_msg, "- Team status: not confirmed yet").unwrap();
+            }
+        } else {
+            writeln!(&mut reply_msg, "- Team: `{}`", team_id).unwrap();
+        }
+        if student.get_existing_credentials(&gid).password().is_some() {
+            writeln!(&mut reply_msg, "- Password: set").unwrap();
+        } else {
+            writeln!(&mut reply_msg, "- Password: [Not set]").unwrap();
+        }
+    } else {
+        writeln!(&mut reply_msg, "- You are not in a team in this server").unwrap();
+    }
+
+    // Preferred queue:
+    if let Some(queue) = student.get_preferred_queue(&gid) {
+        writeln!(&mut reply_msg, "- Default queue for requests: `{}`", queue).unwrap();
+    } else {
+        writeln!(&mut reply_msg, "- Default queue for requests: [Not set]").unwrap();
+    }
+
+    // Submissions:
+    let submissions = student.full_request_history(&gid).len();
+    writeln!(
+        &mut reply_msg,
+        "- Requests sent through Hermes: {}",
+        submissions
+    )
+    .unwrap();
+
+    // Reply with the summary:
+    utils::reply(ctx, reply_msg).await.unwrap_or_else(|e| {
+        panic!(
+            "[whoami] Failed to send reply after user {} requested their summary: {:?}",
+            student.id(),
+            e
+        )
+    });
+
+    Ok(())
+}
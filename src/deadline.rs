@@ -0,0 +1,254 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::utils;
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+use serenity::all::GuildId;
+
+/// Data structure defining a submission deadline in a guild.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct Deadline {
+    /// Name of the deadline (e.g. the assignment it corresponds to). Unique in the guild.
+    #[getset(get = "pub")]
+    name: String,
+    /// When the deadline expires, as a UNIX timestamp (in seconds).
+    #[getset(get_copy = "pub")]
+    timestamp: i64,
+}
+
+/// Data structure grouping the deadlines of a guild.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Serialize, Deserialize, Getters)]
+pub struct GuildDeadlines {
+    /// Identifier for the guild corresponding to these deadlines, for convenience reasons.
+    guild_id: GuildId,
+    /// The guild's deadlines, sorted by expiration.
+    #[getset(get = "pub")]
+    deadlines: Vec<Deadline>,
+}
+
+impl GuildDeadlines {
+    /// Constructor for a GuildDeadlines object, with no deadlines.
+    pub fn new(guild_id: GuildId) -> GuildDeadlines {
+        Self {
+            guild_id,
+            deadlines: Vec::new(),
+        }
+    }
+
+    /// Adds a deadline to the guild, or updates its expiration if it already exists.
+    pub fn set_deadline(&mut self, name: String, timestamp: i64) {
+        self.deadlines.retain(|deadline| deadline.name != name);
+        self.deadlines.push(Deadline { name, timestamp });
+        self.deadlines.sort_by_key(|deadline| deadline.timestamp);
+
+        self.save();
+    }
+
+    /// Removes a deadline from the guild, given its name.
+    ///
+    /// Returns whether the deadline existed.
+    pub fn remove_deadline(&mut self, name: &str) -> bool {
+        let previous_len = self.deadlines.len();
+        self.deadlines.retain(|deadline| deadline.name != name);
+        let removed = self.deadlines.len() != previous_len;
+
+        self.save();
+
+        removed
+    }
+
+    /// Returns the deadlines that have not expired yet at the given time.
+    pub fn upcoming(&self, now: i64) -> impl Iterator<Item = &Deadline> {
+        self.deadlines
+            .iter()
+            .filter(move |deadline| deadline.timestamp > now)
+    }
+
+    /// Returns the upcoming deadline whose surge protection (the given number of minutes before it,
+    /// see `BotConfig::surge_minutes`) applies at the given time, if any.
+    pub fn in_surge(&self, now: i64, minutes: u32) -> Option<&Deadline> {
+        if minutes == 0 {
+            return None;
+        }
+
+        self.upcoming(now)
+            .find(|deadline| deadline.timestamp - minutes as i64 * 60 <= now)
+    }
+
+    /// Exports the deadlines that have not expired yet at the given time as an iCalendar (`.ics`)
+    /// file. Their times are in UTC, and the given timezone is only suggested to show them in.
+    pub fn to_ical(&self, now: i64, timezone: Tz) -> String {
+        let format_utc = |timestamp: i64| {
+            Utc.timestamp_opt(timestamp, 0)
+                .unwrap()
+                .format("%Y%m%dT%H%M%SZ")
+                .to_string()
+        };
+
+        let mut ical = String::new();
+        ical_line(&mut ical, "BEGIN:VCALENDAR");
+        ical_line(&mut ical, "VERSION:2.0");
+        ical_line(&mut ical, "PRODID:-//Hermes//Deadlines//EN");
+        ical_line(&mut ical, &format!("X-WR-TIMEZONE:{}", timezone.name()));
+        for deadline in self.upcoming(now) {
+            ical_line(&mut ical, "BEGIN:VEVENT");
+            ical_line(
+                &mut ical,
+                &format!(
+                    "UID:{}-{}@hermes.{}",
+                    deadline.timestamp,
+                    deadline
+                        .name
+                        .replace(|c: char| !c.is_ascii_alphanumeric(), "-"),
+                    self.guild_id
+                ),
+            );
+            ical_line(&mut ical, &format!("DTSTAMP:{}", format_utc(now)));
+            // Without an end, the event ends when it starts:
+            ical_line(
+                &mut ical,
+                &format!("DTSTART:{}", format_utc(deadline.timestamp)),
+            );
+            ical_line(
+                &mut ical,
+                &format!("SUMMARY:{}", ical_escape(&deadline.name)),
+            );
+            ical_line(&mut ical, "END:VEVENT");
+        }
+        ical_line(&mut ical, "END:VCALENDAR");
+
+        ical
+    }
+
+    /// Saves the guild's deadlines to disk as a JSON file.
+    ///
+    /// Deadline files are saved as `guilds/<guild_id>/deadlines.json`.
+    pub fn save(&self) {
+        let json = serde_json::to_string_pretty(self).unwrap_or_else(|e| {
+            panic!(
+                "[GuildDeadlines] Could not serialize the deadlines for guild {}: {:?}",
+                self.guild_id, e
+            )
+        });
+
+        // Suppose `guilds/<gid>/` directory exists.
+        utils::write_file(&format!("guilds/{}/deadlines.json", self.guild_id), json)
+            .unwrap_or_else(|e| {
+                panic!(
+                "[GuildDeadlines] Could not write the deadlines file for guild {} to disk: {:?}",
+                self.guild_id, e
+            )
+            });
+    }
+}
+
+/// Maximum length of the lines of an iCalendar file, in bytes (without the line break).
+const ICAL_LINE_LEN: usize = 75;
+
+/// Adds a line to an iCalendar file, folding it into several if it is too long (i.e. continuing it
+/// in lines starting with a space), without splitting any character.
+fn ical_line(ical: &mut String, line: &str) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > ICAL_LINE_LEN {
+            ical.push_str("\r\n ");
+            len = 1;
+        }
+        ical.push(c);
+        len += c.len_utf8();
+    }
+    ical.push_str("\r\n");
+}
+
+/// Escapes a text value for an iCalendar file.
+fn ical_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Retrieve the deadlines of a guild, or an empty set of deadlines if the guild has none.
+pub fn get_guild_deadlines(guild_id: &GuildId) -> GuildDeadlines {
+    // Only a missing file means the guild has no deadlines (e.g. not a failed read, which would
+    // lead to them being overwritten):
+    let json = match utils::read_file(&format!("guilds/{}/deadlines.json", guild_id)) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return GuildDeadlines::new(*guild_id)
+        }
+        json => json.unwrap_or_else(|e| {
+            panic!(
+                "[GuildDeadlines] Could not read guilds/{}/deadlines.json: {:?}",
+                guild_id, e
+            )
+        }),
+    };
+
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        panic!(
+            "[GuildDeadlines] Could not parse guilds/{}/deadlines.json as valid JSON: {:?}",
+            guild_id, e
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ical_export() {
+        let deadlines = GuildDeadlines {
+            guild_id: GuildId::new(1),
+            deadlines: vec![
+                Deadline {
+                    name: "Past".to_string(),
+                    timestamp: 1704110400,
+                },
+                Deadline {
+                    name: "Práctica 1; entrega final, con memoria y código fuente (versión definitiva)"
+                        .to_string(),
+                    // 2025-01-01 12:00:00 UTC (13:00 in Madrid):
+                    timestamp: 1735732800,
+                },
+            ],
+        };
+        let ical = deadlines.to_ical(1735000000, chrono_tz::Europe::Madrid);
+
+        // Every line ends in CRLF, and is folded at 75 bytes:
+        assert!(ical.ends_with("END:VCALENDAR\r\n"));
+        let lines: Vec<&str> = ical.strip_suffix("\r\n").unwrap().split("\r\n").collect();
+        assert!(lines.iter().all(|line| line.len() <= ICAL_LINE_LEN));
+        assert!(lines.iter().any(|line| line.starts_with(' ')));
+
+        // Only the upcoming deadline is exported, in UTC:
+        let unfolded = ical.replace("\r\n ", "");
+        assert_eq!(unfolded.matches("BEGIN:VEVENT").count(), 1);
+        assert!(unfolded.contains("\r\nDTSTART:20250101T120000Z\r\n"));
+        assert!(unfolded.contains("\r\nDTSTAMP:20241224T002640Z\r\n"));
+        assert!(unfolded.contains(
+            "\r\nSUMMARY:Práctica 1\\; entrega final\\, con memoria y código fuente \
+            (versión definitiva)\r\n"
+        ));
+        assert!(unfolded.contains("\r\nX-WR-TIMEZONE:Europe/Madrid\r\n"));
+    }
+}
@@ -0,0 +1,137 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Line-based diffs between two texts, in the unified format (as `diff -u`), to compare the
+//! programs sent in two requests.
+
+use std::fmt::Write as _;
+
+/// Maximum number of lines of each text to compare, as the diff takes quadratic time and memory.
+pub const MAX_LINES: usize = 2000;
+
+/// Operation on a line, turning the old text into the new one.
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Computes the operations turning `old` into `new`, from their longest common subsequence of
+/// lines. Each operation comes with the index of its line in the old and the new text.
+fn operations(old: &[&str], new: &[&str]) -> Vec<(Op, usize, usize)> {
+    // Length of the longest common subsequence of the suffixes starting at each pair of lines:
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((Op::Keep, i, j));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removals go first, as in `diff -u`:
+            ops.push((Op::Remove, i, j));
+            i += 1;
+        } else {
+            ops.push((Op::Add, i, j));
+            j += 1;
+        }
+    }
+
+    ops
+}
+
+/// Builds the unified diff turning `old` into `new`, with `context` unchanged lines around each
+/// change, and the given names in the headers.
+///
+/// Returns `None` if either text has more than `MAX_LINES` lines, and an empty string if they have
+/// the same lines.
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_name: &str,
+    new_name: &str,
+    context: usize,
+) -> Option<String> {
+    let old_lines: Vec<_> = old.lines().collect();
+    let new_lines: Vec<_> = new.lines().collect();
+    if old_lines.len() > MAX_LINES || new_lines.len() > MAX_LINES {
+        return None;
+    }
+
+    let ops = operations(&old_lines, &new_lines);
+    let changes: Vec<_> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _, _))| *op != Op::Keep)
+        .map(|(k, _)| k)
+        .collect();
+    let mut out = String::new();
+    if changes.is_empty() {
+        return Some(out);
+    }
+    writeln!(out, "--- {}\n+++ {}", old_name, new_name).unwrap();
+
+    // Group the changes into hunks, merging those with overlapping context:
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(context);
+        let mut end = changes[k];
+        while k + 1 < changes.len() && changes[k + 1] <= end + 2 * context + 1 {
+            k += 1;
+            end = changes[k];
+        }
+        let end = (end + context + 1).min(ops.len());
+        k += 1;
+
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|(op, _, _)| *op != Op::Add).count();
+        let new_count = hunk.iter().filter(|(op, _, _)| *op != Op::Remove).count();
+        let (_, old_start, new_start) = hunk[0];
+        // Empty ranges start at the line before, as in `diff -u`:
+        writeln!(
+            out,
+            "@@ -{},{} +{},{} @@",
+            old_start + usize::from(old_count > 0),
+            old_count,
+            new_start + usize::from(new_count > 0),
+            new_count
+        )
+        .unwrap();
+        for (op, i, j) in hunk {
+            match op {
+                Op::Keep => writeln!(out, " {}", old_lines[*i]),
+                Op::Remove => writeln!(out, "-{}", old_lines[*i]),
+                Op::Add => writeln!(out, "+{}", new_lines[*j]),
+            }
+            .unwrap();
+        }
+    }
+
+    Some(out)
+}
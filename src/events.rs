@@ -0,0 +1,150 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Machine-readable event stream of each guild, so course tooling (e.g. graders) can follow what
+//! happens through Hermes without parsing Discord messages.
+//!
+//! Each line of `guilds/<guild_id>/events.jsonl` is a JSON object with the schema version
+//! (`"version"`, currently 1), when the event happened (`"timestamp"`, as a UNIX timestamp in
+//! seconds), its `"type"`, and the fields of that type of event:
+//! - `submission`: `team`, `user`, `request_id`, `queue` (or `null`) and `program` (the file name),
+//!   when a request is sent to Tablón.
+//! - `result`: `team` (or `null`, if the user left it), `user`, `request_id` and `verdict`, when the
+//!   verdict of a request becomes known.
+//! - `team_joined` and `team_left`: `team` and `user`.
+//! - `team_renamed`: `team` and `name`.
+//! - `team_confirmed`, `team_unconfirmed` and `team_deleted`: `team`.
+//! - `team_id_changed`: `team` (its old identifier) and `new_team`, when the guild's team prefix
+//!   changes (see `team::migrate_prefix`).
+//! - `config_changed`: `user` (or `null`), `field` (or `null`, if the whole configuration was
+//!   replaced) and `value` (its new value, as JSON).
+//!
+//! Fields may be added to the events in the future, but existing ones are only changed along with
+//! the schema version.
+//!
+//! The notable events (e.g. `team_confirmed`) are also posted to the guild's activity feed, when
+//! recorded through `record_notable` (see `activityfeed`).
+
+use crate::{
+    activityfeed::{Activity, Feed},
+    alerts::{self, AlertKind},
+};
+use serde::Serialize;
+use serenity::all::{GuildId, UserId};
+use std::{fs, io::Write as _};
+
+/// Version of the schema of the events.
+const SCHEMA_VERSION: u32 = 1;
+
+/// An event in a guild (see the module documentation for their schemas).
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Submission {
+        team: String,
+        user: UserId,
+        request_id: u16,
+        queue: Option<String>,
+        program: String,
+    },
+    Result {
+        team: Option<String>,
+        user: UserId,
+        request_id: u16,
+        verdict: String,
+    },
+    TeamJoined {
+        team: String,
+        user: UserId,
+    },
+    TeamLeft {
+        team: String,
+        user: UserId,
+    },
+    TeamRenamed {
+        team: String,
+        name: String,
+    },
+    TeamConfirmed {
+        team: String,
+    },
+    TeamUnconfirmed {
+        team: String,
+    },
+    TeamDeleted {
+        team: String,
+    },
+    TeamIdChanged {
+        team: String,
+        new_team: String,
+    },
+    ConfigChanged {
+        user: Option<UserId>,
+        field: Option<String>,
+        value: serde_json::Value,
+    },
+}
+
+/// An event, as recorded in the event stream.
+#[derive(Serialize)]
+struct EventRecord<'a> {
+    version: u32,
+    timestamp: i64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Records an event in the event stream of a guild.
+///
+/// Failures are only reported through the logs, as they should never make a command fail.
+pub fn record(guild_id: &GuildId, event: Event) {
+    let json = serde_json::to_string(&EventRecord {
+        version: SCHEMA_VERSION,
+        timestamp: chrono::Utc::now().timestamp(),
+        event: &event,
+    })
+    .unwrap_or_else(|e| {
+        panic!(
+            "[events] Failed to serialize an event for guild {}: {:?}",
+            guild_id, e
+        )
+    });
+    let result = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(format!("guilds/{}/events.jsonl", guild_id))
+        .and_then(|mut stream| writeln!(stream, "{}", json));
+    if let Err(e) = result {
+        alerts::alert(
+            AlertKind::DiskWrite,
+            format!(
+                "Failed to write to the event stream of guild {}: {}",
+                guild_id, e
+            ),
+        );
+    }
+}
+
+/// Records an event in the event stream of a guild, posting it to the guild's activity feed if it
+/// is a notable one (see `Activity::from_event`).
+pub fn record_notable(feed: &Feed, guild_id: &GuildId, event: Event) {
+    let activity = Activity::from_event(&event);
+    record(guild_id, event);
+    if let Some(activity) = activity {
+        feed.post(*guild_id, activity);
+    }
+}
@@ -0,0 +1,227 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Golden files of the bot's formatted outputs (every `/teamdump` format, the leaderboards, and
+//! the activity feed's embeds), checked by the tests, so formatting changes are reviewed deliberately: a change in the output fails the
+//! tests until the golden files are regenerated with `HERMES_BLESS=1 cargo test golden`, and the
+//! diff of `fixtures/golden/` is committed along the change.
+
+use crate::{
+    activityfeed::Activity,
+    commands::teamdump::{self, DumpFormat, DumpedMember, DumpedTeam},
+    i18n::Language,
+    leaderboard::{self, Standing},
+    utils,
+};
+use serenity::all::UserId;
+use std::{env, fs, path::PathBuf};
+
+/// Directory of the golden files, in the source tree.
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/golden")
+}
+
+/// Teams covering the edge cases of the formats: names needing quotes in CSV, long names, members
+/// without a university ID, teams without results, and teams without members.
+fn sample_teams() -> Vec<DumpedTeam> {
+    let member = |id: u64, name: &str, university_id: Option<&str>| DumpedMember {
+        discord_id: UserId::new(id),
+        discord_name: name.to_string(),
+        university_id: university_id.map(str::to_string),
+    };
+
+    vec![
+        DumpedTeam {
+            id: "g01".to_string(),
+            name: "g01".to_string(),
+            confirmed: true,
+            members: vec![
+                member(100000000000000001, "alice", Some("e12345678")),
+                member(100000000000000002, "bob", Some("e87654321")),
+            ],
+            best_result: Some((1.5, 12)),
+        },
+        DumpedTeam {
+            id: "g02".to_string(),
+            name: "Los \"rápidos\", de Valladolid".to_string(),
+            confirmed: false,
+            members: vec![
+                member(100000000000000003, "carol, the fast", Some("e11111111")),
+                member(100000000000000004, "dave", None),
+            ],
+            best_result: Some((3.0, 7)),
+        },
+        DumpedTeam {
+            id: "g100".to_string(),
+            name:
+                "A team with a very long name that goes on and on, well past any reasonable length"
+                    .to_string(),
+            confirmed: false,
+            members: vec![member(100000000000000005, "erin", Some("e22222222"))],
+            best_result: None,
+        },
+        DumpedTeam {
+            id: "g101".to_string(),
+            name: "g101".to_string(),
+            confirmed: false,
+            members: Vec::new(),
+            best_result: None,
+        },
+    ]
+}
+
+/// Leaderboard of an exercise with `len` teams, with decimal results and ties.
+fn sample_leaderboard(len: usize) -> Vec<Standing> {
+    (0..len)
+        .map(|i| Standing {
+            team_id: format!("g{:02}", i + 1),
+            public_name: if i == 1 {
+                "Los \"rápidos\"".to_string()
+            } else {
+                format!("g{:02}", i + 1)
+            },
+            result: 0.25 + (i / 2) as f64 * 1.5,
+            request_id: (100 + i) as u16,
+        })
+        .collect()
+}
+
+/// Checks an output against its golden file, or overwrites the file with it if `HERMES_BLESS` is
+/// set.
+fn check(file_name: &str, output: &str) {
+    let path = golden_dir().join(file_name);
+    if env::var_os("HERMES_BLESS").is_some() {
+        fs::create_dir_all(golden_dir()).unwrap();
+        fs::write(&path, output).unwrap();
+        return;
+    }
+
+    let golden = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", path.display(), e));
+    assert!(
+        golden == output,
+        "{} differs from the output; if the change is intended, bless it with \
+        `HERMES_BLESS=1 cargo test golden`.\n--- golden\n{}\n--- output\n{}",
+        file_name,
+        golden,
+        output
+    );
+}
+
+/// Checks the `/teamdump` file in a format against its golden file.
+fn check_teamdump(name: &str, format: DumpFormat) {
+    let output = teamdump::dump_file(&sample_teams(), format, teamdump::DEFAULT_MAX_GRADE);
+    check(
+        &format!("teamdump_{}.{}", name, format.extension()),
+        &output,
+    );
+}
+
+#[test]
+fn teamdump_txt() {
+    check_teamdump("txt", DumpFormat::Txt);
+}
+
+#[test]
+fn teamdump_csv() {
+    check_teamdump("csv", DumpFormat::Csv);
+}
+
+#[test]
+fn teamdump_json() {
+    check_teamdump("json", DumpFormat::Json);
+}
+
+#[test]
+fn teamdump_moodle() {
+    check_teamdump("moodle", DumpFormat::Moodle);
+}
+
+#[test]
+fn leaderboard_en() {
+    let output = leaderboard::render(Language::English, "sort", &sample_leaderboard(5));
+    check("leaderboard_en.md", &output);
+}
+
+#[test]
+fn leaderboard_es() {
+    let output = leaderboard::render(Language::Spanish, "sort", &sample_leaderboard(5));
+    check("leaderboard_es.md", &output);
+}
+
+/// A leaderboard too long for a single message, as split to be posted.
+#[test]
+fn leaderboard_split() {
+    let output = leaderboard::render(Language::English, "sort", &sample_leaderboard(120));
+    assert!(output.chars().count() > utils::MAX_MESSAGE_LEN);
+    let chunks = utils::split_message(&output, utils::MAX_MESSAGE_LEN);
+    let output = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| format!("----- message {}/{} -----\n{}", i + 1, chunks.len(), chunk))
+        .collect::<String>();
+    check("leaderboard_split.md", &output);
+}
+
+/// Every kind of activity feed embed, as sent to Discord (without their timestamp, which is the
+/// current time).
+#[test]
+fn activity_embeds() {
+    let activities = [
+        Activity::TeamConfirmed {
+            team: "g01".to_string(),
+        },
+        Activity::PasswordsImported {
+            updated: 12,
+            future: 3,
+        },
+        Activity::ConfigChanged {
+            user: Some(UserId::new(100000000000000001)),
+            field: Some("team_prefix".to_string()),
+            value: serde_json::json!("t"),
+        },
+        Activity::ConfigChanged {
+            user: Some(UserId::new(100000000000000001)),
+            field: Some("bot_channel".to_string()),
+            value: serde_json::json!("a".repeat(150)),
+        },
+        Activity::ConfigChanged {
+            user: None,
+            field: None,
+            value: serde_json::Value::Null,
+        },
+        Activity::BackupCompleted {
+            dir: "backups/2025-01-01".to_string(),
+        },
+        Activity::InactiveTeams {
+            teams: vec!["g02".to_string(), "g100".to_string()],
+            days: 7,
+        },
+        Activity::TablonDown { minutes: 15 },
+        Activity::TablonBack,
+    ];
+    let embeds: Vec<serde_json::Value> = activities
+        .iter()
+        .map(|activity| {
+            let mut embed = serde_json::to_value(activity.embed()).unwrap();
+            embed.as_object_mut().unwrap().remove("timestamp");
+            embed
+        })
+        .collect();
+    let output = serde_json::to_string_pretty(&embeds).unwrap() + "\n";
+    check("activity_embeds.json", &output);
+}
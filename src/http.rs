@@ -0,0 +1,157 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! HTTP server of the bot, shared by every endpoint it exposes to external services (see `api` and
+//! `webhook`).
+
+use crate::{
+    api,
+    cache::Caches,
+    team::{self, Team},
+    webhook,
+};
+use axum::{
+    http::{HeaderMap, StatusCode},
+    Router,
+};
+use poise::serenity_prelude as serenity;
+use rand::{distributions::Alphanumeric, Rng as _};
+use std::{net::SocketAddr, sync::Arc};
+
+/// Length of the tokens generated for authenticating to the endpoints.
+const TOKEN_LEN: usize = 32;
+
+/// State shared by the HTTP endpoints.
+#[derive(Clone)]
+pub struct HttpState {
+    /// Discord's HTTP client, for notifying users.
+    pub http: Arc<serenity::Http>,
+    /// Caches of the guilds' configurations and the students (see `cache`).
+    pub caches: Caches,
+}
+
+/// Starts serving the bot's HTTP endpoints on the given address, in the background.
+pub async fn start(addr: SocketAddr, http: Arc<serenity::Http>, caches: Caches) {
+    let router = Router::new()
+        .merge(api::routes())
+        .merge(webhook::routes())
+        .with_state(HttpState { http, caches });
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .unwrap_or_else(|e| panic!("Could not listen for HTTP requests on {}: {:?}", addr, e));
+    tracing::info!("Serving HTTP endpoints on {}.", addr);
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            tracing::error!("The HTTP server crashed: {}", e);
+        }
+    });
+}
+
+/// Generates a random token for authenticating to the endpoints.
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// Checks that a request presents the expected token, in an `Authorization: Bearer <token>`
+/// header. Requests are rejected as not found when no token is set, so the endpoints of the guilds
+/// that didn't enable them are indistinguishable from those of unknown guilds.
+pub fn authorize(headers: &HeaderMap, expected: Option<&String>) -> Result<(), StatusCode> {
+    let Some(expected) = expected else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let given = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if secrets_match(given, expected) {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// Parses the guild identifier of an endpoint's path, if it belongs to a guild known to the bot.
+pub fn known_guild(guild_id: u64) -> Option<serenity::GuildId> {
+    if guild_id == 0 || std::fs::metadata(format!("guilds/{}", guild_id)).is_err() {
+        return None;
+    }
+
+    Some(serenity::GuildId::new(guild_id))
+}
+
+/// Finds a team of a guild given its identifier in a request. As it comes from outside, only the
+/// valid team identifiers of the guild are looked up (see `GuildTeamInfo::canonical_team_id`), so
+/// it never names any other file.
+///
+/// This reads the team's files, so it must not run in the async runtime.
+pub fn known_team(guild_id: &serenity::GuildId, team_id: &str) -> Option<Team> {
+    let (team_id, _) = team::get_guild_team_info(guild_id)?.canonical_team_id(team_id)?;
+
+    team::get_team(guild_id, &team_id)
+}
+
+/// Compares two secrets in constant time (with respect to their contents).
+fn secrets_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cache::Caches, team::GuildTeamInfo, testutil::TempDataDir, utils};
+
+    #[test]
+    fn only_valid_team_ids_are_looked_up() {
+        let _dir = TempDataDir::new("http-teams");
+        let caches = Caches::default();
+        let gid = serenity::GuildId::new(1);
+        crate::init_guild_files(&caches, &gid);
+        let mut info = GuildTeamInfo::new(gid, utils::load_config(&caches, &gid).team_prefix);
+        let team_id = info.next_team_id(|_| false);
+        info.save();
+        Team::new(&info, team_id);
+
+        assert_eq!(known_team(&gid, "g01").unwrap().id(), "g01");
+        assert_eq!(known_team(&gid, "g1").unwrap().id(), "g01");
+        for team_id in [
+            "",
+            "info",
+            "g02",
+            "g0",
+            "../teams/g01",
+            "g01/../info",
+            "g01.json",
+        ] {
+            assert!(
+                known_team(&gid, team_id).is_none(),
+                "{:?} was found",
+                team_id
+            );
+        }
+    }
+}
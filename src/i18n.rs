@@ -0,0 +1,95 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{student::Student, utils::BotConfig};
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::all::GuildId;
+
+/// Languages the bot can reply in.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum Language {
+    #[default]
+    #[name = "English"]
+    #[serde(rename = "en")]
+    English,
+    #[name = "Español"]
+    #[serde(rename = "es")]
+    Spanish,
+}
+
+/// Language for the replies to a student that only they can see (e.g. ephemeral replies): the
+/// student's own choice, if any, or the guild's language otherwise.
+pub fn user_language(student: &Student, config: &BotConfig, guild_id: &GuildId) -> Language {
+    student.get_language(guild_id).unwrap_or(config.language)
+}
+
+/// Language for the messages that everyone in the guild can see (e.g. public notifications).
+pub fn guild_language(config: &BotConfig) -> Language {
+    config.language
+}
+
+/// Formats a date and time as usual in the given language (`MM/DD/YYYY hh:mm:ss AM` for English,
+/// as in en-US, and `DD/MM/YYYY HH:mm:ss` for Spanish, as in es-ES).
+pub fn format_datetime(lang: Language, datetime: &DateTime<Tz>) -> String {
+    let pattern = match lang {
+        Language::English => "%m/%d/%Y %I:%M:%S %p %Z",
+        Language::Spanish => "%d/%m/%Y %H:%M:%S %Z",
+    };
+
+    datetime.format(pattern).to_string()
+}
+
+/// Formats a day of the year (without the year) as usual in the given language (`MM/DD` for
+/// English, and `DD/MM` for Spanish), e.g. for charts.
+pub fn format_day(lang: Language, date: &NaiveDate) -> String {
+    let pattern = match lang {
+        Language::English => "%m/%d",
+        Language::Spanish => "%d/%m",
+    };
+
+    date.format(pattern).to_string()
+}
+
+/// Uses the decimal separator of the given language in the decimal numbers of a text (e.g. in the
+/// results of a verdict from Tablón, which always uses a point).
+pub fn localize_decimals(lang: Language, text: &str) -> String {
+    match lang {
+        Language::English => text.to_string(),
+        Language::Spanish => Regex::new(r"(\d)\.(\d)")
+            .expect("Failed to compile regex for decimal numbers.")
+            .replace_all(text, "$1,$2")
+            .into_owned(),
+    }
+}
+
+/// Macro for formatting a text in the given language.
+///
+/// Takes the language, the format strings for each language (English first, then Spanish), and the
+/// format arguments, if any.
+macro_rules! tr {
+    ($lang:expr, $en:expr, $es:expr $(, $args:expr)* $(,)?) => {
+        match $lang {
+            crate::i18n::Language::English => format!($en $(, $args)*),
+            crate::i18n::Language::Spanish => format!($es $(, $args)*),
+        }
+    };
+}
+pub(crate) use tr;
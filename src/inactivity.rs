@@ -0,0 +1,116 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Reminders for the teams that have not sent any request to Tablón some days after getting their
+//! password, in the guilds that opt in to them (see `BotConfig::inactivity_days`). Each team is
+//! reminded once per password.
+
+use crate::{
+    activityfeed::{Activity, Feed},
+    cache::Caches,
+    i18n::{self, tr},
+    student,
+    team::{self, Team},
+    utils,
+};
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use serenity::all::GuildId;
+
+/// Whether any member of the team has sent a request to Tablón since the given UNIX timestamp (in
+/// seconds), according to their request history. Requests of unknown time count, to be safe.
+fn has_sent_requests(caches: &Caches, gid: &GuildId, team: &Team, since: i64) -> bool {
+    team.members().iter().any(|member| {
+        student::get_student(caches, member).is_some_and(|student| {
+            student
+                .full_request_history(gid)
+                .iter()
+                .any(|request| request.timestamp().is_none_or(|ts| ts >= since))
+        })
+    })
+}
+
+/// Reminds the teams of the guilds that opt in to it that have not sent any request yet, by DM to
+/// their members, and tells the admins of them if the guild wants it.
+pub async fn nudge_inactive_teams(ctx: &serenity::Context, caches: &Caches, feed: &Feed) {
+    let now = Utc::now().timestamp();
+    for gid in utils::load_guildmap().into_values() {
+        let config = utils::load_config(caches, &gid);
+        if config.inactivity_days == 0 {
+            continue;
+        }
+        let gname = gid
+            .name(&ctx.cache)
+            .unwrap_or_else(|| format!("guild {}", gid));
+
+        let mut nudged = Vec::new();
+        for mut team in team::get_all_teams(&gid) {
+            let Some(since) = team.password_since() else {
+                continue;
+            };
+            if team.nudged()
+                || now - since < config.inactivity_days as i64 * 24 * 3600
+                || has_sent_requests(caches, &gid, &team, since)
+            {
+                continue;
+            }
+
+            for member in team.members().iter() {
+                let Some(student) = student::get_student(caches, member) else {
+                    continue;
+                };
+                let msg = tr!(
+                    i18n::user_language(&student, &config, &gid),
+                    "👋 Your team {} in **{}** got its Tablón password <t:{}:R>, but has not sent \
+                    any request yet. Send your first one with `/request`, and ask your teachers if \
+                    you need a hand!",
+                    "👋 Tu equipo {} en **{}** recibió su contraseña de Tablón <t:{}:R>, pero \
+                    todavía no ha enviado ninguna petición. Envía la primera con `/request`, ¡y \
+                    pregunta a tus profesores si necesitas ayuda!",
+                    team.id(),
+                    gname,
+                    since
+                );
+                if let Err(e) = member
+                    .direct_message(&ctx.http, serenity::CreateMessage::new().content(msg))
+                    .await
+                {
+                    tracing::warn!(
+                        "[inactivity] Could not remind student {} of team {} in guild {}: {}",
+                        member,
+                        team.id(),
+                        gid,
+                        e
+                    );
+                }
+            }
+            team.set_nudged();
+            nudged.push(team.id().clone());
+        }
+
+        if config.inactivity_notify_admins && !nudged.is_empty() {
+            nudged.sort();
+            feed.post(
+                gid,
+                Activity::InactiveTeams {
+                    teams: nudged,
+                    days: config.inactivity_days,
+                },
+            );
+        }
+    }
+}
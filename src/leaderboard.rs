@@ -0,0 +1,156 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Standings of the teams on each exercise (i.e. each of Tablón's queues), from the requests sent
+//! through the bot, and the champion roles of the best team on each of them (see
+//! `BotConfig::lb_champion_roles`).
+
+use crate::{
+    cache::Caches,
+    i18n::{self, tr, Language},
+    student, tablon, team, utils,
+};
+use poise::serenity_prelude as serenity;
+use serenity::{GuildId, Http, UserId};
+use std::collections::{BTreeMap, HashSet};
+
+/// Position of a team on an exercise's leaderboard.
+pub struct Standing {
+    /// The team's ID.
+    pub team_id: String,
+    /// The team's name, as shown in public posts (see `Team::public_name`).
+    pub public_name: String,
+    /// The best (i.e. lowest) result of the team's successful requests on the exercise.
+    pub result: f64,
+    /// The request achieving it.
+    pub request_id: u16,
+}
+
+/// Leaderboard of each exercise (by queue): the teams with successful requests on it, from best to
+/// worst result. Ties go to the first team, by ID.
+pub fn standings(caches: &Caches, guild_id: &GuildId) -> BTreeMap<String, Vec<Standing>> {
+    let mut standings: BTreeMap<String, Vec<Standing>> = BTreeMap::new();
+    for team in team::get_all_teams(guild_id) {
+        // Best result of the team on each exercise:
+        let mut best: BTreeMap<String, (f64, u16)> = BTreeMap::new();
+        let requests = team
+            .members()
+            .iter()
+            .filter_map(|member| student::get_student(caches, member))
+            .filter_map(|member| member.request_history().get(guild_id).cloned())
+            .flatten();
+        for req in requests {
+            let (Some(queue), Some(verdict)) = (req.queue(), req.verdict()) else {
+                continue;
+            };
+            if tablon::is_pending(verdict) || tablon::is_failure(verdict) {
+                continue;
+            }
+            let Some(result) = tablon::verdict_result(verdict) else {
+                continue;
+            };
+            match best.get(queue) {
+                Some((best_result, _)) if *best_result <= result => {}
+                _ => {
+                    best.insert(queue.clone(), (result, req.id()));
+                }
+            }
+        }
+
+        for (queue, (result, request_id)) in best {
+            standings.entry(queue).or_default().push(Standing {
+                team_id: team.id().clone(),
+                public_name: team.public_name().clone(),
+                result,
+                request_id,
+            });
+        }
+    }
+
+    // The teams are visited by ID, so the stable sort keeps ties in that order:
+    for leaderboard in standings.values_mut() {
+        leaderboard.sort_by(|a, b| a.result.total_cmp(&b.result));
+    }
+
+    standings
+}
+
+/// Formats an exercise's leaderboard for a public post, in the given language.
+pub fn render(lang: Language, exercise: &str, leaderboard: &[Standing]) -> String {
+    let mut out = tr!(
+        lang,
+        "## Leaderboard of `{}`\n",
+        "## Clasificación de `{}`\n",
+        exercise
+    );
+    for (position, standing) in leaderboard.iter().enumerate() {
+        out.push_str(&i18n::localize_decimals(
+            lang,
+            &tr!(
+                lang,
+                "{}. **{}**: {} (request #{})\n",
+                "{}. **{}**: {} (petición #{})\n",
+                position + 1,
+                standing.public_name,
+                standing.result,
+                standing.request_id
+            ),
+        ));
+    }
+
+    out
+}
+
+/// Gives each exercise's champion role to the members of its best team (see `standings`), and
+/// removes it from everyone else.
+///
+/// Returns the number of role changes made.
+pub async fn refresh_champion_roles(
+    http: &Http,
+    caches: &Caches,
+    guild_id: &GuildId,
+) -> serenity::Result<usize> {
+    let config = utils::load_config(caches, guild_id);
+    if config.lb_champion_roles.is_empty() {
+        return Ok(0);
+    }
+
+    let standings = standings(caches, guild_id);
+    let members = utils::fetch_all_members(http, guild_id).await?;
+    let mut changes = 0;
+    for (exercise, role) in config.lb_champion_roles.iter() {
+        let champions: HashSet<UserId> = standings
+            .get(exercise)
+            .and_then(|leaderboard| leaderboard.first())
+            .and_then(|best| team::get_team(guild_id, &best.team_id))
+            .map(|team| team.members().clone())
+            .unwrap_or_default();
+        for member in members.iter() {
+            let has_role = member.roles.contains(role);
+            let is_champion = champions.contains(&member.user.id);
+            if has_role && !is_champion {
+                member.remove_role(http, role).await?;
+                changes += 1;
+            } else if !has_role && is_champion {
+                member.add_role(http, role).await?;
+                changes += 1;
+            }
+        }
+    }
+
+    Ok(changes)
+}
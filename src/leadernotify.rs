@@ -0,0 +1,356 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Notifications of the position changes among the leaders of each exercise's leaderboard (see
+//! `BotConfig::notify_leaders`), publicly in the news channel or privately to the teams' members.
+//!
+//! The guilds can batch them into a digest (see `BotConfig::leader_digest_minutes`), so busy
+//! contest periods produce one summary per channel (or per team) every so often, instead of a
+//! notification on every single change.
+
+use crate::{
+    cache::Caches,
+    i18n::{self, tr, Language},
+    leaderboard, student, team, utils,
+};
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use serenity::all::{GuildId, UserId};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+};
+
+/// Change of the position of a team among the leaders of an exercise.
+#[derive(Clone, Serialize, Deserialize)]
+struct PositionChange {
+    exercise: String,
+    team_id: String,
+    /// The team's name, as shown in public posts (see `Team::public_name`).
+    public_name: String,
+    /// Previous position (from 1), or `None` if the team was not among the leaders.
+    from: Option<usize>,
+    /// New position (from 1), or `None` if the team is no longer among the leaders.
+    to: Option<usize>,
+}
+
+impl PositionChange {
+    /// Line describing the change, in the given language.
+    fn describe(&self, lang: Language) -> String {
+        match (self.from, self.to) {
+            (None, Some(to)) => tr!(
+                lang,
+                "📈 **{}** enters the top of `{}` at #{}.",
+                "📈 **{}** entra en la cabeza de `{}` en el puesto #{}.",
+                self.public_name,
+                self.exercise,
+                to
+            ),
+            (Some(from), None) => tr!(
+                lang,
+                "📉 **{}** drops out of the top of `{}` (was #{}).",
+                "📉 **{}** sale de la cabeza de `{}` (estaba en el puesto #{}).",
+                self.public_name,
+                self.exercise,
+                from
+            ),
+            (Some(from), Some(to)) if to < from => tr!(
+                lang,
+                "📈 **{}** climbs from #{} to #{} on `{}`.",
+                "📈 **{}** sube del puesto #{} al #{} en `{}`.",
+                self.public_name,
+                from,
+                to,
+                self.exercise
+            ),
+            (from, to) => tr!(
+                lang,
+                "📉 **{}** falls from #{} to #{} on `{}`.",
+                "📉 **{}** baja del puesto #{} al #{} en `{}`.",
+                self.public_name,
+                from.unwrap_or_default(),
+                to.unwrap_or_default(),
+                self.exercise
+            ),
+        }
+    }
+}
+
+/// What is remembered of a guild's leaderboards between checks.
+#[derive(Default, Serialize, Deserialize)]
+struct LeaderState {
+    /// The leaders of each exercise, from best to worst, by team ID.
+    leaders: BTreeMap<String, Vec<String>>,
+    /// Changes waiting for the next digest (merged by exercise and team).
+    #[serde(default)]
+    pending: Vec<PositionChange>,
+    /// When the first pending change was found (as a UNIX timestamp, in seconds).
+    #[serde(default)]
+    digest_since: Option<i64>,
+}
+
+/// Path of the file with a guild's `LeaderState`.
+fn state_path(gid: &GuildId) -> String {
+    format!("guilds/{}/leaders.json", gid)
+}
+
+/// Leaders of each exercise of a guild, and their public names.
+fn current_leaders(
+    caches: &Caches,
+    gid: &GuildId,
+    leader_count: u8,
+) -> (BTreeMap<String, Vec<String>>, BTreeMap<String, String>) {
+    let mut leaders = BTreeMap::new();
+    let mut names = BTreeMap::new();
+    for (exercise, standings) in leaderboard::standings(caches, gid) {
+        let top = standings
+            .into_iter()
+            .take(leader_count as usize)
+            .map(|standing| {
+                names.insert(standing.team_id.clone(), standing.public_name);
+                standing.team_id
+            })
+            .collect();
+        leaders.insert(exercise, top);
+    }
+
+    (leaders, names)
+}
+
+/// Position changes from the previous leaders to the current ones.
+fn diff_leaders(
+    previous: &BTreeMap<String, Vec<String>>,
+    current: &BTreeMap<String, Vec<String>>,
+    names: &BTreeMap<String, String>,
+) -> Vec<PositionChange> {
+    let empty = Vec::new();
+    let exercises: BTreeSet<&String> = previous.keys().chain(current.keys()).collect();
+
+    let mut changes = Vec::new();
+    for exercise in exercises {
+        let before = previous.get(exercise).unwrap_or(&empty);
+        let after = current.get(exercise).unwrap_or(&empty);
+        let teams: BTreeSet<&String> = before.iter().chain(after.iter()).collect();
+        for team_id in teams {
+            let from = before.iter().position(|id| id == team_id).map(|i| i + 1);
+            let to = after.iter().position(|id| id == team_id).map(|i| i + 1);
+            if from != to {
+                changes.push(PositionChange {
+                    exercise: exercise.clone(),
+                    team_id: team_id.clone(),
+                    public_name: names.get(team_id).unwrap_or(team_id).clone(),
+                    from,
+                    to,
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+/// Adds new changes to the pending ones, so each team appears once per exercise, from its position
+/// before the first change to its latest one (dropping those back where they started).
+fn merge_changes(pending: &mut Vec<PositionChange>, changes: Vec<PositionChange>) {
+    for change in changes {
+        match pending
+            .iter_mut()
+            .find(|old| old.exercise == change.exercise && old.team_id == change.team_id)
+        {
+            Some(old) => {
+                old.to = change.to;
+                old.public_name = change.public_name;
+            }
+            None => pending.push(change),
+        }
+    }
+    pending.retain(|change| change.from != change.to);
+}
+
+/// Checks the leaderboards of the guilds that notify their leaders, notifying the position changes
+/// at once, or adding them to the guild's digest (which is sent once its window has passed).
+pub async fn check_leaders(ctx: &serenity::Context, caches: &Caches) {
+    let now = Utc::now().timestamp();
+    for gid in utils::load_guildmap().into_values() {
+        let config = utils::load_config(caches, &gid);
+        if !config.notify_leaders {
+            // Start afresh if they are enabled again, instead of notifying all the changes since:
+            let _ = fs::remove_file(state_path(&gid));
+            continue;
+        }
+
+        // The first time, just remember the current leaders, instead of flooding the guild with
+        // them:
+        let first_time = fs::metadata(state_path(&gid)).is_err();
+        let mut state: LeaderState = fs::read_to_string(state_path(&gid))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let (leaders, names) = current_leaders(caches, &gid, config.leader_count);
+        let changes = diff_leaders(&state.leaders, &leaders, &names);
+        if changes.is_empty() && !first_time && state.pending.is_empty() {
+            continue;
+        }
+        state.leaders = leaders;
+
+        if !first_time {
+            if config.leader_digest_minutes == 0 {
+                notify(ctx, caches, &gid, changes).await;
+            } else {
+                if state.pending.is_empty() && !changes.is_empty() {
+                    state.digest_since = Some(now);
+                }
+                merge_changes(&mut state.pending, changes);
+                let window = config.leader_digest_minutes as i64 * 60;
+                if state.pending.is_empty() {
+                    state.digest_since = None;
+                } else if state.digest_since.is_none_or(|since| now - since >= window) {
+                    notify(ctx, caches, &gid, std::mem::take(&mut state.pending)).await;
+                    state.digest_since = None;
+                }
+            }
+        }
+
+        let json = serde_json::to_string(&state).unwrap_or_else(|e| {
+            panic!(
+                "[leadernotify] Could not serialize the leaders of guild {}: {:?}",
+                gid, e
+            )
+        });
+        if let Err(e) = fs::write(state_path(&gid), json) {
+            tracing::warn!(
+                "[leadernotify] Could not save the leaders of guild {}: {}",
+                gid,
+                e
+            );
+        }
+    }
+}
+
+/// Notifies position changes in a guild: in a single message to its news channel (pinging the
+/// members of the teams involved), or privately, with a single DM per member of each team involved.
+async fn notify(
+    ctx: &serenity::Context,
+    caches: &Caches,
+    gid: &GuildId,
+    changes: Vec<PositionChange>,
+) {
+    if changes.is_empty() {
+        return;
+    }
+    let config = utils::load_config(caches, gid);
+
+    // Changes of each team, and its members:
+    let mut by_team: BTreeMap<String, (Vec<UserId>, Vec<&PositionChange>)> = BTreeMap::new();
+    for change in changes.iter() {
+        by_team
+            .entry(change.team_id.clone())
+            .or_insert_with(|| {
+                let mut members: Vec<UserId> = team::get_team(gid, &change.team_id)
+                    .map(|team| team.members().iter().copied().collect())
+                    .unwrap_or_default();
+                members.sort();
+                (members, Vec::new())
+            })
+            .1
+            .push(change);
+    }
+
+    if config.public_notify {
+        let channel = gid.channels(&ctx.http).await.ok().and_then(|channels| {
+            channels
+                .into_values()
+                .find(|channel| channel.name == config.bot_news_channel)
+        });
+        let Some(channel) = channel else {
+            tracing::warn!(
+                "[leadernotify] Guild {} has no channel named #{} to notify the leaders in.",
+                gid,
+                config.bot_news_channel
+            );
+            return;
+        };
+
+        let lang = i18n::guild_language(&config);
+        let mut msg = tr!(
+            lang,
+            "🏆 **Leaderboard changes:**\n",
+            "🏆 **Cambios en la clasificación:**\n"
+        );
+        for change in changes.iter() {
+            msg.push_str(&change.describe(lang));
+            msg.push('\n');
+        }
+        let pings = by_team
+            .values()
+            .flat_map(|(members, _)| members.iter())
+            .map(|member| format!("<@{}>", member))
+            .collect::<Vec<_>>();
+        msg.push_str(&pings.join(" "));
+
+        for chunk in utils::split_message(&msg, utils::MAX_MESSAGE_LEN) {
+            if let Err(e) = channel
+                .send_message(&ctx.http, serenity::CreateMessage::new().content(chunk))
+                .await
+            {
+                tracing::warn!(
+                    "[leadernotify] Could not notify the leaders in guild {}: {}",
+                    gid,
+                    e
+                );
+                return;
+            }
+        }
+        return;
+    }
+
+    let gname = gid
+        .name(&ctx.cache)
+        .unwrap_or_else(|| format!("guild {}", gid));
+    for (team_id, (members, team_changes)) in by_team {
+        for member in members {
+            let Some(student) = student::get_student(caches, &member) else {
+                continue;
+            };
+            let lang = i18n::user_language(&student, &config, gid);
+            let mut msg = tr!(
+                lang,
+                "🏆 Leaderboard changes of your team {} in **{}**:\n",
+                "🏆 Cambios en la clasificación de tu equipo {} en **{}**:\n",
+                team_id,
+                gname
+            );
+            for change in team_changes.iter() {
+                msg.push_str(&change.describe(lang));
+                msg.push('\n');
+            }
+            if let Err(e) = member
+                .direct_message(&ctx.http, serenity::CreateMessage::new().content(msg))
+                .await
+            {
+                tracing::warn!(
+                    "[leadernotify] Could not notify student {} of team {} in guild {}: {}",
+                    member,
+                    team_id,
+                    gid,
+                    e
+                );
+            }
+        }
+    }
+}
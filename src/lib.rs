@@ -21,24 +21,23 @@
  * be related with how Rust compilation works).
  */
 
+use darling::FromDeriveInput as _; // FromDeriveInput is to use ConfigStruct::from_derive_input().
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens as _}; // ToTokens is to use function.into_token_stream().
-use syn::{parse_macro_input, spanned::Spanned as _, ItemFn}; // Spanned is to use .span() on language items.
+use syn::{ext::IdentExt as _, parse::Parser as _};
+use syn::{parse_macro_input, spanned::Spanned as _, DeriveInput, ItemFn}; // Spanned is to use .span() on language items. // To parse the options of `require_team`.
 
-// Reference:
-// https://users.rust-lang.org/t/using-macros-to-modify-ast-to-modify-and-add-line-of-codes-in-function/56805/5
-#[proc_macro_attribute]
-pub fn log_cmd(_macro_attrs: TokenStream, function: TokenStream) -> TokenStream {
-    // Parse the function's tokens using syn:
-    let mut function = parse_macro_input!(function as ItemFn);
+/// Extracts the identifier of the first argument of a command function (i.e., its context), or the
+/// compile error to return instead. `macro_name` is used as prefix for the error messages.
+fn context_ident(function: &ItemFn, macro_name: &str) -> Result<syn::Ident, TokenStream> {
     // Extract the first argument of the function:
     let Some(first_arg) = function.sig.inputs.first() else {
-        return darling::Error::from(syn::Error::new(
+        return Err(darling::Error::from(syn::Error::new(
             function.sig.span(),
-            "[log_cmd] function must have at least one argument",
+            format!("[{}] function must have at least one argument", macro_name),
         ))
         .write_errors()
-        .into();
+        .into());
     };
     // Cast the first argument to a typed argument
     // (i.e. `ctx: Context<'_>`):
@@ -46,37 +45,413 @@ pub fn log_cmd(_macro_attrs: TokenStream, function: TokenStream) -> TokenStream
         arg
     } else {
         // syn::FnArg::Receiver(_)
-        return darling::Error::from(syn::Error::new(
+        return Err(darling::Error::from(syn::Error::new(
             first_arg.span(),
-            "[log_cmd] `self` argument is not allowed",
+            format!("[{}] `self` argument is not allowed", macro_name),
         ))
         .write_errors()
-        .into();
+        .into());
     };
     // Extract the identifier of the first argument:
     let syn::Pat::Ident(ident) = &*ctx_arg.pat else {
-        return darling::Error::from(syn::Error::new(
+        return Err(darling::Error::from(syn::Error::new(
             ctx_arg.pat.span(),
-            "[log_cmd] expected an identifier",
+            format!("[{}] expected an identifier", macro_name),
         ))
         .write_errors()
-        .into();
+        .into());
     };
-    let ctx_ident = ident.ident.clone();
 
-    // Insert a new statement at the beginning of the function,
-    // logging the usage of the command to stderr using elog_cmd! and the provided context:
+    Ok(ident.ident.clone())
+}
+
+// Reference:
+// https://users.rust-lang.org/t/using-macros-to-modify-ast-to-modify-and-add-line-of-codes-in-function/56805/5
+#[proc_macro_attribute]
+pub fn log_cmd(_macro_attrs: TokenStream, function: TokenStream) -> TokenStream {
+    // Parse the function's tokens using syn:
+    let mut function = parse_macro_input!(function as ItemFn);
+    let ctx_ident = match context_ident(&function, "log_cmd") {
+        Ok(ident) => ident,
+        Err(error) => return error,
+    };
+
+    // Wrap the body of the function, logging the usage of the command before running it, and its
+    // outcome and duration after it finishes, using the provided context:
+    let output = match &function.sig.output {
+        syn::ReturnType::Type(_, ty) => ty.to_token_stream(),
+        syn::ReturnType::Default => quote! { () },
+    };
+    let body = &function.block;
+    function.block = syn::parse(
+        quote! {
+        {
+            crate::utils::elog_cmd!(#ctx_ident);
+            let __log_cmd_start = std::time::Instant::now();
+            let __log_cmd_result: #output = async #body.await;
+            crate::utils::elog_cmd_outcome!(#ctx_ident, __log_cmd_start.elapsed(), &__log_cmd_result);
+            __log_cmd_result
+        }
+        }
+        .into(),
+    )
+    .unwrap();
+
+    // Return the modified function as a TokenStream:
+    function.into_token_stream().into()
+}
+
+/// Restricts a command to the channel configured in the given `BotConfig` field (e.g.
+/// `#[hermes::require_channel(bot_channel)]`), replying with an error when used anywhere else.
+///
+/// It must be placed above `#[hermes::log_cmd]`, so the rejected invocations are logged too.
+#[proc_macro_attribute]
+pub fn require_channel(macro_attrs: TokenStream, function: TokenStream) -> TokenStream {
+    let field = parse_macro_input!(macro_attrs as syn::Ident);
+    let mut function = parse_macro_input!(function as ItemFn);
+    let ctx_ident = match context_ident(&function, "require_channel") {
+        Ok(ident) => ident,
+        Err(error) => return error,
+    };
+
+    // Insert the check at the beginning of the function:
     function.block.stmts.insert(
         0,
         syn::parse(
             quote! {
-            crate::utils::elog_cmd!(#ctx_ident);
+            if !crate::utils::check_on_proper_channel(
+                #ctx_ident.into(),
+                &crate::utils::load_config(&#ctx_ident.data().caches, &crate::utils::get_guild_id!(#ctx_ident)).#field,
+            )
+            .await
+            {
+                return Ok(());
+            }
             }
             .into(),
         )
         .unwrap(),
     );
 
-    // Return the modified function as a TokenStream:
     function.into_token_stream().into()
 }
+
+/// Injects the prologue of the commands that require the triggering student to be in a team: it
+/// binds `gid` (the guild ID), `student` and `team`, replying with an error and returning early if
+/// the student is not in a team.
+///
+/// Options, separated by commas:
+/// - `password`: the team must also have a password for Tablón, which is bound to `password`.
+/// - `mut`: `student` and `team` are bound as mutable. `mut student` and `mut team` only bind one
+///   of them as mutable.
+#[proc_macro_attribute]
+pub fn require_team(macro_attrs: TokenStream, function: TokenStream) -> TokenStream {
+    let mut function = parse_macro_input!(function as ItemFn);
+    let ctx_ident = match context_ident(&function, "require_team") {
+        Ok(ident) => ident,
+        Err(error) => return error,
+    };
+    // Each option is a sequence of words (`mut` being a keyword, they are parsed as any
+    // identifier):
+    let parse_options = |input: syn::parse::ParseStream| {
+        syn::punctuated::Punctuated::<Vec<syn::Ident>, syn::Token![,]>::parse_terminated_with(
+            input,
+            |input| {
+                let mut words = vec![syn::Ident::parse_any(input)?];
+                while input.peek(syn::Ident::peek_any) {
+                    words.push(syn::Ident::parse_any(input)?);
+                }
+                Ok(words)
+            },
+        )
+    };
+    let options = match parse_options.parse(macro_attrs) {
+        Ok(options) => options,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let (mut require_password, mut mut_student, mut mut_team) = (false, false, false);
+    for option in options {
+        let words: Vec<String> = option.iter().map(|word| word.to_string()).collect();
+        match words.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["password"] => require_password = true,
+            ["mut"] => (mut_student, mut_team) = (true, true),
+            ["mut", "student"] => mut_student = true,
+            ["mut", "team"] => mut_team = true,
+            _ => {
+                return darling::Error::from(syn::Error::new(
+                    option[0].span(),
+                    "[require_team] the only accepted options are `password`, `mut`, \
+                    `mut student` and `mut team`",
+                ))
+                .write_errors()
+                .into()
+            }
+        }
+    }
+    let student = if mut_student {
+        quote! { mut student }
+    } else {
+        quote! { student }
+    };
+    let team = if mut_team {
+        quote! { mut team }
+    } else {
+        quote! { team }
+    };
+
+    let mut prologue: Vec<syn::Stmt> = vec![
+        syn::parse(
+            quote! {
+            let gid = crate::utils::get_guild_id!(#ctx_ident);
+            }
+            .into(),
+        )
+        .unwrap(),
+        syn::parse(
+            quote! {
+            let Some((#student, #team)) =
+                crate::utils::require_team(#ctx_ident.into(), &gid).await
+            else {
+                return Ok(());
+            };
+            }
+            .into(),
+        )
+        .unwrap(),
+    ];
+    if require_password {
+        prologue.push(
+            syn::parse(
+                quote! {
+                let Some(password) =
+                    crate::utils::require_password(#ctx_ident.into(), &student, &gid).await
+                else {
+                    return Ok(());
+                };
+                }
+                .into(),
+            )
+            .unwrap(),
+        );
+    }
+
+    // Insert the prologue at the beginning of the function:
+    function.block.stmts.splice(0..0, prologue);
+
+    function.into_token_stream().into()
+}
+
+/// Options for a field of a struct deriving `ConfigCommands` (i.e. `#[config(...)]` attributes).
+#[derive(darling::FromField)]
+#[darling(attributes(config))]
+struct ConfigField {
+    ident: Option<syn::Ident>,
+    ty: syn::Type,
+    /// Description for the generated subcommand (at most 100 characters, as Discord requires).
+    #[darling(default)]
+    description: Option<String>,
+    /// Spanish translation of `description`.
+    #[darling(default)]
+    description_es: Option<String>,
+    /// Do not generate a subcommand for this field.
+    #[darling(default)]
+    skip: bool,
+    /// The field stores the name of a channel, so the subcommand takes a channel as argument.
+    #[darling(default)]
+    channel: bool,
+    /// Function to check the new value, as `fn(&T) -> Result<(), String>`. The error message is
+    /// sent to the user, and the configuration is not changed.
+    #[darling(default)]
+    validate: Option<syn::Path>,
+    /// Function to call after the configuration was updated, as `fn(&GuildId, &BotConfig)`.
+    #[darling(default)]
+    on_update: Option<syn::Path>,
+}
+
+/// Struct deriving `ConfigCommands`.
+#[derive(darling::FromDeriveInput)]
+#[darling(supports(struct_named))]
+struct ConfigStruct {
+    ident: syn::Ident,
+    data: darling::ast::Data<(), ConfigField>,
+}
+
+/// Derive macro generating a getter/setter `/botconfig` subcommand for each field of a
+/// configuration struct.
+///
+/// The generated subcommands are named after the fields, and take an optional argument: if it is
+/// given, the field is set to it; otherwise, the current value of the field is shown. They are
+/// returned by the generated `commands()` associated function, to be added as subcommands of the
+/// `/botconfig` command.
+///
+/// Every field must have a `#[config(description = "...", description_es = "...")]` attribute (in
+/// English and Spanish), unless it is skipped with `#[config(skip)]`. See `ConfigField` for the
+/// rest of the options.
+#[proc_macro_derive(ConfigCommands, attributes(config))]
+pub fn derive_config_commands(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let config_struct = match ConfigStruct::from_derive_input(&input) {
+        Ok(config_struct) => config_struct,
+        Err(e) => return e.write_errors().into(),
+    };
+    let struct_ident = &config_struct.ident;
+    let fields = config_struct
+        .data
+        .take_struct()
+        .expect("[ConfigCommands] Only structs with named fields are supported.")
+        .fields;
+
+    let mut errors = darling::Error::accumulator();
+    let mut commands = Vec::with_capacity(fields.len());
+    let mut command_idents = Vec::with_capacity(fields.len());
+    for field in fields.iter().filter(|f| !f.skip) {
+        let ident = field
+            .ident
+            .clone()
+            .expect("[ConfigCommands] Only structs with named fields are supported.");
+        let (Some(description), Some(description_es)) = (&field.description, &field.description_es)
+        else {
+            errors.push(darling::Error::from(syn::Error::new(
+                ident.span(),
+                "[ConfigCommands] fields must have a \
+                `#[config(description = \"...\", description_es = \"...\")]` attribute, or be \
+                skipped with `#[config(skip)]`",
+            )));
+            continue;
+        };
+        let field_name = ident.to_string();
+
+        // Channel fields take the channel itself, but only store its name:
+        let (arg_ty, to_value) = if field.channel {
+            (
+                quote! { poise::serenity_prelude::GuildChannel },
+                quote! { let value = value.name; },
+            )
+        } else {
+            let ty = &field.ty;
+            (quote! { #ty }, quote! {})
+        };
+        let validate = field.validate.as_ref().map(|validate| {
+            quote! {
+                if let Err(msg) = #validate(&value) {
+                    crate::utils::reply_error(ctx, format!("**Error:** {}", msg)).await.expect(
+                        format!(
+                            "[botconfig] Failed to send error message for invalid `{}` for guild {}.",
+                            #field_name, gid
+                        )
+                        .as_str(),
+                    );
+
+                    return Ok(());
+                }
+            }
+        });
+        let on_update = field.on_update.as_ref().map(|on_update| {
+            quote! { #on_update(&gid, &config); }
+        });
+
+        commands.push(quote! {
+            #[poise::command(
+                slash_command,
+                ephemeral,
+                rename = #field_name,
+                description_localized("en-US", #description),
+                description_localized("es-ES", #description_es)
+            )]
+            #[hermes::require_channel(bot_channel)]
+            #[hermes::log_cmd]
+            async fn #ident(
+                ctx: crate::Context<'_>,
+                #[description = "The new value. Leave empty to show the current one."]
+                value: Option<#arg_ty>,
+            ) -> Result<(), crate::Error> {
+                let gid = crate::utils::get_guild_id!(ctx);
+                let mut config = crate::utils::load_config(&ctx.data().caches, &gid);
+
+                // Show the current value, if no new value was given:
+                let Some(value) = value else {
+                    crate::utils::reply(ctx, crate::i18n::tr!(
+                        crate::i18n::guild_language(&config),
+                        "`{}` is currently set to `{}`.",
+                        "`{}` tiene actualmente el valor `{}`.",
+                        #field_name,
+                        serde_json::to_string(&config.#ident).expect(
+                            format!(
+                                "[botconfig] Failed to serialize `{}` for guild {}.",
+                                #field_name, gid
+                            )
+                            .as_str()
+                        )
+                    ))
+                    .await
+                    .expect(
+                        format!(
+                            "[botconfig] Failed to send the current `{}` for guild {}.",
+                            #field_name, gid
+                        )
+                        .as_str(),
+                    );
+
+                    return Ok(());
+                };
+                #to_value
+                #validate
+
+                // Update the configuration:
+                config.#ident = value;
+                crate::utils::update_config_persistence(&ctx.data().caches, &config, &gid);
+                crate::events::record_notable(
+                    &ctx.data().feed,
+                    &gid,
+                    crate::events::Event::ConfigChanged {
+                        user: Some(ctx.author().id),
+                        field: Some(#field_name.to_string()),
+                        value: serde_json::to_value(&config.#ident).unwrap_or_default(),
+                    },
+                );
+                #on_update
+
+                // Reply to the user, as confirmation:
+                crate::utils::reply(ctx, crate::i18n::tr!(
+                    crate::i18n::guild_language(&config),
+                    "`{}` has been changed to `{}`.",
+                    "`{}` se ha cambiado a `{}`.",
+                    #field_name,
+                    serde_json::to_string(&config.#ident).expect(
+                        format!(
+                            "[botconfig] Failed to serialize `{}` for guild {}.",
+                            #field_name, gid
+                        )
+                        .as_str()
+                    )
+                ))
+                .await
+                .expect(
+                    format!(
+                        "[botconfig] Failed to send confirmation of `{}` change for guild {}.",
+                        #field_name, gid
+                    )
+                    .as_str(),
+                );
+
+                Ok(())
+            }
+        });
+        command_idents.push(ident);
+    }
+    if let Err(e) = errors.finish() {
+        return e.write_errors().into();
+    }
+
+    quote! {
+        impl #struct_ident {
+            /// Returns the getter/setter `/botconfig` subcommands generated for each field.
+            pub fn commands() -> Vec<poise::Command<crate::Data, crate::Error>> {
+                #(#commands)*
+
+                vec![#(#command_idents()),*]
+            }
+        }
+    }
+    .into()
+}
@@ -0,0 +1,152 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Synthetic workload over the persistence and team management logic, to measure performance
+//! regressions in the storage layer. It runs as a test, with `HERMES_LOAD_TEST_STUDENTS` students
+//! (`DEFAULT_STUDENTS` by default); its timings are shown with
+//! `cargo test --release load_test -- --nocapture`.
+//!
+//! It runs in a fresh temporary data directory, so it never touches the real data.
+use crate::{
+    batch::Batch, cache::Caches, student, student::Student, team, team::GuildTeamInfo,
+    testutil::TempDataDir, utils,
+};
+use serenity::all::{GuildId, UserId};
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+/// Guild used by the load test.
+const GUILD_ID: u64 = 1;
+
+/// Members per team in the load test.
+const TEAM_SIZE: usize = 2;
+
+/// Threads joining teams concurrently.
+const JOIN_THREADS: usize = 8;
+
+/// Students in the load test, unless `HERMES_LOAD_TEST_STUDENTS` is set.
+const DEFAULT_STUDENTS: usize = 200;
+
+/// Runs a phase of the load test, printing how long it took.
+fn phase<T>(name: &str, ops: usize, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let res = f();
+    let elapsed = start.elapsed();
+    println!(
+        "{:<24} {:>8} ops {:>10.1} ms {:>10.1} µs/op",
+        name,
+        ops,
+        elapsed.as_secs_f64() * 1000.0,
+        elapsed.as_secs_f64() * 1e6 / ops.max(1) as f64
+    );
+
+    res
+}
+
+/// Runs the load test with the given number of students (and half as many teams), returning the
+/// total time taken.
+fn run(students: usize) -> Duration {
+    let _dir = TempDataDir::new("loadtest");
+    let caches = Caches::default();
+    let gid = GuildId::new(GUILD_ID);
+    let teams = students.div_ceil(TEAM_SIZE);
+    let start = Instant::now();
+
+    crate::init_guild_files(&caches, &gid);
+
+    phase("create students", students, || {
+        for i in 0..students {
+            let _student =
+                Student::new(&caches, UserId::new(i as u64 + 1), format!("student{}", i));
+        }
+    });
+
+    let team_ids = phase("create teams", teams, || {
+        let mut info = GuildTeamInfo::new(gid, utils::load_config(&caches, &gid).team_prefix);
+        (0..teams)
+            .map(|_| {
+                let tid = info.next_team_id(|tid| team::get_team(&gid, tid).is_some());
+                info.save();
+                let _team = team::Team::new(&info, tid.clone());
+                tid
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // Each thread fills its own teams, so no two threads modify the same files:
+    phase("concurrent joins", students, || {
+        let chunk = team_ids.len().div_ceil(JOIN_THREADS).max(1);
+        let caches = &caches;
+        std::thread::scope(|scope| {
+            for (t, tids) in team_ids.chunks(chunk).enumerate() {
+                scope.spawn(move || {
+                    for (j, tid) in tids.iter().enumerate() {
+                        let mut team = team::get_team(&gid, tid)
+                            .expect("[loadtest] Could not find a created team.");
+                        for k in 0..TEAM_SIZE {
+                            let i = (t * chunk + j) * TEAM_SIZE + k;
+                            if i >= students {
+                                break;
+                            }
+                            let mut student =
+                                student::get_student(caches, &UserId::new(i as u64 + 1))
+                                    .expect("[loadtest] Could not find a created student.");
+                            team.add_member(&mut student);
+                        }
+                    }
+                });
+            }
+        });
+    });
+
+    phase("password import", teams, || {
+        let mut batch = Batch::new(&caches);
+        for tid in team_ids.iter() {
+            if let Some(mut team) = team::get_team(&gid, tid) {
+                team.set_password(format!("pass-{}", tid), &mut batch);
+            }
+        }
+        batch.flush();
+    });
+
+    phase("load (cold cache)", students + teams, || {
+        caches.clear();
+        let loaded = student::get_all_students(&caches).len() + team::get_all_teams(&gid).len();
+        assert_eq!(
+            loaded,
+            students + teams,
+            "[loadtest] Lost students or teams."
+        );
+    });
+
+    let total = start.elapsed();
+    println!("{:<24} {:>32.1} ms", "total", total.as_secs_f64() * 1000.0);
+
+    total
+}
+
+#[test]
+fn load_test() {
+    let students = env::var("HERMES_LOAD_TEST_STUDENTS")
+        .ok()
+        .and_then(|students| students.parse().ok())
+        .unwrap_or(DEFAULT_STUDENTS);
+
+    run(students);
+}
@@ -15,17 +15,68 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+mod abuseguard;
+mod activityfeed;
+mod activitylog;
+mod alerts;
+mod api;
+mod backup;
+mod banner;
+mod batch;
+mod cache;
+mod chaos;
+mod cli;
 mod commands;
+mod deadline;
+mod diff;
+mod events;
+#[cfg(test)]
+mod golden;
+mod http;
+mod i18n;
+mod inactivity;
+mod leaderboard;
+mod leadernotify;
+#[cfg(test)]
+mod loadtest;
+#[cfg(any(test, feature = "mock-tablon"))]
+mod mocktablon;
+mod news;
+mod presence;
+mod redact;
+mod requestrecord;
+mod scheduler;
+mod service;
 mod student;
+mod systemd;
+mod tablon;
+mod tablonclient;
+mod tasks;
 mod team;
+mod teamnotify;
 mod teamrequest;
+#[cfg(test)]
+mod testutil;
 mod utils;
-
-use crate::utils::BotConfig;
+mod webhook;
+mod welcome;
+
+use crate::{
+    i18n::{tr, Language},
+    utils::BotConfig,
+};
+use clap::Parser as _;
 use getset::Getters;
 use poise::serenity_prelude as serenity;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, env, fs};
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 
 /* General data structures: */
 
@@ -44,13 +95,221 @@ pub struct Credentials {
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
 // User data:
-pub struct Data {}
+pub struct Data {
+    /// Whether the bot is under maintenance, rejecting every command but the owner's
+    /// (see `/owner maintenance`).
+    maintenance: AtomicBool,
+    /// Supervisor of the background tasks (see `/health`).
+    tasks: Arc<tasks::TaskManager>,
+    /// Limit of the commands each user can run per minute (see `abuseguard`).
+    guard: abuseguard::CommandGuard,
+    /// Caches of the guilds' configurations and the students (see `cache`).
+    caches: cache::Caches,
+    /// Handle to post to the guilds' activity feeds (see `activityfeed`).
+    feed: activityfeed::Feed,
+    /// State of the requests being sent to Tablón (see `commands::request::Submissions`).
+    submissions: commands::request::Submissions,
+    /// Client of the guilds' Tablón, shared by all the fetches (see `tablon`).
+    tablon: tablon::Tablon,
+}
+
+/// Maximum number of guilds bootstrapped at the same time.
+const BOOTSTRAP_CONCURRENCY: usize = 8;
+
+/// Serializes the updates of the global guild and user maps, as several shards may bootstrap
+/// guilds at the same time.
+static MAPS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Creates the persistent files of a guild that do not exist yet.
+fn init_guild_files(caches: &cache::Caches, gid: &serenity::GuildId) {
+    // Create the guild's directory if it doesn't exist:
+    if fs::metadata(format!("guilds/{}", gid)).is_err() {
+        fs::create_dir(format!("guilds/{}", gid))
+            .unwrap_or_else(|e| panic!("Could not create guilds/{} directory: {:?}", gid, e));
+    }
+
+    // Create the guild's request log, if it doesn't exist:
+    if fs::metadata(format!("guilds/{}/requests.log", gid)).is_err() {
+        utils::write_file(&format!("guilds/{}/requests.log", gid), "")
+            .unwrap_or_else(|e| panic!("Could not create guilds/{}/requests.log: {:?}", gid, e));
+    }
+
+    // Check if the configuration file exists, and create it if it doesn't:
+    if fs::metadata(format!("guilds/{}/config.json", gid)).is_err() {
+        // Use custom default configuration, if found:
+        if fs::metadata("config.json").is_ok() {
+            let (config, _) = utils::parse_config(
+                fs::read_to_string("config.json")
+                    .expect("Could not read the default configuration file.")
+                    .as_str(),
+            )
+            .expect("Could not parse the default configuration file as a BotConfig object.");
+            utils::update_config_persistence(caches, &config, gid);
+        } else {
+            // Use the following default configuration as last resort:
+            let config = BotConfig::default();
+            utils::update_config_persistence(caches, &config, gid);
+        }
+    }
+
+    // Create the guild's team name map, if it doesn't exist:
+    if fs::metadata(format!("guilds/{}/nameMap.json", gid)).is_err() {
+        let json = serde_json::to_string(&HashMap::<String, String>::new()).unwrap_or_else(|e| {
+            panic!(
+                "Could not serialize an initial empty name map into JSON for guild {}: {:?}",
+                gid, e
+            )
+        });
+        utils::write_file(&format!("guilds/{}/nameMap.json", gid), json)
+            .unwrap_or_else(|e| panic!("Could not write guilds/{}/nameMap.json: {:?}", gid, e));
+    }
+
+    // Create the guild's team directory, if it doesn't exist:
+    if fs::metadata(format!("guilds/{}/teams", gid)).is_err() {
+        fs::create_dir(format!("guilds/{}/teams", gid))
+            .unwrap_or_else(|e| panic!("Could not create guilds/{}/teams directory: {:?}", gid, e));
+    }
+}
+
+/// Bootstraps a guild: creates its persistent files and its members' student objects, if missing,
+/// and registers its guild-specific commands.
+///
+/// The members are only scanned the first time the guild is seen, or when `resync` is set; after
+/// that, the students are created as members join (see the `GuildMemberAddition` event).
+///
+/// Returns the sanitized name of the guild, and the name and ID of its scanned (non-bot) members,
+/// to be added to the global maps.
+async fn bootstrap_guild(
+    ctx: &serenity::Context,
+    caches: &cache::Caches,
+    gid: serenity::GuildId,
+    resync: bool,
+) -> (String, Vec<(String, serenity::UserId)>) {
+    let gname = gid
+        .name(&ctx.cache)
+        .unwrap_or_else(|| panic!("Unable to retrieve the name of the guild with id {}.", gid));
+    println!("Hermes entered the guild {} ({}).", gname, gid);
+
+    // The file operations are blocking, so run them out of the async runtime:
+    let init_caches = caches.clone();
+    tokio::task::spawn_blocking(move || init_guild_files(&init_caches, &gid))
+        .await
+        .unwrap_or_else(|e| panic!("Could not initialize the files of guild {}: {:?}", gid, e));
+
+    // Register the WIP commands enabled in the guild:
+    commands::registry::register_in_guild(ctx, &gid, &utils::load_config(caches, &gid))
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "Could not register the guild commands for guild {}: {:?}",
+                gid, e
+            )
+        });
+
+    // Skip the member scan if the guild was already synced:
+    if !resync && utils::load_last_sync(&gid).is_some() {
+        return (utils::sanitize_name(&gname), Vec::new());
+    }
+
+    // Collect the guild's members (ignoring bots):
+    let members = utils::fetch_all_members(&ctx.http, &gid)
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "Could not retrieve the members of the guild {}: {:?}",
+                gid, e
+            )
+        })
+        .into_iter()
+        .filter(|member| !member.user.bot)
+        .map(|member| (member.user.name, member.user.id))
+        .collect::<Vec<_>>();
+
+    // Create new students:
+    let new_students = members.clone();
+    let caches = caches.clone();
+    tokio::task::spawn_blocking(move || {
+        for (name, uid) in new_students {
+            if student::get_student(&caches, &uid).is_none() {
+                let _student = student::Student::new(&caches, uid, name);
+            }
+        }
+        utils::update_last_sync(&gid);
+    })
+    .await
+    .unwrap_or_else(|e| panic!("Could not create the students of guild {}: {:?}", gid, e));
+
+    (utils::sanitize_name(&gname), members)
+}
+
+/// Bootstraps the given guilds concurrently (see `bootstrap_guild`), and adds them and their
+/// members to the global maps.
+async fn bootstrap_guilds(
+    ctx: serenity::Context,
+    caches: cache::Caches,
+    gids: Vec<serenity::GuildId>,
+    resync: bool,
+) {
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(BOOTSTRAP_CONCURRENCY));
+    let mut bootstraps = tokio::task::JoinSet::new();
+    for gid in gids {
+        let ctx = ctx.clone();
+        let caches = caches.clone();
+        let semaphore = Arc::clone(&semaphore);
+        bootstraps.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("The guild bootstrap semaphore was closed.");
+            (gid, bootstrap_guild(&ctx, &caches, gid, resync).await)
+        });
+    }
+
+    let mut guilds = Vec::new();
+    while let Some(result) = bootstraps.join_next().await {
+        match result {
+            Ok(guild) => guilds.push(guild),
+            Err(e) => tracing::error!("Failed to bootstrap a guild: {}", e),
+        }
+    }
+
+    // Update the global maps, once:
+    tokio::task::spawn_blocking(move || {
+        let _lock = MAPS_LOCK.lock().unwrap();
+        let mut guild_map = utils::load_guildmap();
+        let mut user_map = utils::load_usermap();
+        let (mut guild_map_changed, mut user_map_changed) = (false, false);
+
+        for (gid, (std_name, members)) in guilds {
+            // New (or renamed) server found? Add to database:
+            if utils::record_guild_name(&mut guild_map, gid, std_name) {
+                guild_map_changed = true;
+            }
+
+            // Add to the user map (or record their new names):
+            for (name, uid) in members {
+                if utils::record_user_name(&mut user_map, uid, name) {
+                    user_map_changed = true;
+                }
+            }
+        }
+
+        if guild_map_changed {
+            utils::update_guildmap_persistence(&guild_map);
+        }
+        if user_map_changed {
+            utils::update_usermap_persistence(&user_map);
+        }
+    })
+    .await
+    .expect("Could not update the guild and user maps.");
+}
 
 async fn ready(
     ctx: &serenity::Context,
     event: &serenity::FullEvent,
-    _framework: poise::FrameworkContext<'_, Data, Error>,
-    _data: &Data,
+    framework: poise::FrameworkContext<'_, Data, Error>,
+    data: &Data,
 ) -> Result<(), Error> {
     match event {
         // Ready (bot is started):
@@ -68,117 +327,26 @@ async fn ready(
             // Create directories for the persistent data, if necessary:
             utils::init_filesystem();
 
-            // Load "global" data structures:
-            let mut guild_map = utils::load_guildmap();
-            let mut user_map = utils::load_usermap();
-
             ctx.set_presence(None, serenity::OnlineStatus::Online);
 
-            // Check guilds and update related information:
-            for g in &data_about_bot.guilds {
-                let gid = g.id;
-                let gname = gid.name(&ctx.cache).expect(
-                    format!("Unable to retrieve the name of the guild with id {}.", gid).as_str(),
-                );
-                println!("Hermes entered the guild {} ({}).", gname, gid);
-
-                // Create the guild's directory if it doesn't exist:
-                if fs::metadata(format!("guilds/{}", gid)).is_err() {
-                    fs::create_dir(format!("guilds/{}", gid))
-                        .expect(format!("Could not create guilds/{} directory.", gid).as_str());
-                }
-
-                // Create the guild's request log, if it doesn't exist:
-                if fs::metadata(format!("guilds/{}/requests.log", gid)).is_err() {
-                    fs::write(format!("guilds/{}/requests.log", gid).as_str(), "")
-                        .expect(format!("Could not create guilds/{}/requests.log.", gid).as_str());
-                }
-
-                // Check if the configuration file exists, and create it if it doesn't:
-                if fs::metadata(format!("guilds/{}/config.json", gid)).is_err() {
-                    // Use custom default configuration, if found:
-                    if fs::metadata("config.json").is_ok() {
-                        let config: BotConfig = serde_json::from_str(
-                            fs::read_to_string("config.json")
-                                .expect("Could not read the default configuration file.")
-                                .as_str(),
-                        )
-                        .expect(
-                            "Could not parse the default configuration file as a BotConfig object.",
-                        );
-                        utils::update_config_persistence(&config, &gid);
-                    } else {
-                        // Use the following default configuration as last resort:
-                        let config = BotConfig {
-                            tablon_url: String::from("https://frontendv.infor.uva.es"),
-                            team_capacity: 2,
-                            team_prefix: String::from("g"),
-                            bot_channel: String::from("bot-commands"),
-                            lb_channel: String::from("leaderboards"),
-                            notify_leaders: true,
-                            leader_count: 5,
-                            public_notify: true,
-                            bot_news_channel: String::from("bot-news"),
-                            column_separator: String::from(" | "),
-                        };
-                        utils::update_config_persistence(&config, &gid);
-                    }
-                }
-
-                // Create the guild's team name map, if it doesn't exist:
-                if !fs::metadata(format!("guilds/{}/nameMap.json", gid)).is_ok() {
-                    let json = serde_json::to_string(&HashMap::<String, String>::new()).expect(
-                        format!(
-                            "Could not serialize an initial empty name map into JSON for guild {}.",
-                            gid
-                        )
-                        .as_str(),
-                    );
-                    fs::write(format!("guilds/{}/nameMap.json", gid).as_str(), json)
-                        .expect(format!("Could not write guilds/{}/nameMap.json.", gid).as_str());
-                }
-
-                // Create the guild's team directory, if it doesn't exist:
-                if !fs::metadata(format!("guilds/{}/teams", gid)).is_ok() {
-                    fs::create_dir(format!("guilds/{}/teams", gid)).expect(
-                        format!("Could not create guilds/{}/teams directory.", gid).as_str(),
-                    );
-                }
-
-                // New server found? Add to database:
-                let std_name = utils::sanitize_name(&gname);
-                if !guild_map.contains_key(&std_name) {
-                    guild_map.insert(std_name, gid);
-                    utils::update_guildmap_persistence(&guild_map);
-                }
-
-                // Create or update the student's objects on the database:
-                for member in gid.members(&ctx.http, None, None).await.expect(
-                    format!("Could not retrieve the members of the guild {}.", gid).as_str(),
-                ) {
-                    // Ignore bots:
-                    if member.user.bot {
-                        continue;
-                    }
-
-                    let uid = member.user.id;
-                    let name = member.user.name;
-
-                    // Create new students:
-                    if student::get_student(&uid).is_none() {
-                        let _student = student::Student::new(uid, name.clone());
-                    }
-
-                    // Add to the user map (if not present):
-                    if user_map.insert(name.clone(), uid).is_none() {
-                        utils::update_usermap_persistence(&user_map);
-                    }
-
-                    // TODO: add students that joined the server after the bot was added to the
-                    // system.
-                    // TODO: this should probably account for user name changes, too.
-                }
-            }
+            // Start the scheduled tasks (e.g. deadline reminders), the relay of Tablón's news, and
+            // the rotation of the presence:
+            scheduler::start(ctx, &data.caches, &data.feed, &data.tasks);
+            news::start(ctx, &data.caches, &data.tablon, &data.tasks);
+            presence::start(ctx, &data.caches, &data.tasks);
+
+            // Let the service manager know the bot is up:
+            systemd::notify_ready();
+
+            // Check guilds and update related information, in the background (so the shard's
+            // event loop is not blocked by the file-heavy bootstrap):
+            let gids = data_about_bot.guilds.iter().map(|g| g.id).collect();
+            tokio::spawn(bootstrap_guilds(
+                ctx.clone(),
+                data.caches.clone(),
+                gids,
+                false,
+            ));
         }
         // Guild create (the bot joins a new server):
         serenity::FullEvent::GuildCreate { guild, is_new } => {
@@ -186,109 +354,74 @@ async fn ready(
             if *is_new != Some(true) {
                 return Ok(());
             }
-            println!("Hermes entered the guild {} ({}).", guild.name, guild.id);
 
             // Basically, process the guild as they are in the Ready event...
-
-            // Load "global" data structures:
-            let mut guild_map = utils::load_guildmap();
-            let mut user_map = utils::load_usermap();
-
-            let gid = guild.id;
-            let gname = gid.name(&ctx.cache).expect(
-                format!("Unable to retrieve the name of the guild with id {}.", gid).as_str(),
-            );
-            println!("Hermes entered the guild {} ({}).", gname, gid);
-
-            // Create the guild's directory:
-            fs::create_dir(format!("guilds/{}", gid))
-                .expect(format!("Could not create guilds/{} directory.", gid).as_str());
-
-            // Create the guild's request log:
-            fs::write(format!("guilds/{}/requests.log", gid).as_str(), "")
-                .expect(format!("Could not create guilds/{}/requests.log.", gid).as_str());
-
-            // Check if the configuration file exists, and create it if it doesn't:
-            if fs::metadata(format!("guilds/{}/config.json", gid)).is_err() {
-                // Use custom default configuration, if found:
-                if fs::metadata("config.json").is_ok() {
-                    let config: BotConfig = serde_json::from_str(
-                        fs::read_to_string("config.json")
-                            .expect("Could not read the default configuration file.")
-                            .as_str(),
-                    )
-                    .expect(
-                        "Could not parse the default configuration file as a BotConfig object.",
-                    );
-                    utils::update_config_persistence(&config, &gid);
-                } else {
-                    // Use the following default configuration as last resort:
-                    let config = BotConfig {
-                        tablon_url: String::from("https://frontendv.infor.uva.es"),
-                        team_capacity: 2,
-                        team_prefix: String::from("g"),
-                        bot_channel: String::from("bot-commands"),
-                        lb_channel: String::from("leaderboards"),
-                        notify_leaders: true,
-                        leader_count: 5,
-                        public_notify: true,
-                        bot_news_channel: String::from("bot-news"),
-                        column_separator: String::from(" | "),
-                    };
-                    utils::update_config_persistence(&config, &gid);
+            tokio::spawn(bootstrap_guilds(
+                ctx.clone(),
+                data.caches.clone(),
+                vec![guild.id],
+                false,
+            ));
+        }
+        // Component interaction not handled by a collector (e.g. the persistent retry buttons):
+        serenity::FullEvent::InteractionCreate { interaction } => {
+            if let Some(press) = interaction.as_message_component() {
+                if press
+                    .data
+                    .custom_id
+                    .starts_with(commands::request::RETRY_PREFIX)
+                    && retry_check(ctx, framework, data, press).await
+                {
+                    tokio::spawn(commands::request::handle_retry(
+                        ctx.clone(),
+                        data.caches.clone(),
+                        data.submissions.clone(),
+                        press.clone(),
+                    ));
                 }
             }
-
-            // Create the guild's team name map, if it doesn't exist:
-            if !fs::metadata(format!("guilds/{}/nameMap.json", gid)).is_ok() {
-                let json = serde_json::to_string(&HashMap::<String, String>::new()).expect(
-                    format!(
-                        "Could not serialize an initial empty name map into JSON for guild {}.",
-                        gid
-                    )
-                    .as_str(),
-                );
-                fs::write(format!("guilds/{}/nameMap.json", gid).as_str(), json)
-                    .expect(format!("Could not write guilds/{}/nameMap.json.", gid).as_str());
+        }
+        // Guild member addition (a user joins a server):
+        serenity::FullEvent::GuildMemberAddition { new_member } => {
+            if new_member.user.bot {
+                return Ok(());
             }
 
-            // Create the guild's team directory, if it doesn't exist:
-            fs::create_dir(format!("guilds/{}/teams", gid))
-                .expect(format!("Could not create guilds/{}/teams directory.", gid).as_str());
-
-            // Add guild to database:
-            let std_name = utils::sanitize_name(&gname);
-            guild_map.insert(std_name, gid);
-            utils::update_guildmap_persistence(&guild_map);
-
-            // Create or update the student's objects on the database:
-            for member in gid
-                .members(&ctx.http, None, None)
-                .await
-                .expect(format!("Could not retrieve the members of the guild {}.", gid).as_str())
-            {
-                // Ignore bots:
-                if member.user.bot {
-                    continue;
+            let (name, uid) = (new_member.user.name.clone(), new_member.user.id);
+            let caches = data.caches.clone();
+            tokio::task::spawn_blocking(move || {
+                if student::get_student(&caches, &uid).is_none() {
+                    let _student = student::Student::new(&caches, uid, name.clone());
                 }
 
-                let uid = member.user.id;
-                let name = member.user.name;
-
-                // Create new students:
-                if student::get_student(&uid).is_none() {
-                    let _student = student::Student::new(uid, name.clone());
-                }
-
-                // Add to the user map (if not present):
-                if user_map.insert(name.clone(), uid).is_none() {
+                let _lock = MAPS_LOCK.lock().unwrap();
+                let mut user_map = utils::load_usermap();
+                if utils::record_user_name(&mut user_map, uid, name) {
                     utils::update_usermap_persistence(&user_map);
                 }
+            })
+            .await
+            .expect("Could not add the new member to the students.");
 
-                // TODO: add students that joined the server after the bot was added to the
-                // system.
-                // TODO: this should probably account for user name changes, too.
+            // Greet them, if the guild has a welcome message:
+            welcome::greet(ctx, &data.caches, &new_member.guild_id, &uid).await;
+        }
+        // Guild member update (e.g. a user changes their name):
+        serenity::FullEvent::GuildMemberUpdate { event, .. } => {
+            if event.user.bot {
+                return Ok(());
             }
+
+            let (name, uid) = (event.user.name.clone(), event.user.id);
+            tokio::task::spawn_blocking(move || {
+                let _lock = MAPS_LOCK.lock().unwrap();
+                let mut user_map = utils::load_usermap();
+                if utils::record_user_name(&mut user_map, uid, name) {
+                    utils::update_usermap_persistence(&user_map);
+                }
+            })
+            .await
+            .expect("Could not record the new name of a member.");
         }
 
         _ => {}
@@ -297,38 +430,318 @@ async fn ready(
     Ok(())
 }
 
+/// Notice rejecting the commands of a user while the bot is under maintenance, unless they are one
+/// of the bot's owners.
+fn maintenance_notice(
+    data: &Data,
+    owners: &HashSet<serenity::UserId>,
+    user: serenity::UserId,
+    guild_id: Option<serenity::GuildId>,
+) -> Option<String> {
+    if !data.maintenance.load(Ordering::Relaxed) || owners.contains(&user) {
+        return None;
+    }
+
+    let lang = match guild_id {
+        Some(gid) => i18n::guild_language(&utils::load_config(&data.caches, &gid)),
+        None => Language::default(),
+    };
+    Some(tr!(
+        lang,
+        "Hermes is under maintenance. Please, try again later.",
+        "Hermes está en mantenimiento. Por favor, inténtalo más tarde."
+    ))
+}
+
+/// Notice rejecting the commands of a user running too many of them (see `abuseguard`), unless
+/// they are one of the bot's owners.
+fn abuse_notice(
+    data: &Data,
+    owners: &HashSet<serenity::UserId>,
+    user: serenity::UserId,
+    guild_id: Option<serenity::GuildId>,
+) -> Option<String> {
+    if owners.contains(&user) {
+        return None;
+    }
+    let abuseguard::Verdict::Muted { remaining, new } = data.guard.check(user) else {
+        return None;
+    };
+
+    let lang = match guild_id {
+        Some(gid) => i18n::guild_language(&utils::load_config(&data.caches, &gid)),
+        None => Language::default(),
+    };
+    Some(if new {
+        tr!(
+            lang,
+            "You are sending commands too fast, so you cannot use Hermes for {} minute(s). \
+            Please, slow down.",
+            "Estás enviando comandos demasiado rápido, así que no puedes usar Hermes durante {} \
+            minuto(s). Por favor, ve más despacio.",
+            remaining.as_secs().div_ceil(60)
+        )
+    } else {
+        tr!(
+            lang,
+            "You cannot use Hermes until <t:{}:R>, as you sent commands too fast.",
+            "No puedes usar Hermes hasta <t:{}:R>, porque enviaste comandos demasiado rápido.",
+            chrono::Utc::now().timestamp() + remaining.as_secs() as i64
+        )
+    })
+}
+
+/// Rejects every command while the bot is under maintenance, except for the bot's owners.
+async fn maintenance_check(ctx: Context<'_>) -> Result<bool, Error> {
+    let Some(notice) = maintenance_notice(
+        ctx.data(),
+        &ctx.framework().options().owners,
+        ctx.author().id,
+        ctx.guild_id(),
+    ) else {
+        return Ok(true);
+    };
+    utils::send(
+        ctx,
+        poise::CreateReply::default()
+            .content(notice)
+            .ephemeral(true),
+    )
+    .await
+    .expect("Failed to send the maintenance notice.");
+
+    Ok(false)
+}
+
+/// Rejects the commands of the users running too many of them (see `abuseguard`), except for the
+/// bot's owners.
+async fn abuse_check(ctx: Context<'_>) -> Result<bool, Error> {
+    let Some(notice) = abuse_notice(
+        ctx.data(),
+        &ctx.framework().options().owners,
+        ctx.author().id,
+        ctx.guild_id(),
+    ) else {
+        return Ok(true);
+    };
+    utils::send(
+        ctx,
+        poise::CreateReply::default()
+            .content(notice)
+            .ephemeral(true),
+    )
+    .await
+    .expect("Failed to send the notice of a muted user.");
+
+    Ok(false)
+}
+
+/// Checks run before every command: maintenance mode, the limit of commands per user, and then the
+/// guild's permission overrides (see `BotConfig::permissions`).
+async fn command_check(ctx: Context<'_>) -> Result<bool, Error> {
+    Ok(maintenance_check(ctx).await?
+        && abuse_check(ctx).await?
+        && utils::check_permission_overrides(ctx).await)
+}
+
+/// Same checks as `command_check`, for a press of a retry button (which sends a request again, so
+/// the permission overrides of `/request` apply). The press is answered with the notice when
+/// rejected.
+async fn retry_check(
+    ctx: &serenity::Context,
+    framework: poise::FrameworkContext<'_, Data, Error>,
+    data: &Data,
+    press: &serenity::ComponentInteraction,
+) -> bool {
+    let owners = &framework.options().owners;
+    let notice = maintenance_notice(data, owners, press.user.id, press.guild_id)
+        .or_else(|| abuse_notice(data, owners, press.user.id, press.guild_id))
+        .or_else(|| {
+            let config = utils::load_config(&data.caches, &press.guild_id?);
+            let roles = utils::permission_override(&config, "request")?;
+            let allowed = press
+                .member
+                .as_ref()
+                .is_some_and(|member| utils::member_has_roles(member, roles));
+            (!allowed).then(|| utils::missing_roles_notice(&config))
+        });
+    let Some(notice) = notice else {
+        return true;
+    };
+
+    press
+        .create_response(
+            &ctx.http,
+            serenity::CreateInteractionResponse::Message(
+                serenity::CreateInteractionResponseMessage::new()
+                    .content(notice)
+                    .ephemeral(true),
+            ),
+        )
+        .await
+        .expect("Failed to reject the press of a retry button.");
+
+    false
+}
+
+/// Handles the errors of the framework as poise does by default, recording the commands that
+/// panicked as failed (the rest of the outcomes are recorded by `#[hermes::log_cmd]`).
+async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
+    if let poise::FrameworkError::CommandPanic { payload, ctx, .. } = &error {
+        let elapsed = (chrono::Utc::now() - *ctx.created_at())
+            .to_std()
+            .unwrap_or_default();
+        utils::record_cmd_outcome(
+            ctx.guild_id(),
+            ctx.author().id,
+            &ctx.command().qualified_name,
+            redact::invocation(*ctx),
+            elapsed,
+            utils::CmdOutcome::Panicked(payload.as_deref()),
+        );
+    }
+
+    if let Err(e) = poise::builtins::on_error(error).await {
+        tracing::warn!("Failed to handle a framework error: {}", e);
+    }
+}
+
+/// Waits until the process is asked to stop (SIGINT, or SIGTERM on Unix).
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Could not listen for the SIGTERM signal.");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Could not listen for the SIGINT signal.");
+}
+
 #[tokio::main]
 async fn main() {
-    let token = env::var("DISCORD_TOKEN")
-        .expect("Discord token not provided (in DISCORD_TOKEN environmental variable).");
+    let args = cli::Args::parse();
+
+    // Log to stderr (redacting any secrets), with the level set by --log-level or RUST_LOG (info for Hermes, by default):
+    let filter = match &args.log_level {
+        Some(level) => tracing_subscriber::EnvFilter::new(level),
+        None => tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn,hermes=info")),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(redact::StderrWriter)
+        .init();
+
+    // All the persistent data is relative to the data directory:
+    if let Some(data_dir) = &args.data_dir {
+        env::set_current_dir(data_dir).unwrap_or_else(|e| {
+            panic!(
+                "Could not use {} as the data directory: {:?}",
+                data_dir.display(),
+                e
+            )
+        });
+    }
+
+    // Send alerts about operational problems to the webhook, if given:
+    if let Some(webhook_url) = &args.alert_webhook {
+        alerts::init(
+            webhook_url.clone(),
+            std::time::Duration::from_secs(args.tablon_alert_minutes * 60),
+        );
+    }
+
+    // Only serve the mock of Tablón:
+    #[cfg(feature = "mock-tablon")]
+    if let Some(addr) = args.mock_tablon {
+        let addr = mocktablon::start(addr).await;
+        println!("Serving the mock of Tablón on http://{}.", addr);
+        wait_for_shutdown_signal().await;
+        std::process::exit(0);
+    }
+
+    // Only validate the data:
+    if args.dry_run {
+        let (checked, problems) = cli::validate_data();
+        for problem in problems.iter() {
+            eprintln!("{}", problem);
+        }
+        println!(
+            "Checked {} file(s): {} problem(s) found.",
+            checked,
+            problems.len()
+        );
+        std::process::exit(if problems.is_empty() { 0 } else { 1 });
+    }
+
+    let token = args.token();
+    cli::scrub_token_from_panics(token.clone());
+
+    // Only register the commands:
+    if args.register_commands_only {
+        let http = serenity::Http::new(&token);
+        poise::builtins::register_globally(&http, &commands::registry::global())
+            .await
+            .expect("Could not register the commands.");
+        let caches = cache::Caches::default();
+        for gid in utils::load_guildmap().values() {
+            commands::registry::register_in_guild(&http, gid, &utils::load_config(&caches, gid))
+                .await
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "Could not register the guild commands for guild {}: {:?}",
+                        gid, e
+                    )
+                });
+        }
+        println!("The commands have been registered.");
+        return;
+    }
+
     let intents = serenity::GatewayIntents::default()
         | serenity::GatewayIntents::GUILD_MESSAGES
         | serenity::GatewayIntents::GUILD_MEMBERS;
 
+    let tasks = tasks::TaskManager::new();
+    let setup_tasks = Arc::clone(&tasks);
+    let caches = cache::Caches::default();
+    let setup_caches = caches.clone();
+    let tablon_alert_threshold = std::time::Duration::from_secs(args.tablon_alert_minutes * 60);
+    let max_commands_per_minute = args.max_commands_per_minute;
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![
-                commands::botconfig::botconfig(),
-                commands::history::history(),
-                commands::license::license(),
-                commands::passwords::passwords(),
-                commands::request::request(),
-                commands::settings::settings(),
-                commands::team::team(),
-                commands::teamdump::teamdump(),
-                commands::teamedit::teamedit(),
-            ],
+            commands: commands::registry::all(),
+            command_check: Some(|ctx| Box::pin(command_check(ctx))),
+            on_error: |error| Box::pin(on_error(error)),
             event_handler: |ctx, event, framwework, data| {
                 Box::pin(ready(ctx, event, framwework, data))
             },
             ..Default::default()
         })
-        .setup(|ctx, _ready, framework| {
+        .setup(move |ctx, _ready, _framework| {
             Box::pin(async move {
-                poise::builtins::register_globally(ctx, &framework.options().commands)
+                poise::builtins::register_globally(ctx, &commands::registry::global())
                     .await
                     .expect("Could not register the commands.");
-                Ok(Data {})
+                Ok(Data {
+                    maintenance: AtomicBool::new(false),
+                    tasks: setup_tasks,
+                    guard: abuseguard::CommandGuard::new(max_commands_per_minute),
+                    caches: setup_caches.clone(),
+                    feed: activityfeed::Feed::new(
+                        ctx.http.clone(),
+                        setup_caches,
+                        tablon_alert_threshold,
+                    ),
+                    submissions: commands::request::Submissions::default(),
+                    tablon: tablon::Tablon::default(),
+                })
             })
         })
         .build();
@@ -338,5 +751,31 @@ async fn main() {
         .await
         .expect("Could not create the Discord bot client object.");
 
-    client.start().await.expect("The Discord bot crashed.");
+    // Rotate the bot's presence through the live stats:
+    presence::init(
+        std::time::Duration::from_secs(args.presence_interval),
+        args.presence_stats.clone(),
+    );
+
+    // Serve the HTTP endpoints, if enabled:
+    if let Some(addr) = args.http_listen {
+        http::start(addr, client.http.clone(), caches.clone()).await;
+    }
+
+    // Shut down gracefully on SIGINT/SIGTERM:
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        tracing::info!("Shutting down.");
+        systemd::notify_stopping();
+        shard_manager.shutdown_all().await;
+    });
+
+    systemd::start_watchdog(&tasks);
+
+    match args.shards {
+        Some(shards) => client.start_shards(shards).await,
+        None => client.start_autosharded().await,
+    }
+    .expect("The Discord bot crashed.");
 }
@@ -0,0 +1,338 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Mock of Tablón's web pages, serving the fixtures in `fixtures/tablon/`, so the bot (or its
+//! Tablón client, see the tests) can be tested end-to-end without the real service. It is only
+//! built for the tests, or with the `mock-tablon` feature (see `--mock-tablon`).
+//!
+//! The fixtures follow the formats expected by the parsers in `tablon`, but are not captures of
+//! Tablón (see `fixtures/tablon/README.md`); when Tablón's pages change, they should be updated with
+//! the new responses.
+
+use axum::{
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::Html,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Front page, with the announcements.
+const FRONT_PAGE: &str = include_str!("../fixtures/tablon/front.html");
+
+/// Page of a request still in the queue.
+const REQUEST_QUEUED: &str = include_str!("../fixtures/tablon/request_queued.html");
+
+/// Page of a successful request.
+const REQUEST_DONE: &str = include_str!("../fixtures/tablon/request_done.html");
+
+/// Page of a failed request.
+const REQUEST_FAILED: &str = include_str!("../fixtures/tablon/request_failed.html");
+
+/// Team administration page, with the teams' passwords.
+const ADMIN_TEAMS: &str = include_str!("../fixtures/tablon/admin_teams.html");
+
+/// Request administration page of team g01, with its requests.
+const ADMIN_REQUESTS: &str = include_str!("../fixtures/tablon/admin_requests.html");
+
+/// Page of a team, once logged in.
+const TEAM_PAGE: &str = include_str!("../fixtures/tablon/team.html");
+
+/// Login form, shown again after wrong credentials.
+const LOGIN_PAGE: &str = include_str!("../fixtures/tablon/login.html");
+
+/// Credentials of the teams, as listed in the team administration page.
+const TEAM_CREDENTIALS: [(&str, &str); 3] =
+    [("g01", "alpha123"), ("g02", "beta456"), ("g10", "gamma789")];
+
+/// Number of times the front page was served, to check the fetches are coalesced.
+static FRONT_PAGE_HITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of times the team administration page was served, to check the authenticated fetches
+/// are coalesced too.
+static ADMIN_TEAMS_HITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Serves the front page, counting it.
+async fn front_page() -> Html<&'static str> {
+    FRONT_PAGE_HITS.fetch_add(1, Ordering::SeqCst);
+
+    Html(FRONT_PAGE)
+}
+
+/// Query of a request page.
+#[derive(Deserialize)]
+struct RequestQuery {
+    rid: u16,
+}
+
+/// Serves the page of a request: 1 is queued, 2 succeeded, 3 failed, and any other is unknown.
+async fn request(Query(query): Query<RequestQuery>) -> Result<Html<&'static str>, StatusCode> {
+    match query.rid {
+        1 => Ok(Html(REQUEST_QUEUED)),
+        2 => Ok(Html(REQUEST_DONE)),
+        3 => Ok(Html(REQUEST_FAILED)),
+        _ => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Whether a request to the mock has (any) basic authentication, as admin pages require.
+fn is_admin(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("Basic "))
+}
+
+/// Serves the team administration page, only with (any) basic authentication, counting it.
+async fn admin_teams(headers: HeaderMap) -> Result<Html<&'static str>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    ADMIN_TEAMS_HITS.fetch_add(1, Ordering::SeqCst);
+
+    Ok(Html(ADMIN_TEAMS))
+}
+
+/// Query of the request administration page.
+#[derive(Deserialize)]
+struct AdminRequestsQuery {
+    team: String,
+}
+
+/// Serves the request administration page of team g01 (any other is unknown), only with (any)
+/// basic authentication.
+async fn admin_requests(
+    headers: HeaderMap,
+    Query(query): Query<AdminRequestsQuery>,
+) -> Result<Html<&'static str>, StatusCode> {
+    if !is_admin(&headers) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    if query.team != "g01" {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Html(ADMIN_REQUESTS))
+}
+
+/// Value of the `Authorization` header for the given credentials, as sent by the Tablón client.
+fn basic_auth(team: &str, password: &str) -> Option<String> {
+    let request = reqwest::Client::new()
+        .get("http://localhost")
+        .basic_auth(team, Some(password))
+        .build()
+        .ok()?;
+    request
+        .headers()
+        .get(reqwest::header::AUTHORIZATION)?
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+/// Serves the page of a team, only with the basic authentication of one of the teams: without
+/// authentication, it is an error, and with the wrong one, the login form.
+async fn team_page(headers: HeaderMap) -> Result<Html<&'static str>, StatusCode> {
+    let Some(authorization) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    let authenticated = TEAM_CREDENTIALS
+        .iter()
+        .any(|(team, password)| basic_auth(team, password).as_deref() == Some(authorization));
+    if !authenticated {
+        return Ok(Html(LOGIN_PAGE));
+    }
+
+    Ok(Html(TEAM_PAGE))
+}
+
+/// Routes of the mock.
+fn routes() -> Router {
+    Router::new()
+        .route("/", get(front_page))
+        .route("/request", get(request))
+        .route("/admin/teams", get(admin_teams))
+        .route("/admin/requests", get(admin_requests))
+        .route("/team", get(team_page))
+}
+
+/// Starts serving the mock on the given address, in the background, returning the address it
+/// listens on (e.g. for port 0).
+pub async fn start(addr: SocketAddr) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .unwrap_or_else(|e| panic!("[mocktablon] Could not listen on {}: {:?}", addr, e));
+    let addr = listener
+        .local_addr()
+        .expect("[mocktablon] Could not get the address of the mock.");
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, routes()).await {
+            tracing::error!("The mock of Tablón crashed: {}", e);
+        }
+    });
+
+    addr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tablon, tablon::Tablon, Credentials};
+    use std::collections::HashMap;
+
+    /// Starts a mock on any free port, returning its URL.
+    async fn start_mock() -> String {
+        format!(
+            "http://{}",
+            start(SocketAddr::from(([127, 0, 0, 1], 0))).await
+        )
+    }
+
+    /// Credentials with the given team and password.
+    fn login(team: &str, password: &str) -> Credentials {
+        Credentials {
+            team: team.to_string(),
+            password: Some(password.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn request_statuses() {
+        let url = start_mock().await;
+        let client = Tablon::default();
+
+        let queued = client.fetch_request_status(&url, 1).await;
+        assert!(queued.as_deref().is_some_and(tablon::is_pending));
+        let done = client.fetch_request_status(&url, 2).await.unwrap();
+        assert!(!tablon::is_pending(&done) && !tablon::is_failure(&done));
+        assert_eq!(tablon::verdict_result(&done), Some(1.234));
+        let failed = client.fetch_request_status(&url, 3).await;
+        assert!(failed.as_deref().is_some_and(tablon::is_failure));
+        assert!(client.fetch_request_status(&url, 4).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn announcements() {
+        let url = start_mock().await;
+        let client = Tablon::default();
+
+        let announcements = client.fetch_announcements(&url).await.unwrap_or_default();
+        assert_eq!(announcements.len(), 2);
+        assert!(announcements[0].starts_with("Nueva cola disponible"));
+
+        // Identical fetches at once are coalesced into one:
+        let hits = FRONT_PAGE_HITS.load(Ordering::SeqCst);
+        let (first, second) = tokio::join!(
+            client.fetch_announcements(&url),
+            client.fetch_announcements(&url)
+        );
+        assert!(first.is_some());
+        assert_eq!(first, second);
+        assert!(FRONT_PAGE_HITS.load(Ordering::SeqCst) <= hits + 1);
+    }
+
+    #[tokio::test]
+    async fn team_passwords() {
+        let url = start_mock().await;
+        let client = Tablon::default();
+
+        let passwords = client
+            .fetch_team_passwords(&url, &login("root", "toor"), "g")
+            .await;
+        assert_eq!(
+            passwords,
+            Some(HashMap::from([
+                ("g01".to_string(), "alpha123".to_string()),
+                ("g02".to_string(), "beta456".to_string()),
+                ("g10".to_string(), "gamma789".to_string()),
+            ]))
+        );
+
+        // Identical fetches with the same credentials are coalesced into one:
+        let hits = ADMIN_TEAMS_HITS.load(Ordering::SeqCst);
+        let admin = login("admin", "admin");
+        let (first, second) = tokio::join!(
+            client.fetch_team_passwords(&url, &admin, "g"),
+            client.fetch_team_passwords(&url, &admin, "g")
+        );
+        assert!(first.is_some());
+        assert_eq!(first, second);
+        assert_eq!(ADMIN_TEAMS_HITS.load(Ordering::SeqCst), hits + 1);
+
+        // But not with other credentials, as the page depends on them:
+        let other = client
+            .fetch_team_passwords(&url, &login("admin", "nimda"), "g")
+            .await;
+        assert_eq!(first, other);
+        assert_eq!(ADMIN_TEAMS_HITS.load(Ordering::SeqCst), hits + 2);
+    }
+
+    #[tokio::test]
+    async fn team_requests() {
+        let url = start_mock().await;
+        let client = Tablon::default();
+        let admin = login("admin", "admin");
+
+        let requests = client
+            .fetch_team_requests(&url, &admin, "g01")
+            .await
+            .unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[0],
+            tablon::TablonRequest {
+                id: 1,
+                queue: "cola-1".to_string(),
+                sent: "2025-03-10 12:00:05".to_string(),
+                status: "En cola".to_string(),
+            }
+        );
+        assert_eq!(tablon::verdict_result(&requests[1].status), Some(1.234));
+        assert!(tablon::is_failure(&requests[2].status));
+        assert!(client
+            .fetch_team_requests(&url, &admin, "g99")
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn team_credentials() {
+        let url = start_mock().await;
+        let client = Tablon::default();
+
+        assert_eq!(
+            client
+                .check_credentials(&url, &login("g02", "beta456"))
+                .await,
+            Some(true)
+        );
+        assert_eq!(
+            client
+                .check_credentials(&url, &login("g02", "beta465"))
+                .await,
+            Some(false)
+        );
+    }
+}
@@ -0,0 +1,133 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Relay of the announcements in Tablón's front page (e.g. new assignments, or downtime notices) to
+//! the news channel of the guilds that enable it (see `BotConfig::relay_news`).
+
+use crate::{cache::Caches, tablon::Tablon, tasks::TaskManager, utils};
+use poise::serenity_prelude as serenity;
+use std::{collections::HashMap, fs, sync::Arc, time::Duration};
+
+/// Interval between checks of Tablón's announcements.
+const POLL_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of announcements remembered per guild, for deduplication.
+const MAX_SEEN: usize = 200;
+
+/// Starts the relay in the background, supervised by the task manager (which ignores it if it was
+/// already started).
+pub fn start(ctx: &serenity::Context, caches: &Caches, tablon: &Tablon, tasks: &Arc<TaskManager>) {
+    let ctx = ctx.clone();
+    let caches = caches.clone();
+    let tablon = tablon.clone();
+    tasks.spawn_periodic("news", POLL_INTERVAL, move || {
+        let ctx = ctx.clone();
+        let caches = caches.clone();
+        let tablon = tablon.clone();
+        async move {
+            relay_news(&ctx, &caches, &tablon).await;
+
+            Ok(())
+        }
+    });
+}
+
+/// Path of the file with the hashes of the announcements already seen in a guild.
+fn seen_path(gid: &serenity::GuildId) -> String {
+    format!("guilds/{}/news.json", gid)
+}
+
+/// Forwards the new announcements of each guild's Tablón to its news channel.
+async fn relay_news(ctx: &serenity::Context, caches: &Caches, tablon: &Tablon) {
+    // Fetch the announcements once per Tablón instance:
+    let mut announcements = HashMap::<String, Option<Vec<String>>>::new();
+    for gid in utils::load_guildmap().into_values() {
+        let config = utils::load_config(caches, &gid);
+        if !config.relay_news {
+            continue;
+        }
+        if !announcements.contains_key(&config.tablon_url) {
+            let fetched = tablon.fetch_announcements(&config.tablon_url).await;
+            announcements.insert(config.tablon_url.clone(), fetched);
+        }
+        let Some(Some(news)) = announcements.get(&config.tablon_url) else {
+            continue;
+        };
+
+        // The first time, just remember the current announcements, instead of flooding the
+        // channel with them:
+        let first_time = fs::metadata(seen_path(&gid)).is_err();
+        let mut seen: Vec<u64> = fs::read_to_string(seen_path(&gid))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let new: Vec<&String> = news
+            .iter()
+            .filter(|item| !seen.contains(&utils::content_hash(item.as_bytes())))
+            .collect();
+        if new.is_empty() && !first_time {
+            continue;
+        }
+
+        if !first_time {
+            let channel = gid.channels(&ctx.http).await.ok().and_then(|channels| {
+                channels
+                    .into_values()
+                    .find(|channel| channel.name == config.bot_news_channel)
+            });
+            let Some(channel) = channel else {
+                tracing::warn!(
+                    "[news] Guild {} has no channel named #{} to relay Tablón's news to.",
+                    gid,
+                    config.bot_news_channel
+                );
+                continue;
+            };
+            for item in new.iter() {
+                let msg = format!("📰 **News from Tablón:**\n{}", item);
+                if let Err(e) = channel
+                    .send_message(&ctx.http, serenity::CreateMessage::new().content(msg))
+                    .await
+                {
+                    tracing::warn!(
+                        "[news] Could not relay Tablón's news to guild {}: {}",
+                        gid,
+                        e
+                    );
+                }
+            }
+        }
+
+        // Remember the announcements (forgetting the oldest ones):
+        seen.extend(new.iter().map(|item| utils::content_hash(item.as_bytes())));
+        let excess = seen.len().saturating_sub(MAX_SEEN);
+        seen.drain(..excess);
+        let json = serde_json::to_string(&seen).unwrap_or_else(|e| {
+            panic!(
+                "[news] Could not serialize the seen announcements of guild {}: {:?}",
+                gid, e
+            )
+        });
+        if let Err(e) = fs::write(seen_path(&gid), json) {
+            tracing::warn!(
+                "[news] Could not save the seen announcements of guild {}: {}",
+                gid,
+                e
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Presence of the bot, rotating through live stats aggregated over all the guilds (e.g. the
+//! number of teams formed, or whether Tablón is reachable). The stats shown and how often they
+//! change are set per deployment (`--presence-stats` and `--presence-interval`).
+
+use crate::{alerts, cache::Caches, service, tasks::TaskManager, team, utils};
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+
+/// Stats that can be shown in the bot's presence.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Stat {
+    /// Number of teams formed, in all the guilds.
+    Teams,
+    /// Number of requests sent today (in each guild's timezone), in all the guilds.
+    RequestsToday,
+    /// Whether Tablón can currently be reached.
+    Tablon,
+}
+
+/// Rotation of the presence, set once at startup.
+struct PresenceConfig {
+    /// Time between changes of the presence.
+    interval: Duration,
+    /// Stats rotated through, in order.
+    stats: Vec<Stat>,
+}
+
+static CONFIG: OnceLock<PresenceConfig> = OnceLock::new();
+
+/// Counters aggregated over all the guilds.
+struct Counters {
+    teams: usize,
+    requests_today: u64,
+    tablon_up: bool,
+}
+
+impl Counters {
+    /// Aggregates the counters from the persistent data of every guild.
+    fn gather(caches: &Caches) -> Counters {
+        let now = Utc::now().timestamp();
+        let mut teams = 0;
+        let mut requests_today = 0;
+        for gid in utils::load_guildmap().into_values() {
+            let config = utils::load_config(caches, &gid);
+            let day = service::budget_day(&config, now);
+            let guild_teams = team::get_all_teams(&gid);
+            teams += guild_teams.len();
+            requests_today += guild_teams
+                .iter()
+                .map(|team| team.submissions().count_on(&day) as u64)
+                .sum::<u64>();
+        }
+
+        Counters {
+            teams,
+            requests_today,
+            tablon_up: alerts::tablon_down_since().is_none(),
+        }
+    }
+
+    /// Text of the presence showing a stat.
+    fn text(&self, stat: Stat) -> String {
+        match stat {
+            Stat::Teams => format!("{} teams formed", self.teams),
+            Stat::RequestsToday => format!("{} requests today", self.requests_today),
+            Stat::Tablon => format!("Tablón: {}", if self.tablon_up { "OK" } else { "DOWN" }),
+        }
+    }
+}
+
+/// Sets how the presence rotates: through the given stats, changing at the given interval (none,
+/// if zero).
+pub fn init(interval: Duration, stats: Vec<Stat>) {
+    let _ = CONFIG.set(PresenceConfig { interval, stats });
+}
+
+/// Starts rotating the presence in the background, supervised by the task manager (which ignores
+/// it if it was already started), unless no rotation was set.
+pub fn start(ctx: &serenity::Context, caches: &Caches, tasks: &Arc<TaskManager>) {
+    let Some(config) = CONFIG.get() else {
+        return;
+    };
+    if config.interval.is_zero() || config.stats.is_empty() {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    let caches = caches.clone();
+    let next = Arc::new(AtomicUsize::new(0));
+    tasks.spawn_periodic("presence", config.interval, move || {
+        let ctx = ctx.clone();
+        let caches = caches.clone();
+        let next = Arc::clone(&next);
+        async move {
+            let counters = tokio::task::spawn_blocking(move || Counters::gather(&caches))
+                .await
+                .map_err(|e| format!("Could not aggregate the stats: {}", e))?;
+            let stat = config.stats[next.fetch_add(1, Ordering::Relaxed) % config.stats.len()];
+            ctx.set_activity(Some(serenity::ActivityData::custom(counters.text(stat))));
+
+            Ok(())
+        }
+    });
+}
@@ -0,0 +1,171 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Redaction of secrets (i.e. Tablón passwords) from everything the bot logs.
+//!
+//! Command invocations are redacted before being logged (see `invocation`), and every line written
+//! to stderr by the logger goes through `redact` as a last line of defense (see `StderrWriter`).
+
+use crate::Context;
+use std::io::{self, Write};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Placeholder shown instead of a secret.
+pub const PLACEHOLDER: &str = "[REDACTED]";
+
+/// Names of the command parameters holding secrets.
+const SENSITIVE_PARAMS: [&str; 1] = ["password"];
+
+/// Invocation string of a command (as in `Context::invocation_string`), with the values of its
+/// sensitive parameters redacted.
+pub fn invocation(ctx: Context<'_>) -> String {
+    let mut invocation = ctx.invocation_string();
+    if let poise::Context::Application(actx) = ctx {
+        for arg in actx.args {
+            if let poise::serenity_prelude::ResolvedValue::String(value) = arg.value {
+                if SENSITIVE_PARAMS.contains(&arg.name) && !value.is_empty() {
+                    invocation = invocation.replace(
+                        format!(" {}:{}", arg.name, value).as_str(),
+                        format!(" {}:{}", arg.name, PLACEHOLDER).as_str(),
+                    );
+                }
+            }
+        }
+    }
+
+    invocation
+}
+
+/// Redacts anything that looks like a secret in a text: the values of sensitive parameters in
+/// command invocations (e.g. `password:hunter2`, up to the next parameter), and the passwords given
+/// to Tablón's client (`-x hunter2`).
+pub fn redact(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, value_start)) = find_secret(rest) {
+        result.push_str(&rest[..value_start]);
+        result.push_str(PLACEHOLDER);
+        let value = &rest[value_start..];
+        let value_len = if rest[start..].starts_with("-x ") {
+            // A single (shell) word:
+            value.find(char::is_whitespace).unwrap_or(value.len())
+        } else {
+            // Up to the next parameter (` name:`), or the end of the invocation:
+            next_param(value).unwrap_or_else(|| value.find(['`', '\n']).unwrap_or(value.len()))
+        };
+        rest = &value[value_len..];
+    }
+    result.push_str(rest);
+
+    result
+}
+
+/// Finds the first secret in a text, returning where its marker (e.g. `password:`) starts and where
+/// its value starts.
+fn find_secret(text: &str) -> Option<(usize, usize)> {
+    let mut markers: Vec<String> = SENSITIVE_PARAMS
+        .iter()
+        .map(|param| format!(" {}:", param))
+        .collect();
+    markers.push(String::from("-x "));
+
+    // Every occurrence of each marker is checked, as an already redacted (or empty) one may be
+    // followed by one that is not:
+    markers
+        .iter()
+        .filter_map(|marker| {
+            text.match_indices(marker.as_str())
+                .map(|(start, _)| (start, start + marker.len()))
+                .find(|(_, value_start)| {
+                    text[*value_start..]
+                        .chars()
+                        .next()
+                        .is_some_and(|c| !c.is_whitespace())
+                        && !text[*value_start..].starts_with(PLACEHOLDER)
+                })
+        })
+        .min()
+}
+
+/// Position of the next ` name:` parameter in the value of a command invocation parameter.
+fn next_param(value: &str) -> Option<usize> {
+    value.match_indices(' ').map(|(i, _)| i).find(|&i| {
+        let word = value[i + 1..].split(' ').next().unwrap_or_default();
+        word.find(':').is_some_and(|colon| {
+            colon > 0
+                && word[..colon]
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        })
+    })
+}
+
+/// Writer for the logger, redacting secrets from every line before writing it to stderr.
+pub struct StderrWriter;
+
+impl Write for StderrWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The logger writes each line in a single call, so it can be redacted as a whole:
+        io::stderr().write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for StderrWriter {
+    type Writer = StderrWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        StderrWriter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_every_occurrence() {
+        assert_eq!(
+            redact("/teamedit password team:g01 password:hunter2"),
+            "/teamedit password team:g01 password:[REDACTED]"
+        );
+        assert_eq!(
+            redact("client -x first -u g01 -x second"),
+            "client -x [REDACTED] -u g01 -x [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn redacts_after_a_redacted_or_empty_occurrence() {
+        assert_eq!(
+            redact("/a password:[REDACTED] then /b password:hunter2"),
+            "/a password:[REDACTED] then /b password:[REDACTED]"
+        );
+        assert_eq!(
+            redact("/a password: then /b password:hunter2"),
+            "/a password: then /b password:[REDACTED]"
+        );
+        assert_eq!(
+            redact("client -x [REDACTED] and client -x hunter2"),
+            "client -x [REDACTED] and client -x [REDACTED]"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{tablonclient, utils};
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+use serenity::all::{GuildId, UserId};
+use std::{fs, io};
+
+/// Data structure defining a request sent to Tablón through Hermes, as stored in a student's
+/// request history.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Serialize, Deserialize, Getters, CopyGetters)]
+#[serde(from = "RequestRecordRepr")]
+pub struct RequestRecord {
+    /// Tablón's identifier for the request.
+    #[getset(get_copy = "pub")]
+    id: u16,
+    /// When the request was sent, as a UNIX timestamp (in seconds). Unknown for requests recorded
+    /// by older versions of the bot.
+    #[getset(get_copy = "pub")]
+    timestamp: Option<i64>,
+    /// The queue the request was sent to, if known.
+    #[getset(get = "pub")]
+    queue: Option<String>,
+    /// The verdict of the request in Tablón, once known (it is cached when fetched).
+    #[getset(get = "pub")]
+    verdict: Option<String>,
+}
+
+impl RequestRecord {
+    /// Constructor for a request record, given its identifier, timestamp, and queue.
+    pub fn new(id: u16, timestamp: i64, queue: Option<String>) -> RequestRecord {
+        Self {
+            id,
+            timestamp: Some(timestamp),
+            queue,
+            verdict: None,
+        }
+    }
+
+    /// Sets the (cached) verdict of the request.
+    pub fn set_verdict(&mut self, verdict: String) {
+        self.verdict = Some(verdict);
+    }
+}
+
+/// Persistent representations of a `RequestRecord`.
+///
+/// Older versions of the bot stored only the identifiers of the requests.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RequestRecordRepr {
+    Id(u16),
+    Full {
+        id: u16,
+        #[serde(default)]
+        timestamp: Option<i64>,
+        #[serde(default)]
+        queue: Option<String>,
+        #[serde(default)]
+        verdict: Option<String>,
+    },
+}
+
+/// Conversion from any of the persistent representations to a `RequestRecord` object.
+impl From<RequestRecordRepr> for RequestRecord {
+    fn from(repr: RequestRecordRepr) -> RequestRecord {
+        match repr {
+            RequestRecordRepr::Id(id) => RequestRecord {
+                id,
+                timestamp: None,
+                queue: None,
+                verdict: None,
+            },
+            RequestRecordRepr::Full {
+                id,
+                timestamp,
+                queue,
+                verdict,
+            } => RequestRecord {
+                id,
+                timestamp,
+                queue,
+                verdict,
+            },
+        }
+    }
+}
+
+/// Data structure defining the metadata of a request sent to Tablón through Hermes, kept apart
+/// from the students' histories (which only reference the requests), along with a copy of the
+/// program sent.
+///
+/// It is saved as `guilds/<guild_id>/requests/<request_id>.json`, next to the program
+/// (`<request_id>.program`).
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct RequestMetadata {
+    /// Tablón's identifier for the request.
+    #[getset(get_copy = "pub")]
+    id: u16,
+    /// The team the request was sent on behalf of.
+    #[getset(get = "pub")]
+    team: String,
+    /// The student who sent the request.
+    #[getset(get_copy = "pub")]
+    user: UserId,
+    /// Name of the program file sent.
+    #[getset(get = "pub")]
+    filename: String,
+    /// Hash of the program sent (see `utils::content_hash`), as hexadecimal.
+    #[getset(get = "pub")]
+    hash: String,
+    /// Arguments the request was sent with (as given to Tablón's client).
+    #[getset(get = "pub")]
+    args: String,
+    /// The queue the request was sent to, if known.
+    #[getset(get = "pub")]
+    queue: Option<String>,
+    /// When the request was sent, as a UNIX timestamp (in seconds).
+    #[getset(get_copy = "pub")]
+    timestamp: i64,
+    /// The verdict of the request in Tablón, once known.
+    #[getset(get = "pub")]
+    verdict: Option<String>,
+}
+
+impl RequestMetadata {
+    /// Records a request just sent, saving its metadata and a copy of its program (read from
+    /// `program_path`) to disk.
+    pub fn record(
+        guild_id: &GuildId,
+        id: u16,
+        team: String,
+        user: UserId,
+        filename: String,
+        args: String,
+        program_path: &str,
+    ) -> io::Result<RequestMetadata> {
+        let program = fs::read(program_path)?;
+        let metadata = Self {
+            id,
+            team,
+            user,
+            filename,
+            hash: format!("{:016x}", utils::content_hash(&program)),
+            queue: queue_from_args(&args),
+            args,
+            timestamp: chrono::Utc::now().timestamp(),
+            verdict: None,
+        };
+
+        fs::create_dir_all(format!("guilds/{}/requests", guild_id))?;
+        fs::copy(program_path, metadata_program_path(guild_id, id))?;
+        metadata.save(guild_id)?;
+
+        Ok(metadata)
+    }
+
+    /// Sets the verdict of the request, saving it.
+    pub fn set_verdict(&mut self, guild_id: &GuildId, verdict: String) -> io::Result<()> {
+        self.verdict = Some(verdict);
+
+        self.save(guild_id)
+    }
+
+    /// Saves the request's metadata to disk as a JSON file.
+    fn save(&self, guild_id: &GuildId) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).unwrap_or_else(|e| {
+            panic!(
+                "[RequestMetadata] Could not serialize the metadata of request {}: {:?}",
+                self.id, e
+            )
+        });
+
+        utils::write_file(
+            &format!("guilds/{}/requests/{}.json", guild_id, self.id),
+            json,
+        )
+    }
+}
+
+/// Path of the copy of the program sent in a request (see `RequestMetadata`).
+pub fn metadata_program_path(guild_id: &GuildId, request_id: u16) -> String {
+    format!("guilds/{}/requests/{}.program", guild_id, request_id)
+}
+
+/// Retrieves the metadata of a request sent from a guild, if it was recorded (requests sent by
+/// older versions of the bot have none).
+pub fn get_metadata(guild_id: &GuildId, request_id: u16) -> Option<RequestMetadata> {
+    let path = format!("guilds/{}/requests/{}.json", guild_id, request_id);
+    let json = match utils::read_file(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            tracing::warn!("[RequestMetadata] Could not read {}: {}", path, e);
+            return None;
+        }
+    };
+
+    serde_json::from_str(&json).ok()
+}
+
+/// Retrieves the metadata of all the requests recorded in a guild, sorted by request.
+pub fn get_all_metadata(guild_id: &GuildId) -> Vec<RequestMetadata> {
+    let Ok(entries) = fs::read_dir(format!("guilds/{}/requests", guild_id)) else {
+        return Vec::new();
+    };
+
+    let mut requests: Vec<RequestMetadata> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| name.strip_suffix(".json")?.parse::<u16>().ok())
+        .filter_map(|request_id| get_metadata(guild_id, request_id))
+        .collect();
+    requests.sort_by_key(|request| request.id);
+
+    requests
+}
+
+/// Extracts the queue from the arguments of a request (i.e. the value of the `-q` option), if
+/// present.
+pub fn queue_from_args(args: &str) -> Option<String> {
+    let mut parts = tablonclient::split_args(args).ok()?.into_iter();
+    while let Some(part) = parts.next() {
+        if part == "-q" {
+            return parts.next();
+        }
+    }
+
+    None
+}
@@ -0,0 +1,244 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::{
+    activityfeed::Feed,
+    cache::Caches,
+    deadline,
+    i18n::{self, tr},
+    inactivity, leaderboard, leadernotify, student,
+    tasks::TaskManager,
+    utils,
+};
+use chrono::Utc;
+use poise::serenity_prelude as serenity;
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Interval between checks of the scheduled tasks.
+const TICK: Duration = Duration::from_secs(60);
+
+/// Interval between refreshes of the guilds' champion roles (see `BotConfig::lb_champion_roles`), as
+/// each one lists all the members of the guilds using them.
+const CHAMPION_ROLES_REFRESH: Duration = Duration::from_secs(15 * 60);
+
+/// Interval between checks of the teams that have not sent any request yet (see `inactivity`).
+const INACTIVITY_CHECK: Duration = Duration::from_secs(60 * 60);
+
+/// Interval between checks of the leaders of the guilds' leaderboards (see `leadernotify`).
+const LEADERS_CHECK: Duration = Duration::from_secs(60);
+
+/// Starts the scheduler in the background, along with the refresh of the champion roles, the
+/// notifications of the leaders' position changes and the reminders of the inactive teams,
+/// supervised by the task manager (which ignores them if they were already started, as the Ready
+/// event may fire more than once). Each tick also posts Tablón's reachability to the activity
+/// feeds (see `Feed::check_tablon`).
+///
+/// Tasks scheduled while the bot was not running are not executed retroactively.
+pub fn start(ctx: &serenity::Context, caches: &Caches, feed: &Feed, tasks: &Arc<TaskManager>) {
+    let http = Arc::clone(&ctx.http);
+    let inactivity_ctx = ctx.clone();
+    let leaders_ctx = ctx.clone();
+    let ctx = ctx.clone();
+    let inactivity_caches = caches.clone();
+    let inactivity_feed = feed.clone();
+    let leaders_caches = caches.clone();
+    let roles_caches = caches.clone();
+    let caches = caches.clone();
+    let feed = feed.clone();
+    let last_tick = Arc::new(AtomicI64::new(Utc::now().timestamp()));
+
+    tasks.spawn_periodic("scheduler", TICK, move || {
+        let ctx = ctx.clone();
+        let caches = caches.clone();
+        let feed = feed.clone();
+        let last_tick = Arc::clone(&last_tick);
+        async move {
+            let now = Utc::now().timestamp();
+            send_deadline_reminders(&ctx, &caches, last_tick.load(Ordering::SeqCst), now).await;
+            announce_surges(&ctx, &caches, last_tick.load(Ordering::SeqCst), now).await;
+            last_tick.store(now, Ordering::SeqCst);
+            feed.check_tablon();
+
+            Ok(())
+        }
+    });
+
+    tasks.spawn_periodic("inactivity", INACTIVITY_CHECK, move || {
+        let ctx = inactivity_ctx.clone();
+        let caches = inactivity_caches.clone();
+        let feed = inactivity_feed.clone();
+        async move {
+            inactivity::nudge_inactive_teams(&ctx, &caches, &feed).await;
+
+            Ok(())
+        }
+    });
+
+    tasks.spawn_periodic("leaders", LEADERS_CHECK, move || {
+        let ctx = leaders_ctx.clone();
+        let caches = leaders_caches.clone();
+        async move {
+            leadernotify::check_leaders(&ctx, &caches).await;
+
+            Ok(())
+        }
+    });
+
+    tasks.spawn_periodic("champion_roles", CHAMPION_ROLES_REFRESH, move || {
+        let http = Arc::clone(&http);
+        let caches = roles_caches.clone();
+        async move {
+            for gid in utils::load_guildmap().into_values() {
+                if let Err(e) = leaderboard::refresh_champion_roles(&http, &caches, &gid).await {
+                    tracing::warn!(
+                        "[scheduler] Could not refresh the champion roles of guild {}: {}",
+                        gid,
+                        e
+                    );
+                }
+            }
+
+            Ok(())
+        }
+    });
+}
+
+/// Sends the deadline reminders scheduled in the time interval `(from, to]` to the students who
+/// opted in to them.
+async fn send_deadline_reminders(ctx: &serenity::Context, caches: &Caches, from: i64, to: i64) {
+    let guilds = utils::load_guildmap()
+        .into_values()
+        .map(|gid| (gid, deadline::get_guild_deadlines(&gid)))
+        .filter(|(_, deadlines)| deadlines.upcoming(to).next().is_some())
+        .collect::<Vec<_>>();
+    if guilds.is_empty() {
+        return;
+    }
+
+    let students = student::get_all_students(caches);
+    for (gid, deadlines) in guilds {
+        let config = utils::load_config(caches, &gid);
+        let gname = gid
+            .name(&ctx.cache)
+            .unwrap_or_else(|| format!("guild {}", gid));
+
+        for student in students.iter() {
+            let Some(offsets) = student.get_reminders(&gid) else {
+                continue;
+            };
+
+            for deadline in deadlines.upcoming(to) {
+                for offset in offsets {
+                    let remind_at = deadline.timestamp() - *offset as i64 * 3600;
+                    if remind_at <= from || remind_at > to {
+                        continue;
+                    }
+
+                    let msg = format!(
+                        "⏰ Reminder: the deadline **{}** in **{}** expires <t:{}:R> ({}).",
+                        deadline.name(),
+                        gname,
+                        deadline.timestamp(),
+                        utils::format_unix_timestamp(
+                            &config,
+                            i18n::user_language(student, &config, &gid),
+                            deadline.timestamp()
+                        )
+                    );
+                    if let Err(e) = student
+                        .id()
+                        .direct_message(&ctx.http, serenity::CreateMessage::new().content(msg))
+                        .await
+                    {
+                        tracing::warn!(
+                            "[scheduler] Could not send a deadline reminder to student {}: {}",
+                            student.id(),
+                            e
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Announces in the guilds' news channels the surge protections (see `BotConfig::surge_minutes`)
+/// starting in the time interval `(from, to]`.
+async fn announce_surges(ctx: &serenity::Context, caches: &Caches, from: i64, to: i64) {
+    for gid in utils::load_guildmap().into_values() {
+        let config = utils::load_config(caches, &gid);
+        if config.surge_minutes == 0 {
+            continue;
+        }
+        let deadlines = deadline::get_guild_deadlines(&gid);
+        let starting = deadlines
+            .upcoming(to)
+            .filter(|deadline| {
+                let start = deadline.timestamp() - config.surge_minutes as i64 * 60;
+                from < start && start <= to
+            })
+            .collect::<Vec<_>>();
+        if starting.is_empty() {
+            continue;
+        }
+
+        let channel = gid.channels(&ctx.http).await.ok().and_then(|channels| {
+            channels
+                .into_values()
+                .find(|channel| channel.name == config.bot_news_channel)
+        });
+        let Some(channel) = channel else {
+            tracing::warn!(
+                "[scheduler] Guild {} has no channel named #{} to announce the surge protection in.",
+                gid,
+                config.bot_news_channel
+            );
+            continue;
+        };
+        for deadline in starting {
+            let msg = tr!(
+                i18n::guild_language(&config),
+                "🛡️ The deadline **{}** expires <t:{}:R>. Until then, each team can send a request \
+                every {} seconds, and requests wait in a queue to reach Tablón {} seconds apart, \
+                in the order they were sent.",
+                "🛡️ La fecha límite **{}** vence <t:{}:R>. Hasta entonces, cada equipo puede enviar \
+                una petición cada {} segundos, y las peticiones esperan en una cola para llegar a \
+                Tablón con {} segundos de separación, en el orden en que se enviaron.",
+                deadline.name(),
+                deadline.timestamp(),
+                config.team_cooldown.max(config.surge_cooldown),
+                config.surge_spacing
+            );
+            if let Err(e) = channel
+                .send_message(&ctx.http, serenity::CreateMessage::new().content(msg))
+                .await
+            {
+                tracing::warn!(
+                    "[scheduler] Could not announce the surge protection in guild {}: {}",
+                    gid,
+                    e
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,1200 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Logic of the student commands (team management and request submission), apart from Discord, so
+//! it can run on any storage (see `Storage`). The commands only adapt the outcomes into replies.
+
+use crate::{
+    cache::Caches,
+    events::{self, Event},
+    i18n::{tr, Language},
+    requestrecord::{self, RequestMetadata},
+    student,
+    student::Student,
+    tablonclient,
+    team::{self, GuildTeamInfo, SubmissionBudget, Team},
+    teamrequest,
+    utils::{self, BotConfig},
+};
+use chrono::{TimeZone, Utc};
+use serenity::all::{GuildId, UserId};
+use std::sync::Mutex;
+
+/// Access to the persistent data needed by the services.
+pub trait Storage {
+    fn student(&self, id: &UserId) -> Option<Student>;
+    fn team(&self, guild_id: &GuildId, team_id: &str) -> Option<Team>;
+    fn team_info(&self, guild_id: &GuildId) -> Option<GuildTeamInfo>;
+    fn config(&self, guild_id: &GuildId) -> BotConfig;
+    fn request_metadata(&self, guild_id: &GuildId, request_id: u16) -> Option<RequestMetadata>;
+
+    fn save_student(&self, student: &Student);
+    fn save_team(&self, team: &Team);
+    fn save_team_info(&self, info: &GuildTeamInfo);
+    /// Saves a new team, registering its identifier in the guild.
+    fn add_team(&self, team: &Team);
+    /// Deletes an emptied out team, releasing its identifier in the guild.
+    fn delete_team(&self, team: &Team);
+    fn record_event(&self, guild_id: &GuildId, event: Event);
+}
+
+/// The bot's persistent files, read through the given caches.
+pub struct FileStorage<'a>(pub &'a Caches);
+
+impl Storage for FileStorage<'_> {
+    fn student(&self, id: &UserId) -> Option<Student> {
+        student::get_student(self.0, id)
+    }
+
+    fn team(&self, guild_id: &GuildId, team_id: &str) -> Option<Team> {
+        team::get_team(guild_id, team_id)
+    }
+
+    fn team_info(&self, guild_id: &GuildId) -> Option<GuildTeamInfo> {
+        team::get_guild_team_info(guild_id)
+    }
+
+    fn config(&self, guild_id: &GuildId) -> BotConfig {
+        utils::load_config(self.0, guild_id)
+    }
+
+    fn request_metadata(&self, guild_id: &GuildId, request_id: u16) -> Option<RequestMetadata> {
+        requestrecord::get_metadata(guild_id, request_id)
+    }
+
+    fn save_student(&self, student: &Student) {
+        student.save();
+    }
+
+    fn save_team(&self, team: &Team) {
+        team.save();
+    }
+
+    fn save_team_info(&self, info: &GuildTeamInfo) {
+        info.save();
+    }
+
+    fn add_team(&self, team: &Team) {
+        team.save();
+        team.register_id();
+    }
+
+    fn delete_team(&self, team: &Team) {
+        team.delete(self.0);
+    }
+
+    fn record_event(&self, guild_id: &GuildId, event: Event) {
+        events::record(guild_id, event);
+    }
+}
+
+/// Reasons for a team operation to be rejected.
+#[derive(Debug, PartialEq)]
+pub enum TeamError {
+    /// The student is already in a team in the guild.
+    AlreadyInTeam,
+    /// More students were invited than fit in a team, besides its creator.
+    TooManyInvites { max: u8 },
+    /// The student was not invited to the team.
+    NotInvited,
+    /// The team is confirmed, so its members can no longer change.
+    Definitive,
+    /// The team is confirmed, so no one else can be invited to it.
+    ClosedToInvites,
+    /// The team already has as many members as allowed (see `BotConfig::team_capacity`).
+    Full { members: usize, capacity: u8 },
+    /// The guild's teams have no room for any member (see `BotConfig::team_capacity`).
+    NoCapacity,
+}
+
+impl TeamError {
+    /// Message to reply to the student with, in the given language.
+    pub fn message(&self, lang: Language) -> String {
+        match self {
+            TeamError::AlreadyInTeam => tr!(
+                lang,
+                "You are already in a team in this server.",
+                "Ya estás en un equipo en este servidor."
+            ),
+            TeamError::TooManyInvites { max } => tr!(
+                lang,
+                "You can only invite up to {} other student(s) to the team.",
+                "Solo puedes invitar al equipo a {} estudiante(s) más.",
+                max
+            ),
+            TeamError::NotInvited => tr!(
+                lang,
+                "You were not invited to that team.",
+                "No te han invitado a ese equipo."
+            ),
+            TeamError::Definitive => tr!(
+                lang,
+                "You can no longer leave your team, as it is definitive.",
+                "Ya no puedes dejar tu equipo, pues es definitivo."
+            ),
+            TeamError::ClosedToInvites => tr!(
+                lang,
+                "You can no longer invite other students to your team, as it is definitive.",
+                "Ya no puedes invitar a otros estudiantes a tu equipo, pues es definitivo."
+            ),
+            TeamError::Full { members, capacity } => tr!(
+                lang,
+                "That team is already full, with {} of {} member(s).",
+                "Ese equipo ya está completo, con {} de {} miembro(s).",
+                members,
+                capacity
+            ),
+            TeamError::NoCapacity => tr!(
+                lang,
+                "Teams cannot be created in this server, as their capacity is 0.",
+                "No se pueden crear equipos en este servidor, pues su capacidad es 0."
+            ),
+        }
+    }
+}
+
+/// Students that could not be invited to a team.
+#[derive(Debug, PartialEq)]
+pub enum SkippedInvite {
+    /// The creator of the team invited themself.
+    Themself,
+    /// The student is already in another team.
+    InTeam(UserId),
+}
+
+/// The students actually invited, out of the ones the student tried to invite, given the ones that
+/// could not be.
+pub fn invited(student: &Student, others: &[UserId], skipped: &[SkippedInvite]) -> Vec<UserId> {
+    others
+        .iter()
+        .filter(|other| {
+            **other != student.id() && !skipped.contains(&SkippedInvite::InTeam(**other))
+        })
+        .copied()
+        .collect()
+}
+
+impl SkippedInvite {
+    /// Message to reply to the student with, in the given language.
+    pub fn message(&self, lang: Language) -> String {
+        match self {
+            SkippedInvite::Themself => tr!(
+                lang,
+                "You cannot invite yourself to your own team.",
+                "No puedes invitarte a ti mismo a tu propio equipo."
+            ),
+            SkippedInvite::InTeam(id) => tr!(
+                lang,
+                "<@{}> is already in a team in this server.",
+                "<@{}> ya está en un equipo en este servidor.",
+                id
+            ),
+        }
+    }
+}
+
+/// Creates a new team in the guild with the student, and invites the other students to it.
+///
+/// Returns the new team, and the students that could not be invited.
+pub fn create_team(
+    storage: &impl Storage,
+    guild_id: GuildId,
+    student: &mut Student,
+    others: &[UserId],
+) -> Result<(Team, Vec<SkippedInvite>), TeamError> {
+    if student.get_team_id(&guild_id).is_some() {
+        return Err(TeamError::AlreadyInTeam);
+    }
+
+    // Check the amount of invited students do not exceed the allowed team size:
+    let config = storage.config(&guild_id);
+    let max = config
+        .team_capacity
+        .checked_sub(1)
+        .ok_or(TeamError::NoCapacity)?;
+    if others.len() > max as usize {
+        return Err(TeamError::TooManyInvites { max });
+    }
+
+    // Collect the students that can be invited:
+    let mut skipped = Vec::new();
+    let mut students_to_invite = Vec::with_capacity(others.len());
+    for other in others {
+        if *other == student.id() {
+            skipped.push(SkippedInvite::Themself);
+            continue;
+        }
+
+        let other_student = storage
+            .student(other)
+            .unwrap_or_else(|| panic!("[service] Could not find student {} in the system.", other));
+        if other_student.get_team_id(&guild_id).is_some() {
+            skipped.push(SkippedInvite::InTeam(*other));
+            continue;
+        }
+
+        students_to_invite.push(other_student);
+    }
+
+    // Create the team, with the guild's team info (creating it, if it does not exist):
+    let mut info = storage
+        .team_info(&guild_id)
+        .unwrap_or_else(|| GuildTeamInfo::empty(guild_id, config.team_prefix));
+    let team_id = info.next_team_id(|team_id| storage.team(&guild_id, team_id).is_some());
+    storage.save_team_info(&info);
+    let mut team = Team::with_id(&info, team_id);
+    team.insert_member(student);
+    storage.add_team(&team);
+    storage.save_student(student);
+    storage.record_event(
+        &guild_id,
+        Event::TeamJoined {
+            team: team.id().clone(),
+            user: student.id(),
+        },
+    );
+
+    // Send the invitations:
+    for mut other_student in students_to_invite {
+        teamrequest::create(
+            &mut other_student,
+            guild_id,
+            team.id().clone(),
+            student.id(),
+            None,
+        );
+        storage.save_student(&other_student);
+    }
+
+    Ok((team, skipped))
+}
+
+/// Invites other students to the student's team, as long as it is not confirmed and they fit in it,
+/// with an optional message for them.
+///
+/// Returns the students that could not be invited.
+pub fn invite_to_team(
+    storage: &impl Storage,
+    team: &Team,
+    student: &Student,
+    others: &[UserId],
+    message: Option<String>,
+) -> Result<Vec<SkippedInvite>, TeamError> {
+    if team.confirmed() {
+        return Err(TeamError::ClosedToInvites);
+    }
+
+    // Check the amount of invited students do not exceed the allowed team size:
+    let guild_id = *team.guild();
+    let config = storage.config(&guild_id);
+    // FIXME MINOR: This does not account for already existing invitations.
+    let remaining_capacity = (config.team_capacity as usize).saturating_sub(team.members().len());
+    if others.len() > remaining_capacity {
+        return Err(TeamError::TooManyInvites {
+            max: remaining_capacity as u8,
+        });
+    }
+
+    // Send the invitations to the students that can be invited:
+    let mut skipped = Vec::new();
+    for other in others {
+        if *other == student.id() {
+            skipped.push(SkippedInvite::Themself);
+            continue;
+        }
+
+        let mut other_student = storage
+            .student(other)
+            .unwrap_or_else(|| panic!("[service] Could not find student {} in the system.", other));
+        if other_student.get_team_id(&guild_id).is_some() {
+            skipped.push(SkippedInvite::InTeam(*other));
+            continue;
+        }
+
+        teamrequest::create(
+            &mut other_student,
+            guild_id,
+            team.id().clone(),
+            student.id(),
+            message.clone(),
+        );
+        storage.save_student(&other_student);
+    }
+
+    Ok(skipped)
+}
+
+/// Makes the student join a team of the guild they were invited to.
+pub fn join_team(
+    storage: &impl Storage,
+    guild_id: GuildId,
+    student: &mut Student,
+    team_id: &String,
+) -> Result<Team, TeamError> {
+    if student.get_team_id(&guild_id).is_some() {
+        return Err(TeamError::AlreadyInTeam);
+    }
+
+    if !teamrequest::is_invited(student, &guild_id, team_id) {
+        return Err(TeamError::NotInvited);
+    }
+
+    let mut team = storage.team(&guild_id, team_id).unwrap_or_else(|| {
+        panic!(
+            "[service] Could not find team with ID {} in guild {} in the system.",
+            team_id, guild_id
+        )
+    });
+    check_capacity(storage, &team)?;
+    if team.insert_member(student) {
+        storage.save_student(student);
+        storage.save_team(&team);
+        storage.record_event(
+            &guild_id,
+            Event::TeamJoined {
+                team: team.id().clone(),
+                user: student.id(),
+            },
+        );
+    }
+
+    Ok(team)
+}
+
+/// Checks there is room in the team for another member (see `BotConfig::team_capacity`).
+pub fn check_capacity(storage: &impl Storage, team: &Team) -> Result<(), TeamError> {
+    let capacity = storage.config(team.guild()).team_capacity;
+    let members = team.members().len();
+    if members >= capacity as usize {
+        return Err(TeamError::Full { members, capacity });
+    }
+
+    Ok(())
+}
+
+/// Makes the student leave their team, unless it is definitive. The team is deleted if it is
+/// emptied out.
+pub fn leave_team(
+    storage: &impl Storage,
+    team: &mut Team,
+    student: &mut Student,
+) -> Result<(), TeamError> {
+    if team.confirmed() {
+        return Err(TeamError::Definitive);
+    }
+
+    if !team.take_member(student) {
+        return Ok(());
+    }
+    storage.save_student(student);
+    storage.record_event(
+        team.guild(),
+        Event::TeamLeft {
+            team: team.id().clone(),
+            user: student.id(),
+        },
+    );
+    if team.members().is_empty() {
+        storage.delete_team(team);
+    } else {
+        storage.save_team(team);
+    }
+
+    Ok(())
+}
+
+/// Reasons for the arguments of a request to be rejected.
+#[derive(Debug, PartialEq)]
+pub enum RequestError {
+    /// The last command was asked for (with `l`), but there is none.
+    NoLastCommand,
+    /// The arguments could not be split (e.g. an unclosed quote).
+    InvalidArgs(String),
+    /// No queue was given, the student has no preferred one, and the guild has no default one. The
+    /// guild's queues are listed, so the student can choose one (see `BotConfig::queues`).
+    NoQueue { available: Vec<String> },
+}
+
+impl RequestError {
+    /// Message to reply to the student with, in the given language.
+    pub fn message(&self, lang: Language) -> String {
+        match self {
+            RequestError::NoLastCommand => tr!(
+                lang,
+                "**Error:** Can't send request without all arguments, as there is no previous command.",
+                "**Error:** No se puede enviar la petición sin todos los argumentos, pues no hay ningún comando anterior."
+            ),
+            RequestError::InvalidArgs(reason) => tr!(
+                lang,
+                "**Error:** Invalid arguments: {}.",
+                "**Error:** Argumentos no válidos: {}.",
+                reason
+            ),
+            RequestError::NoQueue { available } => {
+                let mut message = tr!(
+                    lang,
+                    "**Error:** Can't send request, as no queue was specified, and no preferred was set. \
+                    Set one with `/settings set_queue`.",
+                    "**Error:** No se puede enviar la petición, pues no se indicó ninguna cola, ni hay una preferida. \
+                    Elige una con `/settings set_queue`."
+                );
+                if !available.is_empty() {
+                    let queues = available
+                        .iter()
+                        .map(|queue| format!("`{}`", queue))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    message.push_str(&tr!(
+                        lang,
+                        " Available queues: {}.",
+                        " Colas disponibles: {}.",
+                        queues
+                    ));
+                }
+                message
+            }
+        }
+    }
+}
+
+/// Names of the guild's queues in Tablón (see `BotConfig::queues`).
+pub fn guild_queues(config: &BotConfig) -> Vec<String> {
+    config
+        .queues
+        .split(',')
+        .map(str::trim)
+        .filter(|queue| !queue.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Builds the arguments of a request to Tablón from the ones given by the student (`l` standing for
+/// their last command), adding their preferred queue (or else the guild's default one) if none was
+/// given.
+///
+/// Returns the arguments, and their equivalent command-line string.
+pub fn request_args(
+    student: &Student,
+    guild_id: &GuildId,
+    config: &BotConfig,
+    extra_args: Option<String>,
+) -> Result<(Vec<String>, String), RequestError> {
+    let extra_args = match extra_args {
+        Some(given_args) if given_args == "l" => student
+            .get_last_command(guild_id)
+            .cloned()
+            .ok_or(RequestError::NoLastCommand)?,
+        Some(given_args) => given_args,
+        None => String::new(),
+    };
+
+    let mut args = tablonclient::split_args(&extra_args).map_err(RequestError::InvalidArgs)?;
+
+    // Add the queue to send the request to:
+    if !args.iter().any(|arg| arg == "-q") {
+        let queue = student
+            .get_preferred_queue(guild_id)
+            .or(Some(&config.default_queue).filter(|queue| !queue.is_empty()))
+            .ok_or_else(|| RequestError::NoQueue {
+                available: guild_queues(config),
+            })?;
+        args.splice(0..0, ["-q".to_string(), queue.clone()]);
+    }
+    let extra_args = tablonclient::join_args(&args);
+
+    Ok((args, extra_args))
+}
+
+/// Reasons for a request to be rejected by its team's budget (see `team::SubmissionBudget`).
+#[derive(Debug, PartialEq)]
+pub enum BudgetError {
+    /// The team sent a request too recently; it can send another one at the given UNIX timestamp
+    /// (in seconds). The member who sent the last one is kept, so the others know.
+    Cooldown { until: i64, last_by: Option<UserId> },
+    /// The team already sent the maximum number of requests for the day.
+    DailyLimit { max: u16 },
+}
+
+impl BudgetError {
+    /// Message to reply to the student with, in the given language.
+    pub fn message(&self, lang: Language) -> String {
+        match self {
+            BudgetError::Cooldown {
+                until,
+                last_by: Some(user),
+            } => tr!(
+                lang,
+                "**Error:** <@{}> sent a request for your team recently. Your team can send \
+                another one <t:{}:R>.",
+                "**Error:** <@{}> envió una petición de tu equipo hace poco. Tu equipo puede \
+                enviar otra <t:{}:R>.",
+                user,
+                until
+            ),
+            BudgetError::Cooldown {
+                until,
+                last_by: None,
+            } => tr!(
+                lang,
+                "**Error:** Your team sent a request recently. It can send another one <t:{}:R>.",
+                "**Error:** Tu equipo envió una petición hace poco. Puede enviar otra <t:{}:R>.",
+                until
+            ),
+            BudgetError::DailyLimit { max } => tr!(
+                lang,
+                "**Error:** Your team has already sent the {} request(s) allowed today.",
+                "**Error:** Tu equipo ya ha enviado las {} petición(es) permitidas hoy.",
+                max
+            ),
+        }
+    }
+}
+
+/// Day (as `YYYY-MM-DD`, in the guild's timezone) of a UNIX timestamp (in seconds), for counting
+/// the daily requests of the teams.
+pub fn budget_day(config: &BotConfig, timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .unwrap_or_default()
+        .with_timezone(&utils::config_timezone(config))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Checks whether a team can send a request to Tablón at the given UNIX timestamp (in seconds),
+/// according to the guild's cooldown and daily limit, which are shared by all its members. During
+/// the surge protection before a deadline, the cooldown is at least `BotConfig::surge_cooldown`.
+pub fn check_budget(
+    team: &Team,
+    config: &BotConfig,
+    now: i64,
+    surge: bool,
+) -> Result<(), BudgetError> {
+    let cooldown = if surge {
+        config.team_cooldown.max(config.surge_cooldown)
+    } else {
+        config.team_cooldown
+    };
+    let budget = team.submissions();
+    if let Some(last) = budget.last() {
+        let until = last + cooldown as i64;
+        if cooldown > 0 && now < until {
+            return Err(BudgetError::Cooldown {
+                until,
+                last_by: budget.last_by(),
+            });
+        }
+    }
+
+    let max = config.team_daily_requests;
+    if max > 0 && budget.count_on(&budget_day(config, now)) >= max {
+        return Err(BudgetError::DailyLimit { max });
+    }
+
+    Ok(())
+}
+
+/// Reserves a request of a member in the team's budget at the given UNIX timestamp (in seconds),
+/// before sending it, if the team can still send one (see `check_budget`). The team is read again
+/// while holding `lock`, so members sending requests at the same time cannot all pass the checks.
+///
+/// Returns the team's budget before the request, to release it if it is not sent after all (see
+/// `release_submission`).
+pub fn reserve_submission(
+    storage: &impl Storage,
+    lock: &Mutex<()>,
+    team: &mut Team,
+    user: UserId,
+    now: i64,
+    surge: bool,
+) -> Result<SubmissionBudget, BudgetError> {
+    let _lock = lock.lock().expect("The budget lock was poisoned.");
+    if let Some(saved) = storage.team(team.guild(), team.id()) {
+        *team = saved;
+    }
+
+    let config = storage.config(team.guild());
+    check_budget(team, &config, now, surge)?;
+    let previous = team.record_submission(user, now, budget_day(&config, now));
+    storage.save_team(team);
+
+    Ok(previous)
+}
+
+/// Releases a request reserved by `reserve_submission` at the given UNIX timestamp (in seconds)
+/// that was not sent after all, given the team's budget before it.
+pub fn release_submission(
+    storage: &impl Storage,
+    lock: &Mutex<()>,
+    team: &mut Team,
+    user: UserId,
+    reserved_at: i64,
+    previous: &SubmissionBudget,
+) {
+    let _lock = lock.lock().expect("The budget lock was poisoned.");
+    if let Some(saved) = storage.team(team.guild(), team.id()) {
+        *team = saved;
+    }
+
+    let day = budget_day(&storage.config(team.guild()), reserved_at);
+    team.release_submission(user, reserved_at, &day, previous);
+    storage.save_team(team);
+}
+
+/// Number of requests a team can still send on the day of the given UNIX timestamp (in seconds),
+/// if the guild limits them.
+pub fn remaining_requests(team: &Team, config: &BotConfig, now: i64) -> Option<u16> {
+    let max = config.team_daily_requests;
+    if max == 0 {
+        return None;
+    }
+
+    Some(max.saturating_sub(team.submissions().count_on(&budget_day(config, now))))
+}
+
+/// Reasons for a student not to be able to send requests to Tablón (see `submission_blockers`).
+#[derive(Debug, PartialEq)]
+pub enum SubmissionBlocker {
+    /// The student has never used the bot, so it knows nothing about them.
+    NotRegistered,
+    /// The student is not in any team of the guild.
+    NoTeam,
+    /// The student's team has not been confirmed yet.
+    Unconfirmed { team: String },
+    /// The student's team has no password for Tablón.
+    NoPassword { team: String },
+    /// The student has no preferred queue, and the guild has no default one.
+    NoQueue { available: Vec<String> },
+    /// The student's team is over its budget of requests.
+    Budget(BudgetError),
+}
+
+impl SubmissionBlocker {
+    /// Description of the problem for the guild's admins, with a suggested fix, in the given
+    /// language.
+    pub fn message(&self, lang: Language) -> String {
+        match self {
+            SubmissionBlocker::NotRegistered => tr!(
+                lang,
+                "They have never used the bot. They must use any of its commands first (e.g. `/help`).",
+                "Nunca ha usado el bot. Tiene que usar antes cualquiera de sus comandos (p. ej. `/help`)."
+            ),
+            SubmissionBlocker::NoTeam => tr!(
+                lang,
+                "They are not in any team. Add them to one with `/teamedit add`.",
+                "No está en ningún equipo. Añádele a uno con `/teamedit add`."
+            ),
+            SubmissionBlocker::Unconfirmed { team } => tr!(
+                lang,
+                "Their team `{}` is not confirmed. Confirm it with `/teamedit confirm`.",
+                "Su equipo `{}` no está confirmado. Confírmalo con `/teamedit confirm`.",
+                team
+            ),
+            SubmissionBlocker::NoPassword { team } => tr!(
+                lang,
+                "Their team `{}` has no password for Tablón. Set it with `/teamedit password`, or \
+                for all the teams with `/passwords`.",
+                "Su equipo `{}` no tiene contraseña de Tablón. Establécela con `/teamedit password`, \
+                o para todos los equipos con `/passwords`.",
+                team
+            ),
+            SubmissionBlocker::NoQueue { available } => {
+                let mut message = tr!(
+                    lang,
+                    "They have no preferred queue, and the guild has no default one. They can choose \
+                    one with `/settings set_queue`, or set a default with `/botconfig default_queue`.",
+                    "No tiene cola preferida, y el servidor no tiene una por defecto. Puede elegir una \
+                    con `/settings set_queue`, o puedes establecer una por defecto con \
+                    `/botconfig default_queue`."
+                );
+                if !available.is_empty() {
+                    let queues = available
+                        .iter()
+                        .map(|queue| format!("`{}`", queue))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    message.push_str(&tr!(
+                        lang,
+                        " Available queues: {}.",
+                        " Colas disponibles: {}.",
+                        queues
+                    ));
+                }
+                message
+            }
+            SubmissionBlocker::Budget(BudgetError::Cooldown { until, .. }) => tr!(
+                lang,
+                "Their team sent a request recently. It can send another one <t:{}:R>.",
+                "Su equipo envió una petición hace poco. Puede enviar otra <t:{}:R>.",
+                until
+            ),
+            SubmissionBlocker::Budget(BudgetError::DailyLimit { max }) => tr!(
+                lang,
+                "Their team has already sent the {} request(s) allowed today. Raise the limit with \
+                `/botconfig team_daily_requests`, if needed.",
+                "Su equipo ya ha enviado las {} petición(es) permitidas hoy. Aumenta el límite con \
+                `/botconfig team_daily_requests`, si es necesario.",
+                max
+            ),
+        }
+    }
+}
+
+/// Lists everything keeping a student from sending requests to Tablón at the given UNIX timestamp
+/// (in seconds), with the same checks as `/request` (see `request_args` and `check_budget`).
+///
+/// Returns an empty list if the student can send requests.
+pub fn submission_blockers(
+    storage: &impl Storage,
+    guild_id: &GuildId,
+    user_id: &UserId,
+    now: i64,
+    surge: bool,
+) -> Vec<SubmissionBlocker> {
+    let Some(student) = storage.student(user_id) else {
+        return vec![SubmissionBlocker::NotRegistered];
+    };
+    let config = storage.config(guild_id);
+
+    let mut blockers = Vec::new();
+    match student
+        .get_team_id(guild_id)
+        .and_then(|team_id| storage.team(guild_id, &team_id))
+    {
+        Some(team) => {
+            if !team.confirmed() {
+                blockers.push(SubmissionBlocker::Unconfirmed {
+                    team: team.id().clone(),
+                });
+            }
+            let password = student
+                .get_credentials(guild_id)
+                .and_then(|credentials| credentials.password().clone());
+            if password.is_none() {
+                blockers.push(SubmissionBlocker::NoPassword {
+                    team: team.id().clone(),
+                });
+            }
+            if let Err(e) = check_budget(&team, &config, now, surge) {
+                blockers.push(SubmissionBlocker::Budget(e));
+            }
+        }
+        None => blockers.push(SubmissionBlocker::NoTeam),
+    }
+    if let Err(RequestError::NoQueue { available }) =
+        request_args(&student, guild_id, &config, None)
+    {
+        blockers.push(SubmissionBlocker::NoQueue { available });
+    }
+
+    blockers
+}
+
+/// Finds a request sent by any member of the team since the given UNIX timestamp (in seconds), with
+/// the same program (by its hash, see `utils::content_hash`) and arguments, if any.
+///
+/// Returns the most recent one.
+pub fn find_duplicate(
+    storage: &impl Storage,
+    guild_id: &GuildId,
+    team: &Team,
+    hash: &str,
+    args: &str,
+    since: i64,
+) -> Option<RequestMetadata> {
+    team.members()
+        .iter()
+        .filter_map(|member| storage.student(member))
+        .flat_map(|member| member.request_history().get(guild_id).cloned())
+        .flatten()
+        .filter(|req| req.timestamp().is_some_and(|timestamp| timestamp >= since))
+        .filter_map(|req| storage.request_metadata(guild_id, req.id()))
+        .filter(|metadata| metadata.hash() == hash && metadata.args() == args)
+        .max_by_key(|metadata| metadata.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, collections::HashMap};
+
+    const GUILD: GuildId = GuildId::new(1);
+
+    /// Storage keeping everything in memory, with a single configuration for every guild.
+    #[derive(Default)]
+    struct MemoryStorage {
+        config: BotConfig,
+        students: RefCell<HashMap<UserId, Student>>,
+        teams: RefCell<HashMap<(GuildId, String), Team>>,
+        infos: RefCell<HashMap<GuildId, GuildTeamInfo>>,
+        events: RefCell<Vec<serde_json::Value>>,
+    }
+
+    impl Storage for MemoryStorage {
+        fn student(&self, id: &UserId) -> Option<Student> {
+            self.students.borrow().get(id).cloned()
+        }
+
+        fn team(&self, guild_id: &GuildId, team_id: &str) -> Option<Team> {
+            self.teams
+                .borrow()
+                .get(&(*guild_id, team_id.to_string()))
+                .cloned()
+        }
+
+        fn team_info(&self, guild_id: &GuildId) -> Option<GuildTeamInfo> {
+            self.infos.borrow().get(guild_id).cloned()
+        }
+
+        fn config(&self, _guild_id: &GuildId) -> BotConfig {
+            self.config.clone()
+        }
+
+        fn request_metadata(
+            &self,
+            _guild_id: &GuildId,
+            _request_id: u16,
+        ) -> Option<RequestMetadata> {
+            None
+        }
+
+        fn save_student(&self, student: &Student) {
+            self.students
+                .borrow_mut()
+                .insert(student.id(), student.clone());
+        }
+
+        fn save_team(&self, team: &Team) {
+            self.teams
+                .borrow_mut()
+                .insert((*team.guild(), team.id().clone()), team.clone());
+        }
+
+        fn save_team_info(&self, info: &GuildTeamInfo) {
+            self.infos
+                .borrow_mut()
+                .insert(info.guild_id(), info.clone());
+        }
+
+        fn add_team(&self, team: &Team) {
+            self.save_team(team);
+        }
+
+        fn delete_team(&self, team: &Team) {
+            self.teams
+                .borrow_mut()
+                .remove(&(*team.guild(), team.id().clone()));
+            let mut info = self.team_info(team.guild()).unwrap();
+            info.release(team.id(), false).unwrap();
+            self.save_team_info(&info);
+        }
+
+        fn record_event(&self, _guild_id: &GuildId, event: Event) {
+            self.events
+                .borrow_mut()
+                .push(serde_json::to_value(event).unwrap());
+        }
+    }
+
+    impl MemoryStorage {
+        fn with_capacity(team_capacity: u8) -> Self {
+            Self {
+                config: BotConfig {
+                    team_capacity,
+                    ..BotConfig::default()
+                },
+                ..Self::default()
+            }
+        }
+
+        /// Registers a new student, returning them.
+        fn new_student(&self, id: u64) -> Student {
+            let student = Student::blank(UserId::new(id), format!("student{}", id));
+            self.save_student(&student);
+            student
+        }
+
+        /// Types of the recorded events, in order.
+        fn event_types(&self) -> Vec<String> {
+            self.events
+                .borrow()
+                .iter()
+                .map(|event| event["type"].as_str().unwrap().to_string())
+                .collect()
+        }
+    }
+
+    /// Marks a team as confirmed, as `Team::confirm` would without saving it.
+    fn confirmed(team: &Team) -> Team {
+        let mut json = serde_json::to_value(team).unwrap();
+        json["confirmed"] = serde_json::Value::Bool(true);
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn create_team_invites_others() {
+        let storage = MemoryStorage::with_capacity(3);
+        let mut creator = storage.new_student(1);
+        storage.new_student(2);
+        let mut in_team = storage.new_student(3);
+        in_team.set_team(GUILD, "g99".to_string(), None);
+        storage.save_student(&in_team);
+
+        let others = [UserId::new(1), UserId::new(2)];
+        let (team, skipped) = create_team(&storage, GUILD, &mut creator, &others).unwrap();
+        assert_eq!(skipped, vec![SkippedInvite::Themself]);
+        assert_eq!(team.id(), "g01");
+        assert!(team.members().contains(&UserId::new(1)));
+        assert_eq!(creator.get_team_id(&GUILD).as_deref(), Some("g01"));
+
+        // Everything was saved through the storage:
+        assert!(storage.team(&GUILD, team.id()).is_some());
+        assert_eq!(storage.team_info(&GUILD).unwrap().count(), 1);
+        let saved = storage.student(&UserId::new(1)).unwrap();
+        assert_eq!(saved.get_team_id(&GUILD).as_deref(), Some("g01"));
+        let invited = storage.student(&UserId::new(2)).unwrap();
+        assert!(teamrequest::is_invited(&invited, &GUILD, "g01"));
+        assert_eq!(storage.event_types(), vec!["team_joined"]);
+
+        // Students in a team cannot be invited:
+        let mut creator = storage.new_student(4);
+        let (team, skipped) =
+            create_team(&storage, GUILD, &mut creator, &[UserId::new(3)]).unwrap();
+        assert_eq!(skipped, vec![SkippedInvite::InTeam(UserId::new(3))]);
+        assert_eq!(team.id(), "g02");
+    }
+
+    #[test]
+    fn create_team_rejections() {
+        let storage = MemoryStorage::with_capacity(2);
+        let mut creator = storage.new_student(1);
+        storage.new_student(2);
+        storage.new_student(3);
+
+        assert_eq!(
+            create_team(
+                &storage,
+                GUILD,
+                &mut creator,
+                &[UserId::new(2), UserId::new(3)]
+            )
+            .unwrap_err(),
+            TeamError::TooManyInvites { max: 1 }
+        );
+        assert!(storage.team_info(&GUILD).is_none());
+
+        create_team(&storage, GUILD, &mut creator, &[]).unwrap();
+        assert_eq!(
+            create_team(&storage, GUILD, &mut creator, &[]).unwrap_err(),
+            TeamError::AlreadyInTeam
+        );
+    }
+
+    #[test]
+    fn create_team_with_no_capacity() {
+        let storage = MemoryStorage::with_capacity(0);
+        let mut creator = storage.new_student(1);
+
+        assert_eq!(
+            create_team(&storage, GUILD, &mut creator, &[]).unwrap_err(),
+            TeamError::NoCapacity
+        );
+        assert!(storage.events.borrow().is_empty());
+    }
+
+    #[test]
+    fn create_team_with_capacity_one() {
+        let storage = MemoryStorage::with_capacity(1);
+        let mut creator = storage.new_student(1);
+        storage.new_student(2);
+
+        assert_eq!(
+            create_team(&storage, GUILD, &mut creator, &[UserId::new(2)]).unwrap_err(),
+            TeamError::TooManyInvites { max: 0 }
+        );
+        let (team, _) = create_team(&storage, GUILD, &mut creator, &[]).unwrap();
+        assert_eq!(
+            check_capacity(&storage, &team).unwrap_err(),
+            TeamError::Full {
+                members: 1,
+                capacity: 1
+            }
+        );
+    }
+
+    #[test]
+    fn join_team_when_invited() {
+        let storage = MemoryStorage::with_capacity(2);
+        let mut creator = storage.new_student(1);
+        storage.new_student(2);
+        let mut stranger = storage.new_student(3);
+        create_team(&storage, GUILD, &mut creator, &[UserId::new(2)]).unwrap();
+
+        assert_eq!(
+            join_team(&storage, GUILD, &mut stranger, &"g01".to_string()).unwrap_err(),
+            TeamError::NotInvited
+        );
+
+        let mut invited = storage.student(&UserId::new(2)).unwrap();
+        let team = join_team(&storage, GUILD, &mut invited, &"g01".to_string()).unwrap();
+        assert_eq!(team.members().len(), 2);
+        assert_eq!(storage.team(&GUILD, team.id()).unwrap().members().len(), 2);
+        let saved = storage.student(&UserId::new(2)).unwrap();
+        assert_eq!(saved.get_team_id(&GUILD).as_deref(), Some("g01"));
+        assert!(!teamrequest::is_invited(&saved, &GUILD, "g01"));
+        assert_eq!(storage.event_types(), vec!["team_joined", "team_joined"]);
+
+        assert_eq!(
+            join_team(&storage, GUILD, &mut invited, &"g01".to_string()).unwrap_err(),
+            TeamError::AlreadyInTeam
+        );
+    }
+
+    #[test]
+    fn join_full_team() {
+        let storage = MemoryStorage::with_capacity(2);
+        let mut creator = storage.new_student(1);
+        storage.new_student(2);
+        storage.new_student(3);
+        let (team, _) = create_team(&storage, GUILD, &mut creator, &[UserId::new(2)]).unwrap();
+
+        // Invitations are not limited by the ones already sent:
+        invite_to_team(&storage, &team, &creator, &[UserId::new(3)], None).unwrap();
+        let mut invited = storage.student(&UserId::new(2)).unwrap();
+        join_team(&storage, GUILD, &mut invited, &"g01".to_string()).unwrap();
+
+        let mut late = storage.student(&UserId::new(3)).unwrap();
+        assert!(teamrequest::is_invited(&late, &GUILD, "g01"));
+        assert_eq!(
+            join_team(&storage, GUILD, &mut late, &"g01".to_string()).unwrap_err(),
+            TeamError::Full {
+                members: 2,
+                capacity: 2
+            }
+        );
+        assert!(late.get_team_id(&GUILD).is_none());
+        assert_eq!(storage.team(&GUILD, team.id()).unwrap().members().len(), 2);
+    }
+
+    #[test]
+    fn leave_team_deletes_empty_teams() {
+        let storage = MemoryStorage::with_capacity(2);
+        let mut creator = storage.new_student(1);
+        storage.new_student(2);
+        create_team(&storage, GUILD, &mut creator, &[UserId::new(2)]).unwrap();
+        let mut member = storage.student(&UserId::new(2)).unwrap();
+        let mut team = join_team(&storage, GUILD, &mut member, &"g01".to_string()).unwrap();
+
+        leave_team(&storage, &mut team, &mut member).unwrap();
+        assert_eq!(team.members().len(), 1);
+        assert_eq!(storage.team(&GUILD, team.id()).unwrap().members().len(), 1);
+        assert!(storage
+            .student(&UserId::new(2))
+            .unwrap()
+            .get_team_id(&GUILD)
+            .is_none());
+
+        // Leaving twice changes nothing:
+        leave_team(&storage, &mut team, &mut member).unwrap();
+        assert_eq!(storage.event_types().len(), 3);
+
+        leave_team(&storage, &mut team, &mut creator).unwrap();
+        assert!(storage.team(&GUILD, team.id()).is_none());
+        assert_eq!(storage.team_info(&GUILD).unwrap().holes(), &["g01"]);
+        assert_eq!(
+            storage.event_types(),
+            vec!["team_joined", "team_joined", "team_left", "team_left"]
+        );
+
+        // The identifier of the deleted team is reused:
+        let (team, _) = create_team(&storage, GUILD, &mut creator, &[]).unwrap();
+        assert_eq!(team.id(), "g01");
+    }
+
+    #[test]
+    fn leave_definitive_team() {
+        let storage = MemoryStorage::with_capacity(2);
+        let mut creator = storage.new_student(1);
+        let (team, _) = create_team(&storage, GUILD, &mut creator, &[]).unwrap();
+        let mut team = confirmed(&team);
+
+        assert_eq!(
+            leave_team(&storage, &mut team, &mut creator).unwrap_err(),
+            TeamError::Definitive
+        );
+        assert!(team.members().contains(&creator.id()));
+        assert_eq!(
+            invite_to_team(&storage, &team, &creator, &[UserId::new(2)], None).unwrap_err(),
+            TeamError::ClosedToInvites
+        );
+    }
+
+    #[test]
+    fn concurrent_submissions_are_reserved_once() {
+        let storage = MemoryStorage {
+            config: BotConfig {
+                team_capacity: 2,
+                team_cooldown: 60,
+                ..BotConfig::default()
+            },
+            ..MemoryStorage::default()
+        };
+        let lock = Mutex::new(());
+        let mut creator = storage.new_student(1);
+        let (team, _) = create_team(&storage, GUILD, &mut creator, &[]).unwrap();
+
+        // Both members checked the team's budget before either sent their request:
+        let (mut first, mut second) = (team.clone(), team);
+        let previous =
+            reserve_submission(&storage, &lock, &mut first, UserId::new(1), 1000, false).unwrap();
+        assert_eq!(
+            reserve_submission(&storage, &lock, &mut second, UserId::new(2), 1010, false)
+                .unwrap_err(),
+            BudgetError::Cooldown {
+                until: 1060,
+                last_by: Some(UserId::new(1))
+            }
+        );
+
+        // Once released (e.g. as the client failed), the team can send another one:
+        release_submission(&storage, &lock, &mut first, UserId::new(1), 1000, &previous);
+        assert_eq!(first.submissions().count(), 0);
+        assert_eq!(first.submissions().last(), None);
+        reserve_submission(&storage, &lock, &mut second, UserId::new(2), 1010, false).unwrap();
+        let saved = storage.team(&GUILD, "g01").unwrap();
+        assert_eq!(saved.submissions().count(), 1);
+        assert_eq!(saved.submissions().last_by(), Some(UserId::new(2)));
+    }
+
+    #[test]
+    fn released_submissions_keep_later_ones() {
+        let storage = MemoryStorage::with_capacity(2);
+        let lock = Mutex::new(());
+        let mut creator = storage.new_student(1);
+        let (mut team, _) = create_team(&storage, GUILD, &mut creator, &[]).unwrap();
+
+        let previous =
+            reserve_submission(&storage, &lock, &mut team, UserId::new(1), 1000, false).unwrap();
+        reserve_submission(&storage, &lock, &mut team, UserId::new(2), 1005, false).unwrap();
+        release_submission(&storage, &lock, &mut team, UserId::new(1), 1000, &previous);
+
+        let saved = storage.team(&GUILD, "g01").unwrap();
+        assert_eq!(saved.submissions().count(), 1);
+        assert_eq!(saved.submissions().last(), Some(1005));
+        assert_eq!(saved.submissions().last_by(), Some(UserId::new(2)));
+    }
+}
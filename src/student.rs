@@ -15,34 +15,118 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{teamrequest::TeamRequest, Credentials};
+use crate::{
+    alerts::{self, AlertKind},
+    cache::Caches,
+    events::{self, Event},
+    i18n::Language,
+    requestrecord::{self, RequestRecord},
+    teamnotify::TeamNotifications,
+    teamrequest::{self, TeamRequest},
+    utils::{self, BotConfig},
+    Credentials,
+};
 use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
 use serenity::all::{GuildId, UserId};
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::Path,
+};
 
 /// Data structure defining a student and its preferences / configuration in the system.
+///
+/// Only the fields shared by every guild are saved in the student's file, along with the guilds
+/// that have a file with the rest of the fields (see `GuildSlice`). Files written by older
+/// versions, with every field or without the list of guilds, are still loaded, and rewritten on
+/// load.
 #[cfg_attr(debug_assertions, derive(Debug))]
-#[derive(Serialize, Deserialize, Getters, CopyGetters)]
+#[derive(Clone, Serialize, Deserialize, Getters, CopyGetters)]
 pub struct Student {
     #[getset(get_copy = "pub")]
     id: UserId,
     #[getset(get = "pub")]
     name: String,
+    #[serde(default)]
     credentials: HashMap<GuildId, Credentials>,
+    #[serde(default)]
     preferred_queue: HashMap<GuildId, String>,
+    #[serde(default)]
     last_command: HashMap<GuildId, String>,
+    #[serde(default)]
     team_requests: HashMap<GuildId, Vec<TeamRequest>>,
+    #[serde(default)]
     #[getset(get = "pub")]
-    request_history: HashMap<GuildId, Vec<u16>>,
+    request_history: HashMap<GuildId, Vec<RequestRecord>>,
+    /// The student's login or identifier in the university, set by admins (see `/teamedit roster`).
+    #[serde(default)]
+    #[getset(get = "pub")]
+    university_id: Option<String>,
+    /// Offsets (in hours before each deadline) at which to send deadline reminders, for the guilds
+    /// the student opted in to them.
+    #[serde(default)]
+    reminders: HashMap<GuildId, Vec<u32>>,
+    /// Language chosen by the student for the replies only they can see, per guild.
+    #[serde(default)]
+    language: HashMap<GuildId, Language>,
+    /// How the student wants to be told of the changes in their team, for the guilds they chose a
+    /// way other than the default (see `teamnotify::notify_members`).
+    #[serde(default)]
+    team_notifications: HashMap<GuildId, TeamNotifications>,
+    /// Guilds with a file of the student's data specific to them (see `GuildSlice`), as of the last
+    /// time the student was loaded; `None` if the student's file predates it (see `load_slices`).
+    #[serde(default)]
+    slices: Option<BTreeSet<GuildId>>,
+    /// Guilds whose file for the student could not be loaded, so it is never overwritten.
+    #[serde(skip)]
+    unreadable_slices: BTreeSet<GuildId>,
+    /// Cache the student was loaded from (see `get_student`), kept up to date when it is saved.
+    #[serde(skip)]
+    cache: Option<Caches>,
+}
+
+/// Part of a student's record shared by every guild, saved as `users/<user_id>.json`.
+#[derive(Serialize)]
+struct GlobalRecord<'a> {
+    id: UserId,
+    name: &'a String,
+    university_id: &'a Option<String>,
+    slices: &'a BTreeSet<GuildId>,
+}
+
+/// Part of a student's record specific to a guild, saved as
+/// `guilds/<guild_id>/students/<user_id>.json`, so a corrupt file only affects its guild, and the
+/// guild's data can be removed along with it.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSlice {
+    credentials: Option<Credentials>,
+    preferred_queue: Option<String>,
+    last_command: Option<String>,
+    team_requests: Option<Vec<TeamRequest>>,
+    request_history: Option<Vec<RequestRecord>>,
+    reminders: Option<Vec<u32>>,
+    language: Option<Language>,
+    team_notifications: Option<TeamNotifications>,
 }
 
 impl Student {
     /// Constructor for a student, given a server they are in.
     ///
     /// Every student must be in at least one server.
-    pub fn new(user_id: UserId, user_name: String) -> Student {
-        let res: Student = Self {
+    pub fn new(caches: &Caches, user_id: UserId, user_name: String) -> Student {
+        let mut res = Self::blank(user_id, user_name);
+        res.cache = Some(caches.clone());
+
+        res.save();
+
+        res
+    }
+
+    /// Constructor for a student with no data yet, without saving it (see `new`).
+    pub fn blank(user_id: UserId, user_name: String) -> Student {
+        Self {
             id: user_id,
             name: user_name.clone(),
             // Containers of size 1, because it is not expected for students to be in more than one
@@ -52,108 +136,115 @@ impl Student {
             last_command: HashMap::with_capacity(1),
             team_requests: HashMap::with_capacity(1),
             request_history: HashMap::with_capacity(1),
-        };
-
-        res.save();
-
-        res
+            university_id: None,
+            reminders: HashMap::new(),
+            language: HashMap::new(),
+            team_notifications: HashMap::new(),
+            slices: Some(BTreeSet::new()),
+            unreadable_slices: BTreeSet::new(),
+            cache: None,
+        }
     }
 
     /* Field accessors: */
 
     pub fn get_credentials(&self, guild: &GuildId) -> Option<&Credentials> {
-        self.credentials.get(&guild)
+        self.credentials.get(guild)
     }
 
     #[inline]
     pub fn get_existing_credentials(&self, guild: &GuildId) -> &Credentials {
-        self.credentials.get(&guild).expect(
-            format!(
+        self.credentials.get(guild).unwrap_or_else(|| {
+            panic!(
                 "[Student {}] Could not find credentials for guild {}.",
                 self.name, guild
             )
-            .as_str(),
-        )
+        })
     }
 
     pub fn get_preferred_queue(&self, guild: &GuildId) -> Option<&String> {
-        self.preferred_queue.get(&guild)
+        self.preferred_queue.get(guild)
     }
 
-    #[inline]
-    pub fn get_existing_preferred_queue(&self, guild: &GuildId) -> &String {
-        self.preferred_queue.get(&guild).expect(
-            format!(
-                "[Student {}] Could not find a preferred queue for guild {}.",
-                self.name, guild
-            )
-            .as_str(),
-        )
+    pub fn get_last_command(&self, guild: &GuildId) -> Option<&String> {
+        self.last_command.get(guild)
     }
 
-    pub fn get_last_command(&self, guild: &GuildId) -> Option<&String> {
-        self.last_command.get(&guild)
+    pub fn get_team_requests(&self, guild: &GuildId) -> Option<&Vec<TeamRequest>> {
+        self.team_requests.get(guild)
     }
 
-    #[inline]
-    pub fn get_existing_last_command(&self, guild: &GuildId) -> &String {
-        self.last_command.get(&guild).expect(
-            format!(
-                "[Student {}] Could not find a last command for guild {}.",
-                self.name, guild
-            )
-            .as_str(),
-        )
+    pub fn get_reminders(&self, guild: &GuildId) -> Option<&Vec<u32>> {
+        self.reminders.get(guild)
     }
 
-    pub fn get_team_requests(&self, guild: &GuildId) -> Option<&Vec<TeamRequest>> {
-        self.team_requests.get(&guild)
+    pub fn get_language(&self, guild: &GuildId) -> Option<Language> {
+        self.language.get(guild).copied()
     }
 
-    #[inline]
-    pub fn get_existing_team_requests(&self, guild: &GuildId) -> &Vec<TeamRequest> {
-        self.team_requests.get(&guild).expect(
-            format!(
-                "[Student {}] Could not find team requests for guild {}.",
-                self.name, guild
-            )
-            .as_str(),
-        )
+    pub fn get_team_notifications(&self, guild: &GuildId) -> TeamNotifications {
+        self.team_notifications
+            .get(guild)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Whether the student's data specific to a guild could not be loaded, so it is missing (e.g.
+    /// the student would seem not to be in a team).
+    pub fn is_slice_unreadable(&self, guild: &GuildId) -> bool {
+        self.unreadable_slices.contains(guild)
     }
 
     /* Other methods: */
 
-    /// Adds a team for one of the guilds this student is in.
-    pub fn add_team(&mut self, guild_id: GuildId, team_id: String, team_password: Option<String>) {
+    /// Sets the student's login or identifier in the university.
+    pub fn set_university_id(&mut self, university_id: String) {
+        self.university_id = Some(university_id);
+
+        self.save();
+    }
+
+    /// Sets the student's team in one of the guilds they are in.
+    ///
+    /// The student is not saved; this is meant to be done by the caller (see `service::Storage`).
+    pub fn set_team(&mut self, guild_id: GuildId, team_id: String, team_password: Option<String>) {
+        // Resolve the team requests for this guild, since the student is now in a team:
+        if let Some(requests) = self.team_requests.get_mut(&guild_id) {
+            teamrequest::accept(requests, &team_id);
+        }
+
         let cred = Credentials {
             team: team_id,
             password: team_password,
         };
 
         self.credentials.insert(guild_id, cred);
-
-        // Remove any team requests for this guild, since the student is now in a team.
-        self.team_requests.remove(&guild_id);
-
-        self.save();
     }
 
     /// Adds the password to the credentials of a guild the student is in.
+    ///
+    /// The student is not saved; this is meant to be done in a batch (see `batch::Batch`).
     pub fn set_password(&mut self, guild_id: &GuildId, password: String) {
         assert!(self.credentials.contains_key(guild_id));
 
         self.credentials
             .get_mut(guild_id)
-            .expect(
-                format!(
+            .unwrap_or_else(|| {
+                panic!(
                     "[Student {}] Could not find credentials for guild {}.",
                     self.name, guild_id
                 )
-                .as_str(),
-            )
+            })
             .password = Some(password);
+    }
 
-        self.save();
+    /// Removes the password from the credentials of a guild the student is in.
+    ///
+    /// The student is not saved; this is meant to be done in a batch (see `batch::Batch`).
+    pub fn clear_password(&mut self, guild_id: &GuildId) {
+        if let Some(credentials) = self.credentials.get_mut(guild_id) {
+            credentials.password = None;
+        }
     }
 
     /// Removes the team for one of the guilds the student is in, given the guild identifier.
@@ -161,33 +252,73 @@ impl Student {
     /// The removed team is probably not confirmed (definitive), so their members could join and
     /// leave at will.
     pub fn remove_team(&mut self, guild_id: &GuildId) {
-        self.credentials.remove(guild_id);
+        self.clear_team(guild_id);
 
         self.save();
     }
 
-    /// Retrieves the team ID for a guild the student might be in.
+    /// Removes the student's team in one of the guilds they are in (see `remove_team`).
     ///
-    /// Returns `None` if the student does not have a team in the provided guild.
-    pub fn get_team_id(&self, guild_id: &GuildId) -> Option<String> {
-        if let Some(credentials) = self.credentials.get(guild_id) {
-            Some(credentials.team.clone())
-        } else {
-            None
-        }
+    /// The student is not saved; this is meant to be done by the caller (see `service::Storage`).
+    pub fn clear_team(&mut self, guild_id: &GuildId) {
+        self.credentials.remove(guild_id);
     }
 
-    /// Adds a new team request for the student.
-    pub fn add_team_request(&mut self, guild_id: GuildId, team_id: String, sender_id: UserId) {
-        let request = (team_id, sender_id).into();
+    /// Changes the identifier of the student's team in a guild, after the team was renamed (see
+    /// `team::migrate_prefix`).
+    ///
+    /// The student is not saved; this is meant to be done in a batch (see `batch::Batch`).
+    pub fn set_team_id(&mut self, guild_id: &GuildId, team_id: String) {
+        self.credentials
+            .get_mut(guild_id)
+            .unwrap_or_else(|| {
+                panic!(
+                    "[Student {}] Could not find credentials for guild {}.",
+                    self.name, guild_id
+                )
+            })
+            .team = team_id;
+    }
 
-        if let Some(requests) = self.team_requests.get_mut(&guild_id) {
-            requests.push(request);
-        } else {
-            self.team_requests.insert(guild_id, vec![request]);
+    /// Changes the teams of the student's team requests in a guild, given the old identifiers of
+    /// the renamed teams mapped to their new ones. Returns whether any request was changed.
+    ///
+    /// The student is not saved; this is meant to be done in a batch (see `batch::Batch`).
+    pub fn rename_team_requests(
+        &mut self,
+        guild_id: &GuildId,
+        renames: &HashMap<String, String>,
+    ) -> bool {
+        let Some(requests) = self.team_requests.get_mut(guild_id) else {
+            return false;
+        };
+
+        let mut changed = false;
+        for request in requests.iter_mut() {
+            if let Some(new_id) = renames.get(request.team_id()) {
+                request.set_team_id(new_id.clone());
+                changed = true;
+            }
         }
 
-        self.save();
+        changed
+    }
+
+    /// Retrieves the team ID for a guild the student might be in.
+    ///
+    /// Returns `None` if the student does not have a team in the provided guild.
+    pub fn get_team_id(&self, guild_id: &GuildId) -> Option<String> {
+        self.credentials
+            .get(guild_id)
+            .map(|credentials| credentials.team.clone())
+    }
+
+    /// Retrieves the student's team requests for a guild to change them, creating an empty list if
+    /// there were none (see `teamrequest`).
+    ///
+    /// The student is not saved.
+    pub fn team_requests_mut(&mut self, guild_id: GuildId) -> &mut Vec<TeamRequest> {
+        self.team_requests.entry(guild_id).or_default()
     }
 
     /// Sets the preferred queue of the student for a given guild.
@@ -204,82 +335,537 @@ impl Student {
         self.save();
     }
 
+    /// Removes the preferred queue of the student for a given guild.
+    ///
+    /// Returns whether the student had a preferred queue set.
+    pub fn clear_preferred_queue(&mut self, guild_id: &GuildId) -> bool {
+        let removed = self.preferred_queue.remove(guild_id).is_some();
+
+        self.save();
+
+        removed
+    }
+
+    /// Removes the last request command the student used in a guild.
+    ///
+    /// Returns whether the student had a last command saved.
+    pub fn clear_last_command(&mut self, guild_id: &GuildId) -> bool {
+        let removed = self.last_command.remove(guild_id).is_some();
+
+        self.save();
+
+        removed
+    }
+
+    /// Sets the language the student wants the bot to reply in, in a guild.
+    pub fn set_language(&mut self, guild_id: GuildId, language: Language) {
+        self.language.insert(guild_id, language);
+
+        self.save();
+    }
+
+    /// Removes the language choice of the student for a guild, so the guild's default is used.
+    pub fn clear_language(&mut self, guild_id: &GuildId) -> bool {
+        let removed = self.language.remove(guild_id).is_some();
+
+        self.save();
+
+        removed
+    }
+
+    /// Sets how the student wants to be told of the changes in their team, in a guild.
+    pub fn set_team_notifications(&mut self, guild_id: GuildId, mode: TeamNotifications) {
+        self.team_notifications.insert(guild_id, mode);
+
+        self.save();
+    }
+
+    /// Opts the student in to deadline reminders for a guild, at the given offsets (in hours
+    /// before each deadline).
+    pub fn set_reminders(&mut self, guild_id: GuildId, offsets: Vec<u32>) {
+        self.reminders.insert(guild_id, offsets);
+
+        self.save();
+    }
+
+    /// Opts the student out of deadline reminders for a guild.
+    ///
+    /// Returns whether the student was opted in.
+    pub fn clear_reminders(&mut self, guild_id: &GuildId) -> bool {
+        let removed = self.reminders.remove(guild_id).is_some();
+
+        self.save();
+
+        removed
+    }
+
     /// Adds a request to the student's request history.
-    pub fn add_request(&mut self, gid: &GuildId, request_id: u16) {
+    ///
+    /// If the history goes over the guild's capacity (see `BotConfig::history_capacity`), its
+    /// oldest requests are moved to the student's archive.
+    pub fn add_request(&mut self, gid: &GuildId, config: &BotConfig, request: RequestRecord) {
         if self.request_history.contains_key(gid) {
             self.request_history
                 .get_mut(gid)
-                .expect(
-                    format!(
+                .unwrap_or_else(|| {
+                    panic!(
                         "[Student {}] No request history for guild {}.",
                         self.name, gid
                     )
-                    .as_str(),
-                )
-                .push(request_id);
+                })
+                .push(request);
         } else {
-            self.request_history.insert(gid.clone(), vec![request_id]);
+            self.request_history.insert(*gid, vec![request]);
+        }
+
+        let capacity = config.history_capacity as usize;
+        let len = self.request_history.get(gid).map_or(0, Vec::len);
+        if capacity > 0 && len > capacity {
+            // The requests are only removed from the record once archived, so none are lost:
+            let mut archived = self.archived_requests(gid);
+            archived.extend_from_slice(&self.request_history[gid][..len - capacity]);
+            match self.save_archive(gid, &archived) {
+                Ok(()) => {
+                    if let Some(hist) = self.request_history.get_mut(gid) {
+                        hist.drain(..len - capacity);
+                    }
+                }
+                Err(e) => tracing::warn!(
+                    "[Student {}] Could not archive the oldest requests in guild {}: {}",
+                    self.id,
+                    gid,
+                    e
+                ),
+            }
         }
 
         self.save();
     }
 
-    /// Saves the student's information to disk as a JSON file.
+    /// Retrieves the requests of the student's request history for a guild that were archived
+    /// (see `Student::add_request`), oldest first.
+    pub fn archived_requests(&self, gid: &GuildId) -> Vec<RequestRecord> {
+        let path = archive_path(gid, &self.id);
+        let json = match utils::read_file(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                tracing::warn!("[Student {}] Could not read {}: {}", self.id, path, e);
+                return Vec::new();
+            }
+        };
+
+        serde_json::from_str(&json).unwrap_or_else(|e| {
+            tracing::warn!("[Student {}] Could not parse {}: {}", self.id, path, e);
+            Vec::new()
+        })
+    }
+
+    /// Saves the archived requests of the student for a guild, removing the archive if there are
+    /// none.
+    fn save_archive(&self, gid: &GuildId, archived: &[RequestRecord]) -> io::Result<()> {
+        let path = archive_path(gid, &self.id);
+        if archived.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+
+        fs::create_dir_all(format!("guilds/{}/history", gid))?;
+        let json = serde_json::to_string(archived).unwrap_or_else(|e| {
+            panic!(
+                "[Student {}] Could not serialize the archived requests: {:?}",
+                self.id, e
+            )
+        });
+        utils::write_file(&path, json)
+    }
+
+    /// Retrieves the student's whole request history for a guild, oldest first: the archived
+    /// requests, followed by the ones in the student's record.
+    pub fn full_request_history(&self, gid: &GuildId) -> Vec<RequestRecord> {
+        let mut hist = self.archived_requests(gid);
+        if let Some(recent) = self.request_history.get(gid) {
+            hist.extend(recent.iter().cloned());
+        }
+
+        hist
+    }
+
+    /// Removes all the requests from the student's request history for a guild, archived ones
+    /// included.
     ///
-    /// Student files are saved as `users/<username>[#discriminator].json`, for readability reasons.
+    /// Returns the number of requests removed.
+    pub fn clear_request_history(&mut self, gid: &GuildId) -> usize {
+        let mut removed = self
+            .request_history
+            .remove(gid)
+            .map_or(0, |hist| hist.len());
+        let archived = self.archived_requests(gid).len();
+        match self.save_archive(gid, &[]) {
+            Ok(()) => removed += archived,
+            Err(e) => tracing::warn!(
+                "[Student {}] Could not remove the archived requests in guild {}: {}",
+                self.id,
+                gid,
+                e
+            ),
+        }
+
+        self.save();
+
+        removed
+    }
+
+    /// Removes a request from the student's request history for a guild (or from their archive).
+    ///
+    /// Returns whether the request was in the history.
+    pub fn forget_request(&mut self, gid: &GuildId, request_id: u16) -> bool {
+        if let Some(hist) = self.request_history.get_mut(gid) {
+            let previous_len = hist.len();
+            hist.retain(|req| req.id() != request_id);
+            if hist.len() != previous_len {
+                self.save();
+
+                return true;
+            }
+        }
+
+        let mut archived = self.archived_requests(gid);
+        let previous_len = archived.len();
+        archived.retain(|req| req.id() != request_id);
+        archived.len() != previous_len && self.save_archive(gid, &archived).is_ok()
+    }
+
+    /// Caches the verdict of a request in the student's request history (or in their archive),
+    /// recording its result event the first time it is known.
+    ///
+    /// Returns whether the request was found.
+    pub fn set_request_verdict(&mut self, gid: &GuildId, request_id: u16, verdict: String) -> bool {
+        let Some(request) = self
+            .request_history
+            .get_mut(gid)
+            .and_then(|hist| hist.iter_mut().find(|req| req.id() == request_id))
+        else {
+            let mut archived = self.archived_requests(gid);
+            let Some(request) = archived.iter_mut().find(|req| req.id() == request_id) else {
+                return false;
+            };
+            request.set_verdict(verdict);
+            if let Err(e) = self.save_archive(gid, &archived) {
+                tracing::warn!(
+                    "[Student {}] Could not save the verdict of archived request {} in guild \
+                    {}: {}",
+                    self.id,
+                    request_id,
+                    gid,
+                    e
+                );
+            }
+
+            return true;
+        };
+        if request.verdict().as_ref() == Some(&verdict) {
+            return true;
+        }
+        request.set_verdict(verdict.clone());
+        if let Some(mut metadata) = requestrecord::get_metadata(gid, request_id) {
+            if let Err(e) = metadata.set_verdict(gid, verdict.clone()) {
+                tracing::warn!(
+                    "[Student] Could not save the verdict of request {} in guild {}: {}",
+                    request_id,
+                    gid,
+                    e
+                );
+            }
+        }
+
+        self.save();
+        events::record(
+            gid,
+            Event::Result {
+                team: self.get_team_id(gid),
+                user: self.id,
+                request_id,
+                verdict,
+            },
+        );
+
+        true
+    }
+
+    /// Takes the part of the student's record specific to a guild, or `None` if there is none.
+    fn slice(&self, gid: &GuildId) -> Option<GuildSlice> {
+        let slice = GuildSlice {
+            credentials: self.credentials.get(gid).cloned(),
+            preferred_queue: self.preferred_queue.get(gid).cloned(),
+            last_command: self.last_command.get(gid).cloned(),
+            team_requests: self.team_requests.get(gid).cloned(),
+            request_history: self.request_history.get(gid).cloned(),
+            reminders: self.reminders.get(gid).cloned(),
+            language: self.language.get(gid).copied(),
+            team_notifications: self.team_notifications.get(gid).copied(),
+        };
+        let empty = slice.credentials.is_none()
+            && slice.preferred_queue.is_none()
+            && slice.last_command.is_none()
+            && slice.team_requests.is_none()
+            && slice.request_history.is_none()
+            && slice.reminders.is_none()
+            && slice.language.is_none()
+            && slice.team_notifications.is_none();
+
+        (!empty).then_some(slice)
+    }
+
+    /// Sets the part of the student's record specific to a guild.
+    fn merge_slice(&mut self, gid: GuildId, slice: GuildSlice) {
+        fn set<T>(map: &mut HashMap<GuildId, T>, gid: GuildId, value: Option<T>) {
+            match value {
+                Some(value) => map.insert(gid, value),
+                None => map.remove(&gid),
+            };
+        }
+
+        set(&mut self.credentials, gid, slice.credentials);
+        set(&mut self.preferred_queue, gid, slice.preferred_queue);
+        set(&mut self.last_command, gid, slice.last_command);
+        set(&mut self.team_requests, gid, slice.team_requests);
+        set(&mut self.request_history, gid, slice.request_history);
+        set(&mut self.reminders, gid, slice.reminders);
+        set(&mut self.language, gid, slice.language);
+        set(&mut self.team_notifications, gid, slice.team_notifications);
+    }
+
+    /// Guilds with some data of the student, in memory.
+    pub fn guilds(&self) -> BTreeSet<GuildId> {
+        let mut guilds = BTreeSet::new();
+        guilds.extend(self.credentials.keys());
+        guilds.extend(self.preferred_queue.keys());
+        guilds.extend(self.last_command.keys());
+        guilds.extend(self.team_requests.keys());
+        guilds.extend(self.request_history.keys());
+        guilds.extend(self.reminders.keys());
+        guilds.extend(self.language.keys());
+        guilds.extend(self.team_notifications.keys());
+
+        guilds
+    }
+
+    /// Loads the parts of the student's record specific to each guild (see `GuildSlice`), over
+    /// the ones in the student's file. Those that cannot be loaded are reported (see
+    /// `is_slice_unreadable`), and never overwritten.
+    ///
+    /// If the student's file was written by an older version (i.e. it has data specific to some
+    /// guild, or not the guilds with a file of the student), the student is saved again, splitting
+    /// it.
+    fn load_slices(&mut self) {
+        let legacy = !self.guilds().is_empty() || self.slices.is_none();
+        let slices = self
+            .slices
+            .take()
+            .unwrap_or_else(|| find_slice_guilds(&self.id));
+
+        for gid in &slices {
+            let path = slice_path(gid, &self.id);
+            let slice = match utils::read_file(&path) {
+                // The guild's data was removed (it is dropped from the list once saved):
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                json => json
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string())),
+            };
+            match slice {
+                Ok(slice) => self.merge_slice(*gid, slice),
+                Err(e) => {
+                    alerts::alert(
+                        AlertKind::DataLoad,
+                        format!("Could not load {} (student {}): {}", path, self.id, e),
+                    );
+                    self.unreadable_slices.insert(*gid);
+                }
+            }
+        }
+        self.slices = Some(slices);
+
+        if legacy {
+            tracing::info!("[Student {}] Splitting the record per guild.", self.id);
+            self.save();
+        }
+    }
+
+    /// Saves the student's information to disk as JSON files: the fields shared by every guild in
+    /// `users/<user_id>.json`, and the rest in a file per guild (see `GuildSlice`), updating the
+    /// cache it was loaded from, if any.
     pub fn save(&self) {
-        let json = serde_json::to_string_pretty(self).expect(
-            format!(
-                "[Student {}] Could not serialize student struct.",
-                self.name
+        // Save (or remove, if emptied) the part of the record specific to each guild, keeping
+        // track of the guilds with a file:
+        let mut guilds = self.guilds();
+        guilds.extend(self.slices.iter().flatten());
+        let mut slices = self.unreadable_slices.clone();
+        for gid in guilds.difference(&self.unreadable_slices) {
+            let path = slice_path(gid, &self.id);
+            let Some(slice) = self.slice(gid) else {
+                if let Err(e) = fs::remove_file(&path) {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        panic!(
+                            "[Student {}] Could not remove {} from disk: {}",
+                            self.id, path, e
+                        );
+                    }
+                }
+                continue;
+            };
+            let json = serde_json::to_string_pretty(&slice).unwrap_or_else(|e| {
+                panic!(
+                    "[Student {}] Could not serialize the student's data for guild {}: {:?}",
+                    self.name, gid, e
+                )
+            });
+            fs::create_dir_all(format!("guilds/{}/students", gid))
+                .and_then(|()| utils::write_file(&path, json))
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "[Student {}] Could not write the student's file for guild {} to disk: {:?}",
+                        self.id, gid, e
+                    )
+                });
+            slices.insert(*gid);
+        }
+
+        let json = serde_json::to_string_pretty(&GlobalRecord {
+            id: self.id,
+            name: &self.name,
+            university_id: &self.university_id,
+            slices: &slices,
+        })
+        .unwrap_or_else(|e| {
+            panic!(
+                "[Student {}] Could not serialize student struct: {:?}",
+                self.name, e
             )
-            .as_str(),
-        );
+        });
 
         // Assume `users/` directory exists.
-        // FIXME MINOR: Account for name changes.
-        fs::write(format!("users/{}.json", self.id), json).expect(
-            format!(
-                "[Student {}] Could not write student file to disk.",
-                self.id
+        utils::write_file(&format!("users/{}.json", self.id), json).unwrap_or_else(|e| {
+            panic!(
+                "[Student {}] Could not write student file to disk: {:?}",
+                self.id, e
             )
-            .as_str(),
-        );
+        });
+        if let Some(cache) = &self.cache {
+            // The cached copy does not keep a handle to its own cache:
+            cache.set_student(Student {
+                slices: Some(slices),
+                cache: None,
+                ..self.clone()
+            });
+        }
     }
 
     /// Loads a Student instance from a JSON string and returns it.
+    ///
+    /// Only the data in the JSON string is loaded (i.e. not the student's files of each guild).
     pub fn from_json(json: &str) -> Student {
         serde_json::from_str(json).expect("[Student] Could not parse data as valid JSON.")
     }
 
-    /// Loads a Student instance saved as JSON from disk and returns it.
+    /// Loads a Student instance saved as JSON from disk, along with its files of each guild, and
+    /// returns it.
     pub fn load(path: &Path) -> Student {
-        let json_str = fs::read_to_string(path)
-            .expect(format!("[Student] Could not load file {}.", path.display()).as_str());
-        Self::from_json(&json_str)
+        let json_str = fs::read_to_string(path).unwrap_or_else(|e| {
+            panic!("[Student] Could not load file {}: {:?}", path.display(), e)
+        });
+        let mut student = Self::from_json(&json_str);
+        student.load_slices();
+
+        student
     }
 }
 
+/// Path of the file of a student's data specific to a guild (see `GuildSlice`).
+fn slice_path(gid: &GuildId, uid: &UserId) -> String {
+    format!("guilds/{}/students/{}.json", gid, uid)
+}
+
+/// Guilds with a file of the student's data specific to them (see `GuildSlice`), looking for it in
+/// every guild's directory, for students whose file does not list them.
+fn find_slice_guilds(uid: &UserId) -> BTreeSet<GuildId> {
+    let Ok(entries) = fs::read_dir("guilds") else {
+        return BTreeSet::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u64>().ok())
+        .filter(|gid| *gid != 0)
+        .map(GuildId::new)
+        .filter(|gid| Path::new(&slice_path(gid, uid)).exists())
+        .collect()
+}
+
+/// Path of the archive of a student's oldest requests in a guild (see `Student::add_request`).
+fn archive_path(gid: &GuildId, uid: &UserId) -> String {
+    format!("guilds/{}/history/{}.json", gid, uid)
+}
+
 /// Retrieves a Student object given its Discord ID, if it exists in the system.
-pub fn get_student(id: &UserId) -> Option<Student> {
-    if let Ok(json) = fs::read_to_string(format!("users/{}.json", id).as_str()) {
-        Some(
-            serde_json::from_str(&json).expect(
-                format!(
-                    "[Student] Could not parse {}'s user file as valid JSON.",
-                    id
-                )
-                .as_str(),
-            ),
-        )
-    } else {
-        None
+pub fn get_student(caches: &Caches, id: &UserId) -> Option<Student> {
+    if let Some(mut student) = caches.student(id) {
+        student.cache = Some(caches.clone());
+        return Some(student);
     }
+
+    // Only a missing file means the student does not exist (e.g. not a failed read, which would
+    // lead to the student being created anew):
+    let json = match utils::read_file(&format!("users/{}.json", id)) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        json => {
+            json.unwrap_or_else(|e| panic!("[Student] Could not read {}'s user file: {:?}", id, e))
+        }
+    };
+    let mut student: Student = serde_json::from_str(&json).unwrap_or_else(|e| {
+        panic!(
+            "[Student] Could not parse {}'s user file as valid JSON: {:?}",
+            id, e
+        )
+    });
+    student.load_slices();
+    caches.set_student(student.clone());
+    student.cache = Some(caches.clone());
+
+    Some(student)
+}
+
+/// Retrieves all the Student objects in the system.
+pub fn get_all_students(caches: &Caches) -> Vec<Student> {
+    let Ok(entries) = fs::read_dir("users") else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        // Skip the user map, and any other non-student file:
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "json")
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|stem| stem.parse::<u64>().is_ok())
+        })
+        .map(|path| {
+            let mut student = Student::load(&path);
+            student.cache = Some(caches.clone());
+            student
+        })
+        .collect()
 }
 
 macro_rules! get_existing_student {
-    ($id:expr) => {
-        student::get_student(&$id)
+    ($caches:expr, $id:expr) => {
+        student::get_student($caches, &$id)
             .expect(format!("[Student] Could not find student {} in the system.", $id).as_str())
     };
 }
@@ -288,8 +874,8 @@ pub(crate) use get_existing_student;
 /// Retrieves a Student object from a generic object that contains is Discord ID.
 // TODO: Move to utils?
 macro_rules! get_student_from_user {
-    ($user:ident) => {
-        student::get_student(&$user.id).expect(
+    ($caches:expr, $user:ident) => {
+        student::get_student($caches, &$user.id).expect(
             format!(
                 "[Student] Could not find student {} in the system.",
                 $user.id
@@ -299,3 +885,159 @@ macro_rules! get_student_from_user {
     };
 }
 pub(crate) use get_student_from_user;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDataDir;
+    use std::fs;
+
+    #[test]
+    fn verdicts_are_recorded_once() {
+        let _dir = TempDataDir::new("student-verdicts");
+        let caches = Caches::default();
+        let gid = GuildId::new(1);
+        crate::init_guild_files(&caches, &gid);
+        let mut sender = Student::new(&caches, UserId::new(1), "sender".to_string());
+        let mut teammate = Student::new(&caches, UserId::new(2), "teammate".to_string());
+        let config = utils::load_config(&caches, &gid);
+        sender.add_request(&gid, &config, RequestRecord::new(7, 0, None));
+
+        // Only the sender has the request, and its result is only recorded the first time:
+        assert!(!teammate.set_request_verdict(&gid, 7, "OK".to_string()));
+        assert!(sender.set_request_verdict(&gid, 7, "OK".to_string()));
+        assert!(sender.set_request_verdict(&gid, 7, "OK".to_string()));
+        let events = fs::read_to_string(format!("guilds/{}/events.jsonl", gid)).unwrap();
+        let results = events
+            .lines()
+            .filter(|line| line.contains("\"type\":\"result\""))
+            .count();
+        assert_eq!(results, 1);
+        assert_eq!(
+            get_student(&caches, &UserId::new(1))
+                .unwrap()
+                .full_request_history(&gid)[0]
+                .verdict()
+                .as_deref(),
+            Some("OK")
+        );
+    }
+
+    #[test]
+    fn guild_data_round_trips_through_its_files() {
+        let _dir = TempDataDir::new("student-slices");
+        let caches = Caches::default();
+        let (first, second) = (GuildId::new(1), GuildId::new(2));
+        crate::init_guild_files(&caches, &first);
+        crate::init_guild_files(&caches, &second);
+        let uid = UserId::new(1);
+        let mut student = Student::new(&caches, uid, "student".to_string());
+        student.set_team(first, "g01".to_string(), Some("secret".to_string()));
+        student.set_language(second, Language::Spanish);
+        student.set_preferred_queue(second, "queue".to_string());
+
+        // The student's file only lists the guilds, whose data is in their own files:
+        let json = fs::read_to_string(format!("users/{}.json", uid)).unwrap();
+        assert!(!json.contains("secret"));
+        assert!(Path::new(&slice_path(&first, &uid)).exists());
+        assert!(Path::new(&slice_path(&second, &uid)).exists());
+
+        let loaded = get_student(&Caches::default(), &uid).unwrap();
+        assert_eq!(loaded.get_team_id(&first).as_deref(), Some("g01"));
+        assert_eq!(
+            loaded
+                .get_existing_credentials(&first)
+                .password()
+                .as_deref(),
+            Some("secret")
+        );
+        assert_eq!(loaded.get_language(&second), Some(Language::Spanish));
+        assert_eq!(
+            loaded.get_preferred_queue(&second).map(String::as_str),
+            Some("queue")
+        );
+        assert_eq!(loaded.slices, Some(BTreeSet::from([first, second])));
+
+        // Emptying a guild's data removes its file, and the guild from the list:
+        let mut student = loaded;
+        student.clear_language(&second);
+        student.clear_preferred_queue(&second);
+        assert!(!Path::new(&slice_path(&second, &uid)).exists());
+        let loaded = get_student(&Caches::default(), &uid).unwrap();
+        assert_eq!(loaded.slices, Some(BTreeSet::from([first])));
+        assert_eq!(loaded.get_language(&second), None);
+    }
+
+    #[test]
+    fn legacy_records_are_split_on_load() {
+        let _dir = TempDataDir::new("student-migration");
+        let caches = Caches::default();
+        let gid = GuildId::new(1);
+        crate::init_guild_files(&caches, &gid);
+        let uid = UserId::new(1);
+        fs::write(
+            format!("users/{}.json", uid),
+            r#"{
+                "id": "1",
+                "name": "student",
+                "credentials": { "1": { "team": "g01", "password": "secret" } },
+                "preferred_queue": { "1": "queue" }
+            }"#,
+        )
+        .unwrap();
+
+        let student = get_student(&caches, &uid).unwrap();
+        assert_eq!(student.get_team_id(&gid).as_deref(), Some("g01"));
+        assert_eq!(
+            student.get_preferred_queue(&gid).map(String::as_str),
+            Some("queue")
+        );
+
+        // The student's file was rewritten without the guild's data, which has its own file:
+        let json = fs::read_to_string(format!("users/{}.json", uid)).unwrap();
+        assert!(!json.contains("secret"));
+        assert!(json.contains("slices"));
+        assert!(fs::read_to_string(slice_path(&gid, &uid))
+            .unwrap()
+            .contains("secret"));
+        let loaded = get_student(&Caches::default(), &uid).unwrap();
+        assert_eq!(loaded.get_team_id(&gid).as_deref(), Some("g01"));
+
+        // Records already split, but without the list of guilds, find their files:
+        fs::write(
+            format!("users/{}.json", uid),
+            r#"{ "id": "1", "name": "student" }"#,
+        )
+        .unwrap();
+        let loaded = get_student(&Caches::default(), &uid).unwrap();
+        assert_eq!(loaded.get_team_id(&gid).as_deref(), Some("g01"));
+        let json = fs::read_to_string(format!("users/{}.json", uid)).unwrap();
+        assert!(json.contains("slices"));
+    }
+
+    #[test]
+    fn unreadable_guild_data_is_reported_and_kept() {
+        let _dir = TempDataDir::new("student-unreadable");
+        let caches = Caches::default();
+        let gid = GuildId::new(1);
+        crate::init_guild_files(&caches, &gid);
+        let uid = UserId::new(1);
+        let mut student = Student::new(&caches, uid, "student".to_string());
+        student.set_team(gid, "g01".to_string(), None);
+        student.save();
+        fs::write(slice_path(&gid, &uid), "{ corrupt").unwrap();
+
+        let mut loaded = get_student(&Caches::default(), &uid).unwrap();
+        assert!(loaded.is_slice_unreadable(&gid));
+        assert_eq!(loaded.get_team_id(&gid), None);
+
+        // Saving the student neither overwrites the file nor forgets it:
+        loaded.set_university_id("student".to_string());
+        assert_eq!(
+            fs::read_to_string(slice_path(&gid, &uid)).unwrap(),
+            "{ corrupt"
+        );
+        let loaded = get_student(&Caches::default(), &uid).unwrap();
+        assert!(loaded.is_slice_unreadable(&gid));
+    }
+}
@@ -0,0 +1,81 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Optional integration with systemd's service manager (with the `systemd` feature): readiness and
+//! stopping notifications, and watchdog keep-alive pings (for services with `WatchdogSec=`).
+//!
+//! Without the feature, or when not running under systemd, every function is a no-op.
+
+use crate::tasks::TaskManager;
+#[cfg(feature = "systemd")]
+use sd_notify::NotifyState;
+use std::sync::Arc;
+#[cfg(feature = "systemd")]
+use std::time::Duration;
+
+/// Notifies the service manager that the bot is ready (i.e., connected to the gateway).
+pub fn notify_ready() {
+    #[cfg(feature = "systemd")]
+    notify(&[NotifyState::Ready]);
+}
+
+/// Notifies the service manager that the bot is shutting down.
+pub fn notify_stopping() {
+    #[cfg(feature = "systemd")]
+    notify(&[NotifyState::Stopping]);
+}
+
+/// Notifies the service manager that the bot is still alive, resetting its watchdog timer.
+#[cfg(feature = "systemd")]
+fn ping_watchdog() {
+    notify(&[NotifyState::Watchdog]);
+}
+
+/// Starts pinging the watchdog periodically in the background (at half its timeout, as systemd
+/// recommends), if the service manager expects it.
+///
+/// The pings run on the bot's runtime, so they stop if it gets stuck, and systemd restarts the
+/// service.
+pub fn start_watchdog(tasks: &Arc<TaskManager>) {
+    #[cfg(feature = "systemd")]
+    {
+        let mut usec = 0;
+        if !sd_notify::watchdog_enabled(false, &mut usec) || usec == 0 {
+            return;
+        }
+        let period = Duration::from_micros(usec) / 2;
+        tracing::info!(
+            "[systemd] Pinging the watchdog every {} ms.",
+            period.as_millis()
+        );
+
+        tasks.spawn_periodic("watchdog", period, || async {
+            ping_watchdog();
+            Ok(())
+        });
+    }
+    #[cfg(not(feature = "systemd"))]
+    let _ = tasks;
+}
+
+/// Sends a notification to the service manager, logging (but otherwise ignoring) failures.
+#[cfg(feature = "systemd")]
+fn notify(state: &[NotifyState]) {
+    if let Err(e) = sd_notify::notify(false, state) {
+        tracing::warn!("[systemd] Failed to notify the service manager: {}", e);
+    }
+}
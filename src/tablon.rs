@@ -0,0 +1,639 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+extern crate reqwest;
+
+use crate::{alerts, utils, Credentials};
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Maximum time to wait for Tablón to answer a request.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Minimum time between two requests to the same host, shared by all the guilds (which may point
+/// to the same Tablón).
+const HOST_SPACING: Duration = Duration::from_millis(250);
+
+/// Time during which identical fetches of a page are served from the same response.
+const COALESCE_WINDOW: Duration = Duration::from_secs(5);
+
+/// HTML tags, to strip them.
+static TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<[^>]*>").expect("Failed to compile regex for HTML tags."));
+
+/// Status of a request in its page (see `Tablon::fetch_request_status`).
+static STATUS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(status|estado)\s*:?\s*(.*)$")
+        .expect("Failed to compile regex for request status.")
+});
+
+/// Announcements in the front page (see `Tablon::fetch_announcements`).
+static ARTICLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<article[^>]*>(.*?)</article>")
+        .expect("Failed to compile regex for announcements.")
+});
+
+/// Tables, and their rows, headers and cells (see `parse_tables`).
+static TABLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<table(\s[^>]*)?>(.*?)</table>").expect("Failed to compile regex for tables.")
+});
+static ROW_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<tr(\s[^>]*)?>(.*?)</tr>").expect("Failed to compile regex for table rows.")
+});
+static HEADER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<th(\s[^>]*)?>(.*?)</th>")
+        .expect("Failed to compile regex for table headers.")
+});
+static CELL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<td(\s[^>]*)?>(.*?)</td>").expect("Failed to compile regex for table cells.")
+});
+
+/// Password inputs (see `is_login_page`).
+static PASSWORD_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<input[^>]*type\s*=\s*["']?password"#)
+        .expect("Failed to compile regex for password inputs.")
+});
+
+/// Numbers in a verdict (see `verdict_result`).
+static NUMBER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\d+(\.\d+)?").expect("Failed to compile regex for verdict results.")
+});
+
+/// Response of Tablón to a fetch.
+#[derive(Clone)]
+struct Page {
+    status: reqwest::StatusCode,
+    body: String,
+}
+
+/// Last response to a page, with when it was received. Each page has its own lock, so concurrent
+/// fetches of it wait for a single response.
+type SharedResponse = Arc<tokio::sync::Mutex<Option<(Instant, Page)>>>;
+
+/// Client of Tablón, shared by its clones, so the fetches of every guild reuse its connections,
+/// and are rate-limited and coalesced together. It is owned by the framework's `Data`, and handed
+/// to the tasks running outside of the commands.
+#[derive(Clone)]
+pub struct Tablon {
+    /// HTTP client, with the timeout of the requests (see `TIMEOUT`).
+    client: reqwest::Client,
+    /// Earliest time the next request to each host can be sent (see `HOST_SPACING`).
+    host_turns: Arc<Mutex<BTreeMap<String, Instant>>>,
+    /// Last response to each page fetched (see `Tablon::fetch`).
+    responses: Arc<Mutex<BTreeMap<String, SharedResponse>>>,
+}
+
+impl Default for Tablon {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(TIMEOUT)
+                .build()
+                .expect("Could not build the HTTP client for Tablón."),
+            host_turns: Arc::default(),
+            responses: Arc::default(),
+        }
+    }
+}
+
+impl Tablon {
+    /// Takes the turn of a request to a host at the given time, so requests to it are at least
+    /// `HOST_SPACING` apart, in order. Returns when the request can be sent.
+    fn take_host_turn(&self, host: &str, now: Instant) -> Instant {
+        let mut turns = self
+            .host_turns
+            .lock()
+            .expect("The turns of Tablón's hosts were poisoned.");
+        let turn = turns.get(host).map_or(now, |next| (*next).max(now));
+        turns.insert(host.to_string(), turn + HOST_SPACING);
+
+        turn
+    }
+
+    /// Shared response of a page (see `Tablon::fetch`), by its key.
+    fn response_entry(&self, key: String) -> SharedResponse {
+        let mut responses = self
+            .responses
+            .lock()
+            .expect("The responses of Tablón were poisoned.");
+        // Forget the expired responses, unless they are being fetched again:
+        responses.retain(|_, entry| {
+            Arc::strong_count(entry) > 1
+                || entry.try_lock().is_ok_and(|response| {
+                    response
+                        .as_ref()
+                        .is_some_and(|(received, _)| received.elapsed() < COALESCE_WINDOW)
+                })
+        });
+
+        Arc::clone(responses.entry(key).or_default())
+    }
+
+    /// Fetches a page from Tablón, with the given query and credentials (e.g. the admin ones), if
+    /// it can be reached, with the rate limit of its host.
+    ///
+    /// Identical fetches (of the same page, with the same query and credentials) within
+    /// `COALESCE_WINDOW` are served from the same response, and concurrent ones wait for it,
+    /// instead of requesting it again.
+    async fn fetch(
+        &self,
+        url: &str,
+        credentials: Option<&Credentials>,
+        query: &[(&str, &str)],
+    ) -> Option<Page> {
+        let mut request = self.client.get(url).query(query);
+        if let Some(credentials) = credentials {
+            request = request.basic_auth(credentials.team(), credentials.password().as_ref());
+        }
+        let request = request.build().ok()?;
+
+        // The page depends on the credentials, whose password is not kept in plain text:
+        let key = match credentials {
+            Some(credentials) => format!(
+                "{} as {}:{:016x}",
+                request.url(),
+                credentials.team(),
+                utils::content_hash(
+                    credentials
+                        .password()
+                        .as_deref()
+                        .unwrap_or_default()
+                        .as_bytes()
+                )
+            ),
+            None => request.url().to_string(),
+        };
+        let entry = self.response_entry(key);
+        let mut response = entry.lock().await;
+        if let Some((received, page)) = response.as_ref() {
+            if received.elapsed() < COALESCE_WINDOW {
+                return Some(page.clone());
+            }
+        }
+
+        let turn =
+            self.take_host_turn(request.url().host_str().unwrap_or_default(), Instant::now());
+        tokio::time::sleep_until(turn.into()).await;
+        let fetched = self.client.execute(request).await;
+        alerts::report_tablon_reachable(fetched.is_ok());
+        let fetched = fetched.ok()?;
+        let page = Page {
+            status: fetched.status(),
+            body: fetched.text().await.ok()?,
+        };
+        *response = Some((Instant::now(), page.clone()));
+
+        Some(page)
+    }
+
+    /// Fetches a page from Tablón (see `Tablon::fetch`), returning its body if it was successful.
+    async fn fetch_body(
+        &self,
+        url: &str,
+        credentials: Option<&Credentials>,
+        query: &[(&str, &str)],
+    ) -> Option<String> {
+        self.fetch(url, credentials, query)
+            .await
+            .filter(|page| page.status.is_success())
+            .map(|page| page.body)
+    }
+
+    /// Fetches the status of a request from its page in Tablón, if it can be found.
+    ///
+    /// The request page is expected to contain a "Status" (or "Estado") label, followed by the
+    /// status of the request (either in the same line, or in the next one, once the HTML tags are
+    /// stripped).
+    pub async fn fetch_request_status(&self, tablon_url: &str, request_id: u16) -> Option<String> {
+        let url = format!("{}/request", tablon_url);
+        let rid = request_id.to_string();
+        let html = self.fetch_body(&url, None, &[("rid", &rid)]).await?;
+
+        // Strip the HTML tags, keeping the text in separate lines:
+        let text = TAG_REGEX.replace_all(&html, "\n");
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        while let Some(line) = lines.next() {
+            if let Some(captures) = STATUS_REGEX.captures(line) {
+                let status = captures.get(2).map_or("", |m| m.as_str());
+                return if status.is_empty() {
+                    lines.next().map(str::to_string)
+                } else {
+                    Some(status.to_string())
+                };
+            }
+        }
+
+        None
+    }
+
+    /// Fetches the announcements in Tablón's front page, if it can be reached.
+    ///
+    /// The announcements are expected to be `<article>` elements. Their text is returned once the
+    /// HTML tags are stripped, with each line trimmed.
+    pub async fn fetch_announcements(&self, tablon_url: &str) -> Option<Vec<String>> {
+        let html = self.fetch_body(tablon_url, None, &[]).await?;
+
+        let announcements = ARTICLE_REGEX
+            .captures_iter(&html)
+            .map(|captures| {
+                TAG_REGEX
+                    .replace_all(&captures[1], "\n")
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .filter(|announcement| !announcement.is_empty())
+            .collect();
+
+        Some(announcements)
+    }
+
+    /// Fetches the registered teams and their passwords from Tablón's team administration page,
+    /// using admin credentials, if it can be reached and understood.
+    ///
+    /// The page is expected to have a table with "Team" and "Password" columns (see
+    /// `parse_team_passwords`).
+    pub async fn fetch_team_passwords(
+        &self,
+        tablon_url: &str,
+        credentials: &Credentials,
+        prefix: &str,
+    ) -> Option<HashMap<String, String>> {
+        let url = format!("{}/admin/teams", tablon_url);
+        let html = self.fetch_body(&url, Some(credentials), &[]).await?;
+
+        let passwords = parse_team_passwords(&html, prefix);
+        if passwords.is_none() {
+            tracing::warn!("No table of teams with their passwords found in {}.", url);
+        }
+
+        passwords
+    }
+
+    /// Fetches all the requests of a team from Tablón's request administration page, using admin
+    /// credentials, if it can be reached and understood.
+    ///
+    /// The page is expected to have a table with a row per request (see `parse_team_requests`).
+    pub async fn fetch_team_requests(
+        &self,
+        tablon_url: &str,
+        credentials: &Credentials,
+        team_id: &str,
+    ) -> Option<Vec<TablonRequest>> {
+        let url = format!("{}/admin/requests", tablon_url);
+        let html = self
+            .fetch_body(&url, Some(credentials), &[("team", team_id)])
+            .await?;
+
+        let requests = parse_team_requests(&html);
+        if requests.is_none() {
+            tracing::warn!(
+                "No table of requests found in {} for team {}.",
+                url,
+                team_id
+            );
+        }
+
+        requests
+    }
+
+    /// Checks whether Tablón accepts a team's credentials, by fetching the team's page with them:
+    /// they are rejected if Tablón answers with an authentication error, or with its login form.
+    ///
+    /// Returns `None` if Tablón could not be reached, or answered with an unexpected error.
+    pub async fn check_credentials(
+        &self,
+        tablon_url: &str,
+        credentials: &Credentials,
+    ) -> Option<bool> {
+        let url = format!("{}/team", tablon_url);
+        let page = self.fetch(&url, Some(credentials), &[]).await?;
+        let status = page.status;
+
+        if status.is_success() {
+            Some(!is_login_page(&page.body))
+        } else if status == reqwest::StatusCode::UNAUTHORIZED
+            || status == reqwest::StatusCode::FORBIDDEN
+        {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// Status keywords (lowercase) for requests that have not finished yet in Tablón.
+const PENDING_STATUSES: [&str; 6] = [
+    "queued",
+    "running",
+    "pending",
+    "en cola",
+    "ejecutando",
+    "pendiente",
+];
+
+/// Status keywords (lowercase) for requests that finished unsuccessfully in Tablón.
+const FAILURE_STATUSES: [&str; 8] = [
+    "error",
+    "fail",
+    "wrong",
+    "incorrect",
+    "timeout",
+    "time limit",
+    "killed",
+    "crash",
+];
+
+/// Header names (lowercase, in English and Spanish) of the columns of Tablón's tables.
+const TEAM_COLUMN: [&str; 2] = ["team", "equipo"];
+const PASSWORD_COLUMN: [&str; 2] = ["password", "contraseña"];
+const REQUEST_COLUMN: [&str; 3] = ["request", "petición", "id"];
+const QUEUE_COLUMN: [&str; 2] = ["queue", "cola"];
+const SENT_COLUMN: [&str; 4] = ["sent", "enviada", "date", "fecha"];
+const STATUS_COLUMN: [&str; 2] = ["status", "estado"];
+
+/// A table of one of Tablón's pages, with the text of its cells.
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Index of the column whose header is any of the given names, ignoring its case and a trailing
+    /// colon.
+    fn column(&self, names: &[&str]) -> Option<usize> {
+        self.headers.iter().position(|header| {
+            let header = header.trim_end_matches(':').trim().to_lowercase();
+            names.contains(&header.as_str())
+        })
+    }
+}
+
+/// Text of a piece of HTML, with its tags stripped and its whitespace collapsed.
+fn html_text(html: &str) -> String {
+    TAG_REGEX
+        .replace_all(html, " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses the tables of a page. The headers of each table are the `<th>` cells of its first row
+/// having them, and its rows, the ones having `<td>` cells.
+fn parse_tables(html: &str) -> Vec<Table> {
+    TABLE_REGEX
+        .captures_iter(html)
+        .map(|table| {
+            let mut headers = Vec::new();
+            let mut rows = Vec::new();
+            for row in ROW_REGEX.captures_iter(&table[2]) {
+                let cells: Vec<String> = CELL_REGEX
+                    .captures_iter(&row[2])
+                    .map(|cell| html_text(&cell[2]))
+                    .collect();
+                if !cells.is_empty() {
+                    rows.push(cells);
+                } else if headers.is_empty() {
+                    headers = HEADER_REGEX
+                        .captures_iter(&row[2])
+                        .map(|header| html_text(&header[2]))
+                        .collect();
+                }
+            }
+            Table { headers, rows }
+        })
+        .collect()
+}
+
+/// Finds the first table of a page having all the given columns (each, by any of its names; see
+/// `Table::column`), returning it with the indices of those columns.
+fn find_table<const N: usize>(html: &str, columns: [&[&str]; N]) -> Option<(Table, [usize; N])> {
+    parse_tables(html).into_iter().find_map(|table| {
+        let mut indices = [0; N];
+        for (index, names) in indices.iter_mut().zip(columns) {
+            *index = table.column(names)?;
+        }
+        Some((table, indices))
+    })
+}
+
+/// Parses the teams whose identifier starts with the given prefix (e.g. "g01"), and their
+/// passwords, from the first table of a page with "Team" and "Password" columns (or "Equipo" and
+/// "Contraseña"), wherever they are among the others. Teams without a password are skipped.
+///
+/// Returns `None` if the page has no such table.
+fn parse_team_passwords(html: &str, prefix: &str) -> Option<HashMap<String, String>> {
+    let (table, [team, password]) = find_table(html, [&TEAM_COLUMN, &PASSWORD_COLUMN])?;
+    let is_team_id = |team: &str| {
+        team.strip_prefix(prefix)
+            .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+    };
+
+    let passwords = table
+        .rows
+        .iter()
+        .filter_map(|row| Some((row.get(team)?, row.get(password)?)))
+        .filter(|(team, password)| is_team_id(team) && !password.is_empty())
+        .map(|(team, password)| (team.clone(), password.clone()))
+        .collect();
+
+    Some(passwords)
+}
+
+/// A request of a team, as listed by Tablón.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, PartialEq, serde::Serialize)]
+pub struct TablonRequest {
+    pub id: u16,
+    pub queue: String,
+    /// When the request was sent, as shown by Tablón.
+    pub sent: String,
+    /// Status (or verdict) of the request.
+    pub status: String,
+}
+
+/// Parses the requests from the first table of a page with "Request", "Queue", "Sent" and "Status"
+/// columns (or their Spanish equivalents, see `REQUEST_COLUMN` and the like), wherever they are
+/// among the others. Rows without a request identifier (e.g. a "no requests" notice) are skipped.
+///
+/// Returns `None` if the page has no such table.
+fn parse_team_requests(html: &str) -> Option<Vec<TablonRequest>> {
+    let (table, [id, queue, sent, status]) = find_table(
+        html,
+        [&REQUEST_COLUMN, &QUEUE_COLUMN, &SENT_COLUMN, &STATUS_COLUMN],
+    )?;
+
+    let requests = table
+        .rows
+        .iter()
+        .filter_map(|row| {
+            Some(TablonRequest {
+                id: row.get(id)?.parse().ok()?,
+                queue: row.get(queue)?.clone(),
+                sent: row.get(sent)?.clone(),
+                status: row.get(status)?.clone(),
+            })
+        })
+        .collect();
+
+    Some(requests)
+}
+
+/// Whether a page of Tablón is its login form, which it may show instead of rejecting the
+/// credentials.
+fn is_login_page(html: &str) -> bool {
+    PASSWORD_REGEX.is_match(html)
+}
+
+/// Whether a request status from Tablón corresponds to a request that has not finished yet (so it
+/// is not a verdict, and should not be cached).
+pub fn is_pending(status: &str) -> bool {
+    let status = status.to_lowercase();
+    PENDING_STATUSES
+        .iter()
+        .any(|pending| status.contains(pending))
+}
+
+/// Whether a request verdict from Tablón corresponds to an unsuccessful request.
+pub fn is_failure(verdict: &str) -> bool {
+    let verdict = verdict.to_lowercase();
+    FAILURE_STATUSES
+        .iter()
+        .any(|failure| verdict.contains(failure))
+}
+
+/// Extracts the result (i.e. the first number, usually the execution time, where lower is better)
+/// reported in a request verdict from Tablón, if any.
+pub fn verdict_result(verdict: &str) -> Option<f64> {
+    NUMBER_REGEX
+        .find(verdict)
+        .and_then(|number| number.as_str().parse::<f64>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn team_passwords_by_columns() {
+        let html = include_str!("../fixtures/tablon/admin_teams.html");
+
+        // Names looking like team identifiers, and teams with other prefixes, are not confused with
+        // them:
+        assert_eq!(
+            parse_team_passwords(html, "g"),
+            Some(HashMap::from([
+                ("g01".to_string(), "alpha123".to_string()),
+                ("g02".to_string(), "beta456".to_string()),
+                ("g10".to_string(), "gamma789".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn team_passwords_in_any_order() {
+        let html = "<table><tr><th>Password:</th><th>Notes</th><th>TEAM</th></tr>\
+            <tr><td>p1</td><td>g02 g03</td><td>g01</td></tr>\
+            <tr><td></td><td></td><td>g02</td></tr>\
+            <tr><td colspan=\"3\">End of the list</td></tr></table>";
+
+        assert_eq!(
+            parse_team_passwords(html, "g"),
+            Some(HashMap::from([("g01".to_string(), "p1".to_string())]))
+        );
+    }
+
+    #[test]
+    fn team_passwords_without_table() {
+        let html = "<table><tr><th>Team</th><th>Name</th></tr><tr><td>g01</td><td>x</td></tr>\
+            </table><p>g02 beta456</p>";
+
+        assert_eq!(parse_team_passwords(html, "g"), None);
+        assert_eq!(parse_team_passwords("<p>Not found</p>", "g"), None);
+    }
+
+    #[test]
+    fn team_requests_by_columns() {
+        let html = include_str!("../fixtures/tablon/admin_requests.html");
+
+        let requests = parse_team_requests(html).unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[1],
+            TablonRequest {
+                id: 2,
+                queue: "cola-1".to_string(),
+                sent: "2025-03-10 12:03:41".to_string(),
+                status: "Accepted: 1.234 s".to_string(),
+            }
+        );
+        assert_eq!(requests[2].queue, "cola-2");
+    }
+
+    #[test]
+    fn team_requests_in_english() {
+        let html = "<table><thead><tr><th>Status</th><th>Request</th><th>Queue</th>\
+            <th>Sent</th></tr></thead><tbody><tr><td colspan=\"4\">No requests yet</td></tr>\
+            <tr><td>Queued</td><td>7</td><td>q</td><td>today</td></tr></tbody></table>";
+
+        assert_eq!(
+            parse_team_requests(html),
+            Some(vec![TablonRequest {
+                id: 7,
+                queue: "q".to_string(),
+                sent: "today".to_string(),
+                status: "Queued".to_string(),
+            }])
+        );
+        assert_eq!(parse_team_requests("<p>Not found</p>"), None);
+    }
+
+    #[test]
+    fn host_turns_are_spaced() {
+        let client = Tablon::default();
+        let now = Instant::now();
+
+        // Requests to a host at once are spaced, in order, but not the ones to other hosts:
+        assert_eq!(client.take_host_turn("tablon.example", now), now);
+        assert_eq!(
+            client.take_host_turn("tablon.example", now),
+            now + HOST_SPACING
+        );
+        assert_eq!(
+            client.take_host_turn("tablon.example", now + HOST_SPACING / 2),
+            now + 2 * HOST_SPACING
+        );
+        assert_eq!(client.take_host_turn("other.example", now), now);
+
+        // Once the host is idle, requests are sent right away:
+        let later = now + 10 * HOST_SPACING;
+        assert_eq!(client.take_host_turn("tablon.example", later), later);
+    }
+
+    #[test]
+    fn login_pages() {
+        assert!(is_login_page(include_str!("../fixtures/tablon/login.html")));
+        assert!(!is_login_page(include_str!("../fixtures/tablon/team.html")));
+    }
+}
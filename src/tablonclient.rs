@@ -0,0 +1,275 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Safe construction of the invocations of Tablón's client program, used to send requests.
+//!
+//! The client is always run directly (never through a shell), with each argument passed
+//! separately, so user-supplied arguments cannot inject commands. The arguments are validated, so
+//! they cannot override the credentials either, and the password is never shown in the logs.
+
+use crate::redact;
+use tokio::process::Command;
+
+/// Options of the client reserved for the credentials, which users cannot pass themselves, as
+/// short options (`-u alice`, `-ualice`, or grouped as in `-vu alice`) and long ones (`--user
+/// alice`, `--user=alice`, or abbreviated as in `--us alice`).
+const RESERVED_OPTIONS: [(char, &str); 2] = [('u', "--user"), ('x', "--password")];
+
+/// Reserved option (see `RESERVED_OPTIONS`) given by an argument, if any.
+fn reserved_option(arg: &str) -> Option<String> {
+    if let Some(long) = arg.strip_prefix("--") {
+        // The client may take an abbreviation of a long option as the option itself:
+        let name = long.split('=').next().unwrap_or_default();
+        return RESERVED_OPTIONS
+            .iter()
+            .find(|(_, reserved)| !name.is_empty() && reserved[2..].starts_with(name))
+            .map(|(_, reserved)| reserved.to_string());
+    }
+
+    // A group of short options ends with the first one taking a value, which may be attached:
+    let short = arg.strip_prefix('-')?;
+    for c in short.chars() {
+        if let Some((reserved, _)) = RESERVED_OPTIONS.iter().find(|(reserved, _)| *reserved == c) {
+            return Some(format!("-{}", reserved));
+        }
+        if !c.is_ascii_alphabetic() {
+            break;
+        }
+    }
+
+    None
+}
+
+/// Invocation of the client for sending a program to Tablón.
+pub struct ClientInvocation {
+    /// Path of the client program.
+    client: String,
+    /// Path of the program to send.
+    program: String,
+    /// Team to authenticate as.
+    team: String,
+    /// Password of the team.
+    password: String,
+    /// Additional (validated) arguments: queue, threads, processes, and program arguments.
+    args: Vec<String>,
+}
+
+impl ClientInvocation {
+    pub fn new(
+        client: String,
+        program: String,
+        team: String,
+        password: String,
+        args: Vec<String>,
+    ) -> ClientInvocation {
+        Self {
+            client,
+            program,
+            team,
+            password,
+            args,
+        }
+    }
+
+    /// Path of the client program.
+    pub fn client(&self) -> &str {
+        &self.client
+    }
+
+    /// Path of the program to send.
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Additional arguments.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Builds the command to run the client.
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.client);
+        cmd.arg(&self.program)
+            .arg("-u")
+            .arg(&self.team)
+            .arg("-x")
+            .arg(&self.password)
+            .args(&self.args);
+
+        cmd
+    }
+
+    /// Equivalent command line, with the password redacted and every argument quoted as needed,
+    /// for logging purposes.
+    pub fn redacted(&self) -> String {
+        let mut parts = vec![
+            shell_quote(&self.client),
+            shell_quote(&self.program),
+            "-u".to_string(),
+            shell_quote(&self.team),
+            "-x".to_string(),
+            redact::PLACEHOLDER.to_string(),
+        ];
+        parts.extend(self.args.iter().map(|arg| shell_quote(arg)));
+
+        parts.join(" ")
+    }
+}
+
+/// Splits user-supplied arguments as a POSIX shell would (supporting single and double quotes, and
+/// backslash escapes), without expanding anything, and validates them.
+pub fn split_args(args: &str) -> Result<Vec<String>, String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = args.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_control() && !c.is_whitespace() => {
+                return Err("control characters are not allowed".to_string());
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    result.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => current.push(c),
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                match chars.next() {
+                    Some(c) => current.push(c),
+                    None => return Err("trailing backslash".to_string()),
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_arg {
+        result.push(current);
+    }
+
+    if let Some(reserved) = result.iter().find_map(|arg| reserved_option(arg)) {
+        return Err(format!(
+            "the `{}` option is reserved for your team's credentials",
+            reserved
+        ));
+    }
+
+    Ok(result)
+}
+
+/// Quotes an argument for a POSIX shell, if needed, so it is shown (and can be split back by
+/// `split_args`) unambiguously.
+pub fn shell_quote(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Joins arguments into a single string that `split_args` splits back into the same arguments.
+pub fn join_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Checks that the name of an uploaded file is safe to use as a file name in the guild's directory
+/// (i.e., a plain name, which cannot point somewhere else).
+pub fn is_safe_filename(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.+".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_the_credential_options() {
+        for args in [
+            "-u alice",
+            "-x hunter2",
+            "-ualice",
+            "-xhunter2",
+            "-vx hunter2",
+            "queue -t 4 -u alice",
+            "--user alice",
+            "--user=alice",
+            "--password=hunter2",
+            "--us alice",
+            "--u=alice",
+            "--pass hunter2",
+            "--p=hunter2",
+            "'-u' alice",
+        ] {
+            let e = split_args(args).expect_err(args);
+            assert!(e.contains("reserved"), "{}: {}", args, e);
+        }
+    }
+
+    #[test]
+    fn accepts_other_options() {
+        assert_eq!(
+            split_args("queue -t 4 -p 2 --users=3 -- -5 'a b'").unwrap(),
+            [
+                "queue",
+                "-t",
+                "4",
+                "-p",
+                "2",
+                "--users=3",
+                "--",
+                "-5",
+                "a b"
+            ]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use crate::alerts::{self, AlertKind};
+use chrono::Utc;
+use getset::{CopyGetters, Getters};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Time to wait before restarting a crashed task.
+const RESTART_DELAY: Duration = Duration::from_secs(10);
+
+/// Status of a background task, as tracked by the `TaskManager`.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Default, Getters, CopyGetters)]
+pub struct TaskStatus {
+    /// When the task last finished a run, as a UNIX timestamp (in seconds).
+    #[getset(get_copy = "pub")]
+    last_run: Option<i64>,
+    /// The error of the last failed run (or crash) of the task, if any.
+    #[getset(get = "pub")]
+    last_error: Option<String>,
+    /// When the task will run next, as a UNIX timestamp (in seconds).
+    #[getset(get_copy = "pub")]
+    next_run: Option<i64>,
+    /// How many times the task has crashed, and been restarted.
+    #[getset(get_copy = "pub")]
+    restarts: u32,
+}
+
+/// Supervisor of the bot's periodic background tasks (e.g. the scheduler), keeping track of their
+/// status and restarting them when they crash.
+#[derive(Default)]
+pub struct TaskManager {
+    tasks: Mutex<BTreeMap<String, TaskStatus>>,
+}
+
+impl TaskManager {
+    pub fn new() -> Arc<TaskManager> {
+        Arc::new(Self::default())
+    }
+
+    /// Returns a snapshot of the status of every task, by name.
+    pub fn statuses(&self) -> BTreeMap<String, TaskStatus> {
+        self.tasks.lock().unwrap().clone()
+    }
+
+    /// Updates the status of a task.
+    fn update(&self, name: &str, update: impl FnOnce(&mut TaskStatus)) {
+        update(
+            self.tasks
+                .lock()
+                .unwrap()
+                .entry(name.to_string())
+                .or_default(),
+        );
+    }
+
+    /// Spawns a task that runs `run` every `period` (the first time, right away), unless a task
+    /// with the same name was already spawned.
+    ///
+    /// Errors returned by `run` are recorded, and the task keeps running. If it panics, the crash
+    /// is recorded, and the task is restarted after a short delay.
+    pub fn spawn_periodic<F, Fut>(self: &Arc<Self>, name: &str, period: Duration, run: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        {
+            let mut tasks = self.tasks.lock().unwrap();
+            if tasks.contains_key(name) {
+                return;
+            }
+            tasks.insert(name.to_string(), TaskStatus::default());
+        }
+
+        let manager = Arc::clone(self);
+        let name = name.to_string();
+        let run = Arc::new(run);
+        tokio::spawn(async move {
+            loop {
+                let task = tokio::spawn(Self::run_periodic(
+                    Arc::clone(&manager),
+                    name.clone(),
+                    period,
+                    Arc::clone(&run),
+                ));
+                // The task only finishes when it crashes:
+                let Err(e) = task.await else {
+                    break;
+                };
+                alerts::alert(
+                    AlertKind::TaskCrash,
+                    format!("Background task {} crashed, restarting it: {}", name, e),
+                );
+                manager.update(&name, |status| {
+                    status.last_error = Some(format!("crashed: {}", e));
+                    status.restarts += 1;
+                    status.next_run = Some(Utc::now().timestamp() + RESTART_DELAY.as_secs() as i64);
+                });
+                tokio::time::sleep(RESTART_DELAY).await;
+            }
+        });
+    }
+
+    /// Runs a periodic task, forever, recording its status.
+    async fn run_periodic<F, Fut>(manager: Arc<Self>, name: String, period: Duration, run: Arc<F>)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+
+            let result = run().await;
+            if let Err(e) = &result {
+                tracing::warn!("[tasks] Task {} failed: {}", name, e);
+            }
+            manager.update(&name, |status| {
+                let now = Utc::now().timestamp();
+                status.last_run = Some(now);
+                status.next_run = Some(now + period.as_secs() as i64);
+                if let Err(e) = result {
+                    status.last_error = Some(e);
+                }
+            });
+        }
+    }
+}
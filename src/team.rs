@@ -15,7 +15,17 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use crate::{student, student::Student, team, utils};
+use crate::{
+    activityfeed::Feed,
+    batch::Batch,
+    cache::Caches,
+    events::{self, Event},
+    i18n::{tr, Language},
+    student,
+    student::Student,
+    team, utils,
+    utils::BotConfig,
+};
 use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
 use serenity::all::{GuildId, UserId};
@@ -34,7 +44,7 @@ use std::{
 /// Confirmed teams are "definitive", and ready to be used to authenticate in Tablón (if a password
 /// has been set).
 #[cfg_attr(debug_assertions, derive(Debug))]
-#[derive(Serialize, Deserialize, Getters, CopyGetters)]
+#[derive(Clone, Serialize, Deserialize, Getters, CopyGetters)]
 pub struct Team {
     /// Team identifier (immutable).
     #[getset(get = "pub")]
@@ -54,35 +64,130 @@ pub struct Team {
     /// Status of the formation of the team.
     #[getset(get_copy = "pub")]
     confirmed: bool,
+    /// Requests sent by the team's members to Tablón (see `SubmissionBudget`).
+    #[serde(default)]
+    #[getset(get = "pub")]
+    submissions: SubmissionBudget,
+    /// Whether the team appears in public leaderboard posts under its ID only, instead of its
+    /// name (see `Team::public_name`). Admin exports always show its name.
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    anonymous: bool,
+    /// When the team got its current password, as a UNIX timestamp (in seconds), to detect teams
+    /// that never use it (see `inactivity`). Unknown for passwords set by older versions.
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    password_since: Option<i64>,
+    /// Whether the team was already reminded that it has not sent any request since it got its
+    /// current password (see `inactivity`).
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    nudged: bool,
+}
+
+/// Record of the recent requests of a team to Tablón, to enforce the guild's cooldown and daily
+/// limit on the whole team, as Tablón's accounts are per team.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Default, Serialize, Deserialize, Getters, CopyGetters)]
+pub struct SubmissionBudget {
+    /// UNIX timestamp (in seconds) of the last request.
+    #[getset(get_copy = "pub")]
+    last: Option<i64>,
+    /// Member who sent the last request.
+    #[getset(get_copy = "pub")]
+    last_by: Option<UserId>,
+    /// Day (as `YYYY-MM-DD`, in the guild's timezone) `count` refers to.
+    #[getset(get = "pub")]
+    day: Option<String>,
+    /// Number of requests sent on `day`.
+    #[getset(get_copy = "pub")]
+    count: u16,
+}
+
+impl SubmissionBudget {
+    /// Number of requests sent on the given day (as `YYYY-MM-DD`).
+    pub fn count_on(&self, day: &str) -> u16 {
+        if self.day.as_deref() == Some(day) {
+            self.count
+        } else {
+            0
+        }
+    }
+}
+
+/// Reasons why a team cannot take a name (see `Team::change_name`).
+#[derive(Debug, PartialEq)]
+pub enum NameError {
+    /// Another team already has the name.
+    Taken { team: String },
+    /// The name is the identifier of another team (existing or yet to be created).
+    Reserved,
+}
+
+impl NameError {
+    /// Message to reply with, in the given language.
+    pub fn message(&self, lang: Language) -> String {
+        match self {
+            NameError::Taken { team } => tr!(
+                lang,
+                "That name is already in use by team {}.",
+                "Ese nombre ya lo usa el equipo {}.",
+                team
+            ),
+            NameError::Reserved => tr!(
+                lang,
+                "Team identifiers cannot be used as names of other teams.",
+                "Los identificadores de equipo no se pueden usar como nombres de otros equipos."
+            ),
+        }
+    }
 }
 
 impl Team {
-    /// Constructor for a team given the identifier of the guild it belongs to, and the team's
-    /// identifier.
-    pub fn new(guild_id: GuildId, id: String) -> Team {
-        // Get the password for the team, if in the guild's team info:
-        let pass = match team::get_guild_team_info(&guild_id) {
-            Some(info) => info.passwords.get(&id).cloned(),
-            None => None,
-        };
+    /// Constructor for a team given the team information of the guild it belongs to (which holds
+    /// its password, if already set), and the team's identifier.
+    pub fn new(info: &GuildTeamInfo, id: String) -> Team {
+        let res = Self::with_id(info, id);
+
+        res.save();
+        res.register_id();
+
+        res
+    }
 
-        let res = Self {
+    /// Constructor for a team with no members yet, without saving it nor registering its identifier
+    /// in the guild's name map (see `new`).
+    pub fn with_id(info: &GuildTeamInfo, id: String) -> Team {
+        let pass = info.passwords.get(&id).cloned();
+        Self {
             id: id.clone(),
+            password_since: pass.as_ref().map(|_| chrono::Utc::now().timestamp()),
             pass,
-            guild: guild_id,
+            guild: info.guild_id,
             name: id,
             members: HashSet::with_capacity(2),
             confirmed: false,
-        };
-
-        res.save();
-
-        // Add team to the corresponding guild's name map:
-        let mut name_map = utils::load_namemap(&res.guild);
-        name_map.insert(res.id.clone(), res.id.clone());
-        utils::update_namemap_persistence(&name_map, &res.guild);
+            submissions: SubmissionBudget::default(),
+            anonymous: false,
+            nudged: false,
+        }
+    }
 
-        res
+    /// Adds a new team's identifier to the guild's name map (where it takes precedence over any
+    /// team named like it, which `change_name` prevents).
+    pub fn register_id(&self) {
+        utils::update_namemap(&self.guild, |name_map| {
+            if let Some(other) = name_map.insert(self.id.clone(), self.id.clone()) {
+                if other != self.id {
+                    tracing::warn!(
+                        "[Team {}-{}] Team {} was named like the new team's identifier.",
+                        self.guild,
+                        self.id,
+                        other
+                    );
+                }
+            }
+        });
     }
 
     /// Adds the given user to the team. If the team reaches its maximum capacity, it also confirms
@@ -90,78 +195,235 @@ impl Team {
     ///
     /// Team capacity must have been set as an environmental variable beforehand.
     pub fn add_member(&mut self, student: &mut Student) {
-        if !self.members.insert(student.id().clone()) {
+        if !self.insert_member(student) {
             return;
         }
 
-        student.add_team(self.guild.clone(), self.id.clone(), self.pass.clone());
-
+        student.save();
         self.save();
+        events::record(
+            &self.guild,
+            Event::TeamJoined {
+                team: self.id.clone(),
+                user: student.id(),
+            },
+        );
     }
 
-    /// Changes the team's name, for customization purposes.
-    pub fn change_name(&mut self, name: String) {
-        let mut name_map = utils::load_namemap(&self.guild);
+    /// Adds the given user to the team, and the team to the student, returning whether they were
+    /// not a member yet.
+    ///
+    /// Neither is saved; this is meant to be done by the caller (see `service::Storage`).
+    pub fn insert_member(&mut self, student: &mut Student) -> bool {
+        if !self.members.insert(student.id()) {
+            return false;
+        }
 
-        if name_map.contains_key(name.as_str()) {
-            return;
+        student.set_team(self.guild, self.id.clone(), self.pass.clone());
+
+        true
+    }
+
+    /// Removes the given user from the team, and the team from the student, returning whether they
+    /// were a member.
+    ///
+    /// Neither is saved; this is meant to be done by the caller (see `service::Storage`).
+    pub fn take_member(&mut self, student: &mut Student) -> bool {
+        if !self.members.remove(&student.id()) {
+            return false;
         }
 
+        student.clear_team(&self.guild);
+
+        true
+    }
+
+    /// Name to show in public leaderboard posts: the team's ID if it is anonymous, or its name
+    /// otherwise.
+    pub fn public_name(&self) -> &String {
+        if self.anonymous {
+            &self.id
+        } else {
+            &self.name
+        }
+    }
+
+    /// Sets whether the team is anonymous in public leaderboard posts, saving it.
+    pub fn set_anonymous(&mut self, anonymous: bool) {
+        self.anonymous = anonymous;
+
+        self.save();
+    }
+
+    /// Changes the team's name, for customization purposes, releasing its previous one.
+    ///
+    /// Fails if another team already has the name, or if the name is a team identifier other than
+    /// the team's own.
+    pub fn change_name(&mut self, name: String) -> Result<(), NameError> {
+        if name != self.id
+            && get_guild_team_info(&self.guild)
+                .is_some_and(|info| info.canonical_team_id(&name).is_some())
+        {
+            return Err(NameError::Reserved);
+        }
+
+        utils::update_namemap(&self.guild, |name_map| {
+            if let Some(other) = name_map.get(&name).filter(|id| **id != self.id) {
+                return Err(NameError::Taken {
+                    team: other.clone(),
+                });
+            }
+            if self.name != self.id && name_map.get(&self.name) == Some(&self.id) {
+                name_map.remove(&self.name);
+            }
+            name_map.insert(name.clone(), self.id.clone());
+
+            Ok(())
+        })?;
         self.name = name;
-        name_map.insert(self.name.clone(), self.id.clone());
-        utils::update_namemap_persistence(&name_map, &self.guild);
 
         self.save();
+        events::record(
+            &self.guild,
+            Event::TeamRenamed {
+                team: self.id.clone(),
+                name: self.name.clone(),
+            },
+        );
+
+        Ok(())
     }
 
-    /// Sets the team's password.
-    pub fn set_password(&mut self, password: String) {
+    /// Sets the team's password, and its members' credentials, in a batch to be flushed by the
+    /// caller.
+    pub fn set_password(&mut self, password: String, batch: &mut Batch) {
+        if self.pass.as_ref() != Some(&password) {
+            self.password_since = Some(chrono::Utc::now().timestamp());
+            self.nudged = false;
+        }
         self.pass = Some(password.clone());
 
         for member in self.members.iter() {
-            student::get_student(member)
-                .expect(
-                    format!(
+            batch
+                .student(member)
+                .unwrap_or_else(|| {
+                    panic!(
                         "[Team {}-{}]Could not find student with ID {}.",
                         self.guild, self.name, member
                     )
-                    .as_str(),
-                )
+                })
                 .set_password(&self.guild, password.clone());
         }
 
+        batch.save_team(self);
+    }
+
+    /// Removes the team's password, and its members' credentials' one, in a batch to be flushed by
+    /// the caller.
+    pub fn clear_password(&mut self, batch: &mut Batch) {
+        self.pass = None;
+        self.password_since = None;
+
+        for member in self.members.iter() {
+            batch
+                .student(member)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "[Team {}-{}]Could not find student with ID {}.",
+                        self.guild, self.name, member
+                    )
+                })
+                .clear_password(&self.guild);
+        }
+
+        batch.save_team(self);
+    }
+
+    /// Notes down that the team was reminded of not having sent any request yet, saving it.
+    pub fn set_nudged(&mut self) {
+        self.nudged = true;
+
         self.save();
     }
 
+    /// Records a request sent to Tablón by a member, at the given UNIX timestamp (in seconds), and
+    /// counts it in the given day (as `YYYY-MM-DD`, in the guild's timezone), without saving the
+    /// team.
+    ///
+    /// Returns the team's budget before the request, to release it if it is not sent after all (see
+    /// `Team::release_submission`).
+    pub fn record_submission(
+        &mut self,
+        user: UserId,
+        timestamp: i64,
+        day: String,
+    ) -> SubmissionBudget {
+        let previous = self.submissions.clone();
+        let count = self.submissions.count_on(&day) + 1;
+        self.submissions = SubmissionBudget {
+            last: Some(timestamp),
+            last_by: Some(user),
+            day: Some(day),
+            count,
+        };
+
+        previous
+    }
+
+    /// Releases a request recorded by `Team::record_submission` (given its member, timestamp and
+    /// day) that was not sent after all, without saving the team. The last request goes back to
+    /// the one in `previous` (the budget before it), unless another member sent one since.
+    pub fn release_submission(
+        &mut self,
+        user: UserId,
+        timestamp: i64,
+        day: &str,
+        previous: &SubmissionBudget,
+    ) {
+        if self.submissions.count_on(day) > 0 {
+            self.submissions.count -= 1;
+        }
+        if self.submissions.last == Some(timestamp) && self.submissions.last_by == Some(user) {
+            self.submissions.last = previous.last;
+            self.submissions.last_by = previous.last_by;
+        }
+    }
+
     /// Removes the given user from the team.
-    pub fn remove_member(&mut self, student: &mut Student) {
-        if !self.members.remove(&student.id()) {
+    pub fn remove_member(&mut self, caches: &Caches, student: &mut Student) {
+        if !self.take_member(student) {
             return;
         }
 
-        student.remove_team(&self.guild);
+        student.save();
+        events::record(
+            &self.guild,
+            Event::TeamLeft {
+                team: self.id.clone(),
+                user: student.id(),
+            },
+        );
 
         if !self.members.is_empty() {
             self.save();
         } else {
             // Delete from the system if it was emptied out:
-            self.delete();
-            let mut name_map = utils::load_namemap(&self.guild);
-            name_map.remove(&self.name);
-            utils::update_namemap_persistence(&name_map, &self.guild);
-
-            // Note down a hole in the guild's team identifiers:
-            let mut info = get_existing_guild_team_info!(&self.guild);
-            info.holes.push(self.id.clone());
-            info.save();
+            self.delete(caches);
         }
     }
 
     /// Confirms the team, making it immutable.
-    pub fn confirm(&mut self) {
+    pub fn confirm(&mut self, feed: &Feed) {
         self.confirmed = true;
 
         self.save();
+        events::record_notable(
+            feed,
+            &self.guild,
+            Event::TeamConfirmed {
+                team: self.id.clone(),
+            },
+        );
     }
 
     /// Unconfirms the team, making it mutable again.
@@ -169,30 +431,54 @@ impl Team {
         self.confirmed = false;
 
         self.save();
+        events::record(
+            &self.guild,
+            Event::TeamUnconfirmed {
+                team: self.id.clone(),
+            },
+        );
     }
 
-    /// Deletes the team from the system.
-    pub fn delete(&self) {
+    /// Deletes the team from the system: its file, every entry of the guild's name map pointing to
+    /// it (so none of its names resolve to a future team reusing its identifier), and its
+    /// identifier, which is noted as a hole to be reused (see `GuildTeamInfo`).
+    pub fn delete(&self, caches: &Caches) {
         // Remove all members from the team, if any reamining:
         for member in self.members.iter() {
-            student::get_student(member)
-                .expect(
-                    format!(
+            student::get_student(caches, member)
+                .unwrap_or_else(|| {
+                    panic!(
                         "[Team {}-{}] Could not find student with ID {}.",
                         self.guild, self.id, member
                     )
-                    .as_str(),
-                )
+                })
                 .remove_team(&self.guild);
         }
 
         // Delete the persistance file for this team:
-        fs::remove_file(format!("guilds/{}/teams/{}.json", self.guild, self.id)).expect(
-            format!(
-                "[Team {}-{}] Could not delete the persistance (JSON) file for the team.",
-                self.guild, self.id,
-            )
-            .as_str(),
+        fs::remove_file(format!("guilds/{}/teams/{}.json", self.guild, self.id)).unwrap_or_else(
+            |e| {
+                panic!(
+                    "[Team {}-{}] Could not delete the persistance (JSON) file for the team: {:?}",
+                    self.guild, self.id, e
+                )
+            },
+        );
+        utils::update_namemap(&self.guild, |name_map| {
+            name_map.retain(|_, id| *id != self.id)
+        });
+
+        // Note down a hole in the guild's team identifiers:
+        let mut info = get_existing_guild_team_info!(&self.guild);
+        if let Err(e) = info.release_team(&self.id) {
+            tracing::warn!("[Team {}-{}] {}", self.guild, self.id, e);
+        }
+
+        events::record(
+            &self.guild,
+            Event::TeamDeleted {
+                team: self.id.clone(),
+            },
         );
     }
 
@@ -200,52 +486,44 @@ impl Team {
     ///
     /// Team files are saved as `<guild_id>/teams/<team_id>.json`.
     pub fn save(&self) {
-        let json = serde_json::to_string_pretty(self).expect(
-            format!(
-                "[Team {}-{}] Could not serialize team struct.",
-                self.guild, self.id
+        let json = serde_json::to_string_pretty(self).unwrap_or_else(|e| {
+            panic!(
+                "[Team {}-{}] Could not serialize team struct: {:?}",
+                self.guild, self.id, e
             )
-            .as_str(),
-        );
+        });
 
         // Suppose `guilds/<gid>/teams/` directory exists.
-        fs::write(
-            format!("guilds/{}/teams/{}.json", self.guild, self.id),
+        utils::write_file(
+            &format!("guilds/{}/teams/{}.json", self.guild, self.id),
             json,
         )
-        .expect(
-            format!(
-                "[Team {}-{}] Could not write team file to disk.",
-                self.guild, self.id
+        .unwrap_or_else(|e| {
+            panic!(
+                "[Team {}-{}] Could not write team file to disk: {:?}",
+                self.guild, self.id, e
             )
-            .as_str(),
-        );
+        });
     }
 
     /// Loads a Team instance from a JSON string and returns it.
     pub fn from_json(json: &str) -> Team {
         serde_json::from_str(json).expect("[Team] Could not parse data as valid JSON.")
     }
-
-    /// Loads a Team instance saved as JSON from disk and returns it.
-    pub fn load(guild_id: &String, team_id: &String) -> Team {
-        let json_str = fs::read_to_string(format!("guilds/{}/teams/{}.json", guild_id, team_id))
-            .expect(
-                format!(
-                    "[Team] Could not load file guilds/{}/teams/{}.json.",
-                    guild_id, team_id
-                )
-                .as_str(),
-            );
-        Self::from_json(&json_str)
-    }
 }
 
 /// Data structure grouping some persistent per-guild information about teams.
+///
+/// The team identifiers are numbered from 1 to `count`, and each of them is either in use by an
+/// existing team, or a hole to be reused. Holes are always canonical (see `canonical_team_id`),
+/// unique, and not in use by any team nor in the guild's name map (see `problems`).
+///
+/// Passwords are kept for holes, as Tablón's accounts are tied to the identifiers, not the teams.
 #[cfg_attr(debug_assertions, derive(Debug))]
-#[derive(Serialize, Deserialize, Getters, CopyGetters)]
+#[derive(Clone, Serialize, Deserialize, Getters, CopyGetters)]
 pub struct GuildTeamInfo {
     /// Identifier for the guild corresponding to this information, for convenience reasons.
+    #[getset(get_copy = "pub")]
     guild_id: GuildId,
     /// The prefix for the team identifiers.
     // It is responsibility of the bot to propagate this value if it changes in the Guild's
@@ -255,6 +533,7 @@ pub struct GuildTeamInfo {
     #[getset(get_copy = "pub")]
     count: u16,
     /// Passwords for each team, already created or future.
+    #[getset(get = "pub")]
     passwords: HashMap<String, String>,
     /// Team identifiers that were used in the past, but not anymore.
     #[getset(get = "pub")]
@@ -264,17 +543,22 @@ pub struct GuildTeamInfo {
 impl GuildTeamInfo {
     /// Constructor for a GuildTeamInfo object.
     pub fn new(guild_id: GuildId, prefix: String) -> GuildTeamInfo {
-        let res = Self {
+        let res = Self::empty(guild_id, prefix);
+
+        res.save();
+
+        res
+    }
+
+    /// Constructor for the information of a guild with no teams yet, without saving it (see `new`).
+    pub fn empty(guild_id: GuildId, prefix: String) -> GuildTeamInfo {
+        Self {
             guild_id,
             prefix,
             count: 0,
             passwords: HashMap::with_capacity(13),
             holes: Vec::new(),
-        };
-
-        res.save();
-
-        res
+        }
     }
 
     /// Updates the prefix for the guild's team's identifiers.
@@ -286,75 +570,172 @@ impl GuildTeamInfo {
 
     /// Registers a new team creation in the guild, returning the identifier it should use, and
     /// incrementing the count if a new identifier is used.
-    pub fn register_new_team(&mut self) -> String {
+    ///
+    /// Holes (and new identifiers) in use by some team, according to `in_use`, are skipped, in case
+    /// the guild's data was left inconsistent (e.g. by a crash, or a manual edit).
+    ///
+    /// The information is not saved; this is meant to be done by the caller (see
+    /// `service::Storage`).
+    pub fn next_team_id(&mut self, in_use: impl Fn(&String) -> bool) -> String {
         // Return a previously used identifier, if available:
-        if !self.holes.is_empty() {
-            let reused_id = self.holes.pop().expect(
-                format!(
-                    "[GuildTeamInfo] Could not pop a hole from the guild {}'s team identifiers.",
-                    self.guild_id
-                )
-                .as_str(),
+        while let Some(reused_id) = self.holes.pop() {
+            if !in_use(&reused_id) {
+                return reused_id;
+            }
+            tracing::warn!(
+                "[GuildTeamInfo] Dropping hole {} of guild {}, which is in use.",
+                reused_id,
+                self.guild_id
             );
-
-            self.save();
-
-            return reused_id;
         }
 
         // Increment the count and return a new team's identifier:
         self.count += 1;
+        while in_use(&self.format_team_id(self.count)) {
+            self.count += 1;
+        }
 
-        self.save();
+        self.format_team_id(self.count)
+    }
 
-        format!("{}{:02}", self.prefix, self.count)
-    }
-
-    /// Registers a specific team creation in the guild, given its identifier.
-    /// The team count is incremented accordingly.
-    /// Panics if the identifier is already in use.
-    pub fn register_specific_team(&mut self, team_id: &String) {
-        // Extract the number from the identifier, to update the count if necessary:
-        let team_num = team_id
-            .chars()
-            .skip(self.prefix.len())
-            .collect::<String>()
-            .parse::<u16>()
-            .expect(
-                format!(
-                    "[GuildTeamInfo] Could not parse team number from identifier {}.",
-                    team_id
-                )
-                .as_str(),
-            );
+    /// Formats the identifier of the team with the given number.
+    fn format_team_id(&self, team_num: u16) -> String {
+        format!("{}{:02}", self.prefix, team_num)
+    }
+
+    /// Parses a team identifier (the prefix followed by a positive number, with or without leading
+    /// zeros), returning its canonical form (e.g. `g1` and `g001` become `g01`), and its number.
+    pub fn canonical_team_id(&self, team_id: &str) -> Option<(String, u16)> {
+        let digits = team_id.strip_prefix(self.prefix.as_str())?;
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let team_num = digits.parse::<u16>().ok().filter(|num| *num > 0)?;
+
+        Some((self.format_team_id(team_num), team_num))
+    }
+
+    /// Registers a specific team creation in the guild, given its identifier, and returns its
+    /// canonical form (see `canonical_team_id`).
+    /// The team count is incremented accordingly, and the skipped identifiers are noted as holes.
+    ///
+    /// Fails if the identifier is not valid, or is already in use.
+    pub fn register_specific_team(&mut self, team_id: &str) -> Result<String, String> {
+        let Some((team_id, team_num)) = self.canonical_team_id(team_id) else {
+            return Err(format!(
+                "`{}` is not a valid team identifier (e.g. {}).",
+                team_id,
+                self.format_team_id(1)
+            ));
+        };
 
         if team_num > self.count {
             // Add the in-between teams as holes:
-            for i in self.count..team_num {
-                self.holes.push(format!("{}{:02}", self.prefix, i));
+            for i in self.count + 1..team_num {
+                let hole = self.format_team_id(i);
+                if !self.holes.contains(&hole) {
+                    self.holes.push(hole);
+                }
             }
             self.count = team_num;
+        } else if get_team(&self.guild_id, &team_id).is_none() {
+            // A hole (or an identifier lost from them, e.g. by a crash while deleting its team):
+            self.holes.retain(|id| *id != team_id);
         } else {
-            // Check if the identifier is already in use, or a hole:
-            if self.holes.contains(&team_id) {
-                self.holes.retain(|id| id != team_id);
-            } else {
-                // FIXME MINOR: This should probably propagate an error.
-                panic!(
-                    "[GuildTeamInfo] Team identifier {} is already in use in guild {}.",
+            return Err(format!(
+                "Team identifier {} is already in use in this server.",
+                team_id
+            ));
+        }
+
+        self.save();
+
+        Ok(team_id)
+    }
+
+    /// Notes down the identifier of a deleted team (or a registered one that will not be used) as a
+    /// hole, to be reused by the next team created.
+    ///
+    /// Fails if the identifier is not canonical, was never registered, or is still in use by a
+    /// team. Releasing a hole again does nothing.
+    pub fn release_team(&mut self, team_id: &str) -> Result<(), String> {
+        let in_use = get_team(&self.guild_id, team_id).is_some();
+        if self.release(team_id, in_use)? {
+            self.save();
+        }
+
+        Ok(())
+    }
+
+    /// Notes down the identifier of a team as a hole (see `release_team`), given whether it is
+    /// still in use by a team, returning whether it was not one yet.
+    ///
+    /// The information is not saved; this is meant to be done by the caller (see
+    /// `service::Storage`).
+    pub fn release(&mut self, team_id: &str, in_use: bool) -> Result<bool, String> {
+        match self.canonical_team_id(team_id) {
+            Some((canonical, team_num)) if canonical == team_id && team_num <= self.count => {}
+            _ => {
+                return Err(format!(
+                    "Team identifier {} was never registered in guild {}.",
                     team_id, self.guild_id
-                );
+                ))
             }
         }
+        if in_use {
+            return Err(format!(
+                "Team identifier {} is still in use in guild {}.",
+                team_id, self.guild_id
+            ));
+        }
+        if self.holes.iter().any(|id| id == team_id) {
+            return Ok(false);
+        }
+        self.holes.push(team_id.to_string());
 
-        self.save();
+        Ok(true)
     }
 
-    /// Discards an identifier for a team that was registered but will not be used.
-    pub fn discard_team(&mut self, team_id: String) {
-        self.holes.push(team_id);
+    /// Checks the invariants of the guild's team identifiers (see `GuildTeamInfo`) against its teams
+    /// and name map, returning the problems found.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let team_ids: HashSet<String> = get_all_teams(&self.guild_id)
+            .into_iter()
+            .map(|team| team.id)
+            .collect();
+        let name_map = utils::load_namemap(&self.guild_id);
 
-        self.save();
+        let mut seen = HashSet::with_capacity(self.holes.len());
+        for hole in self.holes.iter() {
+            match self.canonical_team_id(hole) {
+                Some((canonical, team_num)) if canonical == *hole && team_num <= self.count => {}
+                _ => problems.push(format!("hole {} was never registered", hole)),
+            }
+            if !seen.insert(hole) {
+                problems.push(format!("hole {} is repeated", hole));
+            }
+            if team_ids.contains(hole) {
+                problems.push(format!("hole {} is in use by a team", hole));
+            }
+            if let Some((name, _)) = name_map.iter().find(|(_, id)| *id == hole) {
+                problems.push(format!(
+                    "hole {} is still named `{}` in the name map",
+                    hole, name
+                ));
+            }
+        }
+        for team_num in 1..=self.count {
+            let team_id = self.format_team_id(team_num);
+            if !team_ids.contains(&team_id) && !seen.contains(&team_id) {
+                problems.push(format!(
+                    "identifier {} is neither in use nor a hole",
+                    team_id
+                ));
+            }
+        }
+
+        problems
     }
 
     /// Sets the passwords for the guild's teams.
@@ -364,65 +745,90 @@ impl GuildTeamInfo {
         self.save();
     }
 
+    /// Sets the password for a single team of the guild.
+    pub fn set_password(&mut self, team_id: String, password: String) {
+        self.passwords.insert(team_id, password);
+
+        self.save();
+    }
+
+    /// Removes the password for a single team of the guild, returning whether it had one.
+    pub fn clear_password(&mut self, team_id: &String) -> bool {
+        if self.passwords.remove(team_id).is_none() {
+            return false;
+        }
+
+        self.save();
+
+        true
+    }
+
     /// Saves the guild's team information to disk as a JSON file.
     ///
     /// Team files are saved as `<guild_id>/teams/info.json`.
     pub fn save(&self) {
-        let json = serde_json::to_string_pretty(self).expect(
-            format!(
-                "[GuildTeamInfo] Could not serialize guild team info struct for guild {}.",
-                self.guild_id
+        let json = serde_json::to_string_pretty(self).unwrap_or_else(|e| {
+            panic!(
+                "[GuildTeamInfo] Could not serialize guild team info struct for guild {}: {:?}",
+                self.guild_id, e
             )
-            .as_str(),
-        );
+        });
 
         // Suppose `guilds/<gid>/teams/` directory exists.
-        fs::write(format!("guilds/{}/teams/info.json", self.guild_id), json).expect(
-            format!(
-                "[GuildTeamInfo] Could not write guild team info file for guild {} to disk.",
-                self.guild_id
+        utils::write_file(&format!("guilds/{}/teams/info.json", self.guild_id), json)
+            .unwrap_or_else(|e| {
+                panic!(
+                "[GuildTeamInfo] Could not write guild team info file for guild {} to disk: {:?}",
+                self.guild_id, e
             )
-            .as_str(),
-        );
-    }
-
-    /// Loads a GuildTeamInfo instance from a JSON string and returns it.
-    pub fn from_json(json: &str) -> GuildTeamInfo {
-        serde_json::from_str(json).expect("[GuildTeamInfo] Could not parse data as valid JSON.")
-    }
-
-    /// Loads a GuildTeamInfo instance saved as JSON from disk and returns it.
-    pub fn load(guild_id: &String) -> GuildTeamInfo {
-        let json_str = fs::read_to_string(format!("guilds/{}/teams/info.json", guild_id)).expect(
-            format!(
-                "[GuildTeamInfo] Could not load file guilds/{}/teams/info.json.",
-                guild_id
-            )
-            .as_str(),
-        );
-        Self::from_json(&json_str)
+            });
     }
 }
 
 /* Static methods: */
 
 /// Retrieve a Team object given its guild and ID, if it exists.
-pub fn get_team(guild_id: &GuildId, team_id: &String) -> Option<Team> {
-    if let Ok(json) =
-        fs::read_to_string(format!("guilds/{}/teams/{}.json", guild_id, team_id).as_str())
-    {
-        Some(
-            serde_json::from_str(&json).expect(
-                format!(
-                    "[Team] Could not parse guilds/{}/teams/{}.json as valid JSON.",
-                    guild_id, team_id
-                )
-                .as_str(),
-            ),
+pub fn get_team(guild_id: &GuildId, team_id: &str) -> Option<Team> {
+    // Only a missing file means the team does not exist:
+    let json = match utils::read_file(&format!("guilds/{}/teams/{}.json", guild_id, team_id)) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        json => json.unwrap_or_else(|e| {
+            panic!(
+                "[Team] Could not read guilds/{}/teams/{}.json: {:?}",
+                guild_id, team_id, e
+            )
+        }),
+    };
+
+    Some(serde_json::from_str(&json).unwrap_or_else(|e| {
+        panic!(
+            "[Team] Could not parse guilds/{}/teams/{}.json as valid JSON: {:?}",
+            guild_id, team_id, e
         )
-    } else {
-        None
-    }
+    }))
+}
+
+/// Retrieves all the teams of a guild.
+pub fn get_all_teams(guild_id: &GuildId) -> Vec<Team> {
+    let Ok(entries) = fs::read_dir(format!("guilds/{}/teams", guild_id)) else {
+        return Vec::new();
+    };
+
+    let mut teams: Vec<Team> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        // Skip the guild's team info, and any other non-team file:
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "json")
+                && path.file_stem().is_some_and(|stem| stem != "info")
+        })
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|json| Team::from_json(&json))
+        .collect();
+    // Sort by number (canonical identifiers only differ in length from the 100th team on):
+    teams.sort_by(|a, b| (a.id.len(), &a.id).cmp(&(b.id.len(), &b.id)));
+
+    teams
 }
 
 macro_rules! get_existing_team {
@@ -438,30 +844,25 @@ macro_rules! get_existing_team {
 }
 pub(crate) use get_existing_team;
 
-/// Retrieve a Team object given its guild and ID, if it exists, or create it otherwise.
-pub fn get_or_create_team(guild_id: &GuildId, team_id: &String) -> Team {
-    if let Some(team) = get_team(guild_id, team_id) {
-        team
-    } else {
-        Team::new(guild_id.clone(), team_id.clone())
-    }
-}
-
 /// Retrieve a GuildTeamInfo object given its guild, if it exists.
 pub fn get_guild_team_info(guild_id: &GuildId) -> Option<GuildTeamInfo> {
-    if let Ok(json) = fs::read_to_string(format!("guilds/{}/teams/info.json", guild_id).as_str()) {
-        Some(
-            serde_json::from_str(&json).expect(
-                format!(
-                    "[GuildTeamInfo] Could not parse guilds/{}/teams/info.json as valid JSON.",
-                    guild_id
-                )
-                .as_str(),
-            ),
+    // Only a missing file means the guild has no team info yet:
+    let json = match utils::read_file(&format!("guilds/{}/teams/info.json", guild_id)) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        json => json.unwrap_or_else(|e| {
+            panic!(
+                "[GuildTeamInfo] Could not read guilds/{}/teams/info.json: {:?}",
+                guild_id, e
+            )
+        }),
+    };
+
+    Some(serde_json::from_str(&json).unwrap_or_else(|e| {
+        panic!(
+            "[GuildTeamInfo] Could not parse guilds/{}/teams/info.json as valid JSON: {:?}",
+            guild_id, e
         )
-    } else {
-        None
-    }
+    }))
 }
 
 macro_rules! get_existing_guild_team_info {
@@ -477,13 +878,521 @@ macro_rules! get_existing_guild_team_info {
 }
 pub(crate) use get_existing_guild_team_info;
 
-/// Registers a new team creation in the given guild, returning the identifier it should use, and
-/// incrementing the count.
+/// Propagates a change of the team prefix in a guild's configuration to the guild's team info,
+/// if it exists.
+///
+/// Existing teams keep their identifiers; see `migrate_prefix` to rename them too.
+pub fn propagate_prefix(guild_id: &GuildId, config: &BotConfig) {
+    if let Some(mut info) = get_guild_team_info(guild_id) {
+        info.update_prefix(config.team_prefix.clone());
+    }
+}
+
+/// Changes the prefix of a guild's team identifiers, renaming every existing team accordingly
+/// (e.g. `g01` becomes `t01`): its file, its entries in the guild's name map, its password, and
+/// its members' credentials and the team requests sent on its behalf. Returns the number of renamed
+/// teams.
 ///
-/// This is a convenience shortcut method that avoids having to retrieve the guild's team info
-/// object.
+/// Nothing is changed if some team cannot be renamed (i.e. its identifier does not have the
+/// current prefix, or its new one is already in use). The guild's configuration is not updated.
 ///
-/// The guild's team info object must have been created beforehand.
-pub fn register_team(guild_id: &GuildId) -> String {
-    get_existing_guild_team_info!(guild_id).register_new_team()
+/// Past requests, history and events keep the old identifiers.
+pub fn migrate_prefix(
+    caches: &Caches,
+    guild_id: &GuildId,
+    new_prefix: &str,
+) -> Result<usize, String> {
+    let Some(mut info) = get_guild_team_info(guild_id) else {
+        return Ok(0);
+    };
+    let rename = |team_id: &str| {
+        info.canonical_team_id(team_id)
+            .map(|(_, team_num)| format!("{}{:02}", new_prefix, team_num))
+    };
+
+    // Check every team can be renamed before changing anything:
+    let teams = get_all_teams(guild_id);
+    let mut renames = HashMap::with_capacity(teams.len());
+    for team in teams.iter() {
+        let Some(new_id) = rename(&team.id) else {
+            return Err(format!(
+                "Team {} does not have the current prefix (`{}`), so it cannot be renamed.",
+                team.id, info.prefix
+            ));
+        };
+        renames.insert(team.id.clone(), new_id);
+    }
+    let name_map = utils::load_namemap(guild_id);
+    for (old_id, new_id) in renames.iter() {
+        if renames.contains_key(new_id) {
+            return Err(format!(
+                "Team {} would be renamed to {}, which is already in use.",
+                old_id, new_id
+            ));
+        }
+        if name_map.get(new_id).is_some_and(|id| id != old_id) {
+            return Err(format!(
+                "Team {} would be renamed to {}, which is the name of team {}.",
+                old_id, new_id, name_map[new_id]
+            ));
+        }
+    }
+
+    // Rename the teams, and their members' credentials:
+    let mut batch = Batch::new(caches);
+    for mut team in teams {
+        let new_id = renames[&team.id].clone();
+        for member in team.members.iter() {
+            batch
+                .student(member)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "[Team {}-{}] Could not find student with ID {}.",
+                        team.guild, team.id, member
+                    )
+                })
+                .set_team_id(guild_id, new_id.clone());
+        }
+        if team.name == team.id {
+            team.name = new_id.clone();
+        }
+        team.id = new_id;
+        batch.save_team(&team);
+    }
+    // Rename the teams of the pending team requests:
+    for student in student::get_all_students(caches) {
+        if student
+            .get_team_requests(guild_id)
+            .is_some_and(|requests| requests.iter().any(|r| renames.contains_key(r.team_id())))
+        {
+            batch
+                .student(&student.id())
+                .unwrap_or_else(|| {
+                    panic!(
+                        "[Team] Could not find student with ID {} in guild {}.",
+                        student.id(),
+                        guild_id
+                    )
+                })
+                .rename_team_requests(guild_id, &renames);
+        }
+    }
+    batch.flush();
+
+    // Remove the old team files, once the renamed teams are saved:
+    for (old_id, new_id) in renames.iter() {
+        fs::remove_file(format!("guilds/{}/teams/{}.json", guild_id, old_id)).unwrap_or_else(|e| {
+            panic!(
+                "[Team {}-{}] Could not delete the persistance (JSON) file for the team: {:?}",
+                guild_id, old_id, e
+            )
+        });
+        events::record(
+            guild_id,
+            Event::TeamIdChanged {
+                team: old_id.clone(),
+                new_team: new_id.clone(),
+            },
+        );
+    }
+
+    // Rename the teams in the name map (names equal to the old identifiers included):
+    utils::update_namemap(guild_id, |name_map| {
+        *name_map = name_map
+            .drain()
+            .map(|(name, id)| match renames.get(&id) {
+                Some(new_id) if name == id => (new_id.clone(), new_id.clone()),
+                Some(new_id) => (name, new_id.clone()),
+                None => (name, id),
+            })
+            .collect();
+    });
+
+    // Rename the passwords and holes, including those of teams yet to be created:
+    let renamed = renames.len();
+    let passwords = info
+        .passwords
+        .iter()
+        .map(|(id, pass)| (rename(id).unwrap_or_else(|| id.clone()), pass.clone()))
+        .collect();
+    let holes = info
+        .holes
+        .iter()
+        .map(|id| rename(id).unwrap_or_else(|| id.clone()))
+        .collect();
+    info.passwords = passwords;
+    info.holes = holes;
+    info.update_prefix(new_prefix.to_string());
+
+    Ok(renamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cache::Caches, testutil::TempDataDir};
+    use proptest::prelude::*;
+    use serenity::all::UserId;
+    use std::collections::BTreeSet;
+
+    const GUILD: GuildId = GuildId::new(1);
+
+    /// Creates the files of the guild used by the tests, returning its new team info.
+    fn init_guild(caches: &Caches) -> GuildTeamInfo {
+        crate::init_guild_files(caches, &GUILD);
+        GuildTeamInfo::new(GUILD, utils::load_config(caches, &GUILD).team_prefix)
+    }
+
+    /// Creates a new team with a new student in it, returning both.
+    fn create_team(caches: &Caches, info: &mut GuildTeamInfo, user: u64) -> (Team, Student) {
+        let mut student = Student::new(caches, UserId::new(user), format!("student{}", user));
+        let team_id = info.next_team_id(|team_id| get_team(&GUILD, team_id).is_some());
+        info.save();
+        let mut team = Team::new(info, team_id);
+        team.add_member(&mut student);
+
+        (team, student)
+    }
+
+    /// Loads the current team info of the guild used by the tests.
+    fn load_info() -> GuildTeamInfo {
+        get_guild_team_info(&GUILD).unwrap()
+    }
+
+    #[test]
+    fn new_identifiers_are_consecutive() {
+        let _dir = TempDataDir::new("team-ids");
+        let caches = Caches::default();
+        let mut info = init_guild(&caches);
+        let (g01, _) = create_team(&caches, &mut info, 1);
+        let (g02, _) = create_team(&caches, &mut info, 2);
+
+        assert_eq!(g01.id(), "g01");
+        assert_eq!(g02.id(), "g02");
+        assert_eq!(info.count(), 2);
+        assert!(info.problems().is_empty());
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let _dir = TempDataDir::new("team-names");
+        let caches = Caches::default();
+        let mut info = init_guild(&caches);
+        let (mut g01, _) = create_team(&caches, &mut info, 1);
+        let (mut g02, _) = create_team(&caches, &mut info, 2);
+
+        assert!(g02.change_name("Renamed".to_string()).is_ok());
+        assert_eq!(
+            g01.change_name("Renamed".to_string()),
+            Err(NameError::Taken {
+                team: "g02".to_string(),
+            })
+        );
+
+        // Team identifiers are reserved, in any of their forms:
+        assert_eq!(g01.change_name("g2".to_string()), Err(NameError::Reserved));
+        assert_eq!(g01.change_name("g07".to_string()), Err(NameError::Reserved));
+        assert!(g01.change_name("g01".to_string()).is_ok());
+
+        // Renaming releases the previous name:
+        g01.change_name("Old name".to_string()).unwrap();
+        g01.change_name("New name".to_string()).unwrap();
+        let name_map = utils::load_namemap(&GUILD);
+        assert!(!name_map.contains_key("Old name"));
+        assert_eq!(name_map.get("New name"), Some(&"g01".to_string()));
+        assert_eq!(name_map.get("g01"), Some(&"g01".to_string()));
+    }
+
+    #[test]
+    fn emptied_teams_become_holes() {
+        let _dir = TempDataDir::new("team-holes");
+        let caches = Caches::default();
+        let mut info = init_guild(&caches);
+        let (_g01, _) = create_team(&caches, &mut info, 1);
+        let (mut g02, mut s2) = create_team(&caches, &mut info, 2);
+        create_team(&caches, &mut info, 3);
+        g02.change_name("Renamed".to_string()).unwrap();
+
+        // An emptied team leaves a hole, and none of its names behind:
+        g02.remove_member(&caches, &mut s2);
+        let name_map = utils::load_namemap(&GUILD);
+        assert_eq!(load_info().holes(), &vec!["g02".to_string()]);
+        assert!(get_team(&GUILD, g02.id()).is_none());
+        assert!(!name_map.contains_key("Renamed"));
+        assert!(!name_map.values().any(|id| id == "g02"));
+        assert!(load_info().problems().is_empty());
+
+        // Holes are released only once, and only if valid:
+        let mut info = load_info();
+        assert!(info.release_team("g02").is_ok());
+        assert_eq!(info.holes().len(), 1);
+        assert!(info.release_team("g01").is_err());
+        assert!(info.release_team("g99").is_err());
+        assert!(info.release_team("g3").is_err());
+
+        // Holes are reused before new identifiers, with a clean name:
+        let (g02, _) = create_team(&caches, &mut info, 4);
+        assert_eq!(g02.id(), "g02");
+        assert!(info.holes().is_empty());
+        assert_eq!(info.count(), 3);
+        assert_eq!(g02.name(), "g02");
+        assert!(!utils::load_namemap(&GUILD).contains_key("Renamed"));
+    }
+
+    #[test]
+    fn specific_identifiers() {
+        let _dir = TempDataDir::new("team-specific-ids");
+        let caches = Caches::default();
+        let mut info = init_guild(&caches);
+        create_team(&caches, &mut info, 1);
+        create_team(&caches, &mut info, 2);
+        create_team(&caches, &mut info, 3);
+
+        // Specific identifiers leave holes for the skipped ones, which can be taken later:
+        assert_eq!(info.register_specific_team("g5"), Ok("g05".to_string()));
+        assert_eq!(info.holes(), &vec!["g04".to_string()]);
+        Team::new(&info, "g05".to_string());
+        assert_eq!(info.register_specific_team("g004"), Ok("g04".to_string()));
+        assert!(info.holes().is_empty());
+        Team::new(&info, "g04".to_string());
+        assert!(info.register_specific_team("g03").is_err());
+        assert!(info.problems().is_empty());
+    }
+
+    #[test]
+    fn inconsistent_identifiers_are_recovered_from() {
+        let _dir = TempDataDir::new("team-recovery");
+        let caches = Caches::default();
+        let mut info = init_guild(&caches);
+        create_team(&caches, &mut info, 1);
+        create_team(&caches, &mut info, 2);
+        let (mut g03, mut s3) = create_team(&caches, &mut info, 3);
+        create_team(&caches, &mut info, 4);
+        create_team(&caches, &mut info, 5);
+        g03.remove_member(&caches, &mut s3);
+
+        // A hole in use (e.g. after a crash) is reported and skipped:
+        let mut json = serde_json::to_value(load_info()).unwrap();
+        json["holes"] = serde_json::json!(["g01", "g03"]);
+        let mut info: GuildTeamInfo = serde_json::from_value(json).unwrap();
+        assert!(info.problems().iter().any(|p| p.contains("g01")));
+        let (g03, _) = create_team(&caches, &mut info, 6);
+        let (g06, _) = create_team(&caches, &mut info, 7);
+        assert_eq!(g03.id(), "g03");
+        assert_eq!(g06.id(), "g06");
+        assert!(info.problems().is_empty());
+
+        // A lost identifier is reported, and can be registered again:
+        fs::remove_file(format!("guilds/{}/teams/g06.json", GUILD)).unwrap();
+        assert!(info.problems().iter().any(|p| p.contains("g06")));
+        assert_eq!(info.register_specific_team("g06"), Ok("g06".to_string()));
+    }
+
+    #[test]
+    fn prefixes_are_migrated() {
+        let _dir = TempDataDir::new("team-migrate");
+        let caches = Caches::default();
+        let mut info = init_guild(&caches);
+        let (mut g01, mut s1) = create_team(&caches, &mut info, 1);
+        let (mut g02, _) = create_team(&caches, &mut info, 2);
+        let (mut g03, mut s3) = create_team(&caches, &mut info, 3);
+        g02.change_name("Fast".to_string()).unwrap();
+        g03.remove_member(&caches, &mut s3);
+        let mut s4 = Student::new(&caches, UserId::new(4), "student4".to_string());
+        g01.add_member(&mut s4);
+        let mut invited = Student::new(&caches, UserId::new(5), "student5".to_string());
+        crate::teamrequest::create(&mut invited, GUILD, "g02".to_string(), s1.id(), None);
+        invited.save();
+        let mut info = load_info();
+        info.set_password("g01".to_string(), "pass1".to_string());
+        info.set_password("g04".to_string(), "pass4".to_string());
+
+        assert_eq!(migrate_prefix(&caches, &GUILD, "t"), Ok(2));
+
+        // Everything is renamed, as seen after loading it again:
+        let caches = Caches::default();
+        let ids: Vec<String> = get_all_teams(&GUILD)
+            .iter()
+            .map(|team| team.id().clone())
+            .collect();
+        assert_eq!(ids, vec!["t01".to_string(), "t02".to_string()]);
+        assert!(get_team(&GUILD, "g01").is_none());
+        let t01 = get_team(&GUILD, "t01").unwrap();
+        assert_eq!(t01.name(), "t01");
+        assert_eq!(t01.members().len(), 2);
+        assert_eq!(get_team(&GUILD, "t02").unwrap().name(), "Fast");
+        s1 = student::get_student(&caches, &s1.id()).unwrap();
+        assert_eq!(s1.get_team_id(&GUILD).as_deref(), Some("t01"));
+        s4 = student::get_student(&caches, &s4.id()).unwrap();
+        assert_eq!(s4.get_team_id(&GUILD).as_deref(), Some("t01"));
+        let invited = student::get_student(&caches, &invited.id()).unwrap();
+        assert_eq!(
+            invited.get_team_requests(&GUILD).unwrap()[0].team_id(),
+            "t02"
+        );
+
+        let info = load_info();
+        assert_eq!(info.holes(), &vec!["t03".to_string()]);
+        assert_eq!(
+            info.passwords().get("t01").map(String::as_str),
+            Some("pass1")
+        );
+        assert_eq!(
+            info.passwords().get("t04").map(String::as_str),
+            Some("pass4")
+        );
+        assert!(info.passwords().keys().all(|id| id.starts_with('t')));
+        let name_map = utils::load_namemap(&GUILD);
+        assert_eq!(name_map.get("t01"), Some(&"t01".to_string()));
+        assert_eq!(name_map.get("Fast"), Some(&"t02".to_string()));
+        assert!(!name_map.contains_key("g01"));
+        assert!(info.problems().is_empty());
+    }
+
+    #[test]
+    fn prefix_migrations_are_all_or_nothing() {
+        let _dir = TempDataDir::new("team-migrate-collision");
+        let caches = Caches::default();
+        let mut info = init_guild(&caches);
+        create_team(&caches, &mut info, 1);
+        let (mut g02, _) = create_team(&caches, &mut info, 2);
+
+        // A team named as another one would be renamed to:
+        g02.change_name("t01".to_string()).unwrap();
+        assert!(migrate_prefix(&caches, &GUILD, "t").is_err());
+        assert!(get_team(&GUILD, "g01").is_some());
+        assert!(get_team(&GUILD, "t01").is_none());
+        assert_eq!(load_info().prefix, "g");
+
+        // A team whose identifier does not have the current prefix:
+        g02.change_name("Fast".to_string()).unwrap();
+        let mut stray = get_team(&GUILD, "g02").unwrap();
+        stray.id = "x02".to_string();
+        stray.save();
+        assert!(migrate_prefix(&caches, &GUILD, "t").is_err());
+        assert!(get_team(&GUILD, "g01").is_some());
+        assert_eq!(load_info().prefix, "g");
+    }
+
+    #[test]
+    fn prefix_migrations_without_teams() {
+        let _dir = TempDataDir::new("team-migrate-empty");
+        let caches = Caches::default();
+
+        // Guilds without team information yet have nothing to migrate:
+        assert_eq!(migrate_prefix(&caches, &GUILD, "t"), Ok(0));
+        assert!(get_guild_team_info(&GUILD).is_none());
+
+        // Guilds without teams only rename the passwords of the teams to be created:
+        let mut info = init_guild(&caches);
+        info.set_password("g01".to_string(), "pass1".to_string());
+        assert_eq!(migrate_prefix(&caches, &GUILD, "t"), Ok(0));
+        let mut info = load_info();
+        assert_eq!(info.prefix, "t");
+        assert_eq!(
+            info.passwords().get("t01").map(String::as_str),
+            Some("pass1")
+        );
+        assert_eq!(info.next_team_id(|_| false), "t01");
+    }
+
+    #[test]
+    fn identifiers_from_the_100th_team_on() {
+        let _dir = TempDataDir::new("team-ids-100");
+        let caches = Caches::default();
+        let mut json = serde_json::to_value(init_guild(&caches)).unwrap();
+        json["count"] = serde_json::json!(98);
+        let mut info: GuildTeamInfo = serde_json::from_value(json).unwrap();
+
+        // Identifiers grow a digit, and keep their numeric order:
+        let (g99, _) = create_team(&caches, &mut info, 1);
+        let (g100, _) = create_team(&caches, &mut info, 2);
+        assert_eq!(g99.id(), "g99");
+        assert_eq!(g100.id(), "g100");
+        let teams: Vec<String> = get_all_teams(&GUILD)
+            .iter()
+            .map(|team| team.id().clone())
+            .collect();
+        assert_eq!(teams, vec!["g99".to_string(), "g100".to_string()]);
+
+        assert_eq!(
+            info.canonical_team_id("g0100"),
+            Some(("g100".to_string(), 100))
+        );
+        assert_eq!(
+            info.canonical_team_id("g100"),
+            Some(("g100".to_string(), 100))
+        );
+        assert!(get_team(&GUILD, "g100").is_some());
+        assert_eq!(info.register_specific_team("g101"), Ok("g101".to_string()));
+    }
+
+    /// Step of the sequences of team creations and removals in `next_identifiers_are_unique`.
+    #[derive(Debug, Clone)]
+    enum Step {
+        Create,
+        /// Removes the team at the given position (modulo the number of teams), if any.
+        Remove(usize),
+    }
+
+    fn step() -> impl Strategy<Value = Step> {
+        prop_oneof![Just(Step::Create), any::<usize>().prop_map(Step::Remove)]
+    }
+
+    proptest! {
+        #[test]
+        fn next_identifiers_are_unique(steps in proptest::collection::vec(step(), 1..200)) {
+            let mut info = GuildTeamInfo::empty(GUILD, "g".to_string());
+            let mut in_use = BTreeSet::new();
+            for step in steps {
+                match step {
+                    Step::Create => {
+                        let team_id = info.next_team_id(|team_id| in_use.contains(team_id));
+                        prop_assert!(!in_use.contains(&team_id), "{} reused", team_id);
+                        let (canonical, team_num) = info.canonical_team_id(&team_id).unwrap();
+                        prop_assert_eq!(&canonical, &team_id);
+                        prop_assert!(team_num <= info.count());
+                        in_use.insert(team_id);
+                    }
+                    Step::Remove(i) if !in_use.is_empty() => {
+                        let team_id = in_use.iter().nth(i % in_use.len()).unwrap().clone();
+                        in_use.remove(&team_id);
+                        prop_assert_eq!(info.release(&team_id, false), Ok(true));
+                    }
+                    Step::Remove(_) => {}
+                }
+            }
+
+            // Every identifier up to the count is either in use or a hole:
+            prop_assert_eq!(in_use.len() + info.holes().len(), usize::from(info.count()));
+        }
+
+        #[test]
+        fn canonical_identifiers(team_num in 1..=u16::MAX, zeros in 0..4usize) {
+            let info = GuildTeamInfo::empty(GUILD, "g".to_string());
+            let team_id = format!("g{}{}", "0".repeat(zeros), team_num);
+            let canonical = format!("g{:02}", team_num);
+
+            prop_assert_eq!(
+                info.canonical_team_id(&team_id),
+                Some((canonical.clone(), team_num))
+            );
+            prop_assert_eq!(
+                info.canonical_team_id(&canonical),
+                Some((canonical.clone(), team_num))
+            );
+        }
+
+        #[test]
+        fn canonicalisation_is_idempotent(team_id in "\\PC{0,8}") {
+            let info = GuildTeamInfo::empty(GUILD, "g".to_string());
+            if let Some((canonical, team_num)) = info.canonical_team_id(&team_id) {
+                prop_assert!(canonical.starts_with('g'));
+                prop_assert_eq!(
+                    info.canonical_team_id(&canonical),
+                    Some((canonical.clone(), team_num))
+                );
+            }
+        }
+    }
 }
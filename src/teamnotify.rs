@@ -0,0 +1,310 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Notifications to the members of a team of the changes in it (e.g. someone joining it), so not
+//! only the student or admin making them hears of them, and to the students invited to a team.
+
+use crate::{
+    cache::Caches,
+    i18n::{self, tr, Language},
+    student,
+    team::Team,
+    utils,
+};
+use poise::serenity_prelude as serenity;
+use serde::{Deserialize, Serialize};
+use serenity::all::{GuildId, UserId};
+
+/// How a student wants to be told of the changes in their team.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum TeamNotifications {
+    /// By DM (or else, pinging them in the bot channel, if they don't accept DMs).
+    #[default]
+    #[name = "DM"]
+    #[serde(rename = "dm")]
+    Dm,
+    /// Pinging them in the bot channel.
+    #[name = "Bot channel"]
+    #[serde(rename = "channel")]
+    Channel,
+    /// Not at all.
+    #[name = "Off"]
+    #[serde(rename = "off")]
+    Off,
+}
+
+/// Changes in a team its members are told of.
+pub enum TeamChange {
+    /// The student joined the team.
+    Joined(UserId),
+    /// The student left the team.
+    Left(UserId),
+    /// An admin removed the student from the team (they are told too).
+    Kicked(UserId),
+    /// The team was renamed, by one of its members (or by an admin, if `None`).
+    Renamed { by: Option<UserId>, name: String },
+}
+
+impl TeamChange {
+    /// The student making the change, who already knows about it.
+    fn author(&self) -> Option<UserId> {
+        match self {
+            TeamChange::Joined(user) | TeamChange::Left(user) => Some(*user),
+            TeamChange::Kicked(_) => None,
+            TeamChange::Renamed { by, .. } => *by,
+        }
+    }
+
+    /// Message for the members of the team, in the given language.
+    fn message(&self, lang: Language, team_id: &str, guild_name: &str) -> String {
+        match self {
+            TeamChange::Joined(user) => tr!(
+                lang,
+                "👥 <@{}> has joined your team {} in **{}**.",
+                "👥 <@{}> se ha unido a tu equipo {} en **{}**.",
+                user,
+                team_id,
+                guild_name
+            ),
+            TeamChange::Left(user) => tr!(
+                lang,
+                "👥 <@{}> has left your team {} in **{}**.",
+                "👥 <@{}> ha dejado tu equipo {} en **{}**.",
+                user,
+                team_id,
+                guild_name
+            ),
+            TeamChange::Kicked(user) => tr!(
+                lang,
+                "👥 <@{}> has been removed from your team {} in **{}** by an admin.",
+                "👥 Un administrador ha sacado a <@{}> de tu equipo {} en **{}**.",
+                user,
+                team_id,
+                guild_name
+            ),
+            TeamChange::Renamed {
+                by: Some(user),
+                name,
+            } => tr!(
+                lang,
+                "👥 <@{}> has renamed your team {} in **{}** to \"{}\".",
+                "👥 <@{}> ha renombrado tu equipo {} en **{}** a \"{}\".",
+                user,
+                team_id,
+                guild_name,
+                name
+            ),
+            TeamChange::Renamed { by: None, name } => tr!(
+                lang,
+                "👥 An admin has renamed your team {} in **{}** to \"{}\".",
+                "👥 Un administrador ha renombrado tu equipo {} en **{}** a \"{}\".",
+                team_id,
+                guild_name,
+                name
+            ),
+        }
+    }
+
+    /// Message for the student removed from the team, in the given language.
+    fn kicked_message(lang: Language, team_id: &str, guild_name: &str) -> String {
+        tr!(
+            lang,
+            "👥 An admin has removed you from team {} in **{}**.",
+            "👥 Un administrador te ha sacado del equipo {} en **{}**.",
+            team_id,
+            guild_name
+        )
+    }
+}
+
+/// Tells the members of the team (as it is after the change) of a change in it, as each of them
+/// prefers (see `TeamNotifications`), except its author. Those to ping in the bot channel are
+/// mentioned in a single message there.
+pub async fn notify_members(
+    ctx: &serenity::Context,
+    caches: &Caches,
+    gid: &GuildId,
+    team: &Team,
+    change: TeamChange,
+) {
+    let config = utils::load_config(caches, gid);
+    let gname = gid
+        .name(&ctx.cache)
+        .unwrap_or_else(|| format!("guild {}", gid));
+
+    let mut recipients = team
+        .members()
+        .iter()
+        .filter(|member| Some(**member) != change.author())
+        .copied()
+        .collect::<Vec<_>>();
+    if let TeamChange::Kicked(user) = change {
+        recipients.push(user);
+    }
+    recipients.sort();
+
+    let mut to_ping = Vec::new();
+    for uid in recipients {
+        let Some(student) = student::get_student(caches, &uid) else {
+            continue;
+        };
+        let lang = i18n::user_language(&student, &config, gid);
+        let msg = match change {
+            TeamChange::Kicked(user) if user == uid => {
+                TeamChange::kicked_message(lang, team.id(), &gname)
+            }
+            _ => change.message(lang, team.id(), &gname),
+        };
+        match student.get_team_notifications(gid) {
+            TeamNotifications::Off => {}
+            TeamNotifications::Channel => to_ping.push(uid),
+            TeamNotifications::Dm => {
+                if uid
+                    .direct_message(&ctx.http, serenity::CreateMessage::new().content(msg))
+                    .await
+                    .is_err()
+                {
+                    to_ping.push(uid);
+                }
+            }
+        }
+    }
+    if to_ping.is_empty() {
+        return;
+    }
+
+    // Ping the rest in the bot channel, in the guild's language (as anyone can see it):
+    let lang = i18n::guild_language(&config);
+    let msg = match change {
+        TeamChange::Kicked(user) if to_ping == [user] => {
+            TeamChange::kicked_message(lang, team.id(), &gname)
+        }
+        _ => change.message(lang, team.id(), &gname),
+    };
+    ping_in_bot_channel(ctx, gid, &config.bot_channel, &to_ping, msg, team.id()).await;
+}
+
+/// Tells the students invited to a team of the invitation, as each of them prefers (see
+/// `TeamNotifications`). The sender's message, if any, is only included in the DMs, as the bot
+/// channel is public.
+pub async fn notify_invited(
+    ctx: &serenity::Context,
+    caches: &Caches,
+    gid: &GuildId,
+    team_id: &str,
+    sender: UserId,
+    invited: &[UserId],
+    message: Option<&str>,
+) {
+    let config = utils::load_config(caches, gid);
+    let gname = gid
+        .name(&ctx.cache)
+        .unwrap_or_else(|| format!("guild {}", gid));
+
+    let mut to_ping = Vec::new();
+    for uid in invited {
+        let Some(student) = student::get_student(caches, uid) else {
+            continue;
+        };
+        let lang = i18n::user_language(&student, &config, gid);
+        let mut msg = tr!(
+            lang,
+            "📨 <@{}> has invited you to join team {} in **{}**. Join it with `/team join {}`.",
+            "📨 <@{}> te ha invitado a unirte al equipo {} en **{}**. Únete con `/team join {}`.",
+            sender,
+            team_id,
+            gname,
+            team_id
+        );
+        if let Some(message) = message {
+            msg.push_str(format!("\n> {}", message).as_str());
+        }
+        match student.get_team_notifications(gid) {
+            TeamNotifications::Off => {}
+            TeamNotifications::Channel => to_ping.push(*uid),
+            TeamNotifications::Dm => {
+                if uid
+                    .direct_message(&ctx.http, serenity::CreateMessage::new().content(msg))
+                    .await
+                    .is_err()
+                {
+                    to_ping.push(*uid);
+                }
+            }
+        }
+    }
+    if to_ping.is_empty() {
+        return;
+    }
+
+    // Ping the rest in the bot channel, in the guild's language (as anyone can see it):
+    let msg = tr!(
+        i18n::guild_language(&config),
+        "📨 <@{}> has invited you to join team {}. Check your invitations with \
+        `/team invitations`.",
+        "📨 <@{}> te ha invitado a unirte al equipo {}. Revisa tus invitaciones con \
+        `/team invitations`.",
+        sender,
+        team_id
+    );
+    ping_in_bot_channel(ctx, gid, &config.bot_channel, &to_ping, msg, team_id).await;
+}
+
+/// Mentions the students in a single message in the guild's bot channel, about a team.
+async fn ping_in_bot_channel(
+    ctx: &serenity::Context,
+    gid: &GuildId,
+    bot_channel: &str,
+    to_ping: &[UserId],
+    msg: String,
+    team_id: &str,
+) {
+    let channel = gid.channels(&ctx.http).await.ok().and_then(|channels| {
+        channels
+            .into_values()
+            .find(|channel| channel.name == bot_channel)
+    });
+    let Some(channel) = channel else {
+        tracing::warn!(
+            "[teamnotify] Guild {} has no channel named #{} to notify team {} in.",
+            gid,
+            bot_channel,
+            team_id
+        );
+        return;
+    };
+    let mentions = to_ping
+        .iter()
+        .map(|uid| format!("<@{}>", uid))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if let Err(e) = channel
+        .send_message(
+            &ctx.http,
+            serenity::CreateMessage::new().content(format!("{} {}", mentions, msg)),
+        )
+        .await
+    {
+        tracing::warn!(
+            "[teamnotify] Could not notify the students about team {} in guild {}: {}",
+            team_id,
+            gid,
+            e
+        );
+    }
+}
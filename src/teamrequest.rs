@@ -15,9 +15,49 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+//! Team requests (invitations to join a team), and their lifecycle: they are created when a student
+//! is invited (`create`), listed by the invited student (`list`), and resolved when the student
+//! joins a team or declines them, or when their team is gone (`resolve`).
+use crate::{
+    i18n::{tr, Language},
+    student::Student,
+    team,
+};
 use getset::{CopyGetters, Getters};
 use serde::{Deserialize, Serialize};
-use serenity::all::UserId;
+use serenity::all::{GuildId, UserId};
+
+/// Maximum number of resolved team requests kept per guild for each student, besides the pending
+/// ones. The oldest ones are forgotten.
+const MAX_RESOLVED: usize = 10;
+
+/// Status of a team request.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestStatus {
+    /// The student can still join the team.
+    #[default]
+    Pending,
+    /// The student joined the team.
+    Accepted,
+    /// The student declined to join the team.
+    Declined,
+    /// The student joined another team, or the team was deleted or confirmed.
+    Expired,
+}
+
+impl RequestStatus {
+    /// Name of the status, in the given language.
+    pub fn label(&self, lang: Language) -> String {
+        match self {
+            RequestStatus::Pending => tr!(lang, "pending", "pendiente"),
+            RequestStatus::Accepted => tr!(lang, "accepted", "aceptada"),
+            RequestStatus::Declined => tr!(lang, "declined", "rechazada"),
+            RequestStatus::Expired => tr!(lang, "expired", "caducada"),
+        }
+    }
+}
 
 /// Data structure defining a team request.
 ///
@@ -30,22 +70,40 @@ pub struct TeamRequest {
     team_id: String,
     #[getset(get_copy = "pub")]
     sender_id: UserId,
+    /// When the request was sent, as a UNIX timestamp (in seconds). Unknown for requests sent
+    /// before it was recorded.
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    created_at: Option<i64>,
+    /// Message from the sender to the invited student, if any.
+    #[serde(default)]
+    #[getset(get = "pub")]
+    message: Option<String>,
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    status: RequestStatus,
+    /// Whether the invited student has already seen the request (see `list`).
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    seen: bool,
 }
 
-/// Conversion from a (String, UserId)-tuple to a `TeamRequest` object.
-impl Into<TeamRequest> for (String, UserId) {
-    fn into(self) -> TeamRequest {
-        TeamRequest {
-            team_id: self.0,
-            sender_id: self.1,
-        }
+impl TeamRequest {
+    /// Whether the student can still join the team with this request.
+    pub fn is_pending(&self) -> bool {
+        self.status == RequestStatus::Pending
+    }
+
+    /// Changes the team of the request, after the team was renamed (see `team::migrate_prefix`).
+    pub fn set_team_id(&mut self, team_id: String) {
+        self.team_id = team_id;
     }
 }
 
 /// Conversion from a `TeamRequest` object to a (String, UserId)-tuple.
-impl Into<(String, UserId)> for TeamRequest {
-    fn into(self) -> (String, UserId) {
-        (self.team_id, self.sender_id)
+impl From<TeamRequest> for (String, UserId) {
+    fn from(val: TeamRequest) -> Self {
+        (val.team_id, val.sender_id)
     }
 }
 
@@ -61,10 +119,145 @@ impl<'a> From<&'a TeamRequest> for (&'a String, &'a UserId) {
 /// Comparison of `TeamRequest` objects.
 ///
 /// Two `TeamRequest` objects are considered equal if they have the same `team_id`.
-// The way the bot is implemented right now, there cannot be two TeamRequests with the same team ID
-// and different sender.
+// The way the bot is implemented right now, there cannot be two pending TeamRequests with the same
+// team ID and different sender.
 impl PartialEq for TeamRequest {
     fn eq(&self, other: &Self) -> bool {
         self.team_id == other.team_id
     }
 }
+
+/// Forgets the oldest resolved requests beyond `MAX_RESOLVED`.
+fn prune(requests: &mut Vec<TeamRequest>) {
+    let resolved = requests.iter().filter(|req| !req.is_pending()).count();
+    let mut excess = resolved.saturating_sub(MAX_RESOLVED);
+    // Requests are kept in the order they were sent, so the first resolved ones are the oldest:
+    requests.retain(|req| {
+        if excess > 0 && !req.is_pending() {
+            excess -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+/// Invites the student to join a team of the guild. A pending request for the same team is replaced
+/// by the new one.
+///
+/// The student is not saved; this is meant to be done by the caller (see `service::Storage`).
+pub fn create(
+    student: &mut Student,
+    guild_id: GuildId,
+    team_id: String,
+    sender_id: UserId,
+    message: Option<String>,
+) {
+    let request = TeamRequest {
+        team_id,
+        sender_id,
+        created_at: Some(chrono::Utc::now().timestamp()),
+        message,
+        status: RequestStatus::Pending,
+        seen: false,
+    };
+
+    let requests = student.team_requests_mut(guild_id);
+    requests.retain(|req| !(req.is_pending() && *req == request));
+    requests.push(request);
+    prune(requests);
+}
+
+/// Retrieves the student's team requests in a guild, most recent first, marking them as seen
+/// (the returned ones keep whether they had been seen before).
+///
+/// Pending requests whose team no longer exists, or is already confirmed, are expired first. The
+/// student is saved if any request changed.
+pub fn list(student: &mut Student, guild_id: &GuildId) -> Vec<TeamRequest> {
+    if student.get_team_requests(guild_id).is_none() {
+        return Vec::new();
+    }
+
+    let requests = student.team_requests_mut(*guild_id);
+    let mut changed = false;
+    for req in requests.iter_mut().filter(|req| req.is_pending()) {
+        if team::get_team(guild_id, &req.team_id).is_none_or(|team| team.confirmed()) {
+            req.status = RequestStatus::Expired;
+            changed = true;
+        }
+    }
+    prune(requests);
+
+    let listed: Vec<TeamRequest> = requests.iter().rev().cloned().collect();
+    for req in requests.iter_mut().filter(|req| !req.seen) {
+        req.seen = true;
+        changed = true;
+    }
+    if changed {
+        student.save();
+    }
+
+    listed
+}
+
+/// Resolves the student's pending request for a team of the guild with the given status, saving
+/// the student. Returns whether there was such a request.
+///
+/// Accepting a request expires the rest of the pending ones (see `accept`).
+pub fn resolve(
+    student: &mut Student,
+    guild_id: &GuildId,
+    team_id: &str,
+    status: RequestStatus,
+) -> bool {
+    if student.get_team_requests(guild_id).is_none() {
+        return false;
+    }
+
+    let requests = student.team_requests_mut(*guild_id);
+    let Some(request) = requests
+        .iter_mut()
+        .find(|req| req.is_pending() && req.team_id == team_id)
+    else {
+        return false;
+    };
+    if status == RequestStatus::Accepted {
+        accept(requests, team_id);
+    } else {
+        request.status = status;
+        prune(requests);
+    }
+
+    student.save();
+
+    true
+}
+
+/// Accepts the pending request for a team (if any), after the student joined it, and expires the
+/// rest of the pending ones, as the student is now in a team. The student is not saved.
+pub fn accept(requests: &mut Vec<TeamRequest>, team_id: &str) {
+    for req in requests.iter_mut().filter(|req| req.is_pending()) {
+        req.status = if req.team_id == team_id {
+            RequestStatus::Accepted
+        } else {
+            RequestStatus::Expired
+        };
+    }
+    prune(requests);
+}
+
+/// Whether the student has pending requests to join teams of the guild.
+pub fn has_pending(student: &Student, guild_id: &GuildId) -> bool {
+    student
+        .get_team_requests(guild_id)
+        .is_some_and(|requests| requests.iter().any(TeamRequest::is_pending))
+}
+
+/// Whether the student has a pending request to join a team of the guild.
+pub fn is_invited(student: &Student, guild_id: &GuildId, team_id: &str) -> bool {
+    student.get_team_requests(guild_id).is_some_and(|requests| {
+        requests
+            .iter()
+            .any(|req| req.is_pending() && req.team_id == team_id)
+    })
+}
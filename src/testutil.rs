@@ -0,0 +1,67 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Helpers for the tests using the bot's data directory, which is the working directory of the
+//! whole process.
+use crate::utils;
+use std::{
+    env, fs,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+};
+
+/// Held by the test using the data directory, as it cannot be shared.
+static DATA_DIR: Mutex<()> = Mutex::new(());
+
+/// Fresh temporary data directory, set as the working directory while alive.
+pub struct TempDataDir {
+    dir: PathBuf,
+    previous_dir: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl TempDataDir {
+    /// Creates the directory (with the bot's file structure, see `utils::init_filesystem`), and
+    /// moves into it.
+    pub fn new(name: &str) -> Self {
+        // A failed test leaves the lock poisoned, but the directory is replaced anyway:
+        let lock = DATA_DIR.lock().unwrap_or_else(|e| e.into_inner());
+
+        let dir = env::temp_dir().join(format!("hermes-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)
+            .expect("[testutil] Could not create the temporary data directory.");
+        let previous_dir =
+            env::current_dir().expect("[testutil] Could not get the working directory.");
+        env::set_current_dir(&dir).expect("[testutil] Could not use the temporary data directory.");
+
+        utils::init_filesystem();
+
+        Self {
+            dir,
+            previous_dir,
+            _lock: lock,
+        }
+    }
+}
+
+impl Drop for TempDataDir {
+    fn drop(&mut self) {
+        let _ = env::set_current_dir(&self.previous_dir);
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
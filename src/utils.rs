@@ -15,50 +15,361 @@
  *  You should have received a copy of the GNU General Public License
  *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
+use crate::{
+    alerts::{self, AlertKind},
+    cache::Caches,
+    chaos,
+    i18n::{self, tr, Language},
+    student,
+    student::Student,
+    team,
+    team::Team,
+    Context, Credentials,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use serenity::all::{GuildId, UserId};
-use std::{collections::HashMap, fs};
+use serenity::all::{GuildId, Http, Member, RoleId, UserId};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs, io,
+    io::Write as _,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
 
 /* Data structures: */
 
 /// Data structure encapsulating the per-guild configuration of the bot.
+///
+/// Every field has a default value (see the `Default` implementation), so configuration files
+/// written by older versions of the bot, which lack newer fields, can still be loaded. Unknown
+/// fields (e.g. from newer versions, or removed options) are kept as they are (see `extra`).
 #[cfg_attr(debug_assertions, derive(Debug))]
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, hermes::ConfigCommands)]
+#[serde(default)]
 pub struct BotConfig {
     /// The URL for this guild's Tablón endpoint:
+    #[config(
+        description = "Change the URL for Tablón's endpoint.",
+        description_es = "Cambiar la URL del servicio de Tablón."
+    )]
     pub tablon_url: String,
     /// The number of members a team of students must have, exactly.
+    #[config(
+        description = "Change the capacity for teams on this server.",
+        description_es = "Cambiar la capacidad de los equipos en este servidor.",
+        validate = "validate_team_capacity"
+    )]
     pub team_capacity: u8,
     /// The prefix for the teams' identifiers (e.g. "g" for "g110").
+    // Changing it may require renaming the existing teams, so it has its own subcommand (see
+    // `/botconfig team_prefix`).
+    #[config(skip)]
     pub team_prefix: String,
     /// The name of the guild's (private) channel dedicated for special bot admin commands and
     /// activity monitoring.
+    #[config(
+        description = "Change the guild's channel for usage of special admin-only bot functionalities.",
+        description_es = "Cambiar el canal del servidor para las funciones del bot solo para administradores.",
+        channel
+    )]
     pub bot_channel: String,
+    /// Whether to post the notable changes in the guild (e.g. teams confirmed, or configuration
+    /// updates) to the bot channel, as a live activity feed for its admins (see `activityfeed`).
+    #[config(
+        description = "Change whether to post the guild's notable changes to the bot channel.",
+        description_es = "Cambiar si publicar los cambios destacados del servidor en el canal del bot."
+    )]
+    pub activity_feed: bool,
     /// The name of the guild's public channel dedicated to leaderboard visualizations.
+    #[config(
+        description = "Change the guild's channel for Tablón's leaderboard.",
+        description_es = "Cambiar el canal del servidor para la clasificación de Tablón.",
+        channel
+    )]
     pub lb_channel: String,
     /// Whether to notify the top teams on leaderboards of when their position changes.
+    #[config(
+        description = "Change whether to notify leaders of leaderboard position changes.",
+        description_es = "Cambiar si avisar a los líderes de sus cambios de posición en la clasificación."
+    )]
     pub notify_leaders: bool,
     /// Amount of top teams susceptible of being notified of position changes (see
     /// `notify_leaders`).
+    #[config(
+        description = "Change the number of teams to count as \"leaders\" for position update notifications.",
+        description_es = "Cambiar cuántos equipos cuentan como \"líderes\" para los avisos de cambios de posición."
+    )]
     pub leader_count: u8,
+    /// Minutes over which the position changes of the leaders are batched into a single digest
+    /// (see `leadernotify`). 0 notifies each change as it happens.
+    #[config(
+        description = "Change the minutes to batch leaderboard position changes over (0 for none).",
+        description_es = "Cambiar los minutos en que agrupar los cambios de posición en la clasificación (0 para ninguno)."
+    )]
+    pub leader_digest_minutes: u16,
     /// Whether to post the leaderboard notifications in a public channel in the guild, or just
     /// privately.
+    #[config(
+        description = "Change whether to notify the leaderboard changes publicly.",
+        description_es = "Cambiar si avisar de los cambios en la clasificación públicamente."
+    )]
     pub public_notify: bool,
     /// The name of the guild's public channel where news and notifications (e.g. position updates)
     /// should be sent, if any.
+    #[config(
+        description = "Change the channel for bot news (e.g. public position update notifications).",
+        description_es = "Cambiar el canal de noticias del bot (p. ej., avisos públicos de cambios de posición).",
+        channel
+    )]
     pub bot_news_channel: String,
+    /// Whether to relay the announcements in Tablón's front page to the news channel (see `news`).
+    #[config(
+        description = "Change whether to relay Tablón's announcements to the news channel.",
+        description_es = "Cambiar si reenviar los anuncios de Tablón al canal de noticias."
+    )]
+    pub relay_news: bool,
     /// The field separator for multi-field columns in leaderboard visualizations. This is used
     /// when visualizing more than 3 fields of a leaderboard: the remaining fields will be grouped
     /// in the last column, separated by this.
+    #[config(
+        description = "Change the separator for multi-field columns in leaderboards.",
+        description_es = "Cambiar el separador de las columnas con varios campos en las clasificaciones."
+    )]
     pub column_separator: String,
+    /// The IANA timezone (e.g. "Europe/Madrid") used when displaying timestamps in the guild,
+    /// instead of the local time of the machine hosting the bot.
+    #[config(
+        description = "Change the timezone used for displaying dates and times in this guild.",
+        description_es = "Cambiar la zona horaria con que mostrar fechas y horas en este servidor.",
+        validate = "validate_timezone"
+    )]
+    pub timezone: String,
+    /// The default language for the bot's replies in the guild. Students can choose their own
+    /// language for the replies only they can see (see `/settings language`).
+    #[config(
+        description = "Change the default language for the bot's replies.",
+        description_es = "Cambiar el idioma por defecto de las respuestas del bot."
+    )]
+    pub language: Language,
+    /// Minimum time (in seconds) between two requests of the same team to Tablón, shared by all
+    /// its members. 0 disables it.
+    #[config(
+        description = "Change the minimum time (in seconds) between two requests of a team.",
+        description_es = "Cambiar el tiempo mínimo (en segundos) entre dos peticiones de un equipo."
+    )]
+    pub team_cooldown: u32,
+    /// Maximum number of requests a team can send to Tablón each day (in the guild's timezone),
+    /// shared by all its members. 0 disables it.
+    #[config(
+        description = "Change the maximum number of requests a team can send each day.",
+        description_es = "Cambiar el número máximo de peticiones que un equipo puede enviar cada día."
+    )]
+    pub team_daily_requests: u16,
+    /// Minutes before each deadline during which the surge protection applies: the teams'
+    /// cooldown is raised to `surge_cooldown`, and the guild's requests wait in a local queue, to
+    /// reach Tablón at least `surge_spacing` seconds apart. The policy is announced in the news
+    /// channel when it starts. 0 disables it.
+    #[config(
+        description = "Change the minutes before each deadline during which the surge protection applies.",
+        description_es = "Cambiar los minutos antes de cada fecha límite en que se aplica la protección ante picos."
+    )]
+    pub surge_minutes: u32,
+    /// Minimum time (in seconds) between two requests of the same team during the surge protection
+    /// (see `surge_minutes`), if greater than `team_cooldown`.
+    #[config(
+        description = "Change the team cooldown (in seconds) right before deadlines.",
+        description_es = "Cambiar la espera de los equipos (en segundos) justo antes de las fechas límite."
+    )]
+    pub surge_cooldown: u32,
+    /// Minimum time (in seconds) between two requests of the guild to Tablón during the surge
+    /// protection (see `surge_minutes`).
+    #[config(
+        description = "Change the time (in seconds) between the guild's requests right before deadlines.",
+        description_es = "Cambiar el tiempo (en segundos) entre peticiones del servidor justo antes de las fechas límite."
+    )]
+    pub surge_spacing: u32,
+    /// Minutes during which a request with the same program and arguments as one already sent by
+    /// the team is considered a duplicate, asking the student to confirm it. 0 disables it.
+    #[config(
+        description = "Change the minutes during which resending a team's program asks for confirmation.",
+        description_es = "Cambiar los minutos en que reenviar el programa de un equipo pide confirmación."
+    )]
+    pub duplicate_window: u32,
+    /// Maximum number of requests kept in each student's record; older ones are archived apart
+    /// (see `Student::full_request_history`). 0 keeps them all in the record.
+    #[config(
+        description = "Change the number of requests kept in each student's record.",
+        description_es = "Cambiar el número de peticiones guardadas en el registro de cada estudiante."
+    )]
+    pub history_capacity: u16,
+    /// Whether to keep the directory of each submission (see `commands::request`) once it is sent
+    /// to Tablón, for debugging, instead of removing it. It also allows exporting the programs sent
+    /// for plagiarism checks (see `commands::sourcedump`).
+    #[config(
+        description = "Change whether to keep the submitted programs' directories, for debugging.",
+        description_es = "Cambiar si conservar los directorios de los programas enviados, para depuración."
+    )]
+    pub keep_submissions: bool,
+    /// Whether to prepend a comment to the programs sent to Tablón, identifying the team, the
+    /// student and when they were sent through Hermes (see `banner`).
+    #[config(
+        description = "Change whether to identify the programs sent as sent through Hermes.",
+        description_es = "Cambiar si identificar los programas enviados como enviados a través de Hermes."
+    )]
+    pub tag_submissions: bool,
+    /// Queue for the requests of students who give none, and have no preferred one. Empty for
+    /// none, so those requests are rejected.
+    #[config(
+        description = "Change the queue for requests without a given nor a preferred one.",
+        description_es = "Cambiar la cola de las peticiones sin una cola dada ni preferida."
+    )]
+    pub default_queue: String,
+    /// Names of the guild's queues in Tablón, comma-separated, suggested to students who have not
+    /// chosen a preferred one.
+    #[config(
+        description = "Change the guild's queues (comma-separated) suggested to students.",
+        description_es = "Cambiar las colas del servidor (separadas por comas) sugeridas a los estudiantes."
+    )]
+    pub queues: String,
+    /// Days after getting its password a team that has not sent any request yet is reminded of it
+    /// (see `inactivity`). 0 disables it.
+    #[config(
+        description = "Change the days after getting their password to remind teams that never sent requests.",
+        description_es = "Cambiar los días tras recibir su contraseña para recordar a los equipos sin peticiones."
+    )]
+    pub inactivity_days: u16,
+    /// Whether to also tell the admins, in the activity feed, of the teams reminded of not having
+    /// sent any request (see `inactivity_days`).
+    #[config(
+        description = "Change whether to tell the admins of the teams reminded of their inactivity.",
+        description_es = "Cambiar si avisar a los administradores de los equipos a los que se recordó su inactividad."
+    )]
+    pub inactivity_notify_admins: bool,
+    /// Message sent to the new members of the guild as they join, e.g. with instructions to make a
+    /// team (see `welcome`). Empty for none. Managed through `/botconfig welcome_message`, as it is
+    /// checked and can be cleared.
+    #[config(skip)]
+    pub welcome_message: String,
+    /// The name of the channel to send the welcome message to, or empty to send it by DM.
+    /// Managed through `/botconfig welcome_message`.
+    #[config(skip)]
+    pub welcome_channel: String,
+    /// Work-in-progress commands enabled in the guild (see `commands::registry`). Managed through
+    /// `/botconfig wip_commands`, as it is not a single value.
+    #[config(skip)]
+    pub wip_commands: Vec<String>,
+    /// Roles allowed to run each command (by qualified name, e.g. "teamdump" or "botconfig logs"),
+    /// overriding who can run it. Commands without an override are available to whoever Discord
+    /// lets use them (see `default_member_permissions`). Managed through `/botconfig permissions`.
+    #[config(skip)]
+    pub permissions: BTreeMap<String, Vec<RoleId>>,
+    /// Visibility of the replies of each command (by qualified name, as in `permissions`),
+    /// overriding the command's own default. Managed through `/botconfig visibility`.
+    #[config(skip)]
+    pub reply_visibility: BTreeMap<String, Visibility>,
+    /// Role given to the members of the team with the best result on each exercise (i.e. each of
+    /// Tablón's queues), refreshed periodically (see `leaderboard::refresh_champion_roles`).
+    /// Managed through `/botconfig lb_champion_role`.
+    #[config(skip)]
+    pub lb_champion_roles: BTreeMap<String, RoleId>,
+    /// Client used for the programs with each file extension (without the dot, lowercase), by its
+    /// name in `guilds/<guild_id>/clients/`, unless the student picks one. Other programs use the
+    /// guild's default client (`guilds/<guild_id>/client`). Managed through
+    /// `/botconfig client_extension`.
+    #[config(skip)]
+    pub client_extensions: BTreeMap<String, String>,
+    /// Fields unknown to this version of the bot, kept so they are not lost when the configuration
+    /// is saved (e.g. after going back from a newer version).
+    #[config(skip)]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Visibility of the replies of a command.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize, poise::ChoiceParameter)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Only the user running the command sees its replies.
+    #[name = "ephemeral"]
+    Ephemeral,
+    /// Everyone in the channel sees the replies.
+    #[name = "public"]
+    Public,
+}
+
+/// Default configuration, used as last resort when no custom default configuration file
+/// (`config.json`) is found, and for filling in missing fields of existing configurations.
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            tablon_url: String::from("https://frontendv.infor.uva.es"),
+            team_capacity: 2,
+            team_prefix: String::from("g"),
+            bot_channel: String::from("bot-commands"),
+            activity_feed: true,
+            lb_channel: String::from("leaderboards"),
+            notify_leaders: true,
+            leader_count: 5,
+            leader_digest_minutes: 0,
+            public_notify: true,
+            bot_news_channel: String::from("bot-news"),
+            relay_news: false,
+            column_separator: String::from(" | "),
+            timezone: String::from("Europe/Madrid"),
+            language: Language::English,
+            team_cooldown: 0,
+            team_daily_requests: 0,
+            surge_minutes: 0,
+            surge_cooldown: 300,
+            surge_spacing: 5,
+            duplicate_window: 60,
+            history_capacity: 200,
+            keep_submissions: false,
+            tag_submissions: false,
+            default_queue: String::new(),
+            queues: String::new(),
+            inactivity_days: 0,
+            inactivity_notify_admins: false,
+            welcome_message: String::new(),
+            welcome_channel: String::new(),
+            wip_commands: Vec::new(),
+            permissions: BTreeMap::new(),
+            reply_visibility: BTreeMap::new(),
+            lb_champion_roles: BTreeMap::new(),
+            client_extensions: BTreeMap::new(),
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+/// Data structure encapsulating the per-guild sensitive configuration of the bot.
+///
+/// It is kept apart from `BotConfig` (in `guilds/<guild_id>/secrets.json`), so it is never shown
+/// nor replaced through the regular configuration commands.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GuildSecrets {
+    /// Admin-level credentials for Tablón, used for privileged operations on behalf of the guild's
+    /// admins (e.g. retrieving the teams' passwords, or full result details).
+    pub tablon_admin: Option<Credentials>,
+    /// Token Tablón must present when notifying the guild's results (see `webhook`).
+    pub webhook_token: Option<String>,
+    /// Token external tools must present to use the guild's admin API (see `api`).
+    pub api_token: Option<String>,
 }
 
-/// Macro for logging to stderr the usage of a command.
+/// Macro for logging the usage of a command.
 macro_rules! elog_cmd {
     ($ctx:ident) => {
-        eprintln!(
+        tracing::info!(
             "Executing command `{}`, triggered by <@{}> ({}).",
-            $ctx.invocation_string(),
+            crate::redact::invocation($ctx.into()),
             $ctx.author().id,
             $ctx.author().tag()
         );
@@ -66,6 +377,94 @@ macro_rules! elog_cmd {
 }
 pub(crate) use elog_cmd;
 
+/// How a command invocation finished, as logged by `elog_cmd_outcome!`.
+pub enum CmdOutcome<'a> {
+    /// It returned `Ok`, without replying with an error.
+    Completed,
+    /// It returned `Ok`, but replied with an error (see `reply_error`).
+    ErrorReply,
+    /// It returned an error.
+    Failed(&'a dyn std::fmt::Display),
+    /// It panicked, with the given message (if any).
+    Panicked(Option<&'a str>),
+}
+
+/// Logs the outcome and duration of a command, both to stderr and to the guild's activity log (see
+/// `activitylog`), where only the completed commands count as successful.
+pub fn record_cmd_outcome(
+    guild_id: Option<GuildId>,
+    user: UserId,
+    command: &str,
+    invocation: String,
+    elapsed: std::time::Duration,
+    outcome: CmdOutcome,
+) {
+    match &outcome {
+        CmdOutcome::Completed => tracing::info!(
+            "Command `{}`, triggered by <@{}>, completed in {} ms.",
+            invocation,
+            user,
+            elapsed.as_millis()
+        ),
+        CmdOutcome::ErrorReply => tracing::info!(
+            "Command `{}`, triggered by <@{}>, replied with an error in {} ms.",
+            invocation,
+            user,
+            elapsed.as_millis()
+        ),
+        CmdOutcome::Failed(e) => tracing::warn!(
+            "Command `{}`, triggered by <@{}>, failed in {} ms: {}",
+            invocation,
+            user,
+            elapsed.as_millis(),
+            e
+        ),
+        CmdOutcome::Panicked(msg) => tracing::error!(
+            "Command `{}`, triggered by <@{}>, panicked after {} ms: {}",
+            invocation,
+            user,
+            elapsed.as_millis(),
+            msg.unwrap_or("(no message)")
+        ),
+    }
+    if let Some(gid) = guild_id {
+        crate::activitylog::append(
+            &gid,
+            &crate::activitylog::ActivityEntry::new(
+                chrono::Utc::now().timestamp(),
+                user,
+                command.to_string(),
+                invocation,
+                matches!(outcome, CmdOutcome::Completed),
+                elapsed.as_millis() as u64,
+            ),
+        );
+    }
+}
+
+/// Macro for logging the outcome (`Ok`/`Err`, or an error reply) and duration of a command (see
+/// `record_cmd_outcome`). The panics are logged by the framework's error handler instead.
+macro_rules! elog_cmd_outcome {
+    ($ctx:ident, $elapsed:expr, $result:expr) => {
+        let outcome = match $result {
+            Ok(_) if crate::utils::replied_error($ctx.into()).await => {
+                crate::utils::CmdOutcome::ErrorReply
+            }
+            Ok(_) => crate::utils::CmdOutcome::Completed,
+            Err(e) => crate::utils::CmdOutcome::Failed(e),
+        };
+        crate::utils::record_cmd_outcome(
+            $ctx.guild_id(),
+            $ctx.author().id,
+            &$ctx.command().qualified_name,
+            crate::redact::invocation($ctx.into()),
+            $elapsed,
+            outcome,
+        );
+    };
+}
+pub(crate) use elog_cmd_outcome;
+
 /// Macro for retrieving the guild ID from a Context object.
 macro_rules! get_guild_id {
     ($ctx:ident) => {
@@ -78,24 +477,154 @@ pub(crate) use get_guild_id;
 /// Macro for retrieving the student object from the author of a command.
 macro_rules! get_triggering_student {
     ($ctx:ident) => {
-        student::get_student(&$ctx.author().id)
+        student::get_student(&$ctx.data().caches, &$ctx.author().id)
             .expect(format!("Student {} not found in the system.", $ctx.author().id,).as_str())
     };
 }
 pub(crate) use get_triggering_student;
 
-/// Loads the bot configuration for a guild from its persistent configuration file.
-/// If the configuration file does not exist, it is created with default values.
-pub fn load_config(guild_id: &GuildId) -> BotConfig {
-    let json = fs::read_to_string(format!("guilds/{}/config.json", guild_id))
-        .expect(format!("Could not read guild {}'s configuration file.", guild_id).as_str());
-    serde_json::from_str(&json).expect(
-        format!(
-            "Could not parse guild {}'s configuration as valid JSON.",
-            guild_id
+/// Reads one of the persistent files.
+pub fn read_file(path: &str) -> io::Result<String> {
+    chaos::inject(path)?;
+    fs::read_to_string(path)
+}
+
+/// Number of temporary files created by the bot, so concurrent writes of a file never share one.
+static TMP_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// Writes one of the persistent files, through a temporary file renamed over it, so a failed write
+/// (or a crash) never leaves it half-written.
+pub fn write_file(path: &str, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_file_with_mode(path, contents.as_ref(), 0o666)
+}
+
+/// Writes one of the persistent files like `write_file`, with the given permissions (on Unix
+/// systems, before the umask).
+fn write_file_with_mode(path: &str, contents: &[u8], mode: u32) -> io::Result<()> {
+    chaos::inject(path)?;
+    let tmp_path = format!(
+        "{}.{}-{}.tmp",
+        path,
+        std::process::id(),
+        TMP_FILES.fetch_add(1, Ordering::Relaxed)
+    );
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+    let written = options.open(&tmp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        // The contents must be on disk before the rename makes them visible:
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    });
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+        return written;
+    }
+
+    // Persist the rename itself, if the platform allows syncing directories:
+    if let Some(dir) = Path::new(path).parent() {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let _ = fs::File::open(dir).and_then(|dir| dir.sync_all());
+    }
+
+    Ok(())
+}
+
+/// Loads the bot configuration for a guild, from the cache or from its persistent configuration
+/// file.
+///
+/// Missing fields are filled in with their default values, in which case the configuration file
+/// is upgraded (rewritten) with the resulting configuration. Fields with invalid values (e.g. of
+/// the wrong type, after a manual edit) are reported, and replaced by their default values until
+/// the file is fixed, without rewriting it.
+pub fn load_config(caches: &Caches, guild_id: &GuildId) -> BotConfig {
+    if let Some(config) = caches.config(guild_id) {
+        return config;
+    }
+
+    let json = read_file(&format!("guilds/{}/config.json", guild_id)).unwrap_or_else(|e| {
+        panic!(
+            "Could not read guild {}'s configuration file: {:?}",
+            guild_id, e
         )
-        .as_str(),
-    )
+    });
+    let (config, upgraded) = match parse_config(&json) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            alerts::alert(
+                AlertKind::DataLoad,
+                format!(
+                    "Could not load guild {}'s configuration ({}), so its invalid fields have \
+                    their default values until it is fixed.",
+                    guild_id, e
+                ),
+            );
+            (parse_valid_config_fields(&json), false)
+        }
+    };
+
+    if upgraded {
+        tracing::info!(
+            "Upgrading guild {}'s configuration file to the current format.",
+            guild_id
+        );
+        update_config_persistence(caches, &config, guild_id);
+    }
+    caches.set_config(guild_id, &config);
+
+    config
+}
+
+/// Parses a bot configuration from a JSON string, tolerating missing and unknown fields (see
+/// `BotConfig::extra`), but not invalid values.
+///
+/// Returns the parsed configuration, and whether it lacks some of the fields of the given JSON
+/// (i.e. whether it should be upgraded).
+pub fn parse_config(json: &str) -> Result<(BotConfig, bool), serde_json::Error> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let config: BotConfig = serde_json::from_value(value.clone())?;
+
+    // Compare the fields of the given JSON with the fields of the current configuration format:
+    let current = serde_json::to_value(&config)?;
+    let upgraded = match (value.as_object(), current.as_object()) {
+        (Some(given), Some(current)) => {
+            for key in config.extra.keys() {
+                tracing::warn!("Keeping unknown configuration field `{}`.", key);
+            }
+            current.keys().any(|k| !given.contains_key(k))
+        }
+        _ => false,
+    };
+
+    Ok((config, upgraded))
+}
+
+/// Parses the valid fields of a bot configuration from a JSON string, using the default values of
+/// the rest (e.g. those of the wrong type), or of every field if it is not even valid JSON.
+fn parse_valid_config_fields(json: &str) -> BotConfig {
+    let Ok(serde_json::Value::Object(given)) = serde_json::from_str(json) else {
+        return BotConfig::default();
+    };
+    let mut valid = serde_json::Map::new();
+    for (key, value) in given {
+        let mut candidate = valid.clone();
+        candidate.insert(key, value);
+        if serde_json::from_value::<BotConfig>(serde_json::Value::Object(candidate.clone())).is_ok()
+        {
+            valid = candidate;
+        }
+    }
+
+    serde_json::from_value(serde_json::Value::Object(valid)).unwrap_or_default()
 }
 
 /// Creates the directories and files expected for the bot to function properly.
@@ -107,55 +636,250 @@ pub fn init_filesystem() {
     {
         let json = serde_json::to_string_pretty(&HashMap::<String, GuildId>::new())
             .expect("Could not serialize the initial empty guild map into JSON.");
-        fs::write("guilds/guildMap.json", json).expect("Could not create guilds/guildMap.json");
+        write_file("guilds/guildMap.json", json).expect("Could not create guilds/guildMap.json");
     }
     if !fs::exists("users/userMap.json").expect("Could not check existence of users/userMap.json") {
-        let json = serde_json::to_string_pretty(&HashMap::<String, UserId>::new())
+        let json = serde_json::to_string_pretty(&UserMap::new())
             .expect("Could not serialize the initial empty user map into JSON.");
-        fs::write("users/userMap.json", json).expect("Could not create users/userMap.json");
+        write_file("users/userMap.json", json).expect("Could not create users/userMap.json");
     }
 }
 
-/// Updates the persistent configuration file for a guild.
+/// Updates the persistent configuration file for a guild, and its cached configuration.
 /// It is assumed that the config file exists on disk, since it should have been loaded with
 /// `load_config` beforehand.
-pub fn update_config_persistence(config: &BotConfig, guild_id: &GuildId) {
-    let json = serde_json::to_string_pretty(config).expect(
-        format!(
-            "Could not serialize guild {}'s configuration into JSON.",
-            guild_id
+pub fn update_config_persistence(caches: &Caches, config: &BotConfig, guild_id: &GuildId) {
+    let json = serde_json::to_string_pretty(config).unwrap_or_else(|e| {
+        panic!(
+            "Could not serialize guild {}'s configuration into JSON: {:?}",
+            guild_id, e
         )
-        .as_str(),
-    );
-    fs::write(format!("guilds/{}/config.json", guild_id), json)
-        .expect(format!("Could not write guild {}'s configuration file.", guild_id).as_str());
+    });
+    write_file(&format!("guilds/{}/config.json", guild_id), json).unwrap_or_else(|e| {
+        panic!(
+            "Could not write guild {}'s configuration file: {:?}",
+            guild_id, e
+        )
+    });
+    caches.set_config(guild_id, config);
+}
+
+/// Loads the sensitive configuration for a guild from its persistent secrets file.
+/// If the secrets file does not exist, an empty configuration is returned.
+pub fn load_secrets(guild_id: &GuildId) -> GuildSecrets {
+    let Ok(json) = fs::read_to_string(format!("guilds/{}/secrets.json", guild_id)) else {
+        return GuildSecrets::default();
+    };
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        panic!(
+            "Could not parse guild {}'s secrets file as valid JSON: {:?}",
+            guild_id, e
+        )
+    })
+}
+
+/// Updates the persistent secrets file for a guild.
+///
+/// On Unix systems, the file is only readable and writable by its owner.
+pub fn update_secrets_persistence(secrets: &GuildSecrets, guild_id: &GuildId) {
+    let json = serde_json::to_string_pretty(secrets).unwrap_or_else(|e| {
+        panic!(
+            "Could not serialize guild {}'s secrets into JSON: {:?}",
+            guild_id, e
+        )
+    });
+
+    write_file_with_mode(
+        &format!("guilds/{}/secrets.json", guild_id),
+        json.as_bytes(),
+        0o600,
+    )
+    .unwrap_or_else(|e| panic!("Could not write guild {}'s secrets file: {:?}", guild_id, e));
+}
+
+/// Retrieves the admin-level Tablón credentials of a guild, if they have been set.
+pub fn get_admin_credentials(guild_id: &GuildId) -> Option<Credentials> {
+    load_secrets(guild_id).tablon_admin
 }
 
 /// Loads the persistent guildMap.json file into a HashMap object.
 pub fn load_guildmap() -> HashMap<String, GuildId> {
-    let json =
-        fs::read_to_string("guilds/guildMap.json").expect("Could not read guilds/guildMap.json");
+    let json = read_file("guilds/guildMap.json").expect("Could not read guilds/guildMap.json");
     serde_json::from_str(&json).expect("Could not parse guilds/guildMap.json as valid JSON data.")
 }
 
+/// Records a guild in the guild map under its (sanitized) name, returning whether the map
+/// changed. A renamed guild loses its old name.
+///
+/// If two guilds have the same name, the one with the lowest ID keeps it, and the other one is
+/// recorded as `<name>-<guild ID>`, so neither overwrites the other (whatever the order in which
+/// they are recorded).
+pub fn record_guild_name(
+    guild_map: &mut HashMap<String, GuildId>,
+    guild_id: GuildId,
+    name: String,
+) -> bool {
+    let disambiguated = |name: &String, gid: &GuildId| format!("{}-{}", name, gid);
+
+    let name = match guild_map.get(&name).copied() {
+        Some(other) if other < guild_id => {
+            let unique = disambiguated(&name, &guild_id);
+            tracing::warn!(
+                "Guild {} has the same name as guild {} (\"{}\"), so it is recorded as \"{}\".",
+                guild_id,
+                other,
+                name,
+                unique
+            );
+            unique
+        }
+        Some(other) if other != guild_id => {
+            let unique = disambiguated(&name, &other);
+            tracing::warn!(
+                "Guild {} has the same name as guild {} (\"{}\"), so the latter is recorded as \"{}\".",
+                guild_id,
+                other,
+                name,
+                unique
+            );
+            guild_map.remove(&name);
+            guild_map.insert(unique, other);
+            name
+        }
+        _ => name,
+    };
+
+    if guild_map.get(&name) == Some(&guild_id)
+        && guild_map.values().filter(|gid| **gid == guild_id).count() == 1
+    {
+        return false;
+    }
+    guild_map.retain(|_, gid| *gid != guild_id);
+    guild_map.insert(name, guild_id);
+
+    true
+}
+
 /// Updates the persistent guildMap.json file, which maps Guild names into their IDs.
+///
+/// Guilds recorded under several names (see `record_guild_name`) are reported, as they would be
+/// processed more than once by the scheduled tasks.
 pub fn update_guildmap_persistence(guild_map: &HashMap<String, GuildId>) {
+    let mut names: BTreeMap<GuildId, Vec<&String>> = BTreeMap::new();
+    for (name, gid) in guild_map.iter() {
+        names.entry(*gid).or_default().push(name);
+    }
+    for (gid, names) in names.iter().filter(|(_, names)| names.len() > 1) {
+        tracing::error!(
+            "Guild {} is in the guild map under several names: {:?}.",
+            gid,
+            names
+        );
+    }
+
     let json = serde_json::to_string_pretty(guild_map)
         .expect("Could not serialize the guild map into JSON.");
-    fs::write("guilds/guildMap.json", json).expect("Could not write guilds/guildMap.json.");
+    write_file("guilds/guildMap.json", json).expect("Could not write guilds/guildMap.json.");
+}
+
+/// Names of a user in the user map (see `load_usermap`).
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct UserNames {
+    /// Current user name.
+    pub name: String,
+    /// Previous user names, oldest first.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// Map of every known user's ID to their names, so users can be found by their current or past
+/// names (see `find_users`).
+pub type UserMap = BTreeMap<UserId, UserNames>;
+
+/// Records the current name of a user in the user map, keeping their previous one as an alias.
+///
+/// Returns whether the user map changed.
+pub fn record_user_name(user_map: &mut UserMap, uid: UserId, name: String) -> bool {
+    let names = user_map.entry(uid).or_default();
+    if names.name == name {
+        return false;
+    }
+
+    let previous = std::mem::replace(&mut names.name, name);
+    names.aliases.retain(|alias| *alias != names.name);
+    if !previous.is_empty() && !names.aliases.contains(&previous) {
+        names.aliases.push(previous);
+    }
+
+    true
 }
 
-/// Loads the persistent userMap.json file into a HashMap object.
-pub fn load_usermap() -> HashMap<String, UserId> {
-    let json = fs::read_to_string("users/userMap.json").expect("Could not read users/userMap.json");
-    serde_json::from_str(&json).expect("Could not parse users/userMap.json as valid JSON data.")
+/// Finds the users with the given name: those currently named so or, if none is, those that were
+/// named so before. Names are compared ignoring case, as Discord's user names are.
+pub fn find_users(user_map: &UserMap, name: &str) -> Vec<UserId> {
+    let name = name.to_lowercase();
+    let current = user_map
+        .iter()
+        .filter(|(_, names)| names.name.to_lowercase() == name)
+        .map(|(uid, _)| *uid)
+        .collect::<Vec<_>>();
+    if !current.is_empty() {
+        return current;
+    }
+
+    user_map
+        .iter()
+        .filter(|(_, names)| {
+            names
+                .aliases
+                .iter()
+                .any(|alias| alias.to_lowercase() == name)
+        })
+        .map(|(uid, _)| *uid)
+        .collect()
 }
 
-/// Updates the persistent userMap.json file, which maps User names into their IDs.
-pub fn update_usermap_persistence(user_map: &HashMap<String, UserId>) {
+/// Parses the user map from a JSON string, either in its current format, or in the format of older
+/// versions (mapping each user name to an ID).
+///
+/// Returns the parsed user map, and whether it was in the older format (i.e. whether it should be
+/// upgraded).
+pub fn parse_usermap(json: &str) -> Result<(UserMap, bool), serde_json::Error> {
+    let current_error = match serde_json::from_str::<UserMap>(json) {
+        Ok(user_map) => return Ok((user_map, false)),
+        Err(e) => e,
+    };
+    let legacy: HashMap<String, UserId> = serde_json::from_str(json).map_err(|_| current_error)?;
+
+    let mut user_map = UserMap::new();
+    for (name, uid) in legacy {
+        record_user_name(&mut user_map, uid, name);
+    }
+
+    Ok((user_map, true))
+}
+
+/// Loads the persistent userMap.json file, upgrading it if it was written by an older version (see
+/// `parse_usermap`).
+pub fn load_usermap() -> UserMap {
+    let json = read_file("users/userMap.json").expect("Could not read users/userMap.json");
+    let (user_map, upgraded) =
+        parse_usermap(&json).expect("Could not parse users/userMap.json as valid JSON data.");
+
+    if upgraded {
+        tracing::info!("Upgrading users/userMap.json to the current format.");
+        update_usermap_persistence(&user_map);
+    }
+
+    user_map
+}
+
+/// Updates the persistent userMap.json file, which maps the users' IDs into their names.
+pub fn update_usermap_persistence(user_map: &UserMap) {
     let json = serde_json::to_string_pretty(user_map)
         .expect("Could not serialize the user map into JSON.");
-    fs::write("users/userMap.json", json).expect("Could not write users/userMap.json.");
+    write_file("users/userMap.json", json).expect("Could not write users/userMap.json.");
 }
 
 /// Load the name map for a specific guild.
@@ -163,29 +887,333 @@ pub fn update_usermap_persistence(user_map: &HashMap<String, UserId>) {
 ///
 /// The name map maps the name of a team to its ID.
 pub fn load_namemap(guild_id: &GuildId) -> HashMap<String, String> {
-    let json = fs::read_to_string(format!("guilds/{}/nameMap.json", guild_id).as_str())
-        .expect(format!("Could not read name map for server {}.", guild_id).as_str());
-    serde_json::from_str(&json).expect(
-        format!(
-            "Could not parse guilds/{}/nameMap.json as valid JSON data.",
-            guild_id
+    let json = read_file(&format!("guilds/{}/nameMap.json", guild_id))
+        .unwrap_or_else(|e| panic!("Could not read name map for server {}: {:?}", guild_id, e));
+    serde_json::from_str(&json).unwrap_or_else(|e| {
+        panic!(
+            "Could not parse guilds/{}/nameMap.json as valid JSON data: {:?}",
+            guild_id, e
         )
-        .as_str(),
-    )
+    })
+}
+
+/// Serializes the changes of the guilds' name maps, so that concurrent ones (e.g. two teams renamed
+/// at the same time) are not lost.
+static NAMEMAP_LOCK: Mutex<()> = Mutex::new(());
+
+/// Changes the name map of a guild atomically: loads it, applies the given change to it, and saves
+/// it, while no other name map can be changed. Returns the result of the change.
+pub fn update_namemap<T>(
+    guild_id: &GuildId,
+    change: impl FnOnce(&mut HashMap<String, String>) -> T,
+) -> T {
+    let _lock = NAMEMAP_LOCK.lock().unwrap();
+    let mut name_map = load_namemap(guild_id);
+    let res = change(&mut name_map);
+    update_namemap_persistence(&name_map, guild_id);
+
+    res
 }
 
 /// Updates the persistent nameMap.json file for a specific guild, which maps team names into their
 /// IDs.
+///
+/// Names that are the identifier of a team but point to another team are reported, as they hide
+/// the team they are the identifier of. Use `update_namemap` to change the map without losing
+/// concurrent changes.
 pub fn update_namemap_persistence(name_map: &HashMap<String, String>, guild_id: &GuildId) {
-    let json = serde_json::to_string_pretty(name_map).expect(
-        format!(
-            "Could not serialize the name map for server {} into JSON.",
-            guild_id
+    let team_ids: HashSet<&String> = name_map.values().collect();
+    for (name, id) in name_map.iter() {
+        if name != id && team_ids.contains(name) {
+            tracing::error!(
+                "The name map of guild {} maps team {}'s identifier to team {}.",
+                guild_id,
+                name,
+                id
+            );
+        }
+    }
+
+    let json = serde_json::to_string_pretty(name_map).unwrap_or_else(|e| {
+        panic!(
+            "Could not serialize the name map for server {} into JSON: {:?}",
+            guild_id, e
         )
-        .as_str(),
-    );
-    fs::write(format!("guilds/{}/nameMap.json", guild_id).as_str(), json)
-        .expect(format!("Could not write guilds/{}/nameMap.json.", guild_id).as_str());
+    });
+    write_file(&format!("guilds/{}/nameMap.json", guild_id), json)
+        .unwrap_or_else(|e| panic!("Could not write guilds/{}/nameMap.json: {:?}", guild_id, e));
+}
+
+/// Loads the time (unix timestamp) of the last full scan of a guild's members, if it was ever
+/// scanned.
+pub fn load_last_sync(guild_id: &GuildId) -> Option<i64> {
+    fs::read_to_string(format!("guilds/{}/lastSync", guild_id))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Records that a guild's members have just been fully scanned.
+pub fn update_last_sync(guild_id: &GuildId) {
+    fs::write(
+        format!("guilds/{}/lastSync", guild_id),
+        Utc::now().timestamp().to_string(),
+    )
+    .unwrap_or_else(|e| panic!("Could not write guilds/{}/lastSync: {:?}", guild_id, e));
+}
+
+/// Parses the timezone set in a guild's configuration.
+///
+/// Falls back to UTC if the configured timezone is not a valid IANA timezone name.
+pub fn config_timezone(config: &BotConfig) -> Tz {
+    config.timezone.parse::<Tz>().unwrap_or_else(|_| {
+        tracing::warn!(
+            "Invalid timezone `{}` in the configuration. Using UTC instead.",
+            config.timezone
+        );
+        Tz::UTC
+    })
+}
+
+/// Checks that a timezone is a valid IANA timezone name, to be set in a guild's configuration.
+pub fn validate_timezone(timezone: &String) -> Result<(), String> {
+    match timezone.parse::<Tz>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!(
+            "`{}` is not a valid timezone. Use an IANA timezone name, such as `Europe/Madrid`.",
+            timezone
+        )),
+    }
+}
+
+/// Checks that a team capacity can be set in a guild's configuration: teams need room for at least
+/// their creator.
+pub fn validate_team_capacity(capacity: &u8) -> Result<(), String> {
+    if *capacity == 0 {
+        return Err("Teams must have room for at least 1 member.".to_string());
+    }
+
+    Ok(())
+}
+
+/// Checks that a prefix for team identifiers can be set in a guild's configuration: it is used in
+/// file names, and must not be confused with the teams' numbers.
+pub fn validate_team_prefix(prefix: &String) -> Result<(), String> {
+    if prefix.is_empty()
+        || !prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        || prefix.ends_with(|c: char| c.is_ascii_digit())
+    {
+        return Err(format!(
+            "`{}` is not a valid team prefix. Use ASCII letters, digits, `-` or `_`, not ending \
+            in a digit (e.g. `g`).",
+            prefix
+        ));
+    }
+
+    Ok(())
+}
+
+/// Formats a timestamp for displaying it in a guild, using the guild's configured timezone and the
+/// formats of the given language (see `i18n::format_datetime`).
+pub fn format_timestamp<T: TimeZone>(
+    config: &BotConfig,
+    lang: Language,
+    timestamp: &DateTime<T>,
+) -> String {
+    i18n::format_datetime(lang, &timestamp.with_timezone(&config_timezone(config)))
+}
+
+/// Formats a UNIX timestamp (in seconds) for displaying it in a guild, using the guild's
+/// configured timezone and the formats of the given language.
+pub fn format_unix_timestamp(config: &BotConfig, lang: Language, secs: i64) -> String {
+    match Utc.timestamp_opt(secs, 0).single() {
+        Some(timestamp) => format_timestamp(config, lang, &timestamp),
+        None => format!("[Invalid timestamp {}]", secs),
+    }
+}
+
+/// Parses a date (as `YYYY-MM-DD`) in a guild's configured timezone, returning the UNIX timestamp
+/// (in seconds) of its start.
+pub fn parse_date(config: &BotConfig, date: &str) -> Option<i64> {
+    let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+    config_timezone(config)
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|timestamp| timestamp.timestamp())
+}
+
+/// Parses a date and time (as `YYYY-MM-DD HH:MM`) in a guild's configured timezone, returning its
+/// UNIX timestamp (in seconds).
+pub fn parse_datetime(config: &BotConfig, datetime: &str) -> Option<i64> {
+    let datetime = NaiveDateTime::parse_from_str(datetime.trim(), "%Y-%m-%d %H:%M").ok()?;
+    config_timezone(config)
+        .from_local_datetime(&datetime)
+        .earliest()
+        .map(|timestamp| timestamp.timestamp())
+}
+
+/// Levenshtein distance between two strings (in characters), to suggest the right one on typos.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Hash of some content (64-bit FNV-1a, which is stable across versions of the bot, unlike Rust's
+/// default hasher), e.g. to tell whether an announcement or a program was already seen.
+pub fn content_hash(content: &[u8]) -> u64 {
+    content.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Maximum length of a Discord message, in characters.
+pub const MAX_MESSAGE_LEN: usize = 2000;
+
+/// Splits a text into chunks of at most `max_len` characters, to be sent as separate messages.
+///
+/// The text is split on line boundaries, and lines longer than `max_len` are split on their own.
+/// Code blocks split across chunks are closed at the end of each chunk, and opened again (with
+/// the same language) at the start of the next one. Chunks with only whitespace are dropped, as
+/// Discord does not send them.
+pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
+    let mut splitter = MessageSplitter {
+        max_len,
+        chunks: Vec::new(),
+        chunk: String::new(),
+        chunk_len: 0,
+        fence: None,
+        fence_start: 0,
+        body_start: 0,
+    };
+    for line in text.split_inclusive('\n') {
+        splitter.push_line(line);
+    }
+    splitter.end_chunk();
+
+    splitter.chunks
+}
+
+/// Room left at the end of a chunk to close its code block (a line break, and the fence), if any
+/// (see `split_message`).
+const CLOSING_FENCE_LEN: usize = 4;
+
+/// State of `split_message` along the text.
+struct MessageSplitter {
+    max_len: usize,
+    chunks: Vec<String>,
+    /// The chunk being filled, and its length in characters.
+    chunk: String,
+    chunk_len: usize,
+    /// Line opening the code block the text is in, if any.
+    fence: Option<String>,
+    /// Where the line opening the code block starts in the chunk, and where its contents start (in
+    /// bytes).
+    fence_start: usize,
+    body_start: usize,
+}
+
+impl MessageSplitter {
+    /// The code block the text is in after a line (i.e. the line opens or closes one, or not).
+    ///
+    /// Only fences short enough to be repeated in every chunk are taken into account.
+    fn fence_after(&self, line: &str) -> Option<String> {
+        let trimmed = line.trim();
+        if !trimmed.starts_with("```")
+            || trimmed.chars().count() + CLOSING_FENCE_LEN >= self.max_len / 2
+        {
+            return self.fence.clone();
+        }
+
+        match self.fence {
+            Some(_) => None,
+            None => Some(trimmed.to_string()),
+        }
+    }
+
+    /// Maximum length of the chunk's contents, leaving room to close the code block, if any.
+    fn limit(&self, fence: &Option<String>) -> usize {
+        match fence {
+            Some(_) => self.max_len - CLOSING_FENCE_LEN,
+            None => self.max_len,
+        }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        let fence_after = self.fence_after(line);
+        let line_len = line.chars().count();
+        if self.chunk_len + line_len > self.limit(&fence_after) && !self.chunk.is_empty() {
+            self.end_chunk();
+            self.start_chunk();
+        }
+
+        if self.chunk_len + line_len <= self.limit(&fence_after) {
+            if self.fence.is_none() && fence_after.is_some() {
+                self.fence_start = self.chunk.len();
+                self.body_start = self.chunk.len() + line.len();
+            }
+            self.chunk.push_str(line);
+            self.chunk_len += line_len;
+        } else {
+            // Split the line itself (which cannot open nor close a code block, being so long),
+            // filling each chunk:
+            let mut chars = line.chars().peekable();
+            while chars.peek().is_some() {
+                let room = self.limit(&self.fence) - self.chunk_len;
+                let piece = chars.by_ref().take(room).collect::<String>();
+                self.chunk_len += piece.chars().count();
+                self.chunk.push_str(&piece);
+                if chars.peek().is_some() {
+                    self.end_chunk();
+                    self.start_chunk();
+                }
+            }
+        }
+        self.fence = fence_after;
+    }
+
+    /// Adds the current chunk to the finished ones (unless it only has whitespace), closing its
+    /// code block, if any.
+    fn end_chunk(&mut self) {
+        let mut chunk = std::mem::take(&mut self.chunk);
+        if self.fence.is_some() && chunk.len() == self.body_start {
+            // Nothing in the code block yet, so it is only opened in the next chunk:
+            chunk.truncate(self.fence_start);
+        } else if self.fence.is_some() {
+            if !chunk.ends_with('\n') {
+                chunk.push('\n');
+            }
+            chunk.push_str("```");
+        }
+        if !chunk.trim().is_empty() {
+            self.chunks.push(chunk);
+        }
+    }
+
+    /// Starts a new chunk, opening the code block again, if any.
+    fn start_chunk(&mut self) {
+        self.chunk.clear();
+        self.chunk_len = 0;
+        if let Some(fence) = &self.fence {
+            self.chunk = format!("{}\n", fence);
+            self.chunk_len = fence.chars().count() + 1;
+            self.fence_start = 0;
+            self.body_start = self.chunk.len();
+        }
+    }
 }
 
 /// Transform a guild's name into a custom safe guild name.
@@ -193,6 +1221,589 @@ pub fn update_namemap_persistence(name_map: &HashMap<String, String>, guild_id:
 /// This basically substitutes all spaces with underscores, and slashes with hyphens.
 ///
 /// This is done so a path containing the guild's name can be created without causing any issues.
-pub fn sanitize_name(name: &String) -> String {
+pub fn sanitize_name(name: &str) -> String {
     name.replace(" ", "_").replace("/", "-")
 }
+
+/// Maximum number of members Discord returns per request.
+const MEMBERS_PAGE_SIZE: u64 = 1000;
+
+/// Fetches all the members of a guild, requesting them page by page (Discord returns at most 1000
+/// members per request).
+pub async fn fetch_all_members(
+    http: impl AsRef<Http>,
+    guild_id: &GuildId,
+) -> serenity::Result<Vec<Member>> {
+    let mut members = Vec::new();
+    let mut after = None;
+    loop {
+        let page = guild_id
+            .members(http.as_ref(), Some(MEMBERS_PAGE_SIZE), after)
+            .await?;
+        let last_page = (page.len() as u64) < MEMBERS_PAGE_SIZE;
+        after = page.last().map(|member| member.user.id);
+        members.extend(page);
+        if last_page || after.is_none() {
+            return Ok(members);
+        }
+    }
+}
+
+/// Checks whether a command was invoked in the channel with the given name, replying with an error
+/// otherwise (see `#[hermes::require_channel]`).
+pub async fn check_on_proper_channel(ctx: Context<'_>, channel_name: &String) -> bool {
+    let gid = get_guild_id!(ctx);
+    let lang = i18n::guild_language(&load_config(&ctx.data().caches, &gid));
+    let Some(cmd_channel) = ctx.guild_channel().await else {
+        tracing::warn!(
+            "[utils] The command `{}` was not invoked in a guild channel.",
+            crate::redact::invocation(ctx)
+        );
+        return false;
+    };
+    if cmd_channel.name != *channel_name {
+        reply_error(
+            ctx,
+            tr!(
+                lang,
+                "This command should only be used in the configured bot channel: #{}.",
+                "Este comando solo debe usarse en el canal configurado para el bot: #{}.",
+                channel_name
+            ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[utils] Failed to send reply using the command in an invalid channel in guild {}: {:?}",
+                gid, e
+            )
+        });
+
+        return false;
+    }
+
+    true
+}
+
+/// Roles allowed to run the command with the given qualified name, according to the guild's
+/// permission overrides. The most specific override applies (e.g. one for "botconfig logs" takes
+/// precedence over one for "botconfig").
+pub fn permission_override<'a>(
+    config: &'a BotConfig,
+    qualified_name: &str,
+) -> Option<&'a Vec<RoleId>> {
+    most_specific_override(&config.permissions, qualified_name)
+}
+
+/// Visibility of the replies of the command with the given qualified name, according to the
+/// guild's overrides, with the same precedence as `permission_override`.
+pub fn visibility_override(config: &BotConfig, qualified_name: &str) -> Option<Visibility> {
+    most_specific_override(&config.reply_visibility, qualified_name).copied()
+}
+
+/// Most specific override for a command (by qualified name) in a per-command map: that of the
+/// command itself, or of its closest parent command.
+fn most_specific_override<'a, T>(
+    overrides: &'a BTreeMap<String, T>,
+    qualified_name: &str,
+) -> Option<&'a T> {
+    let mut name = qualified_name;
+    loop {
+        if let Some(value) = overrides.get(name) {
+            return Some(value);
+        }
+        name = name.rsplit_once(' ')?.0;
+    }
+}
+
+/// Whether the replies of a command should be ephemeral: as set for it in the guild's
+/// configuration (see `BotConfig::reply_visibility`), or as the command defaults to otherwise.
+pub fn is_ephemeral(ctx: Context<'_>) -> bool {
+    let default = ctx.command().ephemeral;
+    let Some(gid) = ctx.guild_id() else {
+        return default;
+    };
+
+    match visibility_override(
+        &load_config(&ctx.data().caches, &gid),
+        &ctx.command().qualified_name,
+    ) {
+        Some(visibility) => visibility == Visibility::Ephemeral,
+        None => default,
+    }
+}
+
+/// Replies to a command with the given text, honoring the guild's visibility policy for it (see
+/// `is_ephemeral`).
+pub async fn reply(
+    ctx: Context<'_>,
+    content: impl Into<String>,
+) -> Result<poise::ReplyHandle<'_>, serenity::Error> {
+    send(ctx, poise::CreateReply::default().content(content)).await
+}
+
+/// Replies to a command with the given message, honoring the guild's visibility policy for it (see
+/// `is_ephemeral`), unless the message sets its own visibility (e.g. for errors only meant for the
+/// user).
+pub async fn send(
+    ctx: Context<'_>,
+    mut builder: poise::CreateReply,
+) -> Result<poise::ReplyHandle<'_>, serenity::Error> {
+    if builder.ephemeral.is_none() {
+        builder.ephemeral = Some(is_ephemeral(ctx));
+    }
+
+    ctx.send(builder).await
+}
+
+/// Marker of the command invocations that replied with an error (see `reply_error`).
+struct ErrorReplied;
+
+/// Replies to a command with an error message, like `reply`, so the command is logged as failed
+/// even if it returns `Ok` (see `elog_cmd_outcome!`).
+pub async fn reply_error(
+    ctx: Context<'_>,
+    content: impl Into<String>,
+) -> Result<poise::ReplyHandle<'_>, serenity::Error> {
+    send_error(ctx, poise::CreateReply::default().content(content)).await
+}
+
+/// Replies to a command with an error message, like `send`, so the command is logged as failed
+/// even if it returns `Ok` (see `elog_cmd_outcome!`).
+pub async fn send_error(
+    ctx: Context<'_>,
+    builder: poise::CreateReply,
+) -> Result<poise::ReplyHandle<'_>, serenity::Error> {
+    ctx.set_invocation_data(ErrorReplied).await;
+    send(ctx, builder).await
+}
+
+/// Whether the command replied with an error (see `reply_error`).
+pub async fn replied_error(ctx: Context<'_>) -> bool {
+    ctx.invocation_data::<ErrorReplied>().await.is_some()
+}
+
+/// Checks whether the author of a command is allowed to run it according to the guild's permission
+/// overrides, replying with an error otherwise. Members with the Administrator permission are
+/// always allowed, so the overrides can't lock everyone out of the bot.
+pub async fn check_permission_overrides(ctx: Context<'_>) -> bool {
+    let Some(gid) = ctx.guild_id() else {
+        return true;
+    };
+    let config = load_config(&ctx.data().caches, &gid);
+    let Some(roles) = permission_override(&config, &ctx.command().qualified_name) else {
+        return true;
+    };
+    let Some(member) = ctx.author_member().await else {
+        tracing::warn!(
+            "[utils] Could not retrieve the member invoking `{}` in guild {}.",
+            crate::redact::invocation(ctx),
+            gid
+        );
+        return false;
+    };
+    if member_has_roles(&member, roles) {
+        return true;
+    }
+
+    send(
+        ctx,
+        poise::CreateReply::default()
+            .content(missing_roles_notice(&config))
+            .ephemeral(true),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        panic!(
+            "[utils] Failed to send reply after user {} used `{}` without the required roles: {:?}",
+            ctx.author().id,
+            crate::redact::invocation(ctx),
+            e
+        )
+    });
+
+    false
+}
+
+/// Whether a member is allowed to use a command restricted to the given roles by the guild's
+/// permission overrides: by having one of them, or by being an administrator.
+pub fn member_has_roles(member: &Member, roles: &[RoleId]) -> bool {
+    member
+        .permissions
+        .is_some_and(|perms| perms.administrator())
+        || member.roles.iter().any(|role| roles.contains(role))
+}
+
+/// Notice for a member without any of the roles allowed to use a command.
+pub fn missing_roles_notice(config: &BotConfig) -> String {
+    tr!(
+        i18n::guild_language(config),
+        "You don't have any of the roles allowed to use this command.",
+        "No tienes ninguno de los roles que pueden usar este comando."
+    )
+}
+
+/// Retrieves the triggering student and their team, replying with an error if they are not in a
+/// team (see `#[hermes::require_team]`).
+pub async fn require_team(ctx: Context<'_>, guild_id: &GuildId) -> Option<(Student, Team)> {
+    let student = get_triggering_student!(ctx);
+    let Some(team_id) = student.get_team_id(guild_id) else {
+        let lang = i18n::user_language(
+            &student,
+            &load_config(&ctx.data().caches, guild_id),
+            guild_id,
+        );
+        // The student may be in a team, but their data for the guild could not be loaded:
+        let message = if student.is_slice_unreadable(guild_id) {
+            tr!(
+                lang,
+                "Your data in this server could not be loaded. Please, tell the admins.",
+                "No se han podido cargar tus datos en este servidor. Por favor, avisa a los administradores."
+            )
+        } else {
+            tr!(
+                lang,
+                "You are not in a team in this server.",
+                "No estás en ningún equipo en este servidor."
+            )
+        };
+        reply_error(ctx, message)
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[utils] Failed to send reply after user {} used `{}` without being in a team: {:?}",
+                student.id(), crate::redact::invocation(ctx), e
+            )
+        });
+
+        return None;
+    };
+    let team = team::get_existing_team!(guild_id, &team_id);
+
+    Some((student, team))
+}
+
+/// Retrieves the Tablón password of the student's team, replying with an error if it has not been
+/// set yet (see `#[hermes::require_team(password)]`).
+pub async fn require_password(
+    ctx: Context<'_>,
+    student: &Student,
+    guild_id: &GuildId,
+) -> Option<String> {
+    let password = student
+        .get_credentials(guild_id)
+        .and_then(|credentials| credentials.password().clone());
+    if password.is_none() {
+        let lang = i18n::user_language(
+            student,
+            &load_config(&ctx.data().caches, guild_id),
+            guild_id,
+        );
+        reply_error(
+            ctx,
+            tr!(
+            lang,
+            "**Error:** You cannot send requests to Tablón, as your team has not been registered \
+            yet.",
+            "**Error:** No puedes enviar peticiones a Tablón, ya que tu equipo todavía no ha sido \
+            registrado."
+        ),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            panic!(
+                "[utils] Failed to send reply to student with no password {}: {:?}",
+                student.id(),
+                e
+            )
+        });
+    }
+
+    password
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDataDir;
+
+    /// Files left in the working directory, besides the bot's initial ones.
+    fn leftover_files() -> Vec<String> {
+        fs::read_dir(".")
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name != "guilds" && name != "users")
+            .collect()
+    }
+
+    #[test]
+    fn configs_keep_unknown_fields_and_fill_in_missing_ones() {
+        // Missing fields take their default values, and the configuration is to be upgraded:
+        let (config, upgraded) = parse_config(r#"{ "team_capacity": 3 }"#).unwrap();
+        assert_eq!(config.team_capacity, 3);
+        assert_eq!(config.team_prefix, BotConfig::default().team_prefix);
+        assert!(upgraded);
+
+        // Unknown fields are kept, even when saved again:
+        let mut json = serde_json::to_value(BotConfig::default()).unwrap();
+        json["future_option"] = serde_json::json!({ "enabled": true });
+        let (config, upgraded) = parse_config(&json.to_string()).unwrap();
+        assert!(!upgraded);
+        assert_eq!(
+            config.extra.get("future_option"),
+            Some(&serde_json::json!({ "enabled": true }))
+        );
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(saved["future_option"], json["future_option"]);
+        assert_eq!(saved, json);
+    }
+
+    #[test]
+    fn configs_with_invalid_fields_are_loaded() {
+        let _dir = TempDataDir::new("config-invalid");
+        let caches = Caches::default();
+        let gid = GuildId::new(1);
+        crate::init_guild_files(&caches, &gid);
+        let json = r#"{ "team_capacity": "three", "team_prefix": "t", "bot_channel": 5 }"#;
+        write_file(&format!("guilds/{}/config.json", gid), json).unwrap();
+
+        assert!(parse_config(json).is_err());
+        let config = load_config(&Caches::default(), &gid);
+        assert_eq!(config.team_prefix, "t");
+        assert_eq!(config.team_capacity, BotConfig::default().team_capacity);
+        assert_eq!(config.bot_channel, BotConfig::default().bot_channel);
+
+        // The file is left for the admins to fix:
+        let saved = fs::read_to_string(format!("guilds/{}/config.json", gid)).unwrap();
+        assert_eq!(saved, json);
+    }
+
+    #[test]
+    fn user_names_are_tracked() {
+        let mut user_map = UserMap::new();
+        let uid = UserId::new(1);
+        assert!(record_user_name(&mut user_map, uid, "alice".to_string()));
+        assert!(!record_user_name(&mut user_map, uid, "alice".to_string()));
+        assert!(user_map[&uid].aliases.is_empty());
+
+        // Previous names are kept as aliases, once, and never along with the current one:
+        assert!(record_user_name(&mut user_map, uid, "alice2".to_string()));
+        assert!(record_user_name(&mut user_map, uid, "alice3".to_string()));
+        assert_eq!(user_map[&uid].aliases, vec!["alice", "alice2"]);
+        assert!(record_user_name(&mut user_map, uid, "alice".to_string()));
+        assert_eq!(user_map[&uid].name, "alice");
+        assert_eq!(user_map[&uid].aliases, vec!["alice2", "alice3"]);
+    }
+
+    #[test]
+    fn users_are_found_by_name() {
+        let (alice, bob, carol) = (UserId::new(1), UserId::new(2), UserId::new(3));
+        let mut user_map = UserMap::new();
+        record_user_name(&mut user_map, alice, "alice".to_string());
+        record_user_name(&mut user_map, alice, "Alicia".to_string());
+        record_user_name(&mut user_map, bob, "bob".to_string());
+        record_user_name(&mut user_map, bob, "robert".to_string());
+        record_user_name(&mut user_map, carol, "bob".to_string());
+
+        // Ignoring case, by current name first, and by previous names otherwise:
+        assert_eq!(find_users(&user_map, "ALICIA"), vec![alice]);
+        assert_eq!(find_users(&user_map, "Alice"), vec![alice]);
+        assert_eq!(find_users(&user_map, "bob"), vec![carol]);
+        assert!(find_users(&user_map, "dave").is_empty());
+
+        // Ambiguous names find every user with them:
+        record_user_name(&mut user_map, carol, "Robert".to_string());
+        assert_eq!(find_users(&user_map, "robert"), vec![bob, carol]);
+        assert_eq!(find_users(&user_map, "bob"), vec![bob, carol]);
+    }
+
+    #[test]
+    fn user_maps_are_upgraded() {
+        let _dir = TempDataDir::new("usermap");
+
+        // Maps from names to IDs, of older versions:
+        let legacy = r#"{ "alice": "1", "bob": 2 }"#;
+        let (user_map, upgraded) = parse_usermap(legacy).unwrap();
+        assert!(upgraded);
+        assert_eq!(user_map[&UserId::new(1)].name, "alice");
+        assert_eq!(user_map[&UserId::new(2)].name, "bob");
+
+        // The current format is not upgraded:
+        let current = serde_json::to_string(&user_map).unwrap();
+        let (parsed, upgraded) = parse_usermap(&current).unwrap();
+        assert!(!upgraded);
+        assert_eq!(parsed[&UserId::new(2)].name, "bob");
+        assert!(parse_usermap("[1, 2]").is_err());
+
+        // The file is rewritten in the current format once loaded:
+        write_file("users/userMap.json", legacy).unwrap();
+        assert_eq!(load_usermap()[&UserId::new(1)].name, "alice");
+        let (_, upgraded) =
+            parse_usermap(&fs::read_to_string("users/userMap.json").unwrap()).unwrap();
+        assert!(!upgraded);
+    }
+
+    #[test]
+    fn messages_are_split_on_lines() {
+        // A chunk can take exactly `max_len` characters:
+        assert_eq!(split_message("abcd\nefgh\n", 10), vec!["abcd\nefgh\n"]);
+        assert_eq!(split_message("abcd\nefgh\n", 5), vec!["abcd\n", "efgh\n"]);
+        assert_eq!(split_message("abcd\nefgh\n", 9), vec!["abcd\n", "efgh\n"]);
+
+        // Lines longer than a chunk are split on their own, the rest of the text following them:
+        assert_eq!(
+            split_message("ab\ncdefghijk\nl", 4),
+            vec!["ab\n", "cdef", "ghij", "k\nl"]
+        );
+    }
+
+    #[test]
+    fn messages_are_split_on_characters() {
+        let text = "ñandú\ncigüeña\n🦀🦀🦀🦀🦀\n";
+        let chunks = split_message(text, 4);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 4));
+        assert_eq!(chunks.concat(), text);
+        assert_eq!(chunks[0], "ñand");
+        assert_eq!(chunks[chunks.len() - 2], "🦀🦀🦀🦀");
+    }
+
+    #[test]
+    fn whitespace_only_chunks_are_dropped() {
+        assert_eq!(
+            split_message("abcd\n\n\n\n\n  \nefgh", 5),
+            vec!["abcd\n", "efgh"]
+        );
+        assert_eq!(split_message("abcd\n    \n", 5), vec!["abcd\n"]);
+        assert!(split_message(" \n\n", 5).is_empty());
+    }
+
+    #[test]
+    fn code_blocks_are_closed_and_reopened() {
+        let text = "Results:\n```diff\n- one\n+ two\n- three\n+ four\n```\nDone.\n";
+        let chunks = split_message(text, 32);
+        assert_eq!(
+            chunks,
+            vec![
+                "Results:\n```diff\n- one\n```",
+                "```diff\n+ two\n- three\n```",
+                "```diff\n+ four\n```\nDone.\n",
+            ]
+        );
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 32));
+
+        // Long lines in code blocks too, without leaving an empty code block behind:
+        let text = format!("```\n{}\n```\n", "x".repeat(30));
+        let chunks = split_message(&text, 20);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 20));
+        assert!(
+            chunks
+                .iter()
+                .all(|chunk| chunk.starts_with("```\n") && chunk.trim_end().ends_with("\n```")),
+            "{:?}",
+            chunks
+        );
+        assert_eq!(chunks.concat().matches('x').count(), 30, "{:?}", chunks);
+    }
+
+    #[test]
+    fn concurrent_writes_are_never_torn() {
+        let _dir = TempDataDir::new("write-file");
+        write_file("data.json", "initial").unwrap();
+
+        // Each write is a single repeated character, so a mix of two writes is detected:
+        std::thread::scope(|scope| {
+            for writer in 0..8u8 {
+                scope.spawn(move || {
+                    for _ in 0..50 {
+                        let contents = vec![b'a' + writer; 64 * 1024];
+                        write_file("data.json", contents).unwrap();
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let contents = fs::read("data.json").unwrap();
+                    assert!(
+                        contents == b"initial"
+                            || (contents.len() == 64 * 1024
+                                && contents.iter().all(|byte| *byte == contents[0])),
+                        "Read a torn write."
+                    );
+                }
+            });
+        });
+
+        assert_eq!(leftover_files(), vec!["data.json".to_string()]);
+    }
+
+    #[test]
+    fn failed_writes_leave_no_temporary_files() {
+        let _dir = TempDataDir::new("write-file-failure");
+        fs::create_dir("taken").unwrap();
+        fs::write("taken/file", "").unwrap();
+
+        // The rename over a (non-empty) directory fails:
+        assert!(write_file("taken", "contents").is_err());
+        assert!(write_file("missing/data.json", "contents").is_err());
+        assert_eq!(leftover_files(), vec!["taken".to_string()]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secrets_are_only_readable_by_the_bot() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let _dir = TempDataDir::new("secrets");
+        let gid = GuildId::new(1);
+        fs::create_dir_all(format!("guilds/{}", gid)).unwrap();
+        let secrets = GuildSecrets::default();
+        update_secrets_persistence(&secrets, &gid);
+        update_secrets_persistence(&secrets, &gid);
+
+        let metadata = fs::metadata(format!("guilds/{}/secrets.json", gid)).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read_dir(format!("guilds/{}", gid)).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn guilds_with_the_same_name_are_disambiguated() {
+        let (first, second) = (GuildId::new(10), GuildId::new(20));
+
+        // Whatever the order they are recorded in:
+        let mut guild_maps = [HashMap::new(), HashMap::new()];
+        record_guild_name(&mut guild_maps[0], first, "Server".to_string());
+        record_guild_name(&mut guild_maps[0], second, "Server".to_string());
+        record_guild_name(&mut guild_maps[1], second, "Server".to_string());
+        record_guild_name(&mut guild_maps[1], first, "Server".to_string());
+        assert_eq!(guild_maps[0], guild_maps[1]);
+        assert_eq!(guild_maps[0].get("Server"), Some(&first));
+        assert_eq!(guild_maps[0].get("Server-20"), Some(&second));
+
+        // Renamed guilds lose their previous name:
+        assert!(record_guild_name(
+            &mut guild_maps[0],
+            second,
+            "Other".to_string()
+        ));
+        assert_eq!(guild_maps[0].len(), 2);
+        assert!(!record_guild_name(
+            &mut guild_maps[0],
+            second,
+            "Other".to_string()
+        ));
+    }
+
+    #[test]
+    fn permission_overrides_allow_the_roles_and_administrators() {
+        let roles = [RoleId::new(1), RoleId::new(2)];
+        let mut member = Member::default();
+        assert!(!member_has_roles(&member, &roles));
+
+        member.roles = vec![RoleId::new(3), RoleId::new(2)];
+        assert!(member_has_roles(&member, &roles));
+
+        member.roles = vec![RoleId::new(3)];
+        member.permissions = Some(serenity::all::Permissions::ADMINISTRATOR);
+        assert!(member_has_roles(&member, &roles));
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Endpoint for Tablón to push the results of the requests, notifying the teams that sent them as
+//! soon as they are known (instead of waiting for them to check `/history`).
+//!
+//! Tablón must `POST` a JSON body like `{"request_id": 123, "team": "g01", "verdict": "..."}` to
+//! `/tablon/<guild_id>/results`, with the guild's token (see `/botconfig webhook_token`) in an
+//! `Authorization: Bearer <token>` header.
+
+use crate::{
+    http::{self, HttpState},
+    i18n::{self, tr},
+    student, tablon, utils,
+};
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use poise::serenity_prelude as serenity;
+use serde::Deserialize;
+
+/// Result of a request, as notified by Tablón.
+#[derive(Deserialize)]
+pub struct ResultNotification {
+    request_id: u16,
+    team: String,
+    verdict: String,
+}
+
+/// Routes of the endpoint.
+pub fn routes() -> Router<HttpState> {
+    Router::new().route("/tablon/{guild_id}/results", post(receive_result))
+}
+
+/// Receives the result of a request, records it in the request history of the team's members, and
+/// notifies them by DM.
+async fn receive_result(
+    State(state): State<HttpState>,
+    Path(guild_id): Path<u64>,
+    headers: HeaderMap,
+    Json(notification): Json<ResultNotification>,
+) -> StatusCode {
+    let Some(gid) = http::known_guild(guild_id) else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    // Check the guild's token:
+    if let Err(status) = http::authorize(&headers, utils::load_secrets(&gid).webhook_token.as_ref())
+    {
+        tracing::warn!(
+            "[webhook] Rejected a result notification for guild {} ({}).",
+            gid,
+            status
+        );
+        return status;
+    }
+
+    // The team comes from the body, so only the guild's valid team identifiers are looked up:
+    let team_id = notification.team.clone();
+    let team = tokio::task::spawn_blocking(move || http::known_team(&gid, &team_id)).await;
+    let Ok(Some(team)) = team else {
+        return StatusCode::NOT_FOUND;
+    };
+    if tablon::is_pending(&notification.verdict) {
+        // Not a result yet; nothing to notify.
+        return StatusCode::ACCEPTED;
+    }
+    tracing::info!(
+        "[webhook] Received the result of request #{} of team {} in guild {}.",
+        notification.request_id,
+        team.id(),
+        gid
+    );
+
+    // Record the verdict once, in the history of the member who sent the request (so a single
+    // result event is recorded for the team), and notify all the team's members:
+    let config = utils::load_config(&state.caches, &gid);
+    let mut recorded = false;
+    for uid in team.members().iter() {
+        let Some(mut student) = student::get_student(&state.caches, uid) else {
+            continue;
+        };
+        if !recorded {
+            recorded = student.set_request_verdict(
+                &gid,
+                notification.request_id,
+                notification.verdict.clone(),
+            );
+        }
+
+        let lang = i18n::user_language(&student, &config, &gid);
+        let message = serenity::CreateMessage::new().content(tr!(
+            lang,
+            "📬 The request #{} of your team {} has finished in Tablón: `{}`",
+            "📬 La petición #{} de tu equipo {} ha terminado en Tablón: `{}`",
+            notification.request_id,
+            team.id(),
+            notification.verdict
+        ));
+        let sent = match uid.create_dm_channel(&state.http).await {
+            Ok(channel) => channel.send_message(&state.http, message).await.is_ok(),
+            Err(_) => false,
+        };
+        if !sent {
+            tracing::warn!(
+                "[webhook] Could not notify user {} of the result of request #{}.",
+                uid,
+                notification.request_id
+            );
+        }
+    }
+
+    StatusCode::OK
+}
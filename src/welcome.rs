@@ -0,0 +1,141 @@
+/*
+ *  Hermes - Discord bot for integrating UVa's Tablón into Discord servers.
+ *  Copyright (C) 2025  Manuel de Castro <manuel@infor.uva.es>
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Greeting (and instructions) for the new members of the guilds that set one (see
+//! `BotConfig::welcome_message`), sent by DM or to a channel of the guild as they join.
+
+use crate::{
+    cache::Caches,
+    utils::{self, BotConfig},
+};
+use poise::serenity_prelude as serenity;
+use serenity::all::{GuildId, UserId};
+
+/// Maximum length of a welcome message template, leaving room for the values of its variables in a
+/// single Discord message (longer ones are split, anyway).
+pub const MAX_TEMPLATE_LEN: usize = 1500;
+
+/// Variables that can be used in a welcome message, as `{name}`.
+pub const VARIABLES: [&str; 5] = [
+    "user",
+    "guild",
+    "bot_channel",
+    "team_capacity",
+    "team_prefix",
+];
+
+/// Checks that a welcome message template can be set in a guild's configuration: it is not too
+/// long, and only uses known variables.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    if template.chars().count() > MAX_TEMPLATE_LEN {
+        return Err(format!(
+            "The welcome message cannot be longer than {} characters.",
+            MAX_TEMPLATE_LEN
+        ));
+    }
+
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err("The welcome message has a `{` without its closing `}`.".to_string());
+        };
+        let variable = &rest[start + 1..start + len];
+        if !VARIABLES.contains(&variable) {
+            return Err(format!(
+                "`{{{}}}` is not a known variable. The available ones are: {}.",
+                variable,
+                VARIABLES
+                    .iter()
+                    .map(|var| format!("`{{{}}}`", var))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        rest = &rest[start + len + 1..];
+    }
+
+    Ok(())
+}
+
+/// Fills in the variables of a guild's welcome message for a user, given how to refer to the
+/// guild's bot channel. Literal `\n`s become line breaks, as slash command options cannot have
+/// them.
+pub fn render(config: &BotConfig, user: &UserId, guild_name: &str, bot_channel: &str) -> String {
+    config
+        .welcome_message
+        .replace("\\n", "\n")
+        .replace("{user}", &format!("<@{}>", user))
+        .replace("{guild}", guild_name)
+        .replace("{bot_channel}", bot_channel)
+        .replace("{team_capacity}", &config.team_capacity.to_string())
+        .replace("{team_prefix}", &config.team_prefix)
+}
+
+/// Greets a new member of a guild with its welcome message, if it has one: by DM, or in the
+/// configured channel (see `BotConfig::welcome_channel`).
+pub async fn greet(ctx: &serenity::Context, caches: &Caches, gid: &GuildId, user: &UserId) {
+    let config = utils::load_config(caches, gid);
+    if config.welcome_message.is_empty() {
+        return;
+    }
+
+    let gname = gid
+        .name(&ctx.cache)
+        .unwrap_or_else(|| format!("guild {}", gid));
+    let channels = gid.channels(&ctx.http).await.unwrap_or_default();
+    let bot_channel = channels
+        .values()
+        .find(|channel| channel.name == config.bot_channel)
+        .map_or_else(
+            || format!("#{}", config.bot_channel),
+            |channel| format!("<#{}>", channel.id),
+        );
+    let msg = render(&config, user, &gname, &bot_channel);
+
+    let channel = if config.welcome_channel.is_empty() {
+        None
+    } else {
+        let Some(channel) = channels
+            .values()
+            .find(|channel| channel.name == config.welcome_channel)
+        else {
+            tracing::warn!(
+                "[welcome] Guild {} has no channel named #{} to welcome its new members in.",
+                gid,
+                config.welcome_channel
+            );
+            return;
+        };
+        Some(channel)
+    };
+    for chunk in utils::split_message(&msg, utils::MAX_MESSAGE_LEN) {
+        let message = serenity::CreateMessage::new().content(chunk);
+        let result = match channel {
+            Some(channel) => channel.send_message(&ctx.http, message).await,
+            None => user.direct_message(&ctx.http, message).await,
+        };
+        if let Err(e) = result {
+            tracing::warn!(
+                "[welcome] Could not welcome user {} to guild {}: {}",
+                user,
+                gid,
+                e
+            );
+            return;
+        }
+    }
+}